@@ -0,0 +1,46 @@
+//! Convert polylines in the CSV or JSON export format back into an SVG file.
+//!
+//! The format is determined by the file extension (`.csv` or `.json`).
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+
+use svg2polylines::import::{self, ImportFormat};
+
+fn main() {
+    // Logging
+    env_logger::init();
+
+    // Argument parsing
+    let args: Vec<_> = env::args().collect();
+    if args.len() != 2 {
+        println!("Usage: {} <path/to/file.{{csv,json}}>", args[0]);
+        exit(1);
+    }
+    let path = Path::new(&args[1]);
+    let format = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("csv") => ImportFormat::Csv,
+        Some("json") => ImportFormat::Json,
+        _ => {
+            println!("Error: Unknown file extension, expected .csv or .json");
+            exit(1);
+        }
+    };
+
+    // Load file
+    let input = fs::read_to_string(path).unwrap_or_else(|e| {
+        println!("Error: {e}");
+        exit(2);
+    });
+
+    // Convert and print
+    match import::to_svg(&input, format) {
+        Ok(svg) => print!("{svg}"),
+        Err(e) => {
+            println!("Error: {e}");
+            exit(2);
+        }
+    }
+}
@@ -6,7 +6,7 @@ use drag_controller::{Drag, DragController};
 use piston_window::{
     clear, line, math::Matrix2d, Event, Input, Motion, PistonWindow, Transformed, WindowSettings,
 };
-use svg2polylines::{self, Polyline};
+use svg2polylines::{self, Color, ParseOptions, PolylineWithMetadata};
 
 fn main() {
     // Logging
@@ -28,10 +28,11 @@ fn main() {
     file.read_to_string(&mut s).unwrap();
 
     // Parse data
-    let polylines: Vec<Polyline> = svg2polylines::parse(&s, 0.15, true).unwrap_or_else(|e| {
-        println!("Error: {}", e);
-        exit(2);
-    });
+    let polylines: Vec<PolylineWithMetadata> =
+        svg2polylines::parse_with_metadata(&s, &ParseOptions::default()).unwrap_or_else(|e| {
+            println!("Error: {}", e);
+            exit(2);
+        });
     if polylines.is_empty() {
         println!("Error: No polylines found in input file.");
         exit(2);
@@ -46,9 +47,18 @@ fn main() {
         .build()
         .unwrap();
 
+    // Draw every polyline in its stroke color (or black if it has none)
+    let to_rgba = |color: Option<Color>| match color {
+        Some(c) => [
+            f32::from(c.red) / 255.0,
+            f32::from(c.green) / 255.0,
+            f32::from(c.blue) / 255.0,
+            f32::from(c.alpha) / 255.0,
+        ],
+        None => [0.0, 0.0, 0.0, 1.0],
+    };
+
     // Show window
-    let black = [0.0, 0.0, 0.0, 1.0];
-    let radius = 1.0;
     let mut drag = DragController::new();
     let mut translate: Matrix2d = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
     let mut translate_tmp: Matrix2d = translate.clone();
@@ -91,10 +101,16 @@ fn main() {
         // Redraw
         window.draw_2d(&e, |ctx, g, _device| {
             clear([1.0; 4], g);
-            for polyline in &polylines {
-                for pair in polyline.as_ref().windows(2) {
+            for item in &polylines {
+                let color = to_rgba(item.stroke);
+                let radius = if item.stroke.is_some() {
+                    item.stroke_width / 2.0
+                } else {
+                    1.0
+                };
+                for pair in item.polyline.as_ref().windows(2) {
                     line(
-                        black,
+                        color,
                         radius,
                         [pair[0].x, pair[0].y, pair[1].x, pair[1].y],
                         ctx.transform
@@ -0,0 +1,254 @@
+//! A tiny HTTP conversion service.
+//!
+//! Start it with `cargo run --example serve [address]` and POST an SVG file
+//! to it:
+//!
+//! ```shell
+//! curl --data-binary @drawing.svg 'http://127.0.0.1:8080/convert?format=gcode&tolerance=0.1'
+//! ```
+//!
+//! Supported query parameters:
+//!
+//! - `format`: `json` (default), `csv`, `svg` or `gcode`
+//! - `tolerance`: flattening tolerance, at least 0.001 (default 0.15)
+//! - `preprocess`: `true` (default) or `false`
+//! - `paper`: fit the drawing onto `a5`, `a4`, `a3`, `a2` or `letter` paper
+//! - `landscape`: `true` for landscape orientation (default `false`)
+//!
+//! Every connection is handled on its own thread, and the size and
+//! complexity of the uploaded documents is limited.
+
+use std::{
+    env,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use svg2polylines::{
+    export::{CsvExporter, GcodeExporter, JsonExporter, SvgExporter},
+    paper::{self, FitOptions, Orientation, PaperSize},
+    Exporter, Limits, ParseOptions,
+};
+
+/// Maximum accepted request body size.
+const MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+/// Smallest accepted flattening tolerance, smaller values make curves
+/// explode into millions of points.
+const MIN_TOLERANCE: f64 = 0.001;
+
+/// Time after which a connection that does not send or receive data is
+/// closed.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Limits on the work done for a single document.
+const LIMITS: Limits = Limits {
+    max_input_size: Some(MAX_BODY_SIZE),
+    max_paths: Some(100_000),
+    max_points: Some(1_000_000),
+    max_use_depth: Some(8),
+};
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn error(status: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            body: message.into() + "\n",
+        }
+    }
+}
+
+/// Decode a percent-encoded query string component, where `+` encodes a
+/// space.
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut input = s.bytes();
+    while let Some(b) = input.next() {
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [input.next()?, input.next()?];
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            }
+            _ => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn convert(query: &str, svg: &[u8]) -> Response {
+    let mut options = ParseOptions {
+        limits: LIMITS,
+        ..Default::default()
+    };
+    let mut format = String::from("json");
+    let mut fit: Option<FitOptions> = None;
+    let mut orientation = Orientation::Portrait;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let (Some(key), Some(value)) = (percent_decode(key), percent_decode(value)) else {
+            return Response::error("400 Bad Request", "Invalid query string");
+        };
+        match key.as_str() {
+            "format" => format = value,
+            "tolerance" => match value.parse::<f64>() {
+                Ok(tolerance) if tolerance >= MIN_TOLERANCE && tolerance.is_finite() => {
+                    options.tolerance = tolerance;
+                }
+                _ => {
+                    return Response::error(
+                        "400 Bad Request",
+                        format!(
+                            "The tolerance must be a finite number of at least {MIN_TOLERANCE}"
+                        ),
+                    );
+                }
+            },
+            "preprocess" => options.preprocess = (value != "false").into(),
+            "paper" => {
+                let paper = match value.as_str() {
+                    "a5" => PaperSize::A5,
+                    "a4" => PaperSize::A4,
+                    "a3" => PaperSize::A3,
+                    "a2" => PaperSize::A2,
+                    "letter" => PaperSize::Letter,
+                    other => {
+                        return Response::error(
+                            "400 Bad Request",
+                            format!("Unknown paper size: {other}"),
+                        );
+                    }
+                };
+                fit = Some(FitOptions {
+                    paper,
+                    ..Default::default()
+                });
+            }
+            "landscape" if value == "true" => orientation = Orientation::Landscape,
+            _ => {}
+        }
+    }
+
+    let mut polylines = match svg2polylines::parse_bytes(svg, &options) {
+        Ok(polylines) => polylines,
+        Err(e) => return Response::error("422 Unprocessable Entity", e.to_string()),
+    };
+    if let Some(fit) = fit {
+        paper::fit_to_paper(&mut polylines, &FitOptions { orientation, ..fit });
+    }
+    let (content_type, output) = match format.as_str() {
+        "json" => (
+            "application/json",
+            JsonExporter.export_to_string(&polylines),
+        ),
+        "csv" => ("text/csv", CsvExporter.export_to_string(&polylines)),
+        "svg" => (
+            "image/svg+xml",
+            SvgExporter::default().export_to_string(&polylines),
+        ),
+        "gcode" => (
+            "text/plain",
+            GcodeExporter::default().export_to_string(&polylines),
+        ),
+        other => {
+            return Response::error("400 Bad Request", format!("Unknown format: {other}"));
+        }
+    };
+    match output {
+        Ok(body) => Response {
+            status: "200 OK",
+            content_type,
+            body,
+        },
+        Err(e) => Response::error("500 Internal Server Error", e.to_string()),
+    }
+}
+
+fn handle(stream: &mut TcpStream) -> std::io::Result<Response> {
+    let mut reader = BufReader::new(stream);
+
+    // Request line
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    // Headers
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    if method != "POST" || path != "/convert" {
+        return Ok(Response::error(
+            "404 Not Found",
+            "Send a POST request to /convert",
+        ));
+    }
+    if content_length > MAX_BODY_SIZE {
+        return Ok(Response::error("413 Payload Too Large", "SVG too large"));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(convert(query, &body))
+}
+
+fn serve(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let response =
+        handle(&mut stream).unwrap_or_else(|e| Response::error("400 Bad Request", e.to_string()));
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )
+}
+
+fn main() {
+    // Logging
+    env_logger::init();
+
+    // Argument parsing
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:8080".to_string());
+
+    let listener = TcpListener::bind(&address).unwrap();
+    println!("Listening on http://{address}/convert");
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                thread::spawn(move || {
+                    if let Err(e) = serve(stream) {
+                        println!("Error: {e}");
+                    }
+                });
+            }
+            Err(e) => println!("Error: {e}"),
+        }
+    }
+}
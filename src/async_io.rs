@@ -0,0 +1,237 @@
+//! Asynchronous variants of the parsing and export APIs, based on tokio.
+
+use std::{
+    io::{self, BufWriter, Write},
+    iter,
+};
+
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::mpsc,
+    task,
+};
+
+use crate::{
+    parse_with_options, reader, Error, Exporter, ParseIter, ParseOptions, Polyline, Unit,
+    ViewportClip,
+};
+
+/// The number of output chunks buffered between the exporter and the writer.
+const BUFFERED_CHUNKS: usize = 4;
+
+/// Sends everything written to it as chunks through a channel.
+struct ChannelWriter(mpsc::Sender<Vec<u8>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(buf.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Export `polylines` to an asynchronous writer, e.g. a TCP stream.
+///
+/// The exporter runs on the blocking thread pool, and its output is written
+/// in chunks of a few kilobytes while it is produced, so it is never held in
+/// memory as a whole. If writing fails, the export is aborted.
+pub async fn export_async<E, W>(
+    exporter: E,
+    polylines: Vec<Polyline>,
+    mut w: W,
+) -> Result<(), Error>
+where
+    E: Exporter + Send + 'static,
+    W: AsyncWrite + Unpin,
+{
+    let (tx, mut rx) = mpsc::channel(BUFFERED_CHUNKS);
+    let export = task::spawn_blocking(move || {
+        let mut out = BufWriter::new(ChannelWriter(tx));
+        exporter.export(&polylines, &mut out)?;
+        out.flush()?;
+        Ok(())
+    });
+    let written = async {
+        while let Some(chunk) = rx.recv().await {
+            w.write_all(&chunk).await?;
+        }
+        w.flush().await
+    }
+    .await;
+    // Unblock the exporter if writing failed
+    drop(rx);
+    let result = export.await.map_err(|e| Error::Export(e.to_string()))?;
+    written?;
+    result
+}
+
+/// Whether [`ParseIter`] converts `svg` like [`parse_with_options`], so that
+/// the polylines can be sent while the document is parsed.
+fn is_iterable(svg: &str, options: &ParseOptions) -> bool {
+    reader::is_streamable(options)
+        && !options.strict
+        && options.viewport_clip == ViewportClip::Off
+        && options.unit == Unit::User
+        && !reader::is_data_uri(svg.as_bytes())
+}
+
+/// The polylines of `svg`, converted lazily if the options allow it. The
+/// iteration ends after the first error.
+fn polylines(
+    svg: &str,
+    options: ParseOptions,
+) -> Box<dyn Iterator<Item = Result<Polyline, Error>> + '_> {
+    if !is_iterable(svg, &options) {
+        return match parse_with_options(svg, &options) {
+            Ok(polylines) => Box::new(polylines.into_iter().map(Ok)),
+            Err(e) => Box::new(iter::once(Err(e))),
+        };
+    }
+    let limits = options.limits;
+    if let Err(e) = limits
+        .check_input_size(svg.len())
+        .and_then(|()| limits.check_use_depth(svg))
+    {
+        return Box::new(iter::once(Err(e)));
+    }
+    let mut points = 0;
+    Box::new(ParseIter::with_options(svg, options).map(move |polyline| {
+        let polyline = polyline?;
+        points += polyline.len();
+        limits.check_points(points)?;
+        Ok(polyline)
+    }))
+}
+
+/// Parse an SVG string on the blocking thread pool and receive the resulting
+/// polylines one by one.
+///
+/// If the options allow it, the polylines are sent while the document is
+/// parsed, like with [`parse_iter`](crate::parse_iter). This requires the
+/// same options as streaming with [`parse_reader`](crate::parse_reader),
+/// and additionally that `strict`, `viewport_clip` and `unit` are disabled.
+/// Otherwise, the document is parsed completely before the first polyline is
+/// sent. `buffer` is the capacity of the channel.
+///
+/// After an error, no more polylines are sent. Dropping the receiver stops
+/// the parsing.
+///
+/// This must be called from within a tokio runtime.
+pub fn parse_stream(
+    svg: String,
+    options: ParseOptions,
+    buffer: usize,
+) -> mpsc::Receiver<Result<Polyline, Error>> {
+    let (tx, rx) = mpsc::channel(buffer.max(1));
+    task::spawn_blocking(move || {
+        for polyline in polylines(&svg, options) {
+            let failed = polyline.is_err();
+            if tx.blocking_send(polyline).is_err() || failed {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::CsvExporter;
+    use crate::{Limits, Preprocess};
+
+    fn options() -> ParseOptions {
+        ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        }
+    }
+
+    async fn receive(svg: &str, options: ParseOptions) -> Vec<Result<Polyline, Error>> {
+        let mut rx = parse_stream(svg.to_string(), options, 1);
+        let mut polylines = Vec::new();
+        while let Some(polyline) = rx.recv().await {
+            polylines.push(polyline);
+        }
+        polylines
+    }
+
+    #[tokio::test]
+    async fn test_parse_stream_and_export() {
+        let svg = r#"<svg><path d="M 0,0 1,1"/><path d="M 2,2 3,3"/></svg>"#;
+        let polylines: Vec<Polyline> = receive(svg, options())
+            .await
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(polylines.len(), 2);
+
+        let mut out = Vec::new();
+        export_async(CsvExporter, polylines, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "polyline,x,y\n0,0,0\n0,1,1\n1,2,2\n1,3,3\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_async_chunks() {
+        let polylines: Vec<Polyline> = (0..2000)
+            .map(|i| Polyline::from_vec(vec![(f64::from(i), 0.0).into(), (0.0, 1.0).into()]))
+            .collect();
+        let expected = CsvExporter.export_to_string(&polylines).unwrap();
+        let mut out = Vec::new();
+        export_async(CsvExporter, polylines, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_parse_stream_error() {
+        let polylines = receive("<svg><path d=\"M 0,0 L\"/>", options()).await;
+        assert_eq!(polylines.len(), 1);
+        assert!(polylines[0].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parse_stream_lazy() {
+        let svg = r#"<svg><path d="M 0,0 1,1"/><path d="M 0,0 L"/><path d="M 2,2 3,3"/></svg>"#;
+        // The polylines before the error are sent when streaming...
+        let polylines = receive(svg, options()).await;
+        assert_eq!(polylines.len(), 2);
+        assert!(polylines[0].is_ok());
+        assert!(polylines[1].is_err());
+
+        // ...but not if the whole document is needed
+        let whole = ParseOptions {
+            unit: Unit::Millimeters,
+            ..options()
+        };
+        let polylines = receive(svg, whole).await;
+        assert_eq!(polylines.len(), 1);
+        assert!(polylines[0].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parse_stream_limits() {
+        let svg = r#"<svg><path d="M 0,0 1,1"/><path d="M 2,2 3,3"/></svg>"#;
+        let limited = ParseOptions {
+            limits: Limits {
+                max_points: Some(3),
+                ..Default::default()
+            },
+            ..options()
+        };
+        let polylines = receive(svg, limited).await;
+        assert_eq!(polylines.len(), 2);
+        assert!(matches!(polylines[1], Err(Error::Limit(_))));
+    }
+}
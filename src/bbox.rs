@@ -0,0 +1,103 @@
+//! Axis-aligned bounding boxes of points and polylines.
+
+use crate::{CoordinatePair, Polyline};
+
+/// An axis-aligned rectangle, given by its minimum and maximum coordinates.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct BoundingBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl BoundingBox {
+    pub fn new(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Self {
+        Self {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+
+    /// Return whether the point lies inside the rectangle or on its border.
+    pub fn contains(&self, p: CoordinatePair) -> bool {
+        p.x >= self.min_x && p.x <= self.max_x && p.y >= self.min_y && p.y <= self.max_y
+    }
+
+    /// Return how far the point lies outside of the rectangle along either
+    /// axis, or 0 if it is inside.
+    pub(crate) fn overshoot(&self, p: CoordinatePair) -> f64 {
+        [
+            self.min_x - p.x,
+            p.x - self.max_x,
+            self.min_y - p.y,
+            p.y - self.max_y,
+        ]
+        .iter()
+        .fold(0.0, |acc: f64, &d| acc.max(d))
+    }
+
+    /// Return the largest [`overshoot`](Self::overshoot) of any point of the
+    /// polyline.
+    pub(crate) fn polyline_overshoot(&self, polyline: &Polyline) -> f64 {
+        polyline
+            .iter()
+            .fold(0.0, |acc: f64, &p| acc.max(self.overshoot(p)))
+    }
+}
+
+impl Polyline {
+    /// Return the bounding box of the polyline, or `None` if it has no
+    /// points.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        bounding_box_of(std::slice::from_ref(self))
+    }
+}
+
+/// Return the bounding box of all points of the polylines, or `None` if
+/// there are no points, e.g. to fit a drawing onto a sheet of paper.
+pub fn bounding_box_of(polylines: &[Polyline]) -> Option<BoundingBox> {
+    polylines
+        .iter()
+        .flat_map(|polyline| polyline.iter())
+        .fold(None, |acc, p| match acc {
+            None => Some(BoundingBox::new(p.x, p.y, p.x, p.y)),
+            Some(b) => Some(BoundingBox::new(
+                b.min_x.min(p.x),
+                b.min_y.min(p.y),
+                b.max_x.max(p.x),
+                b.max_y.max(p.y),
+            )),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounding_box() {
+        let a = Polyline::from_vec(vec![(1.0, 5.0).into(), (-2.0, 3.0).into()]);
+        let b = Polyline::from_vec(vec![(4.0, -1.0).into()]);
+        assert_eq!(
+            a.bounding_box(),
+            Some(BoundingBox::new(-2.0, 3.0, 1.0, 5.0))
+        );
+        assert_eq!(
+            bounding_box_of(&[a, Polyline::new(), b]),
+            Some(BoundingBox::new(-2.0, -1.0, 4.0, 5.0))
+        );
+        assert_eq!(Polyline::new().bounding_box(), None);
+        assert_eq!(bounding_box_of(&[]), None);
+    }
+}
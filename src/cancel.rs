@@ -0,0 +1,75 @@
+//! Abort a running conversion from another thread.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::Error;
+
+/// A token to cancel a conversion, see
+/// [`ParseOptions::cancel`](crate::ParseOptions::cancel).
+///
+/// Clones share the same state, so a clone can be kept e.g. by the UI thread
+/// to cancel the conversion running in a worker thread.
+///
+/// ```
+/// use std::thread;
+///
+/// use svg2polylines::{parse_with_options, CancelToken, Error, ParseOptions, Preprocess};
+///
+/// let token = CancelToken::new();
+/// let options = ParseOptions {
+///     preprocess: Preprocess::No,
+///     cancel: Some(token.clone()),
+///     ..Default::default()
+/// };
+/// token.cancel();
+/// let worker = thread::spawn(move || {
+///     parse_with_options(r#"<svg><path d="M 0,0 10,10"/></svg>"#, &options)
+/// });
+/// assert!(matches!(worker.join().unwrap(), Err(Error::Cancelled)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the cancellation of all conversions using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Fail with [`Error::Cancelled`] if the token was cancelled.
+pub(crate) fn check(token: Option<&CancelToken>) -> Result<(), Error> {
+    if token.is_some_and(CancelToken::is_cancelled) {
+        Err(Error::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+        assert!(check(Some(&token)).is_ok());
+        clone.cancel();
+        assert!(token.is_cancelled());
+        assert!(matches!(check(Some(&token)), Err(Error::Cancelled)));
+        assert!(check(None).is_ok());
+    }
+}
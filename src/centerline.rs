@@ -0,0 +1,143 @@
+//! Replace thin outlined strokes by single center lines.
+
+use crate::{CoordinatePair, Polyline};
+
+/// Options for [`centerlines`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CenterlineOptions {
+    /// Closed polylines with an average width up to this value are replaced
+    /// by their center line.
+    pub max_width: f64,
+
+    /// Maximum distance between two points of the center line.
+    pub spacing: f64,
+}
+
+impl Default for CenterlineOptions {
+    fn default() -> Self {
+        Self {
+            max_width: 1.0,
+            spacing: 0.5,
+        }
+    }
+}
+
+fn distance(a: CoordinatePair, b: CoordinatePair) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+fn length(points: &[CoordinatePair]) -> f64 {
+    points.windows(2).map(|w| distance(w[0], w[1])).sum()
+}
+
+/// Return the average width of a closed contour, estimated as
+/// `2 * area / perimeter`. This is exact for long, thin strips.
+fn average_width(points: &[CoordinatePair]) -> f64 {
+    let perimeter = length(points);
+    if perimeter == 0.0 {
+        return 0.0;
+    }
+    let doubled_area: f64 = points
+        .windows(2)
+        .map(|w| w[0].x * w[1].y - w[1].x * w[0].y)
+        .sum();
+    doubled_area.abs() / perimeter
+}
+
+/// Return the center line of a thin closed contour.
+///
+/// The contour is split at its two points that are farthest apart (the ends
+/// of the stroke) and the two resulting sides are averaged.
+fn centerline(points: &[CoordinatePair], spacing: f64) -> Polyline {
+    // Without the closing point
+    let contour = &points[..points.len() - 1];
+    let mut ends = (0, 0);
+    let mut max_distance = 0.0;
+    for (i, &a) in contour.iter().enumerate() {
+        for (j, &b) in contour.iter().enumerate().skip(i + 1) {
+            let d = distance(a, b);
+            if d > max_distance {
+                max_distance = d;
+                ends = (i, j);
+            }
+        }
+    }
+    let (start, end) = ends;
+    let side_a = Polyline::from_vec(contour[start..=end].to_vec());
+    let mut side_b: Vec<CoordinatePair> = contour[end..].to_vec();
+    side_b.extend_from_slice(&contour[..=start]);
+    let side_b = Polyline::from_vec(side_b).reversed();
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let n = ((length(&side_a).max(length(&side_b)) / spacing).ceil() as usize).max(1) + 1;
+    let (side_a, side_b) = (side_a.resample(n), side_b.resample(n));
+    Polyline::from_vec(
+        side_a
+            .iter()
+            .zip(side_b.iter())
+            .map(|(a, b)| CoordinatePair::new(a.x + (b.x - a.x) * 0.5, a.y + (b.y - a.y) * 0.5))
+            .collect(),
+    )
+}
+
+/// Replace thin closed outlines, as found in fonts and traced artwork, by
+/// single-stroke center lines, so that they are not drawn twice.
+///
+/// A closed polyline is considered thin if its average width is at most
+/// [`max_width`](CenterlineOptions::max_width). All other polylines are
+/// returned unchanged. Branching shapes (e.g. the letter "T") are not
+/// supported and result in a single, approximate line.
+pub fn centerlines(polylines: &[Polyline], options: &CenterlineOptions) -> Vec<Polyline> {
+    polylines
+        .iter()
+        .map(|polyline| {
+            if polyline.is_closed()
+                && polyline.len() >= 4
+                && average_width(polyline) <= options.max_width
+            {
+                centerline(polyline, options.spacing.max(f64::EPSILON))
+            } else {
+                polyline.clone()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::line;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_centerlines() {
+        let options = CenterlineOptions {
+            max_width: 1.0,
+            spacing: 5.0,
+        };
+        // A 10 x 0.5 rectangle, the same rectangle without closing it, and a
+        // 10 x 10 square
+        let mut polylines = vec![
+            line(&[(0.0, 0.0), (10.0, 0.0), (10.0, 0.5), (0.0, 0.5)]),
+            line(&[(0.0, 0.0), (10.0, 0.0), (10.0, 0.5), (0.0, 0.5), (0.0, 0.0)]),
+            line(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]),
+        ];
+        polylines[0].close();
+        polylines[2].close();
+        let result = centerlines(&polylines, &options);
+        assert_eq!(result[1], polylines[1]);
+        assert_eq!(result[2], polylines[2]);
+
+        // The center line of the rectangle runs along y = 0.25, the ends lie
+        // on the short edges
+        let center = &result[0];
+        assert_eq!(center.len(), 4);
+        assert!(center
+            .iter()
+            .skip(1)
+            .take(2)
+            .all(|p| (p.y - 0.25).abs() < 1e-9));
+        assert_eq!(center[0].x, 0.0);
+        assert_eq!(center[3].x, 10.0);
+    }
+}
@@ -0,0 +1,465 @@
+//! Clip polylines against a region.
+
+use crate::{flatten::rings_contain, BoundingBox, CoordinatePair, FillRule, Polyline, Warning};
+
+/// What to do with geometry outside the document viewport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewportClip {
+    /// Keep all geometry.
+    #[default]
+    Off,
+
+    /// Cut polylines at the viewport border and discard the parts outside.
+    Clip,
+
+    /// Discard polylines that lie completely outside of the viewport, but
+    /// keep polylines that are at least partially visible unchanged.
+    Drop,
+}
+
+/// Clip the segment from `a` to `b` against `rect` (Liang–Barsky).
+///
+/// Return the parameters `(t0, t1)` of the visible part, or `None` if the
+/// segment is completely outside.
+fn clip_segment(a: CoordinatePair, b: CoordinatePair, rect: &BoundingBox) -> Option<(f64, f64)> {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    let mut t0: f64 = 0.0;
+    let mut t1: f64 = 1.0;
+    for (p, q) in [
+        (-dx, a.x - rect.min_x),
+        (dx, rect.max_x - a.x),
+        (-dy, a.y - rect.min_y),
+        (dy, rect.max_y - a.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let r = q / p;
+            if p < 0.0 {
+                t0 = t0.max(r);
+            } else {
+                t1 = t1.min(r);
+            }
+        }
+    }
+    if t0 > t1 {
+        None
+    } else {
+        Some((t0, t1))
+    }
+}
+
+fn lerp(a: CoordinatePair, b: CoordinatePair, t: f64) -> CoordinatePair {
+    CoordinatePair::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+/// Clip a single polyline against `rect`, appending the visible pieces to
+/// `out`.
+fn clip_polyline(polyline: &Polyline, rect: &BoundingBox, out: &mut Vec<Polyline>) {
+    if polyline.len() == 1 {
+        if rect.contains(polyline[0]) {
+            out.push(polyline.clone());
+        }
+        return;
+    }
+
+    let mut current: Vec<CoordinatePair> = Vec::new();
+    let mut cut = false;
+    for segment in polyline.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let clipped = clip_segment(a, b, rect);
+        cut |= !clipped.is_some_and(|(t0, t1)| t0 <= 0.0 && t1 >= 1.0);
+        if let Some((t0, t1)) = clipped {
+            if t0 > 0.0 && current.len() > 1 {
+                out.push(Polyline::from_vec(std::mem::take(&mut current)));
+            }
+            if t0 > 0.0 || current.is_empty() {
+                current.clear();
+                current.push(lerp(a, b, t0));
+            }
+            current.push(lerp(a, b, t1));
+            if t1 < 1.0 {
+                out.push(Polyline::from_vec(std::mem::take(&mut current)));
+            }
+        } else {
+            if current.len() > 1 {
+                out.push(Polyline::from_vec(std::mem::take(&mut current)));
+            }
+            current.clear();
+        }
+    }
+    if current.len() > 1 {
+        out.push(piece(polyline, current, cut));
+    }
+}
+
+/// Create the polyline of the last clipped piece of `polyline`, which stays
+/// closed if nothing was `cut` off, i.e. the piece is the whole polyline.
+fn piece(polyline: &Polyline, points: Vec<CoordinatePair>, cut: bool) -> Polyline {
+    let mut piece = Polyline::from_vec(points);
+    if !cut && polyline.is_closed() {
+        piece.close();
+    }
+    piece
+}
+
+/// Apply the [`ViewportClip`] mode to a set of polylines.
+pub(crate) fn clip_to_viewport(
+    polylines: Vec<Polyline>,
+    viewport: &BoundingBox,
+    mode: ViewportClip,
+) -> Vec<Polyline> {
+    match mode {
+        ViewportClip::Off => polylines,
+        ViewportClip::Clip => {
+            let mut out = Vec::with_capacity(polylines.len());
+            for polyline in &polylines {
+                clip_polyline(polyline, viewport, &mut out);
+            }
+            out
+        }
+        ViewportClip::Drop => polylines
+            .into_iter()
+            .filter(|polyline| {
+                if polyline.len() == 1 {
+                    return viewport.contains(polyline[0]);
+                }
+                polyline
+                    .windows(2)
+                    .any(|s| clip_segment(s[0], s[1], viewport).is_some())
+            })
+            .collect(),
+    }
+}
+
+/// Which part of the geometry [`clip_to_polygon`] keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskMode {
+    /// Keep the parts inside of the polygon.
+    KeepInside,
+
+    /// Keep the parts outside of the polygon, e.g. to exclude a keep-out zone.
+    KeepOutside,
+}
+
+/// Return whether `p` lies inside the region bounded by the rings (even-odd
+/// rule).
+pub(crate) fn point_in_rings(p: CoordinatePair, rings: &[&[CoordinatePair]]) -> bool {
+    rings_contain(rings.iter().copied(), p, FillRule::EvenOdd)
+}
+
+/// Return the parameters at which the segment from `a` to `b` crosses the
+/// edges of the rings, in ascending order.
+fn ring_crossings(a: CoordinatePair, b: CoordinatePair, rings: &[&[CoordinatePair]]) -> Vec<f64> {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let mut crossings = Vec::new();
+    for polygon in rings {
+        for (i, edge_start) in polygon.iter().enumerate() {
+            let edge_end = polygon[(i + 1) % polygon.len()];
+            let (ex, ey) = (edge_end.x - edge_start.x, edge_end.y - edge_start.y);
+            let denominator = dx * ey - dy * ex;
+            if denominator == 0.0 {
+                continue;
+            }
+            // Parameters along the segment and along the edge
+            let (ox, oy) = (edge_start.x - a.x, edge_start.y - a.y);
+            let t = (ox * ey - oy * ex) / denominator;
+            let u = (ox * dy - oy * dx) / denominator;
+            if t > 0.0 && t < 1.0 && (0.0..=1.0).contains(&u) {
+                crossings.push(t);
+            }
+        }
+    }
+    crossings.sort_by(f64::total_cmp);
+    crossings
+}
+
+/// Clip polylines against a region bounded by closed rings, e.g. a shape
+/// with holes.
+///
+/// The rings are closed implicitly and combined with the even-odd rule.
+/// Rings with less than three points are ignored.
+pub(crate) fn clip_to_rings(
+    polylines: &[Polyline],
+    rings: &[&[CoordinatePair]],
+    mode: MaskMode,
+) -> Vec<Polyline> {
+    let rings: Vec<&[CoordinatePair]> = rings
+        .iter()
+        .copied()
+        .filter(|ring| ring.len() >= 3)
+        .collect();
+    clip_by(polylines, &rings, |p| {
+        point_in_rings(p, &rings) == (mode == MaskMode::KeepInside)
+    })
+}
+
+/// Clip polylines against a stack of shapes, each bounded by closed rings
+/// like in [`clip_to_rings`] and marked as inside or outside of the region.
+///
+/// The topmost shape containing a point decides whether the point is kept.
+/// The union of several shapes is the region of a `<clipPath>`, while the
+/// dark shapes of a `<mask>` cut holes into the bright shapes below.
+pub(crate) fn clip_to_shapes(
+    polylines: &[Polyline],
+    shapes: &[(Vec<Polyline>, bool)],
+) -> Vec<Polyline> {
+    let shapes: Vec<(Vec<&[CoordinatePair]>, bool)> = shapes
+        .iter()
+        .map(|(rings, inside)| {
+            let rings = rings
+                .iter()
+                .map(|ring| ring.as_slice())
+                .filter(|ring| ring.len() >= 3)
+                .collect();
+            (rings, *inside)
+        })
+        .collect();
+    let rings: Vec<&[CoordinatePair]> = shapes
+        .iter()
+        .flat_map(|(rings, _)| rings.iter().copied())
+        .collect();
+    clip_by(polylines, &rings, |p| {
+        shapes
+            .iter()
+            .rev()
+            .find(|(rings, _)| point_in_rings(p, rings))
+            .is_some_and(|&(_, inside)| inside)
+    })
+}
+
+/// Split polylines where they cross the rings and keep the pieces whose
+/// midpoint satisfies `keep`.
+fn clip_by(
+    polylines: &[Polyline],
+    rings: &[&[CoordinatePair]],
+    keep: impl Fn(CoordinatePair) -> bool,
+) -> Vec<Polyline> {
+    let mut out = Vec::new();
+    for polyline in polylines {
+        if polyline.len() == 1 {
+            if keep(polyline[0]) {
+                out.push(polyline.clone());
+            }
+            continue;
+        }
+        let mut current: Vec<CoordinatePair> = Vec::new();
+        let mut cut = false;
+        for segment in polyline.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            let mut ts = vec![0.0];
+            ts.extend(ring_crossings(a, b, rings));
+            ts.push(1.0);
+            for piece in ts.windows(2) {
+                let (t0, t1) = (piece[0], piece[1]);
+                if keep(lerp(a, b, t0 + (t1 - t0) * 0.5)) {
+                    if current.is_empty() {
+                        current.push(lerp(a, b, t0));
+                    }
+                    current.push(lerp(a, b, t1));
+                    continue;
+                }
+                cut = true;
+                if current.len() > 1 {
+                    out.push(Polyline::from_vec(std::mem::take(&mut current)));
+                } else {
+                    current.clear();
+                }
+            }
+        }
+        if current.len() > 1 {
+            out.push(piece(polyline, current, cut));
+        }
+    }
+    out
+}
+
+/// Clip polylines against an arbitrary closed polygon.
+///
+/// The polygon is closed implicitly and may be concave or self-intersecting
+/// (the even-odd rule applies). Polylines are split where they cross the
+/// polygon border. This is independent of any `clipPath` in the document and
+/// can e.g. restrict a plot to a circular sticker.
+pub fn clip_to_polygon(
+    polylines: &[Polyline],
+    polygon: &[CoordinatePair],
+    mode: MaskMode,
+) -> Vec<Polyline> {
+    clip_to_rings(polylines, &[polygon], mode)
+}
+
+/// Return a warning for every polyline that extends beyond the viewport.
+pub(crate) fn out_of_bounds<'a>(
+    polylines: impl IntoIterator<Item = &'a Polyline>,
+    viewport: &BoundingBox,
+) -> Vec<Warning> {
+    polylines
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, polyline)| {
+            let overshoot = viewport.polyline_overshoot(polyline);
+            if overshoot > 0.0 {
+                Some(Warning::OutOfBounds { index, overshoot })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::line;
+
+    #[test]
+    fn test_clip_polyline() {
+        let rect = BoundingBox::new(0.0, 0.0, 10.0, 10.0);
+        // Leaves the viewport and enters again
+        let polyline = line(&[(5.0, 5.0), (15.0, 5.0), (15.0, 8.0), (5.0, 8.0)]);
+        let clipped = clip_to_viewport(vec![polyline], &rect, ViewportClip::Clip);
+        assert_eq!(
+            clipped,
+            vec![
+                line(&[(5.0, 5.0), (10.0, 5.0)]),
+                line(&[(10.0, 8.0), (5.0, 8.0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clip_closed() {
+        let rect = BoundingBox::new(0.0, 0.0, 10.0, 10.0);
+        let mut inside = line(&[(1.0, 1.0), (2.0, 1.0), (2.0, 2.0)]);
+        inside.close();
+        let mut crossing = line(&[(5.0, 5.0), (15.0, 5.0), (5.0, 8.0)]);
+        crossing.close();
+        let clipped = clip_to_viewport(vec![inside, crossing], &rect, ViewportClip::Clip);
+        let closed: Vec<bool> = clipped.iter().map(Polyline::is_closed).collect();
+        assert_eq!(closed, vec![true, false, false]);
+    }
+
+    #[test]
+    fn test_clip_crossing_segment() {
+        let rect = BoundingBox::new(0.0, 0.0, 10.0, 10.0);
+        let polyline = line(&[(-5.0, 5.0), (15.0, 5.0)]);
+        let clipped = clip_to_viewport(vec![polyline], &rect, ViewportClip::Clip);
+        assert_eq!(clipped, vec![line(&[(0.0, 5.0), (10.0, 5.0)])]);
+    }
+
+    #[test]
+    fn test_drop() {
+        let rect = BoundingBox::new(0.0, 0.0, 10.0, 10.0);
+        let inside = line(&[(5.0, 5.0), (15.0, 5.0)]);
+        let outside = line(&[(20.0, 20.0), (30.0, 20.0)]);
+        let result = clip_to_viewport(vec![inside, outside], &rect, ViewportClip::Drop);
+        assert_eq!(result, vec![line(&[(5.0, 5.0), (15.0, 5.0)])]);
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let rect = BoundingBox::new(0.0, 0.0, 10.0, 10.0);
+        let polylines = vec![
+            line(&[(1.0, 1.0), (9.0, 9.0)]),
+            line(&[(5.0, 5.0), (5.0, 12.5), (-1.0, 5.0)]),
+        ];
+        assert_eq!(
+            out_of_bounds(&polylines, &rect),
+            vec![Warning::OutOfBounds {
+                index: 1,
+                overshoot: 2.5
+            }]
+        );
+    }
+
+    #[test]
+    fn test_clip_to_polygon() {
+        // A triangle
+        let polygon: Vec<CoordinatePair> =
+            vec![(0.0, 0.0).into(), (10.0, 0.0).into(), (0.0, 10.0).into()];
+        let polylines = vec![line(&[(-5.0, 2.0), (15.0, 2.0)]), line(&[(20.0, 20.0)])];
+        assert_eq!(
+            clip_to_polygon(&polylines, &polygon, MaskMode::KeepInside),
+            vec![line(&[(0.0, 2.0), (8.0, 2.0)])]
+        );
+        assert_eq!(
+            clip_to_polygon(&polylines, &polygon, MaskMode::KeepOutside),
+            vec![
+                line(&[(-5.0, 2.0), (0.0, 2.0)]),
+                line(&[(8.0, 2.0), (15.0, 2.0)]),
+                line(&[(20.0, 20.0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clip_to_polygon_closed() {
+        // A U shape with a notch from above, which cuts a piece off the top
+        // edge of the square, so that the rest must not be closed across
+        // the notch
+        let polygon: Vec<CoordinatePair> = vec![
+            (0.0, 0.0).into(),
+            (4.0, 0.0).into(),
+            (4.0, 5.0).into(),
+            (6.0, 5.0).into(),
+            (6.0, 0.0).into(),
+            (10.0, 0.0).into(),
+            (10.0, 10.0).into(),
+            (0.0, 10.0).into(),
+        ];
+        let mut square = line(&[(1.0, 2.0), (9.0, 2.0), (9.0, 8.0), (1.0, 8.0)]);
+        square.close();
+        let clipped = clip_to_polygon(&[square], &polygon, MaskMode::KeepInside);
+        assert_eq!(
+            clipped,
+            vec![
+                line(&[(1.0, 2.0), (4.0, 2.0)]),
+                line(&[(6.0, 2.0), (9.0, 2.0), (9.0, 8.0), (1.0, 8.0), (1.0, 2.0)]),
+            ]
+        );
+        assert!(!clipped[1].is_closed());
+
+        // Unchanged if the square lies completely inside
+        let mut square = line(&[(1.0, 6.0), (9.0, 6.0), (9.0, 8.0), (1.0, 8.0)]);
+        square.close();
+        let clipped = clip_to_polygon(&[square], &polygon, MaskMode::KeepInside);
+        assert!(clipped[0].is_closed());
+        assert_eq!(clipped[0].len(), 5);
+    }
+
+    #[test]
+    fn test_clip_to_shapes() {
+        // Two overlapping squares, the second one with a hole
+        let mut shapes = vec![
+            (
+                vec![line(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)])],
+                true,
+            ),
+            (
+                vec![
+                    line(&[(2.0, 0.0), (10.0, 0.0), (10.0, 4.0), (2.0, 4.0)]),
+                    line(&[(6.0, 1.0), (8.0, 1.0), (8.0, 3.0), (6.0, 3.0)]),
+                ],
+                true,
+            ),
+        ];
+        let polylines = vec![line(&[(-1.0, 2.0), (11.0, 2.0)])];
+        assert_eq!(
+            clip_to_shapes(&polylines, &shapes),
+            vec![
+                line(&[(0.0, 2.0), (2.0, 2.0), (4.0, 2.0), (6.0, 2.0)]),
+                line(&[(8.0, 2.0), (10.0, 2.0)]),
+            ]
+        );
+
+        // The second square on top cuts into the first one
+        shapes[1].1 = false;
+        assert_eq!(
+            clip_to_shapes(&polylines, &shapes),
+            vec![line(&[(0.0, 2.0), (2.0, 2.0)])]
+        );
+    }
+}
@@ -0,0 +1,421 @@
+//! Clip polylines by the `<clipPath>` and `<mask>` elements they reference.
+
+use std::{collections::HashMap, str::FromStr};
+
+use log::trace;
+use lyon_geom::euclid::Transform2D;
+use quick_xml::events::{BytesStart, Event};
+use svgtypes::Color;
+
+use crate::{
+    bounding_box_of,
+    clip::clip_to_shapes,
+    css::StyleSheet,
+    parse_path, shape, style,
+    transform::{ComposedTransform, TransformStack},
+    BoundingBox, Error, ParseOptions, Polyline,
+};
+
+/// The region of a `<clipPath>` element, or of a `<mask>` element
+/// approximated as a clip.
+#[derive(Debug, Default)]
+pub(crate) struct ClipPath {
+    /// The rings of every child shape in painting order, in the coordinate
+    /// system of the content, and whether the shape is part of the region.
+    shapes: Vec<(Vec<Polyline>, bool)>,
+
+    /// Whether the coordinates are relative to the bounding box of the
+    /// referencing element (`clipPathUnits="objectBoundingBox"` or
+    /// `maskContentUnits="objectBoundingBox"`).
+    bounding_box_units: bool,
+}
+
+/// The paint of an element inside of a `<mask>`.
+#[derive(Debug, Clone, Copy)]
+struct MaskPaint {
+    /// The luminance of the fill color multiplied with its alpha, or `None`
+    /// if the element is not filled.
+    fill: Option<f64>,
+
+    fill_opacity: f64,
+
+    /// The opacity of the element multiplied with the opacities of its
+    /// ancestors inside of the mask.
+    opacity: f64,
+}
+
+impl Default for MaskPaint {
+    fn default() -> Self {
+        Self {
+            fill: Some(0.0),
+            fill_opacity: 1.0,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl MaskPaint {
+    /// Apply the fill and opacity properties of an element to the paint
+    /// inherited from its parent. With `alpha`, only the alpha channel of
+    /// colors counts (`mask-type="alpha"`).
+    fn inherit(self, e: &BytesStart, sheet: &StyleSheet, alpha: bool) -> Self {
+        let number = |name| {
+            style::property(e, name, sheet)
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .map(|value| value.clamp(0.0, 1.0))
+        };
+        let fill = match style::property(e, "fill", sheet) {
+            None => self.fill,
+            Some(value) if value.trim() == "none" => None,
+            Some(value) => Some(match Color::from_str(&value) {
+                Ok(color) => {
+                    let luminance = if alpha {
+                        1.0
+                    } else {
+                        (0.2125 * f64::from(color.red)
+                            + 0.7154 * f64::from(color.green)
+                            + 0.0721 * f64::from(color.blue))
+                            / 255.0
+                    };
+                    luminance * f64::from(color.alpha) / 255.0
+                }
+                // Gradients and patterns are approximated as white
+                Err(_) => 1.0,
+            }),
+        };
+        Self {
+            fill,
+            fill_opacity: number("fill-opacity").unwrap_or(self.fill_opacity),
+            opacity: self.opacity * number("opacity").unwrap_or(1.0),
+        }
+    }
+
+    /// Return the value of the mask inside of the element, or `None` if the
+    /// element is not painted.
+    fn value(self) -> Option<f64> {
+        Some(self.fill? * self.fill_opacity * self.opacity)
+    }
+}
+
+/// A reference to a clip path from an element or one of its ancestors.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ClipReference {
+    id: String,
+
+    /// The transform of the referencing element, composed with the
+    /// transforms of its ancestors.
+    transform: Transform2D<f64, f64, f64>,
+
+    /// A number identifying the referencing element.
+    element: usize,
+}
+
+impl ClipReference {
+    /// Return the reference of the `clip-path` or `mask` property, e.g.
+    /// `url(#clip)`.
+    pub(crate) fn from_property(
+        value: &str,
+        transform: Transform2D<f64, f64, f64>,
+        element: usize,
+    ) -> Option<Self> {
+        Some(Self {
+            id: style::reference(value)?,
+            transform,
+            element,
+        })
+    }
+}
+
+/// The clip paths and masks of an element and its ancestors.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClipState {
+    /// The clip paths to apply, outermost first.
+    pub(crate) references: Vec<ClipReference>,
+
+    /// Whether the element is part of a `<clipPath>` or `<mask>` and thus
+    /// not rendered.
+    pub(crate) hidden: bool,
+}
+
+impl ClipState {
+    /// Add the `clip-path` and `mask` properties of an element to the clip
+    /// paths inherited from its parent, as far as enabled in `options`.
+    pub(crate) fn inherit(
+        &self,
+        e: &BytesStart,
+        sheet: &StyleSheet,
+        transform: &ComposedTransform,
+        element: usize,
+        options: &ParseOptions,
+    ) -> Self {
+        let mut state = self.clone();
+        let kinds = [
+            ("clip-path", b"clipPath" as &[u8], options.clip_paths),
+            ("mask", b"mask", options.mask_threshold.is_some()),
+        ];
+        for &(property, name, _) in kinds.iter().filter(|&&(_, _, enabled)| enabled) {
+            if let Some(reference) = style::property(e, property, sheet).and_then(|value| {
+                ClipReference::from_property(&value, transform.transform, element)
+            }) {
+                state.references.push(reference);
+            }
+            state.hidden |= e.name() == name;
+        }
+        state
+    }
+}
+
+fn transformed(polyline: &Polyline, t: Transform2D<f64, f64, f64>) -> Polyline {
+    polyline.clone().transform(t)
+}
+
+/// Collect the `<clipPath>` and `<mask>` elements of a document by their
+/// id, as far as enabled in `options`.
+///
+/// Masks are approximated as clips: The shapes whose mask value (luminance
+/// times opacity) reaches `options.mask_threshold` are part of the region,
+/// darker shapes cut holes into the shapes below. The mask region given by
+/// `x`, `y`, `width` and `height` is ignored. Nested `clip-path` and `mask`
+/// references inside of clip paths are ignored.
+pub(crate) fn collect(
+    svg: &str,
+    options: &ParseOptions,
+) -> Result<HashMap<String, ClipPath>, Error> {
+    let mut reader = quick_xml::Reader::from_str(svg);
+    reader.trim_text(true);
+
+    let sheet = if options.mask_threshold.is_some() {
+        StyleSheet::from_svg(svg)
+    } else {
+        StyleSheet::default()
+    };
+    let mut clip_paths = HashMap::new();
+    // The id and contents of the current clip path or mask, with the
+    // transforms and paints of the open elements inside of it
+    let mut current: Option<(String, ClipPath)> = None;
+    let mut transforms = TransformStack::default();
+    let mut paints: Vec<MaskPaint> = Vec::new();
+    // The mask threshold, or `None` inside of a clip path
+    let mut threshold = None;
+    let mut alpha = false;
+    let mut depth = 0;
+    let mut buf = Vec::new();
+    loop {
+        let (e, is_start): (BytesStart, bool) = match reader.read_event(&mut buf) {
+            Ok(Event::Start(e)) => (e, true),
+            Ok(Event::Empty(e)) => (e, false),
+            Ok(Event::End(_)) if current.is_some() => {
+                transforms.pop();
+                paints.pop();
+                depth -= 1;
+                if depth == 0 {
+                    if let Some((id, clip_path)) = current.take() {
+                        trace!("clip_path::collect: Found clip path \"{id}\"");
+                        clip_paths.insert(id, clip_path);
+                    }
+                }
+                buf.clear();
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {
+                buf.clear();
+                continue;
+            }
+            Err(e) => return Err(Error::SvgParse(e.to_string())),
+        };
+        let units = match e.name() {
+            b"clipPath" if options.clip_paths => Some(b"clipPathUnits" as &[u8]),
+            b"mask" if options.mask_threshold.is_some() => Some(b"maskContentUnits" as &[u8]),
+            _ => None,
+        };
+        if let (None, Some(units)) = (&current, units) {
+            if let Some(id) = style::attribute(&e, b"id") {
+                let clip_path = ClipPath {
+                    shapes: Vec::new(),
+                    bounding_box_units: style::attribute(&e, units).as_deref()
+                        == Some("objectBoundingBox"),
+                };
+                if is_start {
+                    current = Some((id, clip_path));
+                    transforms = TransformStack::default();
+                    paints.clear();
+                    threshold = options.mask_threshold.filter(|_| e.name() == b"mask");
+                    alpha = style::property(&e, "mask-type", &sheet).as_deref() == Some("alpha");
+                } else {
+                    // An empty clip path or mask hides the element completely
+                    clip_paths.insert(id, clip_path);
+                }
+            }
+        }
+        if let Some((_, clip_path)) = &mut current {
+            let composed = transforms.compose(&e);
+            let paint = match threshold {
+                Some(_) => paints
+                    .last()
+                    .copied()
+                    .unwrap_or_default()
+                    .inherit(&e, &sheet, alpha),
+                None => MaskPaint::default(),
+            };
+            // Whether the shape is part of the region, `None` if unpainted
+            let inside = match threshold {
+                Some(threshold) => paint.value().map(|value| value >= threshold),
+                None => Some(true),
+            };
+            if let (Some(data), Some(inside)) = (shape::path_data(&e), inside) {
+                if let Some(t) = composed.resolve(options.unsupported, &mut Vec::new())? {
+                    let rings = parse_path(
+                        &data,
+                        options.tolerance,
+                        options.unsupported,
+                        &mut Vec::new(),
+                    )?;
+                    let rings = rings.into_iter().map(|ring| ring.transform(t)).collect();
+                    clip_path.shapes.push((rings, inside));
+                }
+            }
+            if is_start {
+                transforms.push(composed);
+                paints.push(paint);
+                depth += 1;
+            }
+        }
+        buf.clear();
+    }
+    Ok(clip_paths)
+}
+
+/// Clip the polylines of every element by the clip paths it references and
+/// return the polylines of every element in order.
+///
+/// References to missing clip paths are ignored, like in browsers.
+pub(crate) fn apply(
+    elements: Vec<(Vec<Polyline>, Vec<ClipReference>)>,
+    clip_paths: &HashMap<String, ClipPath>,
+) -> Vec<Vec<Polyline>> {
+    let uses_bounding_box = |reference: &ClipReference| {
+        clip_paths
+            .get(&reference.id)
+            .is_some_and(|clip_path| clip_path.bounding_box_units)
+    };
+
+    // The bounding boxes of the referencing elements in their own coordinate
+    // system, for clip paths in `objectBoundingBox` units
+    let mut bounds: HashMap<usize, Vec<Polyline>> = HashMap::new();
+    for (polylines, references) in &elements {
+        for reference in references.iter().filter(|r| uses_bounding_box(r)) {
+            let Some(inverse) = reference.transform.inverse() else {
+                continue;
+            };
+            let local = bounds.entry(reference.element).or_default();
+            local.extend(polylines.iter().map(|p| transformed(p, inverse)));
+        }
+    }
+    let bounds: HashMap<usize, BoundingBox> = bounds
+        .into_iter()
+        .filter_map(|(element, polylines)| Some((element, bounding_box_of(&polylines)?)))
+        .collect();
+
+    let mut result = Vec::new();
+    for (mut polylines, references) in elements {
+        for reference in &references {
+            let Some(clip_path) = clip_paths.get(&reference.id) else {
+                trace!(
+                    "clip_path::apply: Ignore missing clip path \"{}\"",
+                    reference.id
+                );
+                continue;
+            };
+            let mut t = reference.transform;
+            if clip_path.bounding_box_units {
+                let Some(b) = bounds.get(&reference.element) else {
+                    polylines.clear();
+                    break;
+                };
+                t = Transform2D::new(b.width(), 0.0, 0.0, b.height(), b.min_x, b.min_y).then(&t);
+            }
+            let shapes: Vec<(Vec<Polyline>, bool)> = clip_path
+                .shapes
+                .iter()
+                .map(|(rings, inside)| {
+                    let rings = rings.iter().map(|ring| transformed(ring, t)).collect();
+                    (rings, *inside)
+                })
+                .collect();
+            polylines = clip_to_shapes(&polylines, &shapes);
+        }
+        result.push(polylines);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_property() {
+        let t = Transform2D::identity();
+        assert_eq!(
+            ClipReference::from_property(" url( '#clip' )", t, 1),
+            Some(ClipReference {
+                id: "clip".into(),
+                transform: t,
+                element: 1,
+            })
+        );
+        assert_eq!(ClipReference::from_property("none", t, 1), None);
+    }
+
+    #[test]
+    fn test_collect() {
+        let svg = r#"<svg>
+            <clipPath id="a" transform="translate(10)">
+                <rect width="1" height="1"/>
+                <g transform="scale(2)"><path d="M 0,0 L 1,0 L 1,1 Z"/></g>
+            </clipPath>
+            <clipPath id="b" clipPathUnits="objectBoundingBox"/>
+            <path d="M 0,0 L 5,5"/>
+        </svg>"#;
+        let options = ParseOptions {
+            clip_paths: true,
+            ..Default::default()
+        };
+        let clip_paths = collect(svg, &options).unwrap();
+        assert_eq!(clip_paths.len(), 2);
+        let a = &clip_paths["a"];
+        assert_eq!(a.shapes.len(), 2);
+        assert_eq!(a.shapes[1].0[0][2], (12.0, 2.0).into());
+        assert!(!a.bounding_box_units);
+        assert!(clip_paths["b"].bounding_box_units);
+    }
+
+    #[test]
+    fn test_collect_masks() {
+        let svg = r##"<svg>
+            <clipPath id="clip"><rect width="1" height="1"/></clipPath>
+            <mask id="mask" style="fill: white">
+                <rect width="10" height="10"/>
+                <g opacity="0.5">
+                    <rect width="1" height="1"/>
+                    <rect width="1" height="1" opacity="0.5"/>
+                </g>
+                <rect width="1" height="1" fill="#000"/>
+                <rect width="1" height="1" fill="none" stroke="white"/>
+            </mask>
+            <mask id="alpha" mask-type="alpha">
+                <rect width="1" height="1" fill="#000"/>
+            </mask>
+        </svg>"##;
+        let options = ParseOptions {
+            mask_threshold: Some(0.5),
+            ..Default::default()
+        };
+        let clip_paths = collect(svg, &options).unwrap();
+        assert_eq!(clip_paths.len(), 2);
+        let inside: Vec<bool> = clip_paths["mask"].shapes.iter().map(|s| s.1).collect();
+        assert_eq!(inside, vec![true, true, false, false]);
+        assert!(clip_paths["alpha"].shapes[0].1);
+    }
+}
@@ -0,0 +1,278 @@
+//! A minimal CSS implementation for the rules in `<style>` elements.
+//!
+//! Only simple selectors are supported: a type selector (or `*`), an id and
+//! any number of classes, e.g. `path.cut`, `#outline` or `.a.b`. Rules with
+//! combinators, pseudo-classes or attribute selectors are ignored, as are
+//! at-rules like `@media`.
+
+use log::trace;
+use quick_xml::events::{BytesStart, Event};
+
+use crate::{style, Error};
+
+/// A simple selector like `path#id.class`.
+#[derive(Debug, Default, PartialEq)]
+struct Selector {
+    name: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+}
+
+impl Selector {
+    /// Parse a simple selector, return `None` if it's not supported.
+    fn parse(selector: &str) -> Option<Self> {
+        let is_ident = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+        let mut result = Self::default();
+        let mut rest = selector.strip_prefix('*').unwrap_or(selector);
+        let name_len = rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len());
+        if name_len > 0 {
+            result.name = Some(rest[..name_len].to_string());
+            rest = &rest[name_len..];
+        }
+        while let Some(prefix) = rest.chars().next() {
+            let ident = &rest[prefix.len_utf8()..];
+            let len = ident.find(|c: char| !is_ident(c)).unwrap_or(ident.len());
+            if len == 0 {
+                return None;
+            }
+            match prefix {
+                '.' => result.classes.push(ident[..len].to_string()),
+                '#' => result.id = Some(ident[..len].to_string()),
+                _ => return None,
+            }
+            rest = &ident[len..];
+        }
+        Some(result)
+    }
+
+    /// Return the specificity as `(ids, classes, types)`.
+    fn specificity(&self) -> (usize, usize, usize) {
+        (
+            usize::from(self.id.is_some()),
+            self.classes.len(),
+            usize::from(self.name.is_some()),
+        )
+    }
+
+    fn matches(&self, e: &BytesStart) -> bool {
+        if let Some(name) = &self.name {
+            if e.name() != name.as_bytes() {
+                return false;
+            }
+        }
+        if self.id.is_some() && style::attribute(e, b"id") != self.id {
+            return false;
+        }
+        if self.classes.is_empty() {
+            return true;
+        }
+        let classes = style::attribute(e, b"class").unwrap_or_default();
+        self.classes
+            .iter()
+            .all(|class| classes.split_whitespace().any(|c| c == class))
+    }
+}
+
+/// A comma separated list of simple selectors, e.g. `#outline, path.cut`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct SelectorList(Vec<Selector>);
+
+impl SelectorList {
+    pub(crate) fn parse(list: &str) -> Result<Self, Error> {
+        list.split(',')
+            .map(|selector| {
+                let selector = selector.trim();
+                if selector.is_empty() {
+                    return Err(Error::Selector(format!("Empty selector in \"{list}\"")));
+                }
+                Selector::parse(selector)
+                    .ok_or_else(|| Error::Selector(format!("Unsupported selector \"{selector}\"")))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub(crate) fn matches(&self, e: &BytesStart) -> bool {
+        self.0.iter().any(|selector| selector.matches(e))
+    }
+}
+
+/// A rule with a single selector.
+#[derive(Debug, PartialEq)]
+struct Rule {
+    selector: Selector,
+    declarations: Vec<(String, String)>,
+}
+
+/// The rules of all `<style>` elements of a document.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct StyleSheet {
+    rules: Vec<Rule>,
+}
+
+/// Remove `/* ... */` comments.
+fn strip_comments(css: &str) -> String {
+    let mut result = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        result.push_str(&rest[..start]);
+        rest = rest[start + 2..]
+            .find("*/")
+            .map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Parse the declarations of a rule or a `style` attribute.
+pub(crate) fn parse_declarations(block: &str) -> Vec<(String, String)> {
+    block
+        .split(';')
+        .filter_map(|declaration| {
+            let (key, value) = declaration.split_once(':')?;
+            let value = value.trim();
+            let value = value.strip_suffix("!important").unwrap_or(value).trim();
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+impl StyleSheet {
+    /// Parse a style sheet and append its rules.
+    fn add(&mut self, css: &str) {
+        let css = strip_comments(css);
+        let mut rest = css.as_str();
+        while let Some(open) = rest.find('{') {
+            let prelude = rest[..open].trim();
+            // Find the matching closing brace, skipping nested blocks
+            let mut depth = 0;
+            let Some(close) = rest[open..].find(|c| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            }) else {
+                break;
+            };
+            let block = &rest[open + 1..open + close];
+            rest = &rest[open + close + 1..];
+
+            if prelude.starts_with('@') {
+                trace!("StyleSheet: Ignore at-rule {prelude}");
+                continue;
+            }
+            let declarations = parse_declarations(block);
+            for selector in prelude.split(',') {
+                match Selector::parse(selector.trim()) {
+                    Some(selector) => self.rules.push(Rule {
+                        selector,
+                        declarations: declarations.clone(),
+                    }),
+                    None => trace!("StyleSheet: Ignore unsupported selector {selector}"),
+                }
+            }
+        }
+    }
+
+    /// Collect the rules of all `<style>` elements of a document.
+    ///
+    /// Malformed documents result in the rules found up to the error.
+    pub(crate) fn from_svg(svg: &str) -> Self {
+        let mut sheet = Self::default();
+        let mut reader = quick_xml::Reader::from_str(svg);
+        let mut in_style = false;
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event(&mut buf) {
+                Ok(Event::Start(ref e)) if e.name() == b"style" => in_style = true,
+                Ok(Event::End(ref e)) if e.name() == b"style" => in_style = false,
+                Ok(Event::Text(ref e)) if in_style => {
+                    if let Ok(text) = e.unescaped() {
+                        sheet.add(&String::from_utf8_lossy(&text));
+                    }
+                }
+                Ok(Event::CData(ref e)) if in_style => {
+                    sheet.add(&String::from_utf8_lossy(e));
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                Ok(_) => {}
+            }
+            buf.clear();
+        }
+        sheet
+    }
+
+    /// Return the value of a property for an element from the rule with the
+    /// highest specificity (the last one if there are several).
+    pub(crate) fn property(&self, e: &BytesStart, name: &str) -> Option<String> {
+        let mut best = None;
+        for rule in &self.rules {
+            let Some((_, value)) = rule.declarations.iter().rev().find(|(key, _)| key == name)
+            else {
+                continue;
+            };
+            let specificity = rule.selector.specificity();
+            if best.as_ref().is_none_or(|(s, _)| specificity >= *s) && rule.selector.matches(e) {
+                best = Some((specificity, value));
+            }
+        }
+        best.map(|(_, value)| value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selector() {
+        assert_eq!(
+            Selector::parse("path#outline.cut.red"),
+            Some(Selector {
+                name: Some("path".into()),
+                id: Some("outline".into()),
+                classes: vec!["cut".into(), "red".into()],
+            })
+        );
+        assert_eq!(Selector::parse("*"), Some(Selector::default()));
+        assert_eq!(Selector::parse("g path"), None);
+        assert_eq!(Selector::parse("a:hover"), None);
+    }
+
+    #[test]
+    fn test_selector_list() {
+        let list = SelectorList::parse("#outline, path.cut").unwrap();
+        assert!(list.matches(&BytesStart::borrowed(br#"g id="outline""#, 1)));
+        assert!(list.matches(&BytesStart::borrowed(br#"path class="cut""#, 4)));
+        assert!(!list.matches(&BytesStart::borrowed(br#"rect class="cut""#, 4)));
+        assert!(SelectorList::parse("#a,").is_err());
+        assert!(SelectorList::parse("g > path").is_err());
+    }
+
+    #[test]
+    fn test_style_sheet() {
+        let svg = r"
+            <svg>
+                <style>
+                    /* Cut lines */
+                    .cut, #special { stroke: red; stroke-width: 2 }
+                    @media print { .cut { stroke: blue } }
+                    path.cut { stroke: green !important }
+                    g path { stroke: yellow }
+                </style>
+                <style><![CDATA[ .engrave { stroke: black } ]]></style>
+            </svg>
+        ";
+        let sheet = StyleSheet::from_svg(svg);
+        let e = BytesStart::borrowed(br#"path class="engrave cut""#, 4);
+        assert_eq!(sheet.property(&e, "stroke"), Some("green".into()));
+        assert_eq!(sheet.property(&e, "stroke-width"), Some("2".into()));
+        let e = BytesStart::borrowed(br#"rect class="engrave""#, 4);
+        assert_eq!(sheet.property(&e, "stroke"), Some("black".into()));
+        let e = BytesStart::borrowed(br#"rect id="special""#, 4);
+        assert_eq!(sheet.property(&e, "stroke"), Some("red".into()));
+        assert_eq!(sheet.property(&e, "fill"), None);
+    }
+}
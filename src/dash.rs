@@ -0,0 +1,186 @@
+//! Split polylines into the dashes of a `stroke-dasharray`.
+
+use std::{mem, str::FromStr};
+
+use quick_xml::events::BytesStart;
+use svgtypes::{Length, LengthUnit};
+
+use crate::{css::StyleSheet, style, CoordinatePair, Polyline};
+
+/// The dash properties of an element, inherited from its ancestors.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DashStyle {
+    array: Option<String>,
+    offset: Option<String>,
+}
+
+impl DashStyle {
+    /// Apply the `stroke-dasharray` and `stroke-dashoffset` properties of an
+    /// element to the style inherited from its parent.
+    pub(crate) fn inherit(&self, e: &BytesStart, sheet: &StyleSheet) -> Self {
+        Self {
+            array: style::property(e, "stroke-dasharray", sheet).or_else(|| self.array.clone()),
+            offset: style::property(e, "stroke-dashoffset", sheet).or_else(|| self.offset.clone()),
+        }
+    }
+
+    /// Return the dash pattern, or `None` if the stroke is solid.
+    pub(crate) fn dashes(&self) -> Option<Dashes> {
+        Dashes::parse(self.array.as_deref()?, self.offset.as_deref())
+    }
+}
+
+/// Parse a length in user units. Only unitless and `px` lengths are
+/// supported.
+fn length(s: &str) -> Option<f64> {
+    match Length::from_str(s).ok()? {
+        Length {
+            number,
+            unit: LengthUnit::None | LengthUnit::Px,
+        } => Some(number),
+        _ => None,
+    }
+}
+
+/// A dash pattern, alternating between dashes and gaps.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Dashes {
+    /// The lengths of the dashes and gaps, always of even length.
+    array: Vec<f64>,
+    /// The distance into the pattern at the start of a path.
+    offset: f64,
+}
+
+impl Dashes {
+    /// Parse the values of `stroke-dasharray` and `stroke-dashoffset`.
+    ///
+    /// Return `None` for `none` and for invalid patterns (negative values or
+    /// a total length of zero), which are rendered solid.
+    pub(crate) fn parse(array: &str, offset: Option<&str>) -> Option<Self> {
+        let mut array = array
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(length)
+            .collect::<Option<Vec<f64>>>()?;
+        if array.iter().any(|&l| l < 0.0) || array.iter().sum::<f64>() <= 0.0 {
+            return None;
+        }
+        // An odd number of values is repeated to yield an even number
+        if array.len() % 2 == 1 {
+            array.extend_from_within(..);
+        }
+        Some(Self {
+            array,
+            offset: offset.and_then(length).unwrap_or(0.0),
+        })
+    }
+
+    /// Split a polyline into its dashes.
+    ///
+    /// The pattern starts anew for every polyline, like for every subpath in
+    /// SVG. Dashes of zero length result in polylines with two identical
+    /// points.
+    pub(crate) fn apply(&self, polyline: &Polyline) -> Vec<Polyline> {
+        let Some(&first) = polyline.first() else {
+            return Vec::new();
+        };
+
+        // Find the position in the pattern at the start of the polyline
+        let total: f64 = self.array.iter().sum();
+        let mut position = self.offset.rem_euclid(total);
+        let mut index = 0;
+        while position >= self.array[index] {
+            position -= self.array[index];
+            index = (index + 1) % self.array.len();
+        }
+        let mut remaining = self.array[index] - position;
+
+        let mut dashes = Vec::new();
+        let mut current = if index % 2 == 0 {
+            vec![first]
+        } else {
+            Vec::new()
+        };
+        for segment in polyline.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            let length = (b.x - a.x).hypot(b.y - a.y);
+            let mut t = 0.0;
+            while remaining <= length - t {
+                t += remaining;
+                let fraction = if length > 0.0 { t / length } else { 0.0 };
+                let p =
+                    CoordinatePair::new(a.x + (b.x - a.x) * fraction, a.y + (b.y - a.y) * fraction);
+                // Either end the current dash or start a new one
+                current.push(p);
+                if index % 2 == 0 {
+                    dashes.push(Polyline::from_vec(mem::take(&mut current)));
+                }
+                index = (index + 1) % self.array.len();
+                remaining = self.array[index];
+            }
+            remaining -= length - t;
+            // Unless a dash just started at `b`
+            if index % 2 == 0 && current.last() != Some(&b) {
+                current.push(b);
+            }
+        }
+        if current.len() >= 2 {
+            dashes.push(Polyline::from_vec(current));
+        }
+        dashes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::line;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_parse() {
+        let dashes = Dashes::parse("1, 2 3", Some("1px")).unwrap();
+        assert_eq!(dashes.array, vec![1.0, 2.0, 3.0, 1.0, 2.0, 3.0]);
+        assert_eq!(dashes.offset, 1.0);
+        assert_eq!(Dashes::parse("none", None), None);
+        assert_eq!(Dashes::parse("0 0", None), None);
+        assert_eq!(Dashes::parse("1 -1", None), None);
+        assert_eq!(Dashes::parse("10%", None), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let polyline = line(&[(0.0, 0.0), (5.0, 0.0), (5.0, 5.0)]);
+        let dashes = Dashes::parse("4 2", None).unwrap();
+        assert_eq!(
+            dashes.apply(&polyline),
+            vec![
+                line(&[(0.0, 0.0), (4.0, 0.0)]),
+                line(&[(5.0, 1.0), (5.0, 5.0)]),
+            ]
+        );
+        let dashes = Dashes::parse("4 2", Some("-1")).unwrap();
+        assert_eq!(
+            dashes.apply(&polyline),
+            vec![
+                line(&[(1.0, 0.0), (5.0, 0.0)]),
+                line(&[(5.0, 2.0), (5.0, 5.0)]),
+            ]
+        );
+        assert_eq!(dashes.apply(&Polyline::new()), Vec::<Polyline>::new());
+    }
+
+    #[test]
+    fn test_apply_gap_ends_at_vertex() {
+        let polyline = line(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]);
+        let dashes = Dashes::parse("3 1", None).unwrap();
+        assert_eq!(
+            dashes.apply(&polyline),
+            vec![
+                line(&[(0.0, 0.0), (3.0, 0.0)]),
+                line(&[(4.0, 0.0), (4.0, 3.0)]),
+                line(&[(4.0, 4.0), (1.0, 4.0)]),
+            ]
+        );
+    }
+}
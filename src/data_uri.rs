@@ -0,0 +1,117 @@
+//! Decode `data:image/svg+xml` URIs.
+
+use std::borrow::Cow;
+
+use crate::Error;
+
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decode `%XX` escapes. Invalid escapes are kept as they are.
+fn percent_decode(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                out.push(hi << 4 | lo);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+fn base64_decode(input: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &b in input {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            b if b.is_ascii_whitespace() => continue,
+            _ => return Err(Error::SvgParse("Invalid base64 data in data URI".into())),
+        };
+        buffer = buffer << 6 | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            #[allow(clippy::cast_possible_truncation)]
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// If `input` is a `data:image/svg+xml` URI, return the decoded SVG document.
+/// Otherwise return the input unchanged.
+///
+/// Both base64 and URL-encoded (percent-encoded) data are supported.
+pub(crate) fn decode_input(input: &str) -> Result<Cow<'_, str>, Error> {
+    let trimmed = input.trim_start();
+    let Some(header_end) = trimmed
+        .get(..5)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))
+        .and_then(|_| trimmed.find(','))
+    else {
+        return Ok(Cow::Borrowed(input));
+    };
+    let header = trimmed[5..header_end].to_ascii_lowercase();
+    let mut params = header.split(';');
+    let media_type = params.next().unwrap_or_default().trim();
+    if media_type != "image/svg+xml" {
+        return Err(Error::SvgParse(format!(
+            "Unsupported media type in data URI: {media_type}"
+        )));
+    }
+    let data = percent_decode(trimmed[header_end + 1..].trim_end());
+    let data = if params.any(|param| param.trim() == "base64") {
+        base64_decode(&data)?
+    } else {
+        data
+    };
+    String::from_utf8(data)
+        .map(Cow::Owned)
+        .map_err(|e| Error::SvgParse(format!("Data URI is not valid UTF-8: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = r#"<svg><path d="M 0,0 1,1"/></svg>"#;
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(decode_input(SVG).unwrap(), SVG);
+    }
+
+    #[test]
+    fn test_url_encoded() {
+        let uri = "data:image/svg+xml;charset=utf-8,%3Csvg%3E%3Cpath%20d=%22M%200,0%201,1%22/%3E%3C/svg%3E";
+        assert_eq!(decode_input(uri).unwrap(), SVG);
+    }
+
+    #[test]
+    fn test_base64() {
+        let uri = "DATA:image/svg+xml;base64,PHN2Zz48cGF0aCBkPSJNIDAsMCAxLDEiLz48L3N2Zz4=";
+        assert_eq!(decode_input(uri).unwrap(), SVG);
+        assert!(decode_input("data:image/png;base64,AAAA").is_err());
+        assert!(decode_input("data:image/svg+xml;base64,!!").is_err());
+    }
+}
@@ -0,0 +1,182 @@
+//! Map path attributes to cutting depths for 2.5D engraving.
+//!
+//! Use [`parse_with_depths`] to get a depth for every polyline, and
+//! [`GcodeExporter::export_with_depths`](crate::export::GcodeExporter::export_with_depths)
+//! to write G-code that cuts each polyline at its depth.
+
+use std::{collections::HashMap, str::FromStr};
+
+use log::trace;
+use quick_xml::events::BytesStart;
+use svgtypes::Color;
+
+use crate::{
+    css::StyleSheet,
+    parse_elements,
+    style::{attribute, property},
+    Error, ParseOptions, Polyline,
+};
+
+/// Where the cutting depth of a path is taken from.
+///
+/// Depths are inherited from ancestor elements. Paths without a depth are
+/// cut at depth 0.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DepthSource {
+    /// Read the depth from an attribute, e.g. `data-depth`.
+    ///
+    /// Note that preprocessing with usvg removes unknown attributes, so
+    /// this requires [`ParseOptions::preprocess`] to be
+    /// [`Preprocess::No`](crate::Preprocess::No).
+    Attribute(String),
+
+    /// Derive the depth from the stroke color: Black strokes are cut at
+    /// `max_depth`, white strokes (and paths without a stroke) at 0.
+    StrokeDarkness { max_depth: f64 },
+
+    /// Look up the depth by the id (or `inkscape:label`) of the enclosing
+    /// group.
+    Layer(HashMap<String, f64>),
+}
+
+/// Return the depth specified directly on the element, if any.
+pub(crate) fn element_depth(
+    e: &BytesStart,
+    source: &DepthSource,
+    sheet: &StyleSheet,
+) -> Option<f64> {
+    match source {
+        DepthSource::Attribute(name) => {
+            attribute(e, name.as_bytes()).and_then(|value| value.trim().parse().ok())
+        }
+        DepthSource::StrokeDarkness { max_depth } => {
+            let stroke = property(e, "stroke", sheet)?;
+            if stroke == "none" {
+                return Some(0.0);
+            }
+            let color = Color::from_str(&stroke).ok()?;
+            // Relative luminance with the Rec. 709 coefficients
+            let luminance = f64::from(
+                2126 * u32::from(color.red)
+                    + 7152 * u32::from(color.green)
+                    + 722 * u32::from(color.blue),
+            ) / (10_000.0 * 255.0);
+            Some((1.0 - luminance) * max_depth)
+        }
+        DepthSource::Layer(layers) => {
+            if e.name() != b"g" {
+                return None;
+            }
+            [&b"id"[..], &b"inkscape:label"[..]]
+                .iter()
+                .filter_map(|key| attribute(e, key))
+                .find_map(|name| layers.get(&name).copied())
+        }
+    }
+}
+
+/// Parse an SVG string into polylines, together with the cutting depth of
+/// every polyline.
+///
+/// All [`ParseOptions`] apply like in
+/// [`parse_with_options`](crate::parse_with_options), and polylines that are
+/// split (e.g. by clipping) keep the depth of their element.
+pub fn parse_with_depths(
+    svg: &str,
+    options: &ParseOptions,
+    source: &DepthSource,
+) -> Result<Vec<(Polyline, f64)>, Error> {
+    let elements = parse_elements(svg, options, Some(source), &mut Vec::new(), None)?;
+    let result: Vec<(Polyline, f64)> = elements
+        .into_iter()
+        .flat_map(|(depth, polylines)| {
+            let depth = depth.unwrap_or(0.0);
+            polylines.into_iter().map(move |polyline| (polyline, depth))
+        })
+        .collect();
+    trace!("parse_with_depths: Found {} polylines", result.len());
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Preprocess, Unit, ViewportClip};
+
+    fn options() -> ParseOptions {
+        ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        }
+    }
+
+    fn depths(svg: &str, source: &DepthSource) -> Vec<f64> {
+        parse_with_depths(svg, &options(), source)
+            .unwrap()
+            .into_iter()
+            .map(|(_, depth)| depth)
+            .collect()
+    }
+
+    #[test]
+    fn test_attribute_depth() {
+        let svg = r#"<svg>
+            <g data-depth="2"><path d="M 0,0 1,1 M 2,2 3,3"/><path data-depth="0.5" d="M 0,0 1,1"/></g>
+            <path d="M 0,0 1,1"/>
+        </svg>"#;
+        assert_eq!(
+            depths(svg, &DepthSource::Attribute("data-depth".into())),
+            vec![2.0, 2.0, 0.5, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let svg = r#"<svg width="20mm" height="20mm" viewBox="0 0 10 10">
+            <g data-depth="2">
+                <path stroke="black" d="M 5,5 L 15,5"/>
+                <path fill="none" d="M 0,0 1,1"/>
+            </g>
+        </svg>"#;
+        let options = ParseOptions {
+            skip_invisible: true,
+            viewport_clip: ViewportClip::Clip,
+            unit: Unit::Millimeters,
+            ..options()
+        };
+        let result =
+            parse_with_depths(svg, &options, &DepthSource::Attribute("data-depth".into())).unwrap();
+        assert_eq!(
+            result,
+            vec![(
+                Polyline::from_vec(vec![(10.0, 10.0).into(), (20.0, 10.0).into()]),
+                2.0
+            )]
+        );
+    }
+
+    #[test]
+    fn test_stroke_darkness_depth() {
+        let svg = r##"<svg>
+            <path stroke="#000000" d="M 0,0 1,1"/>
+            <path style="fill:none;stroke:#ffffff" d="M 0,0 1,1"/>
+            <path stroke="none" d="M 0,0 1,1"/>
+        </svg>"##;
+        assert_eq!(
+            depths(svg, &DepthSource::StrokeDarkness { max_depth: 3.0 }),
+            vec![3.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_layer_depth() {
+        let svg = r#"<svg>
+            <g id="deep"><path d="M 0,0 1,1"/></g>
+            <g inkscape:label="shallow"><path d="M 0,0 1,1"/></g>
+        </svg>"#;
+        let layers = vec![("deep".to_string(), 2.0), ("shallow".to_string(), 0.2)]
+            .into_iter()
+            .collect();
+        assert_eq!(depths(svg, &DepthSource::Layer(layers)), vec![2.0, 0.2]);
+    }
+}
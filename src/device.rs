@@ -0,0 +1,367 @@
+//! Drive a drawing machine directly.
+//!
+//! Implement [`Device`] for your hardware backend, then pass a [`Plan`] to
+//! [`drive`], which calls the device methods in the right order.
+//!
+//! Machines that understand G-code can be driven with [`GcodeDevice`] over
+//! any byte stream. With the `serial` feature, [`open_serial`] connects to a
+//! machine on a serial port.
+
+use std::io::{Read, Write};
+#[cfg(feature = "serial")]
+use std::time::Duration;
+
+use log::trace;
+
+use crate::{export::GcodeExporter, Error, Polyline};
+
+/// A drawing device, e.g. a pen plotter.
+pub trait Device {
+    /// The error type returned by the device.
+    type Error;
+
+    /// Lift the pen.
+    fn pen_up(&mut self) -> Result<(), Self::Error>;
+
+    /// Lower the pen.
+    fn pen_down(&mut self) -> Result<(), Self::Error>;
+
+    /// Move to the given absolute position. If the pen is down, this draws a
+    /// straight line.
+    fn move_to(&mut self, x: f64, y: f64) -> Result<(), Self::Error>;
+
+    /// Called once after the whole plan has been drawn.
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// The polylines to draw, in drawing order.
+#[derive(Debug, PartialEq, Default)]
+pub struct Plan {
+    polylines: Vec<Polyline>,
+}
+
+impl Plan {
+    /// Create a plan that draws the polylines in the given order.
+    pub fn new(polylines: Vec<Polyline>) -> Self {
+        Self { polylines }
+    }
+
+    /// Return the polylines in drawing order.
+    pub fn polylines(&self) -> &[Polyline] {
+        &self.polylines
+    }
+
+    /// Unwrap and return the polylines.
+    #[must_use]
+    pub fn into_polylines(self) -> Vec<Polyline> {
+        self.polylines
+    }
+}
+
+impl From<Vec<Polyline>> for Plan {
+    fn from(polylines: Vec<Polyline>) -> Self {
+        Self::new(polylines)
+    }
+}
+
+/// Draw a [`Plan`] on a [`Device`].
+///
+/// The pen is lifted before travelling to the start of each polyline and
+/// lowered while drawing it. Empty polylines are skipped.
+pub fn drive<D: Device>(plan: &Plan, device: &mut D) -> Result<(), D::Error> {
+    device.pen_up()?;
+    for polyline in plan.polylines() {
+        let mut points = polyline.iter();
+        let Some(first) = points.next() else {
+            continue;
+        };
+        device.move_to(first.x, first.y)?;
+        device.pen_down()?;
+        for p in points {
+            device.move_to(p.x, p.y)?;
+        }
+        device.pen_up()?;
+    }
+    device.finish()
+}
+
+/// Options for [`GcodeDevice`].
+#[derive(Debug, Clone, Default)]
+pub struct GcodeDeviceOptions {
+    /// Pen heights, feed rate and coordinate precision of the generated
+    /// commands.
+    pub gcode: GcodeExporter,
+
+    /// Wait for an `ok` reply after every command before sending the next
+    /// one, like GRBL and Marlin expect. A reply starting with `error` aborts
+    /// the plan.
+    pub wait_for_ack: bool,
+}
+
+/// A [`Device`] that streams G-code commands over a byte transport, e.g. a
+/// serial port or a TCP socket.
+pub struct GcodeDevice<T> {
+    transport: T,
+    options: GcodeDeviceOptions,
+    pen_is_down: bool,
+    initialized: bool,
+}
+
+impl<T: Read + Write> GcodeDevice<T> {
+    /// Create a new device that talks to the machine through `transport`.
+    pub fn new(transport: T, options: GcodeDeviceOptions) -> Self {
+        Self {
+            transport,
+            options,
+            pen_is_down: false,
+            initialized: false,
+        }
+    }
+
+    /// Unwrap and return the transport.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Read a single line from the transport.
+    fn read_line(&mut self) -> Result<String, Error> {
+        let mut line = Vec::new();
+        let mut byte = [0; 1];
+        loop {
+            if self.transport.read(&mut byte)? == 0 {
+                return Err(Error::Device("Connection closed by device".into()));
+            }
+            match byte[0] {
+                b'\n' => return Ok(String::from_utf8_lossy(&line).trim().to_string()),
+                b => line.push(b),
+            }
+        }
+    }
+
+    /// Send a single command and wait for the acknowledgement, if enabled.
+    fn send(&mut self, command: &str) -> Result<(), Error> {
+        if !self.initialized {
+            self.initialized = true;
+            self.send("G21")?;
+            self.send("G90")?;
+        }
+        trace!("GcodeDevice: {command}");
+        self.transport.write_all(command.as_bytes())?;
+        self.transport.write_all(b"\n")?;
+        self.transport.flush()?;
+        while self.options.wait_for_ack {
+            let reply = self.read_line()?;
+            if reply.starts_with("ok") {
+                break;
+            }
+            if reply.starts_with("error") {
+                return Err(Error::Device(format!("{command}: {reply}")));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Read + Write> Device for GcodeDevice<T> {
+    type Error = Error;
+
+    fn pen_up(&mut self) -> Result<(), Error> {
+        self.pen_is_down = false;
+        let gcode = &self.options.gcode;
+        let command = format!("G0 Z{:.*}", gcode.precision, gcode.travel_z);
+        self.send(&command)
+    }
+
+    fn pen_down(&mut self) -> Result<(), Error> {
+        self.pen_is_down = true;
+        let gcode = &self.options.gcode;
+        let command = format!(
+            "G1 Z{:.*} F{}",
+            gcode.precision, gcode.draw_z, gcode.feed_rate
+        );
+        self.send(&command)
+    }
+
+    fn move_to(&mut self, x: f64, y: f64) -> Result<(), Error> {
+        let prec = self.options.gcode.precision;
+        let command = format!(
+            "{} X{:.*} Y{:.*}",
+            if self.pen_is_down { "G1" } else { "G0" },
+            prec,
+            x,
+            prec,
+            y
+        );
+        self.send(&command)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        Ok(self.transport.flush()?)
+    }
+}
+
+/// Options for [`open_serial`].
+#[cfg(feature = "serial")]
+#[derive(Debug, Clone)]
+pub struct SerialOptions {
+    /// Baud rate of the serial connection.
+    pub baud_rate: u32,
+
+    /// Hardware or software flow control.
+    pub flow_control: serialport::FlowControl,
+
+    /// How long to wait for a reply from the machine.
+    pub timeout: Duration,
+
+    /// Options for the generated G-code. Acknowledgements are enabled by
+    /// default, since most serial firmwares have small receive buffers.
+    pub device: GcodeDeviceOptions,
+}
+
+#[cfg(feature = "serial")]
+impl Default for SerialOptions {
+    fn default() -> Self {
+        Self {
+            baud_rate: 115_200,
+            flow_control: serialport::FlowControl::None,
+            timeout: Duration::from_secs(10),
+            device: GcodeDeviceOptions {
+                wait_for_ack: true,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Open the serial port at `path` (e.g. `/dev/ttyUSB0` or `COM3`) and return
+/// a [`Device`] that streams G-code to it.
+#[cfg(feature = "serial")]
+pub fn open_serial(
+    path: &str,
+    options: &SerialOptions,
+) -> Result<GcodeDevice<Box<dyn serialport::SerialPort>>, Error> {
+    let port = serialport::new(path, options.baud_rate)
+        .flow_control(options.flow_control)
+        .timeout(options.timeout)
+        .open()
+        .map_err(std::io::Error::from)?;
+    Ok(GcodeDevice::new(port, options.device.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoordinatePair;
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Device for Recorder {
+        type Error = ();
+
+        fn pen_up(&mut self) -> Result<(), ()> {
+            self.0.push("up".into());
+            Ok(())
+        }
+
+        fn pen_down(&mut self) -> Result<(), ()> {
+            self.0.push("down".into());
+            Ok(())
+        }
+
+        fn move_to(&mut self, x: f64, y: f64) -> Result<(), ()> {
+            self.0.push(format!("{x},{y}"));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_drive() {
+        let plan = Plan::new(vec![
+            Polyline::from_vec(vec![
+                CoordinatePair::new(0.0, 0.0),
+                CoordinatePair::new(1.0, 1.0),
+            ]),
+            Polyline::new(),
+            Polyline::from_vec(vec![CoordinatePair::new(2.0, 2.0)]),
+        ]);
+        let mut recorder = Recorder::default();
+        drive(&plan, &mut recorder).unwrap();
+        assert_eq!(
+            recorder.0,
+            vec!["up", "0,0", "down", "1,1", "up", "2,2", "down", "up"]
+        );
+    }
+
+    /// An in-memory transport that replies to every command with a fixed
+    /// answer.
+    struct Loopback {
+        sent: Vec<u8>,
+        reply: &'static [u8],
+        pending: Vec<u8>,
+    }
+
+    impl Read for Loopback {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            Ok(n)
+        }
+    }
+
+    impl Write for Loopback {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.sent.extend_from_slice(buf);
+            if buf.ends_with(b"\n") {
+                self.pending.extend_from_slice(self.reply);
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn loopback(reply: &'static [u8]) -> Loopback {
+        Loopback {
+            sent: vec![],
+            reply,
+            pending: vec![],
+        }
+    }
+
+    #[test]
+    fn test_gcode_device() {
+        let plan = Plan::new(vec![Polyline::from_vec(vec![
+            CoordinatePair::new(0.0, 0.0),
+            CoordinatePair::new(1.0, 2.0),
+        ])]);
+        let options = GcodeDeviceOptions {
+            wait_for_ack: true,
+            ..Default::default()
+        };
+        let mut device = GcodeDevice::new(loopback(b"echo\nok\n"), options);
+        drive(&plan, &mut device).unwrap();
+        assert_eq!(
+            String::from_utf8(device.into_inner().sent).unwrap(),
+            "G21\nG90\nG0 Z5.000\nG0 X0.000 Y0.000\nG1 Z0.000 F1000\nG1 X1.000 Y2.000\nG0 Z5.000\n"
+        );
+    }
+
+    #[test]
+    fn test_gcode_device_error_reply() {
+        let options = GcodeDeviceOptions {
+            wait_for_ack: true,
+            ..Default::default()
+        };
+        let mut device = GcodeDevice::new(loopback(b"error:2\n"), options);
+        assert_eq!(
+            device.pen_up().unwrap_err().to_string(),
+            "Device error: G21: error:2"
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Access the document dimensions together with the polylines.
+
+use std::str::{self, FromStr};
+
+use log::trace;
+use lyon_geom::euclid::Transform2D;
+use quick_xml::events::{BytesStart, Event};
+use svgtypes::{Align, AspectRatio, Length, LengthUnit};
+
+use crate::{data_uri, parse_with_warnings, BoundingBox, Error, ParseOptions, Polyline, Warning};
+
+/// The `viewBox` of an SVG document.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ViewBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl ViewBox {
+    /// Return the transform that maps the view box onto a viewport of the
+    /// given size, the way renderers display it.
+    ///
+    /// With an `align` other than `none`, the aspect ratio is preserved: The
+    /// view box is scaled uniformly so that it fits into the viewport
+    /// (`meet`) or covers it completely (`slice`), and is then aligned within
+    /// the viewport.
+    pub fn transform_to(
+        &self,
+        width: f64,
+        height: f64,
+        aspect: AspectRatio,
+    ) -> Transform2D<f64, f64, f64> {
+        let mut sx = width / self.width;
+        let mut sy = height / self.height;
+        if aspect.align != Align::None {
+            let s = if aspect.slice { sx.max(sy) } else { sx.min(sy) };
+            sx = s;
+            sy = s;
+        }
+
+        // Distribute the free space according to the alignment
+        let free_x = width - self.width * sx;
+        let free_y = height - self.height * sy;
+        let (align_x, align_y) = match aspect.align {
+            Align::None | Align::XMinYMin => (0.0, 0.0),
+            Align::XMidYMin => (0.5, 0.0),
+            Align::XMaxYMin => (1.0, 0.0),
+            Align::XMinYMid => (0.0, 0.5),
+            Align::XMidYMid => (0.5, 0.5),
+            Align::XMaxYMid => (1.0, 0.5),
+            Align::XMinYMax => (0.0, 1.0),
+            Align::XMidYMax => (0.5, 1.0),
+            Align::XMaxYMax => (1.0, 1.0),
+        };
+        Transform2D::scale(sx, sy).then_translate(
+            (
+                free_x * align_x - self.x * sx,
+                free_y * align_y - self.y * sy,
+            )
+                .into(),
+        )
+    }
+}
+
+/// A parsed SVG document.
+#[derive(Debug, PartialEq)]
+pub struct Document {
+    /// The `width` attribute of the root element, if present.
+    pub width: Option<Length>,
+
+    /// The `height` attribute of the root element, if present.
+    pub height: Option<Length>,
+
+    /// The `viewBox` attribute of the root element, if present.
+    pub view_box: Option<ViewBox>,
+
+    /// The `preserveAspectRatio` attribute of the root element, or the
+    /// default (`xMidYMid meet`).
+    pub preserve_aspect_ratio: AspectRatio,
+
+    /// The polylines contained in the document.
+    pub polylines: Vec<Polyline>,
+
+    /// Problems that were found while parsing, e.g. polylines outside of the
+    /// viewport.
+    pub warnings: Vec<Warning>,
+}
+
+/// Attributes of the root `<svg>` element.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct RootAttributes {
+    pub(crate) width: Option<Length>,
+    pub(crate) height: Option<Length>,
+    pub(crate) view_box: Option<ViewBox>,
+    pub(crate) preserve_aspect_ratio: AspectRatio,
+}
+
+impl RootAttributes {
+    /// Read the attributes of the root element, which must be an `<svg>`.
+    pub(crate) fn from_element(e: &BytesStart) -> Result<Self, Error> {
+        if e.name() != b"svg" {
+            return Err(Error::SvgParse("Root element is not <svg>".into()));
+        }
+        let mut root = Self::default();
+        for attr in e.attributes().filter_map(Result::ok) {
+            let Some(value) = attr
+                .unescaped_value()
+                .ok()
+                .and_then(|v| str::from_utf8(&v).map(str::to_string).ok())
+            else {
+                continue;
+            };
+            match attr.key {
+                b"width" => root.width = Length::from_str(&value).ok(),
+                b"height" => root.height = Length::from_str(&value).ok(),
+                b"preserveAspectRatio" => {
+                    root.preserve_aspect_ratio = AspectRatio::from_str(&value).unwrap_or_default();
+                }
+                b"viewBox" => {
+                    root.view_box = svgtypes::ViewBox::from_str(&value).ok().map(|vb| ViewBox {
+                        x: vb.x,
+                        y: vb.y,
+                        width: vb.w,
+                        height: vb.h,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(root)
+    }
+
+    /// Return the visible area in user units.
+    ///
+    /// This is the `viewBox` if present, otherwise the rectangle given by
+    /// `width` and `height` if they are unitless or in `px`.
+    pub(crate) fn viewport(&self) -> Option<BoundingBox> {
+        if let Some(vb) = self.view_box {
+            return Some(BoundingBox::new(
+                vb.x,
+                vb.y,
+                vb.x + vb.width,
+                vb.y + vb.height,
+            ));
+        }
+        let user_units = |length: Option<Length>| match length {
+            Some(Length {
+                number,
+                unit: LengthUnit::None | LengthUnit::Px,
+            }) => Some(number),
+            _ => None,
+        };
+        Some(BoundingBox::new(
+            0.0,
+            0.0,
+            user_units(self.width)?,
+            user_units(self.height)?,
+        ))
+    }
+}
+
+impl Document {
+    /// Return the transform that maps user units onto the viewport given by
+    /// `width` and `height`, honoring `preserveAspectRatio`.
+    ///
+    /// The result is in the units of `width` and `height`. Return `None` if
+    /// the document has no `viewBox`, `width` or `height`, or if they use
+    /// percentages.
+    pub fn viewport_transform(&self) -> Option<Transform2D<f64, f64, f64>> {
+        let number = |length: Option<Length>| match length {
+            Some(Length {
+                unit: LengthUnit::Percent,
+                ..
+            })
+            | None => None,
+            Some(length) => Some(length.number),
+        };
+        Some(self.view_box?.transform_to(
+            number(self.width)?,
+            number(self.height)?,
+            self.preserve_aspect_ratio,
+        ))
+    }
+}
+
+/// Read the dimensions from the root `<svg>` element.
+///
+/// Invalid attribute values are ignored.
+pub(crate) fn parse_root(svg: &str) -> Result<RootAttributes, Error> {
+    let mut reader = quick_xml::Reader::from_str(svg);
+    reader.trim_text(true);
+
+    let mut buf = Vec::new();
+    let root = loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e) | Event::Empty(ref e)) => {
+                break RootAttributes::from_element(e)?;
+            }
+            Ok(Event::Eof) => return Err(Error::SvgParse("Missing <svg> element".into())),
+            Ok(_) => {}
+            Err(e) => return Err(Error::SvgParse(e.to_string())),
+        }
+        buf.clear();
+    };
+    trace!("parse_root: {root:?}");
+    Ok(root)
+}
+
+/// Parse an SVG string into a [`Document`], which contains the document
+/// dimensions in addition to the polylines.
+///
+/// The dimensions are read from the original input, so the units of `width`
+/// and `height` are preserved even if the document is preprocessed.
+pub fn parse_document(svg: &str, options: &ParseOptions) -> Result<Document, Error> {
+    let svg = data_uri::decode_input(svg)?;
+    let root = parse_root(&svg)?;
+    let mut warnings = Vec::new();
+    let polylines = parse_with_warnings(&svg, options, &mut warnings)?;
+    Ok(Document {
+        width: root.width,
+        height: root.height,
+        view_box: root.view_box,
+        preserve_aspect_ratio: root.preserve_aspect_ratio,
+        polylines,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Preprocess;
+
+    #[test]
+    fn test_parse_document() {
+        let input = r#"
+            <?xml version="1.0" encoding="UTF-8" standalone="no"?>
+            <svg xmlns="http://www.w3.org/2000/svg" width="210mm" height="297mm" viewBox="0 0 210 297">
+                <path d="M 10,10 20,20" />
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let document = parse_document(input, &options).unwrap();
+        assert_eq!(document.width, Some(Length::new(210.0, LengthUnit::Mm)));
+        assert_eq!(document.height, Some(Length::new(297.0, LengthUnit::Mm)));
+        assert_eq!(
+            document.view_box,
+            Some(ViewBox {
+                x: 0.0,
+                y: 0.0,
+                width: 210.0,
+                height: 297.0
+            })
+        );
+        assert_eq!(document.polylines.len(), 1);
+        assert!(document.warnings.is_empty());
+        assert_eq!(document.viewport_transform(), Some(Transform2D::identity()));
+    }
+
+    #[test]
+    fn test_view_box_transform() {
+        let view_box = ViewBox {
+            x: 10.0,
+            y: 0.0,
+            width: 100.0,
+            height: 50.0,
+        };
+        let map = |aspect: &str, x: f64, y: f64| {
+            let aspect = AspectRatio::from_str(aspect).unwrap();
+            let t = view_box.transform_to(200.0, 200.0, aspect);
+            let p = t.transform_point((x, y).into());
+            (p.x, p.y)
+        };
+        // Scaled by 2, centered vertically
+        assert_eq!(map("xMidYMid meet", 10.0, 0.0), (0.0, 50.0));
+        assert_eq!(map("xMinYMax", 110.0, 50.0), (200.0, 200.0));
+        // Scaled by 4, the right half is cut off
+        assert_eq!(map("xMinYMin slice", 60.0, 50.0), (200.0, 200.0));
+        assert_eq!(map("xMaxYMin slice", 110.0, 0.0), (200.0, 0.0));
+        // Stretched
+        assert_eq!(map("none", 110.0, 50.0), (200.0, 200.0));
+    }
+
+    #[test]
+    fn test_parse_document_out_of_bounds() {
+        let input = r#"<svg viewBox="0 0 10 10"><path d="M 5,5 15,5"/></svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let document = parse_document(input, &options).unwrap();
+        assert_eq!(
+            document.warnings,
+            vec![Warning::OutOfBounds {
+                index: 0,
+                overshoot: 5.0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_root_without_dimensions() {
+        let root = parse_root(r#"<svg><path d="M 0,0 1,1"/></svg>"#).unwrap();
+        assert_eq!(root, RootAttributes::default());
+        assert!(parse_root("<html/>").is_err());
+    }
+
+    #[test]
+    fn test_viewport() {
+        let root = parse_root(r#"<svg width="100" height="50px"/>"#).unwrap();
+        assert_eq!(
+            root.viewport(),
+            Some(BoundingBox::new(0.0, 0.0, 100.0, 50.0))
+        );
+        let root = parse_root(r#"<svg width="100mm" height="50mm"/>"#).unwrap();
+        assert_eq!(root.viewport(), None);
+        let root =
+            parse_root(r#"<svg width="100mm" height="50mm" viewBox="-10 0 100 50"/>"#).unwrap();
+        assert_eq!(
+            root.viewport(),
+            Some(BoundingBox::new(-10.0, 0.0, 90.0, 50.0))
+        );
+    }
+}
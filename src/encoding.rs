@@ -0,0 +1,177 @@
+//! Decode SVG documents that are not encoded in UTF-8.
+
+use std::{borrow::Cow, str};
+
+use log::trace;
+
+use crate::Error;
+
+/// The encodings supported by [`decode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Windows1252,
+}
+
+/// The characters of the bytes `0x80` to `0x9F` in Windows-1252. The other
+/// bytes are the same as in Latin-1.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// Detect the encoding of a document from its byte order mark or the
+/// `encoding` of its XML declaration. Documents without either are UTF-8.
+///
+/// Only the start of the document is needed.
+pub(crate) fn detect(start: &[u8]) -> Result<Encoding, Error> {
+    match start {
+        [0xEF, 0xBB, 0xBF, ..] => return Ok(Encoding::Utf8),
+        [0xFF, 0xFE, ..] | [b'<', 0, b'?', 0, ..] => return Ok(Encoding::Utf16Le),
+        [0xFE, 0xFF, ..] | [0, b'<', 0, b'?', ..] => return Ok(Encoding::Utf16Be),
+        _ => {}
+    }
+    let Some(label) = declared_encoding(start) else {
+        return Ok(Encoding::Utf8);
+    };
+    match label.to_ascii_lowercase().as_str() {
+        // Without a byte order mark, UTF-16 is detected above, so the
+        // declaration is wrong
+        "utf-8" | "utf8" | "us-ascii" | "ascii" | "utf-16" => Ok(Encoding::Utf8),
+        "iso-8859-1" | "iso8859-1" | "iso_8859-1" | "latin1" | "latin-1" | "l1" => {
+            Ok(Encoding::Latin1)
+        }
+        "windows-1252" | "cp1252" => Ok(Encoding::Windows1252),
+        _ => Err(Error::Encoding(format!("Unsupported encoding: {label}"))),
+    }
+}
+
+/// Return the `encoding` of the XML declaration at the start of the
+/// document, if any.
+fn declared_encoding(start: &[u8]) -> Option<&str> {
+    let declaration = start.strip_prefix(b"<?xml")?;
+    let end = declaration.windows(2).position(|w| w == b"?>")?;
+    let declaration = str::from_utf8(&declaration[..end]).ok()?;
+    let value = declaration.split("encoding").nth(1)?.trim_start();
+    let value = value.strip_prefix('=')?.trim_start();
+    let quote = value.chars().next().filter(|&c| c == '"' || c == '\'')?;
+    value[1..].split(quote).next()
+}
+
+/// Decode a document into a string, respecting its byte order mark and XML
+/// declaration.
+///
+/// UTF-8, UTF-16, Latin-1 (ISO-8859-1) and Windows-1252 are supported.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Cow<'_, str>, Error> {
+    let encoding = detect(bytes)?;
+    trace!("decode: Detected {encoding:?}");
+    match encoding {
+        Encoding::Utf8 => {
+            let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            str::from_utf8(bytes)
+                .map(Cow::Borrowed)
+                .map_err(|e| Error::Encoding(e.to_string()))
+        }
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let pairs = bytes.chunks_exact(2);
+            if !pairs.remainder().is_empty() {
+                return Err(Error::Encoding("Truncated UTF-16 input".into()));
+            }
+            let units: Vec<u16> = pairs
+                .map(|pair| {
+                    let pair = [pair[0], pair[1]];
+                    if encoding == Encoding::Utf16Le {
+                        u16::from_le_bytes(pair)
+                    } else {
+                        u16::from_be_bytes(pair)
+                    }
+                })
+                .collect();
+            let units = units.strip_prefix(&[0xFEFF]).unwrap_or(&units);
+            String::from_utf16(units)
+                .map(Cow::Owned)
+                .map_err(|e| Error::Encoding(e.to_string()))
+        }
+        Encoding::Latin1 => Ok(Cow::Owned(bytes.iter().map(|&b| char::from(b)).collect())),
+        Encoding::Windows1252 => Ok(Cow::Owned(
+            bytes
+                .iter()
+                .map(|&b| match b {
+                    0x80..=0x9F => WINDOWS_1252[usize::from(b - 0x80)],
+                    _ => char::from(b),
+                })
+                .collect(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(s: &str, little_endian: bool) -> Vec<u8> {
+        s.encode_utf16()
+            .flat_map(|unit| {
+                if little_endian {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect(b"<svg/>").unwrap(), Encoding::Utf8);
+        assert_eq!(detect(b"\xEF\xBB\xBF<svg/>").unwrap(), Encoding::Utf8);
+        assert_eq!(
+            detect(b"<?xml version='1.0' encoding='ISO-8859-1'?><svg/>").unwrap(),
+            Encoding::Latin1
+        );
+        assert_eq!(
+            detect(b"<?xml version=\"1.0\" encoding = \"windows-1252\"?>").unwrap(),
+            Encoding::Windows1252
+        );
+        assert_eq!(
+            detect(&utf16("<?xml version=\"1.0\"?>", true)).unwrap(),
+            Encoding::Utf16Le
+        );
+        assert_eq!(
+            detect(&utf16("\u{FEFF}<svg/>", false)).unwrap(),
+            Encoding::Utf16Be
+        );
+        assert!(matches!(
+            detect(b"<?xml version=\"1.0\" encoding=\"EBCDIC\"?>"),
+            Err(Error::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode() {
+        let svg = "<?xml version=\"1.0\" encoding=\"UTF-16\"?><svg id=\"é€\"/>";
+        assert_eq!(decode(&utf16(svg, true)).unwrap(), svg);
+        assert_eq!(
+            decode(&utf16(&format!("\u{FEFF}{svg}"), false)).unwrap(),
+            svg
+        );
+        assert_eq!(
+            decode(b"<?xml version=\"1.0\" encoding=\"latin1\"?><svg id=\"\xE9\"/>").unwrap(),
+            "<?xml version=\"1.0\" encoding=\"latin1\"?><svg id=\"é\"/>"
+        );
+        assert_eq!(
+            decode(b"<?xml version=\"1.0\" encoding=\"cp1252\"?><svg id=\"\x80\"/>").unwrap(),
+            "<?xml version=\"1.0\" encoding=\"cp1252\"?><svg id=\"€\"/>"
+        );
+        assert_eq!(decode(b"\xEF\xBB\xBF<svg/>").unwrap(), "<svg/>");
+        assert!(matches!(
+            decode(b"<svg id=\"\xE9\"/>"),
+            Err(Error::Encoding(_))
+        ));
+    }
+}
@@ -1,13 +1,117 @@
+use std::fmt;
+
+use crate::{flatten::PathError, strict::StrictViolation, validate::EnvelopeViolation};
+
+/// The element that caused an error and its position in the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Index of the element in document order.
+    pub element: usize,
+
+    /// The `id` attribute of the element, if present.
+    pub id: Option<String>,
+
+    /// Byte offset of the element in the document.
+    ///
+    /// This is `None` if the document was modified before parsing, e.g. by
+    /// preprocessing or XML recovery, because the offset would not match the
+    /// input. Use the `id` or index to find the element in that case.
+    pub offset: Option<usize>,
+
+    /// Line of the element, starting at 1. Only known if the input is
+    /// available as a string.
+    pub line: Option<usize>,
+
+    /// Column of the element in characters, starting at 1.
+    pub column: Option<usize>,
+}
+
+impl ErrorContext {
+    /// Set the line and column from the byte offset in `text`.
+    pub(crate) fn locate(&mut self, text: &str) {
+        let Some(before) = self.offset.and_then(|offset| text.get(..offset)) else {
+            return;
+        };
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        self.line = Some(before.matches('\n').count() + 1);
+        self.column = Some(before[line_start..].chars().count() + 1);
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "element {}", self.element)?;
+        if let Some(id) = &self.id {
+            write!(f, " (#{id})")?;
+        }
+        match (self.line, self.column, self.offset) {
+            (Some(line), Some(column), _) => write!(f, " at line {line}, column {column}"),
+            (_, _, Some(offset)) => write!(f, " at byte {offset}"),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("SVG parse error: {0}")]
     SvgParse(String),
+    #[cfg(feature = "usvg")]
     #[error("Could not simplify SVG with usvg: {0}")]
     Usvg(#[from] usvg::Error),
+    #[error("Encoding error: {0}")]
+    Encoding(String),
     #[error("SVG path parse error: {0}")]
     PathParse(String),
     #[error("Polyline error: {0}")]
     Polyline(String),
     #[error("Transform error: {0}")]
     Transform(String),
+    #[error("Invalid selector: {0}")]
+    Selector(String),
+    #[error("Device error: {0}")]
+    Device(String),
+    #[error("Export error: {0}")]
+    Export(String),
+    #[error("{} polyline(s) exceed the machine envelope", .0.len())]
+    Envelope(Vec<EnvelopeViolation>),
+    #[error("{} problem(s) prevent a faithful conversion", .0.len())]
+    Strict(Vec<StrictViolation>),
+    #[error("Import error: {0}")]
+    Import(String),
+    #[error("Invalid pipeline configuration: {0}")]
+    Pipeline(String),
+    #[error("Resource limit exceeded: {0}")]
+    Limit(String),
+    #[error("Parsing was cancelled")]
+    Cancelled,
+    #[error("{source} in {context}")]
+    Element {
+        context: ErrorContext,
+        source: Box<Error>,
+    },
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "watch")]
+    #[error("File watch error: {0}")]
+    Watch(String),
+}
+
+impl Error {
+    /// Return the element that caused the error, if known.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Element { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+}
+
+impl From<PathError> for Error {
+    fn from(e: PathError) -> Self {
+        match e {
+            PathError::Parse(message) => Self::PathParse(message),
+            PathError::Polyline(message) => Self::Polyline(message),
+        }
+    }
 }
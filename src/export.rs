@@ -0,0 +1,334 @@
+//! Write polylines to files, sockets or in-memory buffers.
+//!
+//! All built-in exporters implement the [`Exporter`] trait, which writes to
+//! any [`io::Write`](std::io::Write) implementation. Implement the trait
+//! yourself to plug in a custom output format.
+
+use std::io::Write;
+
+use quick_xml::escape::escape;
+
+use crate::{bounding_box_of, Error, Polyline};
+
+/// A writer that serializes polylines into some output format.
+pub trait Exporter {
+    /// Write `polylines` to `w`.
+    fn export<W: Write>(&self, polylines: &[Polyline], w: W) -> Result<(), Error>;
+
+    /// Export `polylines` into a string.
+    fn export_to_string(&self, polylines: &[Polyline]) -> Result<String, Error> {
+        let mut buf = Vec::new();
+        self.export(polylines, &mut buf)?;
+        String::from_utf8(buf).map_err(|e| Error::Export(e.to_string()))
+    }
+}
+
+/// Write one CSV row per point, with the columns `polyline`, `x` and `y`.
+///
+/// The `polyline` column contains the index of the polyline the point belongs
+/// to.
+#[derive(Debug, Clone, Default)]
+pub struct CsvExporter;
+
+impl Exporter for CsvExporter {
+    fn export<W: Write>(&self, polylines: &[Polyline], mut w: W) -> Result<(), Error> {
+        writeln!(w, "polyline,x,y")?;
+        for (i, polyline) in polylines.iter().enumerate() {
+            for p in polyline {
+                writeln!(w, "{},{},{}", i, p.x, p.y)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write a JSON array of polylines, each being an array of `{"x": .., "y":
+/// ..}` objects.
+///
+/// This is the same representation that serde produces for
+/// `Vec<Vec<CoordinatePair>>`. Non-finite coordinates are written as `null`.
+#[derive(Debug, Clone, Default)]
+pub struct JsonExporter;
+
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+impl Exporter for JsonExporter {
+    fn export<W: Write>(&self, polylines: &[Polyline], mut w: W) -> Result<(), Error> {
+        write!(w, "[")?;
+        for (i, polyline) in polylines.iter().enumerate() {
+            if i > 0 {
+                write!(w, ",")?;
+            }
+            write!(w, "[")?;
+            for (j, p) in polyline.iter().enumerate() {
+                if j > 0 {
+                    write!(w, ",")?;
+                }
+                write!(
+                    w,
+                    r#"{{"x":{},"y":{}}}"#,
+                    json_number(p.x),
+                    json_number(p.y)
+                )?;
+            }
+            write!(w, "]")?;
+        }
+        writeln!(w, "]")?;
+        Ok(())
+    }
+}
+
+/// Write an SVG document with one `<polyline>` element per polyline.
+///
+/// The `viewBox` is set to the extents of the drawing.
+#[derive(Debug, Clone)]
+pub struct SvgExporter {
+    /// Stroke color of the polylines.
+    pub stroke: String,
+
+    /// Stroke width of the polylines.
+    pub stroke_width: f64,
+}
+
+impl Default for SvgExporter {
+    fn default() -> Self {
+        Self {
+            stroke: "black".to_string(),
+            stroke_width: 1.0,
+        }
+    }
+}
+
+impl Exporter for SvgExporter {
+    fn export<W: Write>(&self, polylines: &[Polyline], mut w: W) -> Result<(), Error> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        match bounding_box_of(polylines) {
+            Some(bounds) => writeln!(
+                w,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" viewBox="{} {} {} {}">"#,
+                bounds.min_x,
+                bounds.min_y,
+                bounds.width(),
+                bounds.height()
+            )?,
+            None => writeln!(
+                w,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1">"#
+            )?,
+        }
+        let stroke = escape(self.stroke.as_bytes());
+        let stroke = String::from_utf8_lossy(&stroke);
+        for polyline in polylines {
+            let points: Vec<String> = polyline
+                .iter()
+                .map(|p| format!("{},{}", p.x, p.y))
+                .collect();
+            writeln!(
+                w,
+                r#"  <polyline points="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+                points.join(" "),
+                stroke,
+                self.stroke_width
+            )?;
+        }
+        writeln!(w, "</svg>")?;
+        Ok(())
+    }
+}
+
+/// Write G-code for a pen plotter or engraver.
+///
+/// The tool is lifted to `travel_z` before moving to the start of every
+/// polyline, then lowered to `draw_z`. Coordinates are written in
+/// millimeters, using absolute positioning.
+#[derive(Debug, Clone)]
+pub struct GcodeExporter {
+    /// Z position while travelling between polylines.
+    pub travel_z: f64,
+
+    /// Z position while drawing.
+    pub draw_z: f64,
+
+    /// Feed rate while drawing (in mm/min).
+    pub feed_rate: f64,
+
+    /// Number of decimal places written for coordinates.
+    pub precision: usize,
+
+    /// Maximum depth cut in a single pass by
+    /// [`export_with_depths`](Self::export_with_depths). Deeper polylines are
+    /// cut in multiple passes. `None` cuts every polyline in one pass.
+    pub depth_per_pass: Option<f64>,
+}
+
+impl Default for GcodeExporter {
+    fn default() -> Self {
+        Self {
+            travel_z: 5.0,
+            draw_z: 0.0,
+            feed_rate: 1000.0,
+            precision: 3,
+            depth_per_pass: None,
+        }
+    }
+}
+
+impl GcodeExporter {
+    fn write_header<W: Write>(w: &mut W) -> Result<(), Error> {
+        writeln!(w, "G21")?;
+        writeln!(w, "G90")?;
+        Ok(())
+    }
+
+    /// Draw a polyline at height `z`.
+    fn write_polyline<W: Write>(
+        &self,
+        w: &mut W,
+        polyline: &Polyline,
+        z: f64,
+    ) -> Result<(), Error> {
+        let prec = self.precision;
+        let mut points = polyline.iter();
+        let Some(first) = points.next() else {
+            return Ok(());
+        };
+        writeln!(w, "G0 Z{:.*}", prec, self.travel_z)?;
+        writeln!(w, "G0 X{:.*} Y{:.*}", prec, first.x, prec, first.y)?;
+        writeln!(w, "G1 Z{:.*} F{}", prec, z, self.feed_rate)?;
+        for p in points {
+            writeln!(w, "G1 X{:.*} Y{:.*}", prec, p.x, prec, p.y)?;
+        }
+        Ok(())
+    }
+
+    fn write_footer<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        writeln!(w, "G0 Z{:.*}", self.precision, self.travel_z)?;
+        Ok(())
+    }
+
+    /// Write G-code that cuts every polyline at its depth below `draw_z`,
+    /// e.g. as returned by [`parse_with_depths`](crate::depth::parse_with_depths).
+    ///
+    /// If [`depth_per_pass`](Self::depth_per_pass) is set, deep polylines are
+    /// cut in multiple passes of increasing depth.
+    pub fn export_with_depths<W: Write>(
+        &self,
+        polylines: &[(Polyline, f64)],
+        mut w: W,
+    ) -> Result<(), Error> {
+        Self::write_header(&mut w)?;
+        for (polyline, depth) in polylines {
+            let depth = depth.max(0.0);
+            match self.depth_per_pass {
+                Some(step) if step > 0.0 && depth > step => {
+                    let mut pass_depth = 0.0;
+                    while pass_depth < depth {
+                        pass_depth = (pass_depth + step).min(depth);
+                        self.write_polyline(&mut w, polyline, self.draw_z - pass_depth)?;
+                    }
+                }
+                _ => self.write_polyline(&mut w, polyline, self.draw_z - depth)?,
+            }
+        }
+        self.write_footer(&mut w)
+    }
+}
+
+impl Exporter for GcodeExporter {
+    fn export<W: Write>(&self, polylines: &[Polyline], mut w: W) -> Result<(), Error> {
+        Self::write_header(&mut w)?;
+        for polyline in polylines {
+            self.write_polyline(&mut w, polyline, self.draw_z)?;
+        }
+        self.write_footer(&mut w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoordinatePair;
+
+    fn polylines() -> Vec<Polyline> {
+        vec![
+            Polyline::from_vec(vec![
+                CoordinatePair::new(0.0, 0.0),
+                CoordinatePair::new(1.5, 2.0),
+            ]),
+            Polyline::from_vec(vec![CoordinatePair::new(3.0, 4.0)]),
+        ]
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            CsvExporter.export_to_string(&polylines()).unwrap(),
+            "polyline,x,y\n0,0,0\n0,1.5,2\n1,3,4\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            JsonExporter.export_to_string(&polylines()).unwrap(),
+            "[[{\"x\":0,\"y\":0},{\"x\":1.5,\"y\":2}],[{\"x\":3,\"y\":4}]]\n"
+        );
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = SvgExporter::default()
+            .export_to_string(&polylines())
+            .unwrap();
+        assert!(svg.contains(r#"viewBox="0 0 3 4""#));
+        assert!(svg.contains(r#"<polyline points="0,0 1.5,2" fill="none""#));
+
+        let exporter = SvgExporter {
+            stroke: r#"red"/><script>"#.to_string(),
+            ..Default::default()
+        };
+        let svg = exporter.export_to_string(&polylines()).unwrap();
+        assert!(svg.contains(r#"stroke="red&quot;/&gt;&lt;script&gt;""#));
+    }
+
+    #[test]
+    fn test_gcode() {
+        let gcode = GcodeExporter::default()
+            .export_to_string(&polylines()[..1])
+            .unwrap();
+        assert_eq!(
+            gcode,
+            "G21\nG90\nG0 Z5.000\nG0 X0.000 Y0.000\nG1 Z0.000 F1000\nG1 X1.500 Y2.000\nG0 Z5.000\n"
+        );
+    }
+
+    #[test]
+    fn test_gcode_with_depths() {
+        let exporter = GcodeExporter {
+            precision: 1,
+            depth_per_pass: Some(1.0),
+            ..Default::default()
+        };
+        let polyline = Polyline::from_vec(vec![(0.0, 0.0).into(), (1.0, 0.0).into()]);
+        let mut out = Vec::new();
+        exporter
+            .export_with_depths(&[(polyline, 2.5)], &mut out)
+            .unwrap();
+        let plunges: Vec<_> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .filter(|line| line.starts_with("G1 Z"))
+            .map(str::to_string)
+            .collect();
+        assert_eq!(
+            plunges,
+            vec!["G1 Z-1.0 F1000", "G1 Z-2.0 F1000", "G1 Z-2.5 F1000"]
+        );
+    }
+}
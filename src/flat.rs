@@ -0,0 +1,128 @@
+//! All polylines in one contiguous buffer.
+
+use crate::{CoordinatePair, Polyline};
+
+/// The points of many polylines stored in a single contiguous buffer,
+/// together with an offset table.
+///
+/// Polyline `i` consists of the points `offsets[i]..offsets[i + 1]`. This
+/// layout maps directly onto `NumPy` arrays or GPU vertex buffers and avoids
+/// one allocation per polyline.
+#[derive(Debug, PartialEq)]
+pub struct FlatPolylines {
+    /// All points, polyline after polyline.
+    points: Vec<CoordinatePair>,
+
+    /// Start index of every polyline in `points`, followed by the total
+    /// number of points. This always contains at least one element.
+    offsets: Vec<usize>,
+}
+
+impl FlatPolylines {
+    /// Copy the points of `polylines` into a single buffer.
+    pub fn from_polylines(polylines: &[Polyline]) -> Self {
+        let total = polylines.iter().map(|p| p.len()).sum();
+        let mut points = Vec::with_capacity(total);
+        let mut offsets = Vec::with_capacity(polylines.len() + 1);
+        offsets.push(0);
+        for polyline in polylines {
+            points.extend_from_slice(polyline);
+            offsets.push(points.len());
+        }
+        Self { points, offsets }
+    }
+
+    /// Return all points, polyline after polyline.
+    pub fn points(&self) -> &[CoordinatePair] {
+        &self.points
+    }
+
+    /// Return the start index of every polyline in
+    /// [`points`](Self::points), followed by the total number of points.
+    ///
+    /// This always contains at least one element.
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// Return the number of polylines.
+    pub fn len(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    /// Return whether there are no polylines.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the points of polyline `index`.
+    pub fn get(&self, index: usize) -> Option<&[CoordinatePair]> {
+        let start = *self.offsets.get(index)?;
+        let end = *self.offsets.get(index + 1)?;
+        self.points.get(start..end)
+    }
+
+    /// Iterate over the polylines as slices of points.
+    pub fn iter(&self) -> impl Iterator<Item = &[CoordinatePair]> {
+        self.offsets
+            .windows(2)
+            .map(move |w| &self.points[w[0]..w[1]])
+    }
+
+    /// Return the length (number of points) of every polyline.
+    pub fn lengths(&self) -> Vec<usize> {
+        self.offsets.windows(2).map(|w| w[1] - w[0]).collect()
+    }
+
+    /// Return all coordinates as a flat slice `[x0, y0, x1, y1, ...]`
+    /// without copying.
+    pub fn as_flat_slice(&self) -> &[f64] {
+        // SAFETY: See `Polyline::as_flat_slice`.
+        unsafe {
+            std::slice::from_raw_parts(self.points.as_ptr().cast::<f64>(), self.points.len() * 2)
+        }
+    }
+}
+
+impl Default for FlatPolylines {
+    fn default() -> Self {
+        Self::from_polylines(&[])
+    }
+}
+
+impl From<&[Polyline]> for FlatPolylines {
+    fn from(polylines: &[Polyline]) -> Self {
+        Self::from_polylines(polylines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flat_polylines() {
+        let polylines = vec![
+            Polyline::from_vec(vec![(0.0, 1.0).into(), (2.0, 3.0).into()]),
+            Polyline::new(),
+            Polyline::from_vec(vec![(4.0, 5.0).into()]),
+        ];
+        let flat = FlatPolylines::from_polylines(&polylines);
+        assert_eq!(flat.len(), 3);
+        assert_eq!(flat.offsets(), &[0, 2, 2, 3]);
+        assert_eq!(flat.points().len(), 3);
+        assert_eq!(flat.lengths(), vec![2, 0, 1]);
+        assert_eq!(flat.as_flat_slice(), &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(flat.get(2), Some(&[CoordinatePair::new(4.0, 5.0)][..]));
+        assert_eq!(flat.get(3), None);
+        let roundtrip: Vec<_> = flat
+            .iter()
+            .map(|points| Polyline::from_vec(points.to_vec()))
+            .collect();
+        assert_eq!(roundtrip, polylines);
+
+        let empty = FlatPolylines::from_polylines(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.iter().count(), 0);
+    }
+}
@@ -0,0 +1,1652 @@
+//! Flatten SVG path expressions into polylines.
+//!
+//! This module only needs `core` and `alloc`, so it is available without the
+//! `std` feature, e.g. to flatten path expressions received over a serial
+//! connection on the microcontroller of a plotter:
+//!
+//! ```
+//! use svg2polylines::flatten::flatten;
+//!
+//! let polylines = flatten("M 0,0 L 10,0 Q 20,0 20,10", 0.15).unwrap();
+//! assert_eq!(polylines.len(), 1);
+//! assert_eq!(polylines[0][0], (0.0, 0.0).into());
+//! ```
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::{
+    convert::TryInto,
+    f64::consts::PI,
+    fmt, mem,
+    ops::{Deref, DerefMut, Index},
+    slice,
+    str::{self, FromStr},
+};
+
+use log::trace;
+use lyon_geom::{
+    euclid::{Point2D, Transform2D},
+    CubicBezierSegment, QuadraticBezierSegment,
+};
+
+// Newer versions of `core` provide the float methods themselves
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::Float;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An error while flattening a path expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// The path expression is invalid or contains an unsupported segment.
+    Parse(String),
+    /// A polyline operation failed.
+    Polyline(String),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(message) => write!(f, "SVG path parse error: {message}"),
+            Self::Polyline(message) => write!(f, "Polyline error: {message}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PathError {}
+
+/// A pair of x and y coordinates.
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(C)]
+pub struct CoordinatePair {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl CoordinatePair {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    /// Apply a 2D transformation.
+    pub fn transform(&mut self, t: Transform2D<f64, f64, f64>) {
+        let Point2D { x, y, .. } = t.transform_point(Point2D::new(self.x, self.y));
+        self.x = x;
+        self.y = y;
+    }
+}
+
+impl From<(f64, f64)> for CoordinatePair {
+    fn from(val: (f64, f64)) -> Self {
+        Self { x: val.0, y: val.1 }
+    }
+}
+
+/// The orientation of a polyline, see [`Polyline::winding`].
+///
+/// The orientation is given as displayed, with the y axis pointing down like
+/// in SVG documents. In a coordinate system with the y axis pointing up, it
+/// is the other way around.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// How to decide whether a point lies inside of polylines that overlap
+/// themselves or each other, like the SVG `fill-rule` property.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum FillRule {
+    /// A point is inside if the polylines wind around it at least once in
+    /// total, counting clockwise and counterclockwise turns with opposite
+    /// signs. This is the default of SVG.
+    #[default]
+    NonZero,
+
+    /// A point is inside if a ray from it crosses the polylines an odd
+    /// number of times, so that nested polylines alternate between filled
+    /// and holes.
+    EvenOdd,
+}
+
+/// Return whether `p` lies inside the region bounded by the rings according
+/// to the fill rule. Every ring is implicitly closed.
+///
+/// Points on the border may be inside or outside.
+pub(crate) fn rings_contain<'a>(
+    rings: impl IntoIterator<Item = &'a [CoordinatePair]>,
+    p: CoordinatePair,
+    rule: FillRule,
+) -> bool {
+    let mut winding = 0_i64;
+    for ring in rings {
+        let Some(&last) = ring.last() else {
+            continue;
+        };
+        let mut a = last;
+        for &b in ring {
+            // The side of the edge from `a` to `b` on which `p` lies
+            let side = (b.x - a.x) * (p.y - a.y) - (p.x - a.x) * (b.y - a.y);
+            if a.y <= p.y && b.y > p.y && side > 0.0 {
+                winding += 1;
+            } else if a.y > p.y && b.y <= p.y && side < 0.0 {
+                winding -= 1;
+            }
+            a = b;
+        }
+    }
+    match rule {
+        FillRule::NonZero => winding != 0,
+        FillRule::EvenOdd => winding % 2 != 0,
+    }
+}
+
+/// Return whether `p` lies inside the region bounded by the polylines, e.g.
+/// a shape with holes, according to the fill rule. Every polyline is
+/// implicitly closed, see [`Polyline::contains_point`].
+///
+/// ```
+/// use svg2polylines::{point_in_polylines, FillRule, Polyline};
+///
+/// let square = |min: f64, max: f64| {
+///     Polyline::from_vec(vec![
+///         (min, min).into(),
+///         (max, min).into(),
+///         (max, max).into(),
+///         (min, max).into(),
+///     ])
+/// };
+/// let polylines = [square(0.0, 10.0), square(2.0, 4.0)];
+/// let p = (3.0, 3.0).into();
+/// assert!(point_in_polylines(&polylines, p, FillRule::NonZero));
+/// assert!(!point_in_polylines(&polylines, p, FillRule::EvenOdd));
+/// ```
+pub fn point_in_polylines(polylines: &[Polyline], p: CoordinatePair, rule: FillRule) -> bool {
+    rings_contain(
+        polylines.iter().map(|polyline| &polyline.points[..]),
+        p,
+        rule,
+    )
+}
+
+/// A polyline is a vector of [`CoordinatePair`] instances.
+///
+/// Note: This is a wrapper around a [`Vec`] that can be iterated and indexed.
+/// To get access to the underlying vector, use [`.as_ref()`](Polyline::as_ref)
+/// or [`.unwrap()`](Polyline::unwrap). Whether the polyline is closed is
+/// stored separately, see [`is_closed`](Polyline::is_closed).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyline {
+    pub(crate) points: Vec<CoordinatePair>,
+    pub(crate) closed: bool,
+}
+
+impl Polyline {
+    /// Create a new, empty polyline.
+    pub fn new() -> Self {
+        Polyline::from_vec(vec![])
+    }
+
+    /// Create a new polyline from a vector.
+    pub fn from_vec(vec: Vec<CoordinatePair>) -> Self {
+        Polyline {
+            points: vec,
+            closed: false,
+        }
+    }
+
+    /// Whether the polyline is closed, i.e. it ends with a `ClosePath`
+    /// segment or was closed with [`close`](Self::close). The last point of
+    /// a closed polyline equals its first point.
+    ///
+    /// Polylines that merely end at their first point are not closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Close the polyline by repeating its first point at the end, unless
+    /// the last point equals the first point already.
+    ///
+    /// Empty polylines cannot be closed.
+    pub fn close(&mut self) {
+        if let Some(&first) = self.points.first() {
+            if self.points.last() != Some(&first) {
+                self.points.push(first);
+            }
+            self.closed = true;
+        }
+    }
+
+    /// Reverse the direction of the polyline in place, e.g. to start
+    /// drawing it at its end.
+    ///
+    /// A closed polyline stays closed and keeps its start point, but its
+    /// [`winding`](Self::winding) is inverted.
+    pub fn reverse(&mut self) {
+        self.points.reverse();
+    }
+
+    /// Return the polyline with its direction reversed, see
+    /// [`reverse`](Self::reverse).
+    #[must_use]
+    pub fn reversed(mut self) -> Polyline {
+        self.reverse();
+        self
+    }
+
+    /// Apply a transformation to all coordinate pairs, e.g. a calibration
+    /// matrix or an offset. See [`transform_all`] to transform several
+    /// polylines in place.
+    #[must_use]
+    pub fn transform(mut self, t: Transform2D<f64, f64, f64>) -> Self {
+        for p in &mut self.points {
+            p.transform(t);
+        }
+        self
+    }
+
+    /// Unwrap and return the inner vector.
+    #[must_use]
+    pub fn unwrap(self) -> Vec<CoordinatePair> {
+        self.points
+    }
+
+    /// Return the total Euclidean length of the polyline, i.e. the sum of
+    /// the distances between consecutive points.
+    pub fn length(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|w| (w[1].x - w[0].x).hypot(w[1].y - w[0].y))
+            .sum()
+    }
+
+    /// Return the signed area enclosed by the polyline, which is positive for
+    /// clockwise polylines (see [`Winding`]).
+    ///
+    /// The polyline is treated as closed, i.e. its last point is connected to
+    /// its first point. Self-intersecting polylines result in the sum of the
+    /// signed areas of their loops.
+    pub fn signed_area(&self) -> f64 {
+        let Some(&last) = self.points.last() else {
+            return 0.0;
+        };
+        let mut prev = last;
+        let mut doubled_area = 0.0;
+        for &p in &self.points {
+            doubled_area += prev.x * p.y - p.x * prev.y;
+            prev = p;
+        }
+        doubled_area / 2.0
+    }
+
+    /// Return the orientation of the polyline, or `None` if it does not
+    /// enclose an area, see [`signed_area`](Self::signed_area).
+    pub fn winding(&self) -> Option<Winding> {
+        let area = self.signed_area();
+        if area > 0.0 {
+            Some(Winding::Clockwise)
+        } else if area < 0.0 {
+            Some(Winding::CounterClockwise)
+        } else {
+            None
+        }
+    }
+
+    /// Return whether `p` lies inside the polyline according to the fill
+    /// rule. The polyline is implicitly closed, and the rule only matters if
+    /// it crosses itself.
+    ///
+    /// Points on the border may be inside or outside.
+    pub fn contains_point(&self, p: CoordinatePair, rule: FillRule) -> bool {
+        rings_contain([&self.points[..]], p, rule)
+    }
+
+    /// Return the centroid of the polyline, or `None` if it has no points.
+    ///
+    /// If the polyline encloses an area (see
+    /// [`signed_area`](Self::signed_area)), this is the centroid of that
+    /// area. Otherwise it is the centroid of the line segments, or the first
+    /// point if all points are equal.
+    pub fn centroid(&self) -> Option<CoordinatePair> {
+        let &first = self.points.first()?;
+        let area = self.signed_area();
+        if area != 0.0 {
+            let mut prev = self.points[self.points.len() - 1];
+            let (mut x, mut y) = (0.0, 0.0);
+            for &p in &self.points {
+                let cross = prev.x * p.y - p.x * prev.y;
+                x += (prev.x + p.x) * cross;
+                y += (prev.y + p.y) * cross;
+                prev = p;
+            }
+            return Some(CoordinatePair::new(x / (6.0 * area), y / (6.0 * area)));
+        }
+        let (mut x, mut y, mut length) = (0.0, 0.0, 0.0);
+        for w in self.points.windows(2) {
+            let segment = (w[1].x - w[0].x).hypot(w[1].y - w[0].y);
+            x += (w[0].x + w[1].x) * segment;
+            y += (w[0].y + w[1].y) * segment;
+            length += segment;
+        }
+        if length > 0.0 {
+            // The sums are of doubled segment midpoints
+            Some(CoordinatePair::new(x / (2.0 * length), y / (2.0 * length)))
+        } else {
+            Some(first)
+        }
+    }
+
+    /// Return the convex hull of the points of the polyline as a closed,
+    /// clockwise polyline (see [`Winding`]).
+    ///
+    /// If all points lie on a line, the result is the open polyline between
+    /// the two extreme points, or a single point if all points are equal.
+    #[must_use]
+    pub fn convex_hull(&self) -> Polyline {
+        let mut points = self.points.clone();
+        points.sort_unstable_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        points.dedup();
+        if points.len() < 3 {
+            return Polyline::from_vec(points);
+        }
+
+        // Andrew's monotone chain: build the lower and then the upper hull,
+        // dropping points that do not make a counterclockwise turn (in a
+        // coordinate system with the y axis pointing up)
+        let cross = |o: CoordinatePair, a: CoordinatePair, b: CoordinatePair| {
+            (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+        };
+        let mut hull: Vec<CoordinatePair> = Vec::with_capacity(points.len() + 1);
+        for &p in &points {
+            while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        let lower = hull.len() + 1;
+        for &p in points.iter().rev().skip(1) {
+            while hull.len() >= lower && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        if hull.len() < 4 {
+            // Collinear points, the upper hull went back to the first point
+            hull.truncate(2);
+            return Polyline::from_vec(hull);
+        }
+        Polyline {
+            points: hull,
+            closed: true,
+        }
+    }
+
+    /// Return a new polyline with `n` points that are evenly spaced along the
+    /// path of this polyline.
+    ///
+    /// The first and last point are always preserved (if `n >= 2`).
+    #[must_use]
+    pub fn resample(&self, n: usize) -> Polyline {
+        let points = &self.points;
+        let first = match points.first() {
+            Some(first) if n > 0 => *first,
+            _ => return Polyline::new(),
+        };
+        let segment_lengths: Vec<f64> = points
+            .windows(2)
+            .map(|w| (w[1].x - w[0].x).hypot(w[1].y - w[0].y))
+            .collect();
+        let total: f64 = segment_lengths.iter().sum();
+        if n == 1 || total == 0.0 {
+            return Polyline::from_vec(vec![first; n]);
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let step = total / (n - 1) as f64;
+        let mut resampled = Vec::with_capacity(n);
+        resampled.push(first);
+        let mut segment = 0;
+        let mut segment_start = 0.0;
+        for i in 1..n - 1 {
+            #[allow(clippy::cast_precision_loss)]
+            let target = step * i as f64;
+            while segment < segment_lengths.len() - 1
+                && segment_start + segment_lengths[segment] < target
+            {
+                segment_start += segment_lengths[segment];
+                segment += 1;
+            }
+            let length = segment_lengths[segment];
+            let t = if length > 0.0 {
+                (target - segment_start) / length
+            } else {
+                0.0
+            };
+            let (a, b) = (points[segment], points[segment + 1]);
+            resampled.push(CoordinatePair::new(
+                a.x + (b.x - a.x) * t,
+                a.y + (b.y - a.y) * t,
+            ));
+        }
+        resampled.push(points[points.len() - 1]);
+        Polyline {
+            points: resampled,
+            closed: self.closed,
+        }
+    }
+
+    /// Return the coordinates as a flat slice `[x0, y0, x1, y1, ...]` without
+    /// copying.
+    pub fn as_flat_slice(&self) -> &[f64] {
+        // SAFETY: `CoordinatePair` is `#[repr(C)]` and consists of two `f64`
+        // fields without padding, so `n` pairs have the same layout as `2 * n`
+        // `f64` values.
+        unsafe { slice::from_raw_parts(self.points.as_ptr().cast::<f64>(), self.points.len() * 2) }
+    }
+
+    /// Return the coordinates as a slice of `[x, y]` arrays without copying.
+    pub fn as_point_array(&self) -> &[[f64; 2]] {
+        // SAFETY: See `as_flat_slice`. `[f64; 2]` has the same layout as
+        // `CoordinatePair`.
+        unsafe { slice::from_raw_parts(self.points.as_ptr().cast::<[f64; 2]>(), self.points.len()) }
+    }
+
+    /// Return the coordinates as `[x, y]` arrays of `f32`, e.g. for motion
+    /// controllers without double precision floats.
+    ///
+    /// Coordinates are rounded to the nearest `f32`, and coordinates outside
+    /// of its range become infinite.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn to_f32(&self) -> Vec<[f32; 2]> {
+        self.points
+            .iter()
+            .map(|p| [p.x as f32, p.y as f32])
+            .collect()
+    }
+
+    /// Return the coordinates as `[x, y]` arrays of integers, e.g. for
+    /// stepper based controllers that work with fixed-point positions.
+    ///
+    /// The coordinates are multiplied by `scale` and rounded to the nearest
+    /// integer, so with coordinates in millimeters (see
+    /// [`ParseOptions::unit`](crate::ParseOptions::unit)), a scale of
+    /// `1000.0` results in micrometers. Coordinates outside of the range of
+    /// `i64` saturate.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn to_fixed(&self, scale: f64) -> Vec<[i64; 2]> {
+        self.points
+            .iter()
+            .map(|p| [(p.x * scale).round() as i64, (p.y * scale).round() as i64])
+            .collect()
+    }
+}
+
+/// Return the total length of all polylines, see [`Polyline::length`].
+///
+/// The distances between the polylines (e.g. pen-up travel) are not included.
+pub fn total_length(polylines: &[Polyline]) -> f64 {
+    polylines.iter().map(Polyline::length).sum()
+}
+
+/// Apply a transformation to all coordinate pairs of the polylines in place.
+///
+/// ```
+/// use svg2polylines::{transform_all, Polyline, Transform2D};
+///
+/// let mut polylines = vec![Polyline::from_vec(vec![(1.0, 2.0).into()])];
+/// transform_all(&mut polylines, Transform2D::translation(10.0, 0.0));
+/// assert_eq!(polylines[0][0], (11.0, 2.0).into());
+/// ```
+pub fn transform_all(polylines: &mut [Polyline], t: Transform2D<f64, f64, f64>) {
+    for p in polylines
+        .iter_mut()
+        .flat_map(|polyline| polyline.points.iter_mut())
+    {
+        p.transform(t);
+    }
+}
+
+/// Create a polyline from coordinate tuples, to keep tests short.
+#[cfg(all(test, feature = "std"))]
+pub(crate) fn line(points: &[(f64, f64)]) -> Polyline {
+    Polyline::from_vec(points.iter().map(|&p| p.into()).collect())
+}
+
+// Guarantee the layout that the flat views rely on
+const _: () = assert!(mem::size_of::<CoordinatePair>() == mem::size_of::<[f64; 2]>());
+const _: () = assert!(mem::align_of::<CoordinatePair>() == mem::align_of::<[f64; 2]>());
+
+impl From<CoordinatePair> for [f64; 2] {
+    fn from(p: CoordinatePair) -> Self {
+        [p.x, p.y]
+    }
+}
+
+impl From<Polyline> for Vec<[f64; 2]> {
+    /// Convert the polyline into a vector of `[x, y]` arrays, reusing the
+    /// allocation.
+    fn from(polyline: Polyline) -> Self {
+        let mut points = mem::ManuallyDrop::new(polyline.points);
+        let (ptr, len, capacity) = (points.as_mut_ptr(), points.len(), points.capacity());
+        // SAFETY: The element types have the same size and alignment (see
+        // above), and the original vector is not dropped.
+        unsafe { Vec::from_raw_parts(ptr.cast::<[f64; 2]>(), len, capacity) }
+    }
+}
+
+impl AsRef<Vec<CoordinatePair>> for Polyline {
+    fn as_ref(&self) -> &Vec<CoordinatePair> {
+        &self.points
+    }
+}
+
+impl Default for Polyline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Index<usize> for Polyline {
+    type Output = CoordinatePair;
+
+    fn index(&self, id: usize) -> &Self::Output {
+        &self.points[id]
+    }
+}
+
+impl IntoIterator for Polyline {
+    type Item = CoordinatePair;
+    type IntoIter = vec::IntoIter<Self::Item>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Polyline {
+    type Item = &'a CoordinatePair;
+    type IntoIter = slice::Iter<'a, CoordinatePair>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.iter()
+    }
+}
+
+impl Deref for Polyline {
+    type Target = Vec<CoordinatePair>;
+    fn deref(&self) -> &Self::Target {
+        &self.points
+    }
+}
+
+impl DerefMut for Polyline {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.points
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct CurrentLine {
+    /// The polyline containing the coordinate pairs for the current line.
+    pub(crate) line: Polyline,
+
+    /// This is set to the start coordinates of the previous polyline if the
+    /// path expression contains multiple polylines.
+    prev_end: Option<CoordinatePair>,
+}
+
+/// Simple data structure that acts as a [`Polyline`] buffer.
+impl CurrentLine {
+    pub(crate) fn new() -> Self {
+        Self {
+            line: Polyline::new(),
+            prev_end: None,
+        }
+    }
+
+    /// Add a [`CoordinatePair`] to the internal polyline.
+    pub(crate) fn add_absolute(&mut self, pair: CoordinatePair) {
+        self.line.push(pair);
+        self.line.closed = false;
+    }
+
+    /// Add a relative [`CoordinatePair`] to the internal polyline.
+    pub(crate) fn add_relative(&mut self, pair: CoordinatePair) {
+        if let Some(last) = self.line.last() {
+            let cp = CoordinatePair::new(last.x + pair.x, last.y + pair.y);
+            self.add_absolute(cp);
+        } else if let Some(last) = self.prev_end {
+            self.add_absolute(CoordinatePair::new(last.x + pair.x, last.y + pair.y));
+        } else {
+            self.add_absolute(pair);
+        }
+    }
+
+    /// Add a [`CoordinatePair`] to the internal polyline.
+    pub(crate) fn add(&mut self, abs: bool, pair: CoordinatePair) {
+        if abs {
+            self.add_absolute(pair);
+        } else {
+            self.add_relative(pair);
+        }
+    }
+
+    /// A polyline is only valid if it has more than 1 [`CoordinatePair`].
+    pub(crate) fn is_valid(&self) -> bool {
+        self.line.len() > 1
+    }
+
+    /// Return the last [`CoordinatePair`] (if the line is not empty).
+    pub(crate) fn last_pair(&self) -> Option<CoordinatePair> {
+        self.line.last().copied()
+    }
+
+    /// Return the last x coordinate (if the line is not empty).
+    pub(crate) fn last_x(&self) -> Option<f64> {
+        self.line.last().map(|pair| pair.x)
+    }
+
+    /// Return the last y coordinate (if the line is not empty).
+    pub(crate) fn last_y(&self) -> Option<f64> {
+        self.line.last().map(|pair| pair.y)
+    }
+
+    /// Close the line by adding the first entry to the end.
+    pub(crate) fn close(&mut self) -> Result<(), PathError> {
+        if self.line.len() < 2 {
+            Err(PathError::Polyline(
+                "Lines with less than 2 coordinate pairs cannot be closed.".into(),
+            ))
+        } else {
+            let first = self.line[0];
+            self.line.push(first);
+            self.line.closed = true;
+            self.prev_end = Some(first);
+            Ok(())
+        }
+    }
+
+    /// Reserve capacity for `additional` more [`CoordinatePair`]s.
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.line.points.reserve_exact(additional);
+    }
+
+    /// Replace the internal [`Polyline`] with a new instance and return the
+    /// previously stored [`Polyline`].
+    pub(crate) fn finish(&mut self) -> Polyline {
+        self.prev_end = self.line.last().copied();
+        let mut tmp = Polyline::new();
+        mem::swap(&mut self.line, &mut tmp);
+        tmp
+    }
+}
+
+/// The estimated number of points of every subpath, in the order of their
+/// `MoveTo` segments.
+pub(crate) fn subpath_estimates(expr: &str) -> impl Iterator<Item = usize> + '_ {
+    expr.split(['M', 'm']).skip(1).map(estimate_points)
+}
+
+/// Estimate the number of points of a subpath from the numbers in its path
+/// data, assuming two numbers per point, plus one point for closing it.
+///
+/// Curves are usually flattened into more points, so this is a lower bound
+/// for most paths.
+fn estimate_points(subpath: &str) -> usize {
+    let mut numbers = 0;
+    let mut closes = 0;
+    let mut in_number = false;
+    for b in subpath.bytes() {
+        let numeric = b.is_ascii_digit() || b == b'.';
+        if numeric && !in_number {
+            numbers += 1;
+        }
+        if b == b'Z' || b == b'z' {
+            closes += 1;
+        }
+        in_number = numeric;
+    }
+    numbers / 2 + closes
+}
+
+/// A segment of a path expression.
+///
+/// The variants are the same as those of `svgtypes::PathSegment`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(missing_docs)]
+pub enum PathSegment {
+    MoveTo {
+        abs: bool,
+        x: f64,
+        y: f64,
+    },
+    LineTo {
+        abs: bool,
+        x: f64,
+        y: f64,
+    },
+    HorizontalLineTo {
+        abs: bool,
+        x: f64,
+    },
+    VerticalLineTo {
+        abs: bool,
+        y: f64,
+    },
+    CurveTo {
+        abs: bool,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x: f64,
+        y: f64,
+    },
+    SmoothCurveTo {
+        abs: bool,
+        x2: f64,
+        y2: f64,
+        x: f64,
+        y: f64,
+    },
+    Quadratic {
+        abs: bool,
+        x1: f64,
+        y1: f64,
+        x: f64,
+        y: f64,
+    },
+    SmoothQuadratic {
+        abs: bool,
+        x: f64,
+        y: f64,
+    },
+    EllipticalArc {
+        abs: bool,
+        rx: f64,
+        ry: f64,
+        x_axis_rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        x: f64,
+        y: f64,
+    },
+    ClosePath {
+        abs: bool,
+    },
+}
+
+#[cfg(feature = "std")]
+impl From<svgtypes::PathSegment> for PathSegment {
+    fn from(segment: svgtypes::PathSegment) -> Self {
+        use svgtypes::PathSegment as S;
+        match segment {
+            S::MoveTo { abs, x, y } => Self::MoveTo { abs, x, y },
+            S::LineTo { abs, x, y } => Self::LineTo { abs, x, y },
+            S::HorizontalLineTo { abs, x } => Self::HorizontalLineTo { abs, x },
+            S::VerticalLineTo { abs, y } => Self::VerticalLineTo { abs, y },
+            S::CurveTo {
+                abs,
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => Self::CurveTo {
+                abs,
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            },
+            S::SmoothCurveTo { abs, x2, y2, x, y } => Self::SmoothCurveTo { abs, x2, y2, x, y },
+            S::Quadratic { abs, x1, y1, x, y } => Self::Quadratic { abs, x1, y1, x, y },
+            S::SmoothQuadratic { abs, x, y } => Self::SmoothQuadratic { abs, x, y },
+            S::EllipticalArc {
+                abs,
+                rx,
+                ry,
+                x_axis_rotation,
+                large_arc,
+                sweep,
+                x,
+                y,
+            } => Self::EllipticalArc {
+                abs,
+                rx,
+                ry,
+                x_axis_rotation,
+                large_arc,
+                sweep,
+                x,
+                y,
+            },
+            S::ClosePath { abs } => Self::ClosePath { abs },
+        }
+    }
+}
+
+/// Reads the commands and numbers of a path expression.
+pub(crate) struct Tokens<'a> {
+    pub(crate) bytes: &'a [u8],
+    pub(crate) pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub(crate) fn new(expr: &'a str) -> Self {
+        Self {
+            bytes: expr.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    pub(crate) fn skip_spaces(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_digits(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip spaces and an optional comma after a number or flag.
+    fn skip_separator(&mut self) {
+        self.skip_spaces();
+        if self.bytes.get(self.pos) == Some(&b',') {
+            self.pos += 1;
+        }
+    }
+
+    /// Parse a number followed by an optional comma, with the same grammar as
+    /// svgtypes.
+    pub(crate) fn number(&mut self) -> Option<f64> {
+        self.skip_spaces();
+        let start = self.pos;
+        if let Some(b'+' | b'-') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+        match self.bytes.get(self.pos)? {
+            b'0'..=b'9' => self.skip_digits(),
+            b'.' => {}
+            _ => return None,
+        }
+        if self.bytes.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            self.skip_digits();
+        }
+        if let Some(b'e' | b'E') = self.bytes.get(self.pos) {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.bytes.get(self.pos) {
+                self.pos += 1;
+            }
+            self.skip_digits();
+        }
+        let text = str::from_utf8(&self.bytes[start..self.pos]).ok()?;
+        let number = f64::from_str(text).ok().filter(|n| n.is_finite())?;
+        self.skip_separator();
+        Some(number)
+    }
+
+    pub(crate) fn pair(&mut self) -> Option<CoordinatePair> {
+        Some(CoordinatePair::new(self.number()?, self.number()?))
+    }
+
+    /// Parse an arc flag, which is a single digit that may be followed by
+    /// the next number without a separator.
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_spaces();
+        let flag = match self.bytes.get(self.pos)? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.pos += 1;
+        self.skip_separator();
+        Some(flag)
+    }
+}
+
+/// An iterator over the [`PathSegment`]s of a path expression.
+///
+/// Like `svgtypes::PathParser`, implicit commands are made explicit, and the
+/// iteration ends after the first error.
+pub struct PathSegments<'a> {
+    tokens: Tokens<'a>,
+    prev_command: Option<u8>,
+}
+
+impl<'a> PathSegments<'a> {
+    pub fn new(expr: &'a str) -> Self {
+        Self {
+            tokens: Tokens::new(expr),
+            prev_command: None,
+        }
+    }
+
+    fn next_segment(&mut self) -> Option<PathSegment> {
+        let tokens = &mut self.tokens;
+        let b = *tokens.bytes.get(tokens.pos)?;
+        let (command, implicit) = match (b, self.prev_command) {
+            (
+                b'M' | b'm' | b'L' | b'l' | b'H' | b'h' | b'V' | b'v' | b'C' | b'c' | b'S' | b's'
+                | b'Q' | b'q' | b'T' | b't' | b'A' | b'a' | b'Z' | b'z',
+                _,
+            ) => {
+                tokens.pos += 1;
+                (b, false)
+            }
+            // Numbers repeat the previous command, or are lines after a move
+            (b'0'..=b'9' | b'.' | b'+' | b'-', Some(b'M')) => (b'L', true),
+            (b'0'..=b'9' | b'.' | b'+' | b'-', Some(b'm')) => (b'l', true),
+            (b'0'..=b'9' | b'.' | b'+' | b'-', Some(command))
+                if command != b'Z' && command != b'z' =>
+            {
+                (command, false)
+            }
+            _ => return None,
+        };
+        if self.prev_command.is_none() && command != b'M' && command != b'm' {
+            // The first segment must be a MoveTo
+            return None;
+        }
+        let abs = command.is_ascii_uppercase();
+        let segment = match command.to_ascii_lowercase() {
+            b'm' => {
+                let CoordinatePair { x, y } = tokens.pair()?;
+                PathSegment::MoveTo { abs, x, y }
+            }
+            b'l' => {
+                let CoordinatePair { x, y } = tokens.pair()?;
+                PathSegment::LineTo { abs, x, y }
+            }
+            b'h' => PathSegment::HorizontalLineTo {
+                abs,
+                x: tokens.number()?,
+            },
+            b'v' => PathSegment::VerticalLineTo {
+                abs,
+                y: tokens.number()?,
+            },
+            b'c' => PathSegment::CurveTo {
+                abs,
+                x1: tokens.number()?,
+                y1: tokens.number()?,
+                x2: tokens.number()?,
+                y2: tokens.number()?,
+                x: tokens.number()?,
+                y: tokens.number()?,
+            },
+            b's' => PathSegment::SmoothCurveTo {
+                abs,
+                x2: tokens.number()?,
+                y2: tokens.number()?,
+                x: tokens.number()?,
+                y: tokens.number()?,
+            },
+            b'q' => PathSegment::Quadratic {
+                abs,
+                x1: tokens.number()?,
+                y1: tokens.number()?,
+                x: tokens.number()?,
+                y: tokens.number()?,
+            },
+            b't' => {
+                let CoordinatePair { x, y } = tokens.pair()?;
+                PathSegment::SmoothQuadratic { abs, x, y }
+            }
+            b'a' => PathSegment::EllipticalArc {
+                abs,
+                rx: tokens.number()?,
+                ry: tokens.number()?,
+                x_axis_rotation: tokens.number()?,
+                large_arc: tokens.flag()?,
+                sweep: tokens.flag()?,
+                x: tokens.number()?,
+                y: tokens.number()?,
+            },
+            _ => PathSegment::ClosePath { abs },
+        };
+        self.prev_command = Some(match (implicit, abs) {
+            (true, true) => b'M',
+            (true, false) => b'm',
+            (false, _) => command,
+        });
+        Some(segment)
+    }
+}
+
+impl Iterator for PathSegments<'_> {
+    type Item = Result<PathSegment, PathError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tokens.skip_spaces();
+        if self.tokens.pos >= self.tokens.bytes.len() {
+            return None;
+        }
+        let start = self.tokens.pos;
+        let segment = self.next_segment();
+        if segment.is_none() {
+            self.tokens.pos = self.tokens.bytes.len();
+        }
+        Some(
+            segment.ok_or_else(|| {
+                PathError::Parse(format!("Unexpected data at position {}", start + 1))
+            }),
+        )
+    }
+}
+
+/// Flatten a path expression into polylines, approximating curves and arcs
+/// with lines of the given tolerance.
+///
+/// This is the path flattening of [`parse`](crate::parse) for a single `d`
+/// attribute, without transformations, styles or any other parts of an SVG
+/// document.
+pub fn flatten(expr: &str, tol: f64) -> Result<Vec<Polyline>, PathError> {
+    trace!("flatten");
+    let mut lines = Vec::new();
+    let mut line = CurrentLine::new();
+    let mut estimates = subpath_estimates(expr);
+    let mut prev_segment = None;
+    for segment in PathSegments::new(expr) {
+        let segment = segment?;
+        if let PathSegment::MoveTo { .. } = segment {
+            // Allocate the polyline of the new subpath at once
+            if line.is_valid() {
+                lines.push(line.finish());
+            }
+            line.reserve(estimates.next().unwrap_or(0));
+        }
+        flatten_segment(
+            &segment,
+            prev_segment.replace(segment),
+            &mut line,
+            tol,
+            &mut lines,
+        )?;
+    }
+    if line.is_valid() {
+        lines.push(line.finish());
+    }
+    Ok(lines)
+}
+
+/// Helper method for parsing both `CurveTo` and `SmoothCurveTo`.
+#[allow(clippy::too_many_arguments)]
+fn handle_cubic_curve(
+    current_line: &mut CurrentLine,
+    tol: f64,
+    abs: bool,
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    x: f64,
+    y: f64,
+) -> Result<(), PathError> {
+    let current = current_line.last_pair().ok_or_else(|| {
+        PathError::Parse("Invalid state: CurveTo or SmoothCurveTo on empty CurrentLine".into())
+    })?;
+    let curve = if abs {
+        CubicBezierSegment {
+            from: Point2D::new(current.x, current.y),
+            ctrl1: Point2D::new(x1, y1),
+            ctrl2: Point2D::new(x2, y2),
+            to: Point2D::new(x, y),
+        }
+    } else {
+        CubicBezierSegment {
+            from: Point2D::new(current.x, current.y),
+            ctrl1: Point2D::new(current.x + x1, current.y + y1),
+            ctrl2: Point2D::new(current.x + x2, current.y + y2),
+            to: Point2D::new(current.x + x, current.y + y),
+        }
+    };
+    for point in curve.flattened(tol) {
+        current_line.add_absolute(CoordinatePair::new(point.x, point.y));
+    }
+    Ok(())
+}
+
+/// Flatten a single path segment into `current_line`, pushing the previous
+/// line to `lines` if the segment starts a new one.
+#[allow(clippy::similar_names)]
+pub(crate) fn flatten_segment(
+    segment: &PathSegment,
+    prev_segment: Option<PathSegment>,
+    current_line: &mut CurrentLine,
+    tol: f64,
+    lines: &mut Vec<Polyline>,
+) -> Result<(), PathError> {
+    trace!("flatten_segment");
+    #[allow(clippy::match_wildcard_for_single_variants)]
+    match segment {
+        &PathSegment::MoveTo { abs, x, y } => {
+            trace!("flatten_segment: MoveTo");
+            if current_line.is_valid() {
+                lines.push(current_line.finish());
+            }
+            current_line.add(abs, CoordinatePair::new(x, y));
+        }
+        &PathSegment::LineTo { abs, x, y } => {
+            trace!("flatten_segment: LineTo");
+            current_line.add(abs, CoordinatePair::new(x, y));
+        }
+        &PathSegment::HorizontalLineTo { abs, x } => {
+            trace!("flatten_segment: HorizontalLineTo");
+            match (current_line.last_y(), abs) {
+                (Some(y), true) => current_line.add_absolute(CoordinatePair::new(x, y)),
+                (Some(_), false) => current_line.add_relative(CoordinatePair::new(x, 0.0)),
+                (None, _) => {
+                    return Err(PathError::Parse(
+                        "Invalid state: HorizontalLineTo on emtpy CurrentLine".into(),
+                    ))
+                }
+            }
+        }
+        &PathSegment::VerticalLineTo { abs, y } => {
+            trace!("flatten_segment: VerticalLineTo");
+            match (current_line.last_x(), abs) {
+                (Some(x), true) => current_line.add_absolute(CoordinatePair::new(x, y)),
+                (Some(_), false) => current_line.add_relative(CoordinatePair::new(0.0, y)),
+                (None, _) => {
+                    return Err(PathError::Parse(
+                        "Invalid state: VerticalLineTo on emtpy CurrentLine".into(),
+                    ))
+                }
+            }
+        }
+        &PathSegment::CurveTo {
+            abs,
+            x1,
+            y1,
+            x2,
+            y2,
+            x,
+            y,
+        } => {
+            trace!("flatten_segment: CurveTo");
+            handle_cubic_curve(current_line, tol, abs, x1, y1, x2, y2, x, y)?;
+        }
+        &PathSegment::SmoothCurveTo { abs, x2, y2, x, y } => {
+            trace!("flatten_segment: SmoothCurveTo");
+
+            // Who on earth thought it would be a good idea to add a shortcut
+            // for curves with a mirrored control point? It generally makes
+            // implementations much more complex, while the data is perfectly
+            // equivalent to a fully written-out cubic curve m(
+            match prev_segment {
+                Some(
+                    PathSegment::CurveTo {
+                        x2: prev_x2,
+                        y2: prev_y2,
+                        x: prev_x,
+                        y: prev_y,
+                        ..
+                    }
+                    | PathSegment::SmoothCurveTo {
+                        x2: prev_x2,
+                        y2: prev_y2,
+                        x: prev_x,
+                        y: prev_y,
+                        ..
+                    },
+                ) => {
+                    // We have a previous curve. Mirror the previous control
+                    // point 2 along the previous end point.
+                    let dx = prev_x - prev_x2;
+                    let dy = prev_y - prev_y2;
+                    let (x1, y1) = if abs {
+                        let current = current_line.last_pair().ok_or_else(|| {
+                            PathError::Parse(
+                                "Invalid state: CurveTo or SmoothCurveTo on empty CurrentLine"
+                                    .into(),
+                            )
+                        })?;
+                        (current.x + dx, current.y + dy)
+                    } else {
+                        (dx, dy)
+                    };
+                    handle_cubic_curve(current_line, tol, abs, x1, y1, x2, y2, x, y)?;
+                }
+                Some(_) | None => {
+                    // The previous segment was not a curve. Use the current
+                    // point as reference.
+                    match current_line.last_pair() {
+                        Some(pair) => {
+                            let x1 = pair.x;
+                            let y1 = pair.y;
+                            handle_cubic_curve(current_line, tol, abs, x1, y1, x2, y2, x, y)?;
+                        }
+                        None => {
+                            return Err(PathError::Parse(
+                                "Invalid state: SmoothCurveTo without a reference point".into(),
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+        &PathSegment::Quadratic { abs, x1, y1, x, y } => {
+            trace!("flatten_segment: Quadratic");
+            let current = current_line.last_pair().ok_or_else(|| {
+                PathError::Parse("Invalid state: Quadratic on empty CurrentLine".into())
+            })?;
+            let curve = if abs {
+                QuadraticBezierSegment {
+                    from: Point2D::new(current.x, current.y),
+                    ctrl: Point2D::new(x1, y1),
+                    to: Point2D::new(x, y),
+                }
+            } else {
+                QuadraticBezierSegment {
+                    from: Point2D::new(current.x, current.y),
+                    ctrl: Point2D::new(current.x + x1, current.y + y1),
+                    to: Point2D::new(current.x + x, current.y + y),
+                }
+            };
+            for point in curve.flattened(tol) {
+                current_line.add_absolute(CoordinatePair::new(point.x, point.y));
+            }
+        }
+        &PathSegment::ClosePath { .. } => {
+            trace!("flatten_segment: ClosePath");
+            current_line
+                .close()
+                .map_err(|e| PathError::Parse(format!("Invalid state: {e}")))?;
+        }
+        &PathSegment::EllipticalArc {
+            abs,
+            rx,
+            ry,
+            x_axis_rotation,
+            large_arc,
+            sweep,
+            x,
+            y,
+        } => {
+            // The following code and comments are based on this project:
+            // https://github.com/BigBadaboom/androidsvg (Apache-2 license)
+            // And more specifically here:
+            // https://github.com/BigBadaboom/androidsvg/blob/1ad1c08c4f7ee09fcdd3dca31f8f31db7cacd3b0/androidsvg/src/main/java/com/caverock/androidsvg/utils/SVGAndroidRenderer.java#L2874-L3021
+            //
+            // This code in turn is partially based on the Batik library
+            // (Apache-2 license).
+
+            // SVG arc representation uses "endpoint parameterization" where we
+            // specify the start and endpoint of the arc. This is to be
+            // consistent with the other path commands. However we need to
+            // convert this to "centre point parameterization" in order to
+            // calculate the arc. Handily, the SVG spec provides all the
+            // required maths in section "F.6 Elliptical arc implementation
+            // notes".
+            trace!("flatten_segment: EllipticalArc");
+            let current = current_line.last_pair().ok_or_else(|| {
+                PathError::Parse("Invalid state: EllipticalArc on empty CurrentLine".into())
+            })?;
+            let last_x = current.x;
+            let last_y = current.y;
+
+            // Calculating the end points of the curve based on the abs flag
+            let x_end = if abs { x } else { current.x + x };
+            let y_end = if abs { y } else { current.y + y };
+
+            // If the endpoints (x, y) and (x0, y0) are identical, then this is
+            // equivalent to omitting the elliptical arc segment entirely.
+            // (behavior specified by the spec)
+            let error_margin = f64::EPSILON;
+            if (last_x - x_end).abs() < error_margin && (last_y - y_end).abs() < error_margin {
+                return Ok(());
+            }
+
+            // Handle degenerate case (behavior specified by the spec)
+            if rx == 0.0 || ry == 0.0 {
+                current_line.add(abs, CoordinatePair::new(x_end, y_end));
+                return Ok(());
+            }
+
+            // Sign of the radii is ignored (behavior specified by the spec)
+            let mut rx = rx.abs();
+            let mut ry = ry.abs();
+
+            // Convert angle from degrees to radians
+            let angle_rad = (x_axis_rotation % 360.0) * (PI / 180.0);
+            let cos_angle = angle_rad.cos();
+            let sin_angle = angle_rad.sin();
+
+            // We simplify the calculations by transforming the arc so that the origin is at the
+            // midpoint calculated above followed by a rotation to line up the coordinate axes
+            // with the axes of the ellipse.
+
+            // Compute the midpoint of the line between the current and the end point
+            let dx2 = (last_x - x_end) / 2.0;
+            let dy2 = (last_y - y_end) / 2.0;
+
+            // Step 1: Compute (x1', y1')
+            // x1,y1 is the midpoint vector rotated to take the arc's angle out of consideration
+            let x1 = cos_angle * dx2 + sin_angle * dy2;
+            let y1 = -sin_angle * dx2 + cos_angle * dy2;
+
+            let mut rx_sq = rx * rx;
+            let mut ry_sq = ry * ry;
+            let x1_sq = x1 * x1;
+            let y1_sq = y1 * y1;
+
+            // Check that radii are large enough.
+            // If they are not, the spec says to scale them up so they are.
+            // This is to compensate for potential rounding errors/differences between SVG implementations.
+            let radii_check = x1_sq / rx_sq + y1_sq / ry_sq;
+            if radii_check > 0.99999 {
+                let radii_scale = radii_check.sqrt() * 1.00001;
+                rx *= radii_scale;
+                ry *= radii_scale;
+                rx_sq = rx * rx;
+                ry_sq = ry * ry;
+            }
+
+            // Step 2 : Compute (cx1, cy1) - the transformed centre point
+            let mut sign = if large_arc == sweep { -1.0 } else { 1.0 };
+            let sq = ((rx_sq * ry_sq) - (rx_sq * y1_sq) - (ry_sq * x1_sq))
+                / ((rx_sq * y1_sq) + (ry_sq * x1_sq));
+            let sq = if sq < 0.0 { 0.0 } else { sq };
+            let coef = sign * sq.sqrt();
+            let cx1 = coef * ((rx * y1) / ry);
+            let cy1 = coef * -((ry * x1) / rx);
+
+            // Step 3 : Compute (cx, cy) from (cx1, cy1)
+            let sx2 = (last_x + x_end) / 2.0;
+            let sy2 = (last_y + y_end) / 2.0;
+            let cx = sx2 + (cos_angle * cx1 - sin_angle * cy1);
+            let cy = sy2 + (sin_angle * cx1 + cos_angle * cy1);
+
+            // Step 4 : Compute the angleStart (angle1) and the angleExtent (dangle)
+            let ux = (x1 - cx1) / rx;
+            let uy = (y1 - cy1) / ry;
+            let vx = (-x1 - cx1) / rx;
+            let vy = (-y1 - cy1) / ry;
+
+            // Angle betwen two vectors is +/- acos( u.v / len(u) * len(v))
+            // Where '.' is the dot product. And +/- is calculated from the sign of the cross product (u x v)
+
+            // Compute the start angle
+            // The angle between (ux,uy) and the 0deg angle (1,0)
+            let mut n = ((ux * ux) + (uy * uy)).sqrt(); // len(u) * len(1,0) == len(u)
+            let mut p = ux; // u.v == (ux,uy).(1,0) == (1 * ux) + (0 * uy) == ux
+            sign = if uy < 0.0 { -1.0 } else { 1.0 }; // u x v == (1 * uy - ux * 0) == uy
+            let mut angle_start = sign * (p / n).acos(); // No need for checking the acos here. (p >= n) should always be true.
+
+            // Compute the angle extent
+            n = ((ux * ux + uy * uy) * (vx * vx + vy * vy)).sqrt();
+            p = ux * vx + uy * vy;
+            sign = if (ux * vy - uy * vx) < 0.0 { -1.0 } else { 1.0 };
+
+            let val = p / n;
+
+            let checked_arc_cos = if val < -1.0 {
+                PI
+            } else if val > 1.0 {
+                0.0
+            } else {
+                val.acos()
+            };
+            let mut angle_extent = sign * checked_arc_cos;
+
+            // Catch angleExtents of 0, which will cause problems later in arcToBeziers
+            if angle_extent == 0.0 {
+                current_line.add(abs, CoordinatePair::new(x_end, y_end));
+                return Ok(());
+            }
+
+            let two_pi = PI * 2.0;
+            if !sweep && angle_extent > 0.0 {
+                angle_extent -= two_pi;
+            } else if sweep && angle_extent < 0.0 {
+                angle_extent += two_pi;
+            }
+            angle_extent %= two_pi;
+            angle_start %= two_pi;
+
+            // Many elliptical arc implementations including the Java2D and Android ones, only
+            // support arcs that are axis aligned. Therefore we need to substitute the arc
+            // with bezier curves. The following function call will generate the beziers for
+            // a unit circle that covers the arc angles we want.
+
+            // The following code generates the control points and endpoints for a set of bezier
+            // curves that match a circular arc starting from angle 'angleStart' and sweep
+            // the angle 'angleExtent'.
+            // The circle the arc follows will be centred on (0,0) and have a radius of 1.0.
+            //
+            // Each bezier can cover no more than 90 degrees, so the arc will be divided evenly
+            // into a maximum of four curves.
+            //
+            // The resulting control points will later be scaled and rotated to match the final
+            // arc required.
+
+            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            let num_segments = (angle_extent.abs() * 2.0 / PI).ceil() as u64;
+
+            #[allow(clippy::cast_precision_loss)] // Cannot happen
+            let angle_increment: f64 = angle_extent / num_segments as f64;
+
+            // The length of each control point vector is given by the following formula.
+            let control_length =
+                4.0 / 3.0 * (angle_increment / 2.0).sin() / (1.0 + (angle_increment / 2.0).cos());
+
+            let num_segments_usize: usize = num_segments.try_into().unwrap();
+            let mut bezier_points = Vec::with_capacity(num_segments_usize * 3);
+            for i in 0..num_segments {
+                #[allow(clippy::cast_precision_loss)] // Cannot happen
+                let mut angle = angle_start + i as f64 * angle_increment;
+                // Calculate the control vector at this angle
+                let mut dx = angle.cos();
+                let mut dy = angle.sin();
+
+                // First control point
+                bezier_points.push((dx - control_length * dy, dy + control_length * dx));
+
+                // Second control point
+                angle += angle_increment;
+                dx = angle.cos();
+                dy = angle.sin();
+                bezier_points.push((dx + control_length * dy, dy - control_length * dx));
+
+                // Endpoint of bezier
+                bezier_points.push((dx, dy));
+            }
+
+            // Check if no points were generated
+            let len = bezier_points.len();
+            if len == 0 {
+                return Ok(());
+            }
+
+            // Calculate a transformation matrix that will move and scale these bezier points to the correct location.
+            let mut bezier_points: Vec<(f64, f64)> = bezier_points
+                .into_iter()
+                // Scale
+                .map(|(a, b)| (a * rx, b * ry))
+                // Rotate around the calculated centre point
+                .map(|(a, b)| {
+                    let s = angle_rad.sin();
+                    let c = angle_rad.cos();
+
+                    let px = a - cx1;
+                    let py = b - cy1;
+
+                    let x_new = px * c - py * s;
+                    let y_new = px * s + py * c;
+
+                    (x_new + cx1, y_new + cy1)
+                })
+                // Translate
+                .map(|(a, b)| (a + cx, b + cy))
+                .collect();
+
+            // The last point in the bezier set should match exactly the last coord pair in the arc (ie: x,y). But
+            // considering all the mathematical manipulation we have been doing, it is bound to be off by a tiny
+            // fraction. Experiments show that it can be up to around 0.00002. So why don't we just set it to
+            // exactly what it ought to be.
+            bezier_points[len - 1] = (x_end, y_end);
+
+            // Final step is to add the bezier curves to the path
+            let mut last_x = last_x;
+            let mut last_y = last_y;
+            // Step trough points 3 at a time
+            for i in (0..bezier_points.len()).step_by(3) {
+                let curve = CubicBezierSegment {
+                    from: Point2D::new(last_x, last_y),
+                    ctrl1: Point2D::new(bezier_points[i].0, bezier_points[i].1),
+                    ctrl2: Point2D::new(bezier_points[i + 1].0, bezier_points[i + 1].1),
+                    to: Point2D::new(bezier_points[i + 2].0, bezier_points[i + 2].1),
+                };
+                // End of last curve is used as start point of next curve
+                last_x = bezier_points[i + 2].0;
+                last_y = bezier_points[i + 2].1;
+                for point in curve.flattened(tol) {
+                    current_line.add_absolute(CoordinatePair::new(point.x, point.y));
+                }
+            }
+        }
+        other => {
+            return Err(PathError::Parse(format!(
+                "Unsupported path segment: {other:?}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[cfg(feature = "std")]
+    const EXPRESSIONS: [&str; 5] = [
+        "M10-20l30.1.5.1-20z",
+        "M 0,0 L 10,0 10,10 Z m 5,5 h 3 v-4.5e1 H 0 V .5",
+        "M0 0C1 1 2 2 3 3S4 4 5 5 s1,1 2,2 Q1 1 2 2 q 1 1 2 2",
+        "M 0 0 A 5 5 0 1 0 10 10 a5,5 30 0110,10 A 1 2 3 0 1 4 5 6 7 8 1 1 9 10",
+        "m 1 1 2 2 T 3 3",
+    ];
+
+    #[test]
+    fn test_current_line() {
+        let mut line = CurrentLine::new();
+        assert!(!line.is_valid());
+        assert_eq!(line.last_x(), None);
+        assert_eq!(line.last_y(), None);
+        line.add_absolute((1.0, 2.0).into());
+        assert!(!line.is_valid());
+        assert_eq!(line.last_x(), Some(1.0));
+        assert_eq!(line.last_y(), Some(2.0));
+        line.add_absolute((2.0, 3.0).into());
+        assert!(line.is_valid());
+        assert_eq!(line.last_x(), Some(2.0));
+        assert_eq!(line.last_y(), Some(3.0));
+        let finished = line.finish();
+        assert_eq!(finished.len(), 2);
+        assert_eq!(finished[0], (1.0, 2.0).into());
+        assert_eq!(finished[1], (2.0, 3.0).into());
+        assert!(!line.is_valid());
+    }
+
+    #[test]
+    fn test_current_line_close() {
+        let mut line = CurrentLine::new();
+        assert_eq!(
+            line.close().unwrap_err().to_string(),
+            "Polyline error: Lines with less than 2 coordinate pairs cannot be closed.",
+        );
+        line.add_absolute((1.0, 2.0).into());
+        assert_eq!(
+            line.close().unwrap_err().to_string(),
+            "Polyline error: Lines with less than 2 coordinate pairs cannot be closed.",
+        );
+        line.add_absolute((2.0, 3.0).into());
+        assert!(line.close().is_ok());
+        let finished = line.finish();
+        assert_eq!(finished.len(), 3);
+        assert_eq!(finished[0], (1.0, 2.0).into());
+        assert_eq!(finished[2], (1.0, 2.0).into());
+    }
+
+    #[test]
+    fn test_estimate_points() {
+        assert_eq!(estimate_points(""), 0);
+        assert_eq!(estimate_points(" 0,0 L 10.5,-1e5 20 .5"), 3);
+        assert_eq!(estimate_points(" 0 0 C 1 1 2 2 3 3 Z"), 5);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_path_segments() {
+        for expr in EXPRESSIONS {
+            let expected: Vec<PathSegment> = svgtypes::PathParser::from(expr)
+                .map(|segment| segment.unwrap().into())
+                .collect();
+            let segments: Vec<PathSegment> = PathSegments::new(expr).map(Result::unwrap).collect();
+            assert_eq!(segments, expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_path_segments_invalid() {
+        for expr in [
+            "L 1,1",
+            "M 0,0 Z 1,1",
+            "M 0,0 L 1,,1",
+            "M 0,0 A 1 1 0 2 0 1 1",
+            "M 0,0 X",
+        ] {
+            let mut segments = PathSegments::new(expr);
+            assert!(
+                segments.by_ref().any(|segment| segment.is_err()),
+                "{}",
+                expr
+            );
+            assert!(segments.next().is_none(), "{}", expr);
+        }
+        assert_eq!(
+            PathSegments::new("M 0,0 X").nth(1).unwrap().unwrap_err(),
+            PathError::Parse("Unexpected data at position 7".into())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_flatten() {
+        use crate::{parse_segments, UnsupportedPolicy};
+
+        for expr in EXPRESSIONS {
+            let expected =
+                parse_segments(expr, 0.15, UnsupportedPolicy::Error, None, &mut Vec::new())
+                    .map_err(|e| e.to_string());
+            assert_eq!(
+                flatten(expr, 0.15).map_err(|e| e.to_string()),
+                expected,
+                "{expr}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_flatten_errors() {
+        assert_eq!(
+            flatten("M 0,0 T 1,1", 0.15).unwrap_err().to_string(),
+            "SVG path parse error: Unsupported path segment: \
+             SmoothQuadratic { abs: true, x: 1.0, y: 1.0 }"
+        );
+        assert!(matches!(flatten("M 0,0 Z", 0.15), Err(PathError::Parse(_))));
+        assert_eq!(flatten("", 0.15), Ok(Vec::new()));
+    }
+}
@@ -0,0 +1,91 @@
+//! Configure the fonts used to convert text during preprocessing.
+
+use std::path::PathBuf;
+
+#[cfg(feature = "usvg")]
+use log::{trace, warn};
+
+/// The fonts available to convert `<text>` elements into outlines.
+///
+/// Text is only converted when preprocessing with usvg, see
+/// [`Preprocess`](crate::Preprocess), so these options have no effect without
+/// the `usvg` feature. Without any fonts, text elements are dropped.
+///
+/// ```
+/// use svg2polylines::{FontOptions, ParseOptions};
+///
+/// let options = ParseOptions {
+///     fonts: FontOptions {
+///         system_fonts: true,
+///         fallback_family: Some("DejaVu Sans".into()),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FontOptions {
+    /// Whether to load the fonts installed on the system.
+    pub system_fonts: bool,
+
+    /// Font files (TrueType, OpenType or collections) to load.
+    pub font_files: Vec<PathBuf>,
+
+    /// Directories to search recursively for font files.
+    pub font_dirs: Vec<PathBuf>,
+
+    /// The font family used for text without a `font-family`. Defaults to
+    /// usvg's default, "Times New Roman".
+    pub fallback_family: Option<String>,
+}
+
+impl FontOptions {
+    /// Load these fonts into the font database of the usvg options and set
+    /// the fallback family.
+    ///
+    /// Loading the system fonts can take a while, so use a
+    /// [`Parser`](crate::Parser) to load them only once when converting many
+    /// documents. Font files that cannot be read are skipped with a warning
+    /// in the log.
+    #[cfg(feature = "usvg")]
+    pub fn load(&self, usvg_options: &mut usvg::Options) {
+        if self.system_fonts {
+            usvg_options.fontdb.load_system_fonts();
+        }
+        for dir in &self.font_dirs {
+            usvg_options.fontdb.load_fonts_dir(dir);
+        }
+        for file in &self.font_files {
+            if let Err(e) = usvg_options.fontdb.load_font_file(file) {
+                warn!("Cannot load font file {}: {}", file.display(), e);
+            }
+        }
+        if let Some(family) = &self.fallback_family {
+            usvg_options.font_family.clone_from(family);
+        }
+        trace!("FontOptions: Loaded {} fonts", usvg_options.fontdb.len());
+    }
+}
+
+#[cfg(all(test, feature = "usvg"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let mut usvg_options = usvg::Options::default();
+        FontOptions::default().load(&mut usvg_options);
+        assert_eq!(
+            usvg_options.font_family,
+            usvg::Options::default().font_family
+        );
+        let options = FontOptions {
+            font_files: vec!["does-not-exist.ttf".into()],
+            fallback_family: Some("Sans".into()),
+            ..Default::default()
+        };
+        options.load(&mut usvg_options);
+        assert_eq!(usvg_options.font_family, "Sans");
+        assert_eq!(usvg_options.fontdb.len(), 0);
+    }
+}
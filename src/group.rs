@@ -0,0 +1,170 @@
+//! Convert only a part of the document.
+
+use std::borrow::Cow;
+
+use log::trace;
+use quick_xml::events::{BytesEnd, BytesStart, Event};
+
+use crate::{parse_with_options, Error, ParseOptions, Polyline};
+
+fn has_id(e: &BytesStart, id: &str) -> bool {
+    e.attributes().filter_map(Result::ok).any(|attr| {
+        attr.key == b"id" && attr.unescaped_value().ok() == Some(Cow::from(id.as_bytes()))
+    })
+}
+
+enum Capture {
+    Defs,
+    Group,
+}
+
+/// Return a copy of the document that only contains the element with the
+/// given id, its descendants, its ancestors (so that inherited transforms and
+/// styles are preserved) and all `<defs>` elements.
+pub(crate) fn extract_subtree(svg: &str, id: &str) -> Result<String, Error> {
+    let mut reader = quick_xml::Reader::from_str(svg);
+    let mut buf = Vec::new();
+
+    // Start tags of the currently open elements
+    let mut stack: Vec<BytesStart<'static>> = Vec::new();
+    let mut ancestors: Vec<BytesStart<'static>> = Vec::new();
+    let mut defs: Vec<Event<'static>> = Vec::new();
+    let mut group: Vec<Event<'static>> = Vec::new();
+    let mut capture: Option<(Capture, usize)> = None;
+
+    loop {
+        let event = reader
+            .read_event(&mut buf)
+            .map_err(|e| Error::SvgParse(e.to_string()))?;
+        match (&mut capture, event) {
+            (_, Event::Eof) => break,
+            (_, Event::Decl(_) | Event::DocType(_) | Event::PI(_)) => {}
+            (Some((target, depth)), event) => {
+                match event {
+                    Event::Start(_) => *depth += 1,
+                    Event::End(_) => *depth -= 1,
+                    _ => {}
+                }
+                let done = *depth == 0;
+                match target {
+                    Capture::Defs => defs.push(event.into_owned()),
+                    Capture::Group => group.push(event.into_owned()),
+                }
+                if done {
+                    capture = None;
+                }
+            }
+            (None, Event::Start(e)) => {
+                if group.is_empty() && has_id(&e, id) {
+                    ancestors.clone_from(&stack);
+                    group.push(Event::Start(e.into_owned()));
+                    capture = Some((Capture::Group, 1));
+                } else if e.name() == b"defs" {
+                    defs.push(Event::Start(e.into_owned()));
+                    capture = Some((Capture::Defs, 1));
+                } else {
+                    stack.push(e.into_owned());
+                }
+            }
+            (None, Event::Empty(e)) => {
+                if group.is_empty() && has_id(&e, id) {
+                    ancestors.clone_from(&stack);
+                    group.push(Event::Empty(e.into_owned()));
+                }
+            }
+            (None, Event::End(_)) => {
+                stack.pop();
+            }
+            (None, _) => {}
+        }
+        buf.clear();
+    }
+
+    if group.is_empty() {
+        return Err(Error::SvgParse(format!("No element with id \"{id}\"")));
+    }
+    trace!(
+        "extract_subtree: Found element \"{id}\" at depth {}",
+        ancestors.len()
+    );
+
+    let mut writer = quick_xml::Writer::new(Vec::new());
+    let mut write = |event: &Event| {
+        writer
+            .write_event(event)
+            .map_err(|e| Error::SvgParse(e.to_string()))
+    };
+    for (i, ancestor) in ancestors.iter().enumerate() {
+        write(&Event::Start(ancestor.clone()))?;
+        // Definitions go right below the root element
+        if i == 0 {
+            for event in &defs {
+                write(event)?;
+            }
+        }
+    }
+    for event in &group {
+        write(event)?;
+    }
+    for ancestor in ancestors.iter().rev() {
+        write(&Event::End(BytesEnd::borrowed(ancestor.name())))?;
+    }
+    String::from_utf8(writer.into_inner()).map_err(|e| Error::SvgParse(e.to_string()))
+}
+
+/// Parse only the element with the given id and its descendants.
+///
+/// The id may be given with or without a leading `#`. Transforms of the
+/// ancestor elements still apply (if the document is preprocessed), and
+/// `<defs>` elements are kept, so references to gradients or clip paths are
+/// resolved as in the full document.
+///
+/// This is useful for documents that contain several layers, e.g. one for
+/// cutting and one for engraving.
+pub fn parse_group(svg: &str, id: &str, options: &ParseOptions) -> Result<Vec<Polyline>, Error> {
+    let subtree = extract_subtree(svg, id.strip_prefix('#').unwrap_or(id))?;
+    parse_with_options(&subtree, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Preprocess;
+
+    const INPUT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">
+  <g id="layers" transform="matrix(1 0 0 1 10 0)">
+    <g id="layer-engrave"><path d="M 0,0 1,1"/><path d="M 2,2 3,3"/></g>
+    <g id="layer-cut"><path d="M 5,5 L 6,6"/></g>
+  </g>
+  <defs><linearGradient id="gradient"/></defs>
+</svg>"#;
+
+    #[test]
+    fn test_extract_subtree() {
+        let subtree = extract_subtree(INPUT, "layer-cut").unwrap();
+        assert_eq!(
+            subtree,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100"><defs><linearGradient id="gradient"/></defs><g id="layers" transform="matrix(1 0 0 1 10 0)"><g id="layer-cut"><path d="M 5,5 L 6,6"/></g></g></svg>"#
+        );
+        assert!(extract_subtree(INPUT, "missing").is_err());
+    }
+
+    #[test]
+    fn test_parse_group() {
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let result = parse_group(INPUT, "#layer-engrave", &options).unwrap();
+        assert_eq!(result.len(), 2);
+        let result = parse_group(INPUT, "layer-cut", &options).unwrap();
+        assert_eq!(
+            result,
+            vec![Polyline::from_vec(vec![
+                (15.0, 5.0).into(),
+                (16.0, 6.0).into()
+            ])]
+        );
+    }
+}
@@ -0,0 +1,401 @@
+//! Fill closed shapes with lines, e.g. for pen plotters.
+//!
+//! The polylines passed to [`fill`] are the rings of a single region, which
+//! are closed implicitly and combined with the even-odd rule, so holes (like
+//! in the letter "O") are left empty.
+
+use std::{
+    collections::HashMap,
+    f64::consts::{PI, TAU},
+};
+
+use crate::{
+    bounding_box_of,
+    clip::{clip_to_rings, point_in_rings, MaskMode},
+    BoundingBox, CoordinatePair, Polyline,
+};
+
+/// How to fill a region.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillStrategy {
+    /// Parallel lines at an angle in degrees (clockwise, like SVG
+    /// rotations). Consecutive lines alternate their direction.
+    Hatch { angle: f64 },
+
+    /// Two sets of parallel lines at the given angles in degrees.
+    CrossHatch { angles: [f64; 2] },
+
+    /// Inward offsets of the outline, one every `spacing`.
+    Concentric,
+
+    /// An Archimedean spiral around the center of the bounding box, clipped
+    /// to the region.
+    Spiral,
+}
+
+impl Default for FillStrategy {
+    fn default() -> Self {
+        Self::Hatch { angle: 45.0 }
+    }
+}
+
+/// Options for [`fill`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FillOptions {
+    pub strategy: FillStrategy,
+
+    /// The distance between neighbouring lines, usually the pen width.
+    pub spacing: f64,
+}
+
+impl Default for FillOptions {
+    fn default() -> Self {
+        Self {
+            strategy: FillStrategy::default(),
+            spacing: 1.0,
+        }
+    }
+}
+
+/// Return the center and the radius of the circle around the bounds.
+fn circumcircle(bounds: BoundingBox) -> (CoordinatePair, f64) {
+    let (width, height) = (bounds.width(), bounds.height());
+    let center = CoordinatePair::new(bounds.min_x + width * 0.5, bounds.min_y + height * 0.5);
+    (center, width.hypot(height) * 0.5)
+}
+
+/// Return parallel lines at `angle` (in degrees) across the region. One line
+/// goes through the center of the bounding box.
+fn hatch_lines(
+    rings: &[&[CoordinatePair]],
+    bounds: BoundingBox,
+    angle: f64,
+    spacing: f64,
+) -> Vec<Polyline> {
+    let (center, radius) = circumcircle(bounds);
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let reach = radius + spacing;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let count = (radius / spacing).floor() as i64;
+    let mut lines = Vec::new();
+    for k in -count..=count {
+        #[allow(clippy::cast_precision_loss)]
+        let offset = k as f64 * spacing;
+        let (x, y) = (center.x - sin * offset, center.y + cos * offset);
+        let mut line = Polyline::from_vec(vec![
+            CoordinatePair::new(x - cos * reach, y - sin * reach),
+            CoordinatePair::new(x + cos * reach, y + sin * reach),
+        ]);
+        if k % 2 != 0 {
+            line.reverse();
+        }
+        lines.push(line);
+    }
+    clip_to_rings(&lines, rings, MaskMode::KeepInside)
+}
+
+/// Return the distance to the border of the region, positive inside and
+/// negative outside.
+fn signed_distance(p: CoordinatePair, rings: &[&[CoordinatePair]]) -> f64 {
+    let mut distance = f64::INFINITY;
+    for ring in rings {
+        for (i, &a) in ring.iter().enumerate() {
+            let b = ring[(i + 1) % ring.len()];
+            let (dx, dy) = (b.x - a.x, b.y - a.y);
+            let length_squared = dx * dx + dy * dy;
+            let t = if length_squared > 0.0 {
+                (((p.x - a.x) * dx + (p.y - a.y) * dy) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            distance = distance.min((a.x + dx * t - p.x).hypot(a.y + dy * t - p.y));
+        }
+    }
+    if point_in_rings(p, rings) {
+        distance
+    } else {
+        -distance
+    }
+}
+
+/// A sampled scalar field on a regular grid.
+struct Grid {
+    origin: CoordinatePair,
+    cell: f64,
+    width: usize,
+    height: usize,
+    values: Vec<f64>,
+}
+
+/// An edge of the grid: The x and y index of its first point, and whether
+/// it is vertical.
+type Edge = (usize, usize, bool);
+
+impl Grid {
+    fn value(&self, i: usize, j: usize) -> f64 {
+        self.values[j * self.width + i]
+    }
+
+    /// Return the point on an edge where the field has the value `level`.
+    #[allow(clippy::cast_precision_loss, clippy::many_single_char_names)]
+    fn crossing(&self, (i, j, vertical): Edge, level: f64) -> CoordinatePair {
+        let a = self.value(i, j);
+        let b = if vertical {
+            self.value(i, j + 1)
+        } else {
+            self.value(i + 1, j)
+        };
+        // The edge is crossed, so `a` and `b` are on different sides of `level`
+        let t = (level - a) / (b - a);
+        let (x, y) = if vertical {
+            (i as f64, j as f64 + t)
+        } else {
+            (i as f64 + t, j as f64)
+        };
+        CoordinatePair::new(self.origin.x + x * self.cell, self.origin.y + y * self.cell)
+    }
+
+    /// Return the contour lines at `level` (marching squares).
+    fn contours(&self, level: f64) -> Vec<Polyline> {
+        let mut segments: Vec<[Edge; 2]> = Vec::new();
+        for j in 0..self.height - 1 {
+            for i in 0..self.width - 1 {
+                let corners = [
+                    self.value(i, j),
+                    self.value(i + 1, j),
+                    self.value(i + 1, j + 1),
+                    self.value(i, j + 1),
+                ];
+                // Edge k connects the corners k and k + 1
+                let edges = [
+                    (i, j, false),
+                    (i + 1, j, true),
+                    (i, j + 1, false),
+                    (i, j, true),
+                ];
+                let inside = corners.map(|v| v > level);
+                let crossed: Vec<usize> = (0..4)
+                    .filter(|&k| inside[k] != inside[(k + 1) % 4])
+                    .collect();
+                match crossed.len() {
+                    2 => segments.push([edges[crossed[0]], edges[crossed[1]]]),
+                    4 => {
+                        // Saddle: Decide by the value in the center of the cell
+                        let center = corners.iter().sum::<f64>() / 4.0;
+                        if (center > level) == inside[0] {
+                            segments.push([edges[0], edges[1]]);
+                            segments.push([edges[2], edges[3]]);
+                        } else {
+                            segments.push([edges[3], edges[0]]);
+                            segments.push([edges[1], edges[2]]);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // Join the segments at their shared edges
+        let mut adjacent: HashMap<Edge, Vec<usize>> = HashMap::new();
+        for (index, segment) in segments.iter().enumerate() {
+            for &edge in segment {
+                adjacent.entry(edge).or_default().push(index);
+            }
+        }
+        let mut used = vec![false; segments.len()];
+        let next = |edge: Edge, used: &mut Vec<bool>| {
+            let &index = adjacent[&edge].iter().find(|&&index| !used[index])?;
+            used[index] = true;
+            let [a, b] = segments[index];
+            Some(if a == edge { b } else { a })
+        };
+        let mut contours = Vec::new();
+        for index in 0..segments.len() {
+            if used[index] {
+                continue;
+            }
+            used[index] = true;
+            let [first, second] = segments[index];
+            let mut forward = vec![first, second];
+            let mut end = second;
+            while let Some(edge) = next(end, &mut used) {
+                forward.push(edge);
+                end = edge;
+            }
+            let mut backward = Vec::new();
+            let mut end = first;
+            while let Some(edge) = next(end, &mut used) {
+                backward.push(edge);
+                end = edge;
+            }
+            backward.reverse();
+            backward.extend(forward);
+            let closed = backward.len() > 2 && backward.first() == backward.last();
+            let mut contour = Polyline::from_vec(
+                backward
+                    .into_iter()
+                    .map(|edge| self.crossing(edge, level))
+                    .collect(),
+            );
+            if closed {
+                contour.close();
+            }
+            contours.push(contour);
+        }
+        contours
+    }
+}
+
+/// Return inward offsets of the outline every `spacing`, computed as the
+/// contours of the distance field of the region.
+fn concentric(rings: &[&[CoordinatePair]], bounds: BoundingBox, spacing: f64) -> Vec<Polyline> {
+    let cell = spacing / 4.0;
+    // A margin of one cell around the region makes sure that all contours
+    // are closed
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let size = |extent: f64| (extent / cell).ceil() as usize + 3;
+    let (width, height) = (size(bounds.width()), size(bounds.height()));
+    let origin = CoordinatePair::new(bounds.min_x - cell, bounds.min_y - cell);
+    let mut values = Vec::with_capacity(width * height);
+    for j in 0..height {
+        for i in 0..width {
+            #[allow(clippy::cast_precision_loss)]
+            let p = CoordinatePair::new(origin.x + i as f64 * cell, origin.y + j as f64 * cell);
+            values.push(signed_distance(p, rings));
+        }
+    }
+    let grid = Grid {
+        origin,
+        cell,
+        width,
+        height,
+        values,
+    };
+
+    let max = grid.values.iter().copied().fold(0.0, f64::max);
+    let mut contours = Vec::new();
+    let mut level = spacing;
+    while level < max {
+        contours.extend(grid.contours(level));
+        level += spacing;
+    }
+    contours
+}
+
+/// Return a spiral around the center of the bounding box, clipped to the
+/// region.
+fn spiral(rings: &[&[CoordinatePair]], bounds: BoundingBox, spacing: f64) -> Vec<Polyline> {
+    let (center, radius) = circumcircle(bounds);
+    let mut points = vec![center];
+    let mut angle = 0.0;
+    let end = TAU * radius / spacing;
+    while angle < end {
+        let r = spacing * angle / TAU;
+        // Advance by about a quarter of the spacing along the spiral
+        angle += (spacing * 0.25 / r).min(PI / 8.0);
+        let r = spacing * angle / TAU;
+        points.push(CoordinatePair::new(
+            center.x + r * angle.cos(),
+            center.y + r * angle.sin(),
+        ));
+    }
+    clip_to_rings(&[Polyline::from_vec(points)], rings, MaskMode::KeepInside)
+}
+
+/// Fill the region bounded by `rings` according to the options.
+///
+/// Returns no lines for a non-positive spacing.
+pub fn fill(rings: &[Polyline], options: &FillOptions) -> Vec<Polyline> {
+    let spacing = options.spacing;
+    let Some(bounds) = bounding_box_of(rings).filter(|_| spacing > 0.0) else {
+        return Vec::new();
+    };
+    let rings: Vec<&[CoordinatePair]> = rings.iter().map(|ring| ring.as_slice()).collect();
+    match options.strategy {
+        FillStrategy::Hatch { angle } => hatch_lines(&rings, bounds, angle, spacing),
+        FillStrategy::CrossHatch { angles } => {
+            let mut lines = hatch_lines(&rings, bounds, angles[0], spacing);
+            lines.extend(hatch_lines(&rings, bounds, angles[1], spacing));
+            lines
+        }
+        FillStrategy::Concentric => concentric(&rings, bounds, spacing),
+        FillStrategy::Spiral => spiral(&rings, bounds, spacing),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::line;
+
+    fn square(min: f64, max: f64) -> Polyline {
+        line(&[(min, min), (max, min), (max, max), (min, max), (min, min)])
+    }
+
+    fn fill_square(strategy: FillStrategy) -> Vec<Polyline> {
+        let options = FillOptions {
+            strategy,
+            spacing: 2.0,
+        };
+        fill(&[square(0.0, 10.0)], &options)
+    }
+
+    #[test]
+    fn test_hatch() {
+        let lines = fill_square(FillStrategy::Hatch { angle: 0.0 });
+        let ys: Vec<f64> = lines.iter().map(|l| l[0].y).collect();
+        assert_eq!(ys, vec![1.0, 3.0, 5.0, 7.0, 9.0]);
+        for l in &lines {
+            assert_eq!(l.len(), 2);
+            assert!(((l[0].x - l[1].x).abs() - 10.0).abs() < 1e-9);
+        }
+        // Alternating directions
+        assert!(lines[0][0].x < lines[0][1].x);
+        assert!(lines[1][0].x > lines[1][1].x);
+
+        let lines = fill_square(FillStrategy::CrossHatch {
+            angles: [0.0, 90.0],
+        });
+        assert_eq!(lines.len(), 10);
+    }
+
+    #[test]
+    fn test_hatch_hole() {
+        let options = FillOptions {
+            strategy: FillStrategy::Hatch { angle: 0.0 },
+            spacing: 2.0,
+        };
+        let lines = fill(&[square(0.0, 10.0), square(4.0, 6.0)], &options);
+        assert_eq!(lines.len(), 6);
+    }
+
+    #[test]
+    fn test_concentric() {
+        let contours = fill_square(FillStrategy::Concentric);
+        assert_eq!(contours.len(), 2);
+        for (contour, inset) in contours.iter().zip([2.0, 4.0]) {
+            assert!(contour.is_closed());
+            for p in contour {
+                let distance = p.x.min(10.0 - p.x).min(p.y).min(10.0 - p.y);
+                assert!((distance - inset).abs() < 0.2, "{:?}", p);
+            }
+        }
+    }
+
+    #[test]
+    fn test_spiral() {
+        let lines = fill_square(FillStrategy::Spiral);
+        assert!(!lines.is_empty());
+        for p in lines.iter().flat_map(|l| l.iter()) {
+            assert!(p.x > -1e-9 && p.x < 10.0 + 1e-9 && p.y > -1e-9 && p.y < 10.0 + 1e-9);
+        }
+        assert!(fill(
+            &[square(0.0, 10.0)],
+            &FillOptions {
+                spacing: 0.0,
+                ..Default::default()
+            }
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+//! Extract inline SVG documents from HTML pages.
+
+use crate::{parse_with_options, Error, ParseOptions, Polyline};
+
+/// Return the position of the next `<svg` or `</svg` tag in the lowercased
+/// document, starting at `from`, and whether it is a closing tag.
+fn next_tag(lower: &str, from: usize) -> Option<(usize, bool)> {
+    let mut pos = from;
+    while let Some(offset) = lower[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &lower[start + 1..];
+        let (closing, rest) = match rest.strip_prefix('/') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        // Skip other elements, e.g. `<svgfoo>`
+        if let Some(after) = rest.strip_prefix("svg") {
+            if after.starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+                return Some((start, closing));
+            }
+        }
+        pos = start + 1;
+    }
+    None
+}
+
+/// Find all top-level `<svg>...</svg>` fragments in an HTML document.
+///
+/// Nested `<svg>` elements are part of their enclosing fragment. Unclosed
+/// fragments are ignored.
+pub fn find_svg_fragments(html: &str) -> Vec<&str> {
+    let lower = html.to_ascii_lowercase();
+    let mut fragments = Vec::new();
+    let mut pos = 0;
+    let mut depth = 0;
+    let mut start = 0;
+    while let Some((tag, closing)) = next_tag(&lower, pos) {
+        let Some(end) = lower[tag..].find('>').map(|end| tag + end + 1) else {
+            break;
+        };
+        let self_closing = lower[..end].ends_with("/>");
+        if closing {
+            if depth > 0 {
+                depth -= 1;
+                if depth == 0 {
+                    fragments.push(&html[start..end]);
+                }
+            }
+        } else {
+            if depth == 0 {
+                start = tag;
+            }
+            if self_closing {
+                if depth == 0 {
+                    fragments.push(&html[start..end]);
+                }
+            } else {
+                depth += 1;
+            }
+        }
+        pos = end;
+    }
+    fragments
+}
+
+/// Lowercase the names of all `<svg>` tags in the fragment, since HTML
+/// ignores their case but XML does not.
+fn lowercase_svg_tags(fragment: &str) -> String {
+    let lower = fragment.to_ascii_lowercase();
+    let mut svg = String::with_capacity(fragment.len());
+    let mut pos = 0;
+    while let Some((tag, closing)) = next_tag(&lower, pos) {
+        let name_end = tag + if closing { "</svg".len() } else { "<svg".len() };
+        svg.push_str(&fragment[pos..tag]);
+        svg.push_str(&lower[tag..name_end]);
+        pos = name_end;
+    }
+    svg.push_str(&fragment[pos..]);
+    svg
+}
+
+/// Parse the inline SVG number `index` (starting at 0) of an HTML document.
+///
+/// Inline SVGs in HTML often omit the namespace declaration, which is added
+/// if necessary. The case of the `<svg>` tags is ignored like in HTML.
+pub fn parse_html(
+    html: &str,
+    index: usize,
+    options: &ParseOptions,
+) -> Result<Vec<Polyline>, Error> {
+    let fragments = find_svg_fragments(html);
+    let fragment = fragments.get(index).ok_or_else(|| {
+        Error::SvgParse(format!(
+            "HTML input contains {} <svg> element(s), requested number {}",
+            fragments.len(),
+            index + 1
+        ))
+    })?;
+    let mut svg = lowercase_svg_tags(fragment);
+    let root_end = svg.find('>').unwrap_or(svg.len());
+    if !svg[..root_end].contains("xmlns=") {
+        svg.insert_str("<svg".len(), r#" xmlns="http://www.w3.org/2000/svg""#);
+    }
+    parse_with_options(&svg, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Preprocess;
+
+    const INPUT: &str = r#"<!DOCTYPE html>
+<html>
+<body>
+  <p>An icon: <SVG viewBox="0 0 10 10"><path d="M 0,0 1,1"/><svg><path d="M 2,2 3,3"/></svg></SVG></p>
+  <svgfoo></svgfoo>
+  <svg xmlns="http://www.w3.org/2000/svg"><path d="M 5,5 6,6"/></svg>
+  <svg/>
+</body>
+</html>"#;
+
+    #[test]
+    fn test_find_svg_fragments() {
+        let fragments = find_svg_fragments(INPUT);
+        assert_eq!(fragments.len(), 3);
+        assert!(fragments[0].starts_with("<SVG viewBox"));
+        assert!(fragments[0].ends_with("</svg></SVG>"));
+        assert_eq!(
+            fragments[1],
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><path d="M 5,5 6,6"/></svg>"#
+        );
+        assert_eq!(fragments[2], "<svg/>");
+    }
+
+    #[test]
+    fn test_parse_html() {
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        assert_eq!(parse_html(INPUT, 0, &options).unwrap().len(), 2);
+        assert_eq!(parse_html(INPUT, 1, &options).unwrap().len(), 1);
+        assert!(parse_html(INPUT, 2, &options).unwrap().is_empty());
+        assert!(parse_html(INPUT, 3, &options).is_err());
+    }
+}
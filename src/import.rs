@@ -0,0 +1,219 @@
+//! Read polylines back from the output of the CSV and JSON exporters.
+//!
+//! Together with the [`SvgExporter`] this allows round trips: flatten an SVG,
+//! post-process the points with some other tool and convert the result back
+//! into an SVG for preview.
+
+use std::str::FromStr;
+
+use crate::{
+    export::{Exporter, SvgExporter},
+    CoordinatePair, Error, Polyline,
+};
+
+/// The format of the data to import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// The format written by [`CsvExporter`](crate::export::CsvExporter).
+    Csv,
+    /// The format written by [`JsonExporter`](crate::export::JsonExporter).
+    Json,
+}
+
+fn parse_number(value: &str) -> Result<f64, Error> {
+    f64::from_str(value.trim()).map_err(|_| Error::Import(format!("Invalid number: {value}")))
+}
+
+/// Read polylines from CSV with the columns `polyline`, `x` and `y`.
+///
+/// The header line is optional. Consecutive rows with the same polyline index
+/// are joined into one polyline.
+pub fn from_csv(input: &str) -> Result<Vec<Polyline>, Error> {
+    let mut polylines = Vec::new();
+    let mut current: Option<(&str, Vec<CoordinatePair>)> = None;
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line.starts_with("polyline")) {
+            continue;
+        }
+        let columns: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [index, x, y] = columns[..] else {
+            return Err(Error::Import(format!(
+                "Line {}: Expected 3 columns, found {}",
+                i + 1,
+                columns.len()
+            )));
+        };
+        let point = CoordinatePair::new(parse_number(x)?, parse_number(y)?);
+        match &mut current {
+            Some((current_index, points)) if *current_index == index => {
+                points.push(point);
+            }
+            _ => {
+                if let Some((_, points)) = current.take() {
+                    polylines.push(Polyline::from_vec(points));
+                }
+                current = Some((index, vec![point]));
+            }
+        }
+    }
+    if let Some((_, points)) = current {
+        polylines.push(Polyline::from_vec(points));
+    }
+    Ok(polylines)
+}
+
+/// A minimal reader for the JSON subset written by the JSON exporter.
+struct JsonReader<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> JsonReader<'a> {
+    fn peek(&mut self) -> Option<char> {
+        self.input[self.pos..].chars().find(|c| !c.is_whitespace())
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        self.skip_whitespace();
+        if self.input[self.pos..].starts_with(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            Err(Error::Import(format!(
+                "Expected '{c}' at position {}",
+                self.pos
+            )))
+        }
+    }
+
+    /// Parse a comma separated list enclosed in `open` and `close`.
+    fn list<T>(
+        &mut self,
+        open: char,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, Error>,
+    ) -> Result<Vec<T>, Error> {
+        self.expect(open)?;
+        let mut items = Vec::new();
+        if self.peek() == Some(close) {
+            self.expect(close)?;
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if self.peek() == Some(',') {
+                self.expect(',')?;
+            } else {
+                self.expect(close)?;
+                return Ok(items);
+            }
+        }
+    }
+
+    fn token(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let rest = &self.input[self.pos..];
+        let len = rest
+            .find(|c: char| c == ',' || c == '}' || c == ']' || c == ':' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn key_value(&mut self) -> Result<(&'a str, f64), Error> {
+        let key = self.token().trim_matches('"');
+        self.expect(':')?;
+        let value = match self.token() {
+            "null" => f64::NAN,
+            number => parse_number(number)?,
+        };
+        Ok((key, value))
+    }
+
+    fn point(&mut self) -> Result<CoordinatePair, Error> {
+        let (mut x, mut y) = (None, None);
+        for (key, value) in self.list('{', '}', Self::key_value)? {
+            match key {
+                "x" => x = Some(value),
+                "y" => y = Some(value),
+                _ => {}
+            }
+        }
+        match (x, y) {
+            (Some(x), Some(y)) => Ok(CoordinatePair::new(x, y)),
+            _ => Err(Error::Import("Point without x or y coordinate".into())),
+        }
+    }
+
+    fn polyline(&mut self) -> Result<Polyline, Error> {
+        self.list('[', ']', Self::point).map(Polyline::from_vec)
+    }
+}
+
+/// Read polylines from a JSON array of arrays of `{"x": .., "y": ..}`
+/// objects. `null` coordinates are read as NaN.
+pub fn from_json(input: &str) -> Result<Vec<Polyline>, Error> {
+    let mut reader = JsonReader { input, pos: 0 };
+    let polylines = reader.list('[', ']', JsonReader::polyline)?;
+    if reader.peek().is_some() {
+        return Err(Error::Import("Trailing characters after JSON array".into()));
+    }
+    Ok(polylines)
+}
+
+/// Convert CSV or JSON polyline data into an SVG document, using the default
+/// [`SvgExporter`].
+pub fn to_svg(input: &str, format: ImportFormat) -> Result<String, Error> {
+    let polylines = match format {
+        ImportFormat::Csv => from_csv(input)?,
+        ImportFormat::Json => from_json(input)?,
+    };
+    SvgExporter::default().export_to_string(&polylines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{CsvExporter, JsonExporter};
+
+    fn polylines() -> Vec<Polyline> {
+        vec![
+            Polyline::from_vec(vec![(0.0, 0.0).into(), (1.5, -2.0).into()]),
+            Polyline::from_vec(vec![(3.0, 4.0).into()]),
+        ]
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let csv = CsvExporter.export_to_string(&polylines()).unwrap();
+        assert_eq!(from_csv(&csv).unwrap(), polylines());
+        assert!(from_csv("0,1").is_err());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = JsonExporter.export_to_string(&polylines()).unwrap();
+        assert_eq!(from_json(&json).unwrap(), polylines());
+        assert_eq!(
+            from_json(r#" [ [ { "y" : 2, "x" : 1e1 } ] , [ ] ] "#).unwrap(),
+            vec![
+                Polyline::from_vec(vec![(10.0, 2.0).into()]),
+                Polyline::new()
+            ]
+        );
+        assert!(from_json("[[{\"x\":1}]]").is_err());
+        assert!(from_json("[[]]]").is_err());
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let svg = to_svg("polyline,x,y\n0,0,0\n0,10,10\n", ImportFormat::Csv).unwrap();
+        assert!(svg.contains("<polyline"));
+    }
+}
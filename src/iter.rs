@@ -0,0 +1,141 @@
+//! Lazily convert the paths of a document into polylines.
+
+use std::{borrow::Cow, iter::FusedIterator, vec};
+
+use crate::{
+    convert_element, css::StyleSheet, Definitions, Error, ParseOptions, PathElements, Polyline,
+    Preprocess,
+};
+
+/// The polylines of a path together with its `id`.
+pub(crate) type IdentifiedPath = (Option<String>, Vec<Polyline>);
+
+/// An iterator over the polylines of an SVG document, see [`parse_iter`].
+pub struct ParseIter<'a> {
+    svg: &'a str,
+    elements: PathElements<'a, &'a [u8]>,
+    definitions: Definitions,
+    /// The remaining polylines of the current path.
+    polylines: vec::IntoIter<Polyline>,
+    done: bool,
+}
+
+/// Parse an SVG string lazily into [`Polyline`]s.
+///
+/// Paths are only parsed and flattened when the iterator reaches them, so
+/// huge documents can be processed with bounded memory, and stopping early
+/// skips the rest of the document. The SVG is not preprocessed, so the
+/// polylines are the same as those of [`parse`](crate::parse) with
+/// `preprocess` set to `false`. Data URIs are not supported.
+///
+/// The iteration ends after the first error.
+///
+/// ```
+/// let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+///     <path d="M 0,0 10,10"/>
+///     <path d="M 0,0 X"/>
+/// </svg>"#;
+/// let mut polylines = svg2polylines::parse_iter(svg, 0.15);
+/// assert_eq!(polylines.next().unwrap().unwrap().len(), 2);
+/// assert!(polylines.next().unwrap().is_err());
+/// assert!(polylines.next().is_none());
+/// ```
+pub fn parse_iter(svg: &str, tol: f64) -> ParseIter<'_> {
+    let options = ParseOptions {
+        tolerance: tol,
+        preprocess: Preprocess::No,
+        ..Default::default()
+    };
+    ParseIter::with_options(svg, options)
+}
+
+impl<'a> ParseIter<'a> {
+    /// Iterate over the polylines of `svg` with the given options, which
+    /// must be streamable, see [`is_streamable`](crate::reader::is_streamable).
+    pub(crate) fn with_options(svg: &'a str, options: ParseOptions) -> Self {
+        ParseIter {
+            svg,
+            elements: PathElements::borrowed(svg, StyleSheet::default(), Cow::Owned(options)),
+            definitions: Definitions::default(),
+            polylines: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    /// Parse the next path into polylines and return them with the `id` of
+    /// the path, or return `None` at the end of the document.
+    pub(crate) fn next_path(&mut self) -> Result<Option<IdentifiedPath>, Error> {
+        while let Some(element) = self.elements.next_element()? {
+            let id = element.id.clone();
+            let converted = convert_element(
+                element,
+                &self.definitions,
+                &self.elements.options,
+                &mut Vec::new(),
+            )
+            .map_err(|mut e| {
+                if let Error::Element { context, .. } = &mut e {
+                    context.locate(self.svg);
+                }
+                e
+            })?;
+            if let Some((polylines, _)) = converted {
+                return Ok(Some((id, polylines)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Iterator for ParseIter<'_> {
+    type Item = Result<Polyline, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(polyline) = self.polylines.next() {
+                return Some(Ok(polyline));
+            }
+            if self.done {
+                return None;
+            }
+            match self.next_path() {
+                Ok(Some((_, polylines))) => self.polylines = polylines.into_iter(),
+                Ok(None) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+impl FusedIterator for ParseIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_parse_iter() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <g transform="translate(10 0)">
+                <path d="M 0,0 L 10,10 M 20,20 L 30,30"/>
+                <rect x="0" y="0" width="5" height="5"/>
+            </g>
+            <path d="M 0,0 C 10,0 10,10 0,10"/>
+        </svg>"#;
+        let polylines: Vec<Polyline> = parse_iter(svg, 0.15).map(Result::unwrap).collect();
+        assert_eq!(polylines.len(), 4);
+        assert_eq!(polylines, parse(svg, 0.15, false).unwrap());
+    }
+
+    #[test]
+    fn test_parse_iter_malformed() {
+        let mut polylines = parse_iter(r#"<svg><path d="M 0,0 L 1,1"></svg>"#, 0.15);
+        assert!(matches!(polylines.next(), Some(Ok(_))));
+        assert!(matches!(polylines.next(), Some(Err(Error::SvgParse(_)))));
+        assert!(polylines.next().is_none());
+    }
+}
@@ -0,0 +1,179 @@
+//! Give polylines a sketchy, hand-drawn look.
+
+use crate::{CoordinatePair, Polyline};
+
+/// Options for [`jitter`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JitterOptions {
+    /// Maximum displacement of a point in either direction.
+    pub amplitude: f64,
+
+    /// Number of noise waves per unit of length. Higher values result in a
+    /// more wobbly line.
+    pub frequency: f64,
+
+    /// Number of times every polyline is drawn, each time with different
+    /// noise.
+    pub passes: usize,
+
+    /// Seed for the noise generator. The same seed always produces the same
+    /// output.
+    pub seed: u64,
+}
+
+impl Default for JitterOptions {
+    fn default() -> Self {
+        Self {
+            amplitude: 1.0,
+            frequency: 0.1,
+            passes: 1,
+            seed: 0,
+        }
+    }
+}
+
+/// Hash a lattice coordinate into a pseudo random value in `[-1, 1]`
+/// (splitmix64).
+fn lattice_value(seed: u64, index: i64) -> f64 {
+    #[allow(clippy::cast_sign_loss)]
+    let mut z = seed ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    #[allow(clippy::cast_precision_loss)]
+    let unit = (z >> 11) as f64 / (1_u64 << 53) as f64;
+    unit * 2.0 - 1.0
+}
+
+/// Smooth one-dimensional value noise in `[-1, 1]`.
+fn noise(seed: u64, t: f64) -> f64 {
+    let floor = t.floor();
+    let frac = t - floor;
+    #[allow(clippy::cast_possible_truncation)]
+    let i = floor as i64;
+    let a = lattice_value(seed, i);
+    let b = lattice_value(seed, i + 1);
+    let smooth = frac * frac * (3.0 - 2.0 * frac);
+    a + (b - a) * smooth
+}
+
+/// Split long segments, so that consecutive points are at most `max_distance`
+/// apart. Every resulting point is paired with its distance along the line.
+fn subdivide(polyline: &Polyline, max_distance: f64) -> Vec<(CoordinatePair, f64)> {
+    let mut points = Vec::with_capacity(polyline.len());
+    let mut distance = 0.0;
+    for (i, &p) in polyline.iter().enumerate() {
+        if i > 0 {
+            let prev = polyline[i - 1];
+            let length = (p.x - prev.x).hypot(p.y - prev.y);
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let steps = (length / max_distance).ceil().max(1.0) as usize;
+            for step in 1..steps {
+                #[allow(clippy::cast_precision_loss)]
+                let t = step as f64 / steps as f64;
+                points.push((
+                    CoordinatePair::new(prev.x + (p.x - prev.x) * t, prev.y + (p.y - prev.y) * t),
+                    distance + length * t,
+                ));
+            }
+            distance += length;
+        }
+        points.push((p, distance));
+    }
+    points
+}
+
+/// Perturb all points with smooth noise, giving the drawing a hand-drawn look.
+///
+/// Long segments are subdivided first, so that straight lines get wobbly as
+/// well. If more than one pass is requested, every polyline is emitted once
+/// per pass, each time with different noise.
+pub fn jitter(polylines: &[Polyline], options: &JitterOptions) -> Vec<Polyline> {
+    // Four points per noise wave are enough for a smooth look
+    let max_distance = if options.frequency > 0.0 {
+        0.25 / options.frequency
+    } else {
+        f64::INFINITY
+    };
+
+    let mut result = Vec::with_capacity(polylines.len() * options.passes);
+    for pass in 0..options.passes {
+        for (index, polyline) in polylines.iter().enumerate() {
+            let seed = options
+                .seed
+                .wrapping_add((pass as u64) << 32)
+                .wrapping_add(index as u64 * 2);
+            let points = subdivide(polyline, max_distance)
+                .into_iter()
+                .map(|(p, distance)| {
+                    let t = distance * options.frequency;
+                    CoordinatePair::new(
+                        p.x + options.amplitude * noise(seed, t),
+                        p.y + options.amplitude * noise(seed + 1, t),
+                    )
+                })
+                .collect();
+            let mut jittered = Polyline::from_vec(points);
+            if polyline.is_closed() {
+                // The closing point got different noise than the first one
+                jittered.pop();
+                jittered.close();
+            }
+            result.push(jittered);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line() -> Polyline {
+        Polyline::from_vec(vec![
+            CoordinatePair::new(0.0, 0.0),
+            CoordinatePair::new(100.0, 0.0),
+        ])
+    }
+
+    #[test]
+    fn test_jitter_bounded_and_deterministic() {
+        let options = JitterOptions {
+            amplitude: 2.0,
+            frequency: 0.1,
+            passes: 2,
+            seed: 42,
+        };
+        let result = jitter(&[line()], &options);
+        assert_eq!(result.len(), 2);
+        // 100 units at 2.5 units per point
+        assert_eq!(result[0].len(), 41);
+        assert_ne!(result[0], result[1]);
+        for (jittered, original) in result[0].iter().zip(subdivide(&line(), 2.5)) {
+            assert!((jittered.x - original.0.x).abs() <= 2.0);
+            assert!((jittered.y - original.0.y).abs() <= 2.0);
+        }
+        assert_eq!(jitter(&[line()], &options), result);
+
+        let mut triangle = Polyline::from_vec(vec![
+            (0.0, 0.0).into(),
+            (10.0, 0.0).into(),
+            (0.0, 10.0).into(),
+        ]);
+        triangle.close();
+        let result = jitter(&[triangle], &options);
+        assert!(result[0].is_closed());
+        assert_eq!(result[0].first(), result[0].last());
+    }
+
+    #[test]
+    fn test_jitter_zero_amplitude() {
+        let options = JitterOptions {
+            amplitude: 0.0,
+            ..Default::default()
+        };
+        let result = jitter(&[line()], &options);
+        assert_eq!(result[0][0], (0.0, 0.0).into());
+        assert_eq!(result[0][result[0].len() - 1], (100.0, 0.0).into());
+    }
+}
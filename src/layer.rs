@@ -0,0 +1,98 @@
+//! Inkscape layers.
+//!
+//! Inkscape stores layers as groups with `inkscape:groupmode="layer"`, named
+//! by their `inkscape:label`.
+
+use log::trace;
+use quick_xml::events::{BytesStart, Event};
+
+use crate::{style, Error};
+
+/// Return the name of a layer, or `None` if the element is not a layer.
+///
+/// Layers without a label are named by their `id`.
+pub(crate) fn name(e: &BytesStart) -> Option<String> {
+    if style::attribute(e, b"inkscape:groupmode").as_deref() != Some("layer") {
+        return None;
+    }
+    Some(
+        style::attribute(e, b"inkscape:label")
+            .or_else(|| style::attribute(e, b"id"))
+            .unwrap_or_default(),
+    )
+}
+
+/// Return a copy of the document without the layers that are not listed in
+/// `layers`.
+///
+/// Sublayers of a listed layer are kept. Elements outside of any layer are
+/// kept as well.
+pub(crate) fn filter(svg: &str, layers: &[String]) -> Result<String, Error> {
+    let mut reader = quick_xml::Reader::from_str(svg);
+    let mut writer = quick_xml::Writer::new(Vec::new());
+    let mut write = |event: &Event| {
+        writer
+            .write_event(event)
+            .map_err(|e| Error::SvgParse(e.to_string()))
+    };
+    let mut buf = Vec::new();
+
+    // Whether the open elements are inside of a listed layer
+    let mut listed: Vec<bool> = Vec::new();
+    // Nesting depth inside of a dropped layer
+    let mut skip = 0;
+    loop {
+        let event = reader
+            .read_event(&mut buf)
+            .map_err(|e| Error::SvgParse(e.to_string()))?;
+        match event {
+            Event::Eof => break,
+            Event::Start(_) if skip > 0 => skip += 1,
+            Event::End(_) if skip > 0 => skip -= 1,
+            _ if skip > 0 => {}
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let parent = listed.last().copied().unwrap_or(false);
+                match name(e) {
+                    Some(name) if !parent && !layers.contains(&name) => {
+                        trace!("layer::filter: Drop layer \"{name}\"");
+                        if matches!(event, Event::Start(_)) {
+                            skip = 1;
+                        }
+                    }
+                    layer => {
+                        if matches!(event, Event::Start(_)) {
+                            listed.push(parent || layer.is_some());
+                        }
+                        write(&event)?;
+                    }
+                }
+            }
+            event => {
+                if matches!(event, Event::End(_)) {
+                    listed.pop();
+                }
+                write(&event)?;
+            }
+        }
+        buf.clear();
+    }
+    String::from_utf8(writer.into_inner()).map_err(|e| Error::SvgParse(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let input = r#"<svg xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape"><g inkscape:groupmode="layer" inkscape:label="Pen 1"><path d="M 0,0 1,1"/><g inkscape:groupmode="layer" id="sub"/></g><g inkscape:groupmode="layer" inkscape:label="Pen 2"><path d="M 2,2 3,3"/></g><g inkscape:groupmode="layer" id="empty"/><path d="M 4,4 5,5"/></svg>"#;
+        assert_eq!(
+            filter(input, &["Pen 1".into()]).unwrap(),
+            r#"<svg xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape"><g inkscape:groupmode="layer" inkscape:label="Pen 1"><path d="M 0,0 1,1"/><g inkscape:groupmode="layer" id="sub"/></g><path d="M 4,4 5,5"/></svg>"#
+        );
+        assert_eq!(
+            filter(input, &["empty".into(), "sub".into()]).unwrap(),
+            r#"<svg xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape"><g inkscape:groupmode="layer" id="empty"/><path d="M 4,4 5,5"/></svg>"#
+        );
+    }
+}
@@ -8,8 +8,13 @@
 //! [Lyon](https://github.com/nical/lyon) library. SVG files are preprocessed /
 //! simplified using [usvg](https://docs.rs/usvg/).
 //!
-//! **Note: Currently the path style is completely ignored. Only the path itself is
-//! returned.**
+//! By default, only the geometry of the paths is returned and their style is
+//! ignored. The style is read where it is requested:
+//! [`parse_with_metadata`] returns the stroke, fill and class of every
+//! polyline, and [`ParseOptions`] can split dashed paths into their dashes
+//! ([`dashes`](ParseOptions::dashes)), replace strokes by their outlines
+//! ([`outline_strokes`](ParseOptions::outline_strokes)) and drop paths
+//! without fill and stroke ([`skip_invisible`](ParseOptions::skip_invisible)).
 //!
 //! ## MSRV
 //!
@@ -18,7 +23,31 @@
 //! ## Serialization
 //!
 //! You can optionally get serde 1 support by enabling the `serde` feature.
+//!
+//! ## Serial plotting
+//!
+//! Enable the `serial` feature to get
+//! [`device::open_serial`](device/fn.open_serial.html), which streams
+//! G-code to a machine connected to a serial port.
+//!
+//! ## Async
+//!
+//! Enable the `async` feature to get the [`async_io`](async_io/index.html)
+//! module, which provides tokio based variants of the parsing and export APIs.
+//!
+//! ## File watching
+//!
+//! Enable the `watch` feature to get [`watch`], which re-parses an SVG file
+//! whenever it changes.
+//!
+//! ## `no_std`
+//!
+//! Without the default `std` feature, the crate only contains the
+//! [`flatten`] module and the [`Polyline`] type, so that path expressions can
+//! be flattened using `core` and `alloc` only, e.g. on a microcontroller.
+//! Parsing SVG documents requires the `std` feature.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(clippy::all)]
 #![warn(clippy::pedantic)]
 #![allow(clippy::single_match)]
@@ -26,292 +55,666 @@
 #![allow(clippy::must_use_candidate)]
 #![allow(clippy::too_many_lines)]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
 use std::{
-    convert::{From, TryInto},
-    f64, mem,
-    ops::Index,
-    str,
+    borrow::Cow,
+    collections::HashMap,
+    convert::From,
+    f64,
+    io::BufRead,
+    str::{self, FromStr},
 };
 
+#[cfg(feature = "std")]
 use log::trace;
-use lyon_geom::{
-    euclid::{Point2D, Transform2D},
-    CubicBezierSegment, QuadraticBezierSegment,
-};
-use quick_xml::events::Event;
+#[cfg(feature = "std")]
+use quick_xml::events::{BytesStart, Event};
+#[cfg(feature = "std")]
 use svgtypes::{PathParser, PathSegment};
 
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use crate::{
+    clip_path::{ClipPath, ClipReference, ClipState},
+    css::StyleSheet,
+    dash::{DashStyle, Dashes},
+    depth::DepthSource,
+    document::RootAttributes,
+    marker::{Marker, MarkerState, Markers},
+    outline::{Stroke, StrokeStyle},
+    pattern::{Pattern, PatternState},
+    style::Paint,
+    transform::{ComposedTransform, TransformStack},
+};
 
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "std")]
+mod bbox;
+#[cfg(feature = "std")]
+mod cancel;
+#[cfg(feature = "std")]
+pub mod centerline;
+#[cfg(feature = "std")]
+mod clip;
+#[cfg(feature = "std")]
+mod clip_path;
+#[cfg(feature = "std")]
+mod css;
+#[cfg(feature = "std")]
+mod dash;
+#[cfg(feature = "std")]
+mod data_uri;
+#[cfg(feature = "std")]
+pub mod depth;
+#[cfg(feature = "std")]
+pub mod device;
+#[cfg(feature = "std")]
+mod document;
+#[cfg(feature = "std")]
+mod encoding;
+#[cfg(feature = "std")]
 mod error;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+mod flat;
+pub mod flatten;
+#[cfg(feature = "std")]
+mod fonts;
+#[cfg(feature = "std")]
+mod group;
+#[cfg(feature = "std")]
+pub mod hatch;
+#[cfg(feature = "std")]
+mod html;
+#[cfg(feature = "std")]
+pub mod import;
+#[cfg(feature = "std")]
+mod iter;
+#[cfg(feature = "std")]
+pub mod jitter;
+#[cfg(feature = "std")]
+mod layer;
+#[cfg(feature = "std")]
+mod limits;
+#[cfg(feature = "std")]
+mod line_path;
+#[cfg(feature = "std")]
+pub mod lint;
+#[cfg(feature = "std")]
+mod marker;
+#[cfg(feature = "std")]
+mod metadata;
+#[cfg(feature = "std")]
+pub mod morph;
+#[cfg(feature = "std")]
+mod multi;
+#[cfg(feature = "std")]
+mod nesting;
+#[cfg(feature = "std")]
+pub mod optimize;
+#[cfg(feature = "std")]
+mod options;
+#[cfg(feature = "std")]
+mod outline;
+#[cfg(feature = "std")]
+mod pack;
+#[cfg(feature = "std")]
+mod pages;
+#[cfg(feature = "std")]
+pub mod paper;
+#[cfg(feature = "std")]
+mod parser;
+#[cfg(feature = "std")]
+mod pattern;
+#[cfg(feature = "std")]
+pub mod pens;
+#[cfg(feature = "std")]
+mod pipeline;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+mod recover;
+#[cfg(feature = "std")]
+mod select;
+#[cfg(feature = "std")]
+mod shape;
+#[cfg(feature = "std")]
+mod simplify;
+#[cfg(feature = "std")]
+mod sink;
+#[cfg(feature = "std")]
+mod strict;
+#[cfg(feature = "std")]
+mod style;
+#[cfg(feature = "std")]
+mod transform;
+#[cfg(feature = "std")]
+mod unit;
+#[cfg(feature = "std")]
+mod validate;
+#[cfg(feature = "std")]
+mod warning;
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "std")]
+pub mod weave;
+
+#[cfg(feature = "std")]
+pub use bbox::{bounding_box_of, BoundingBox};
+#[cfg(feature = "std")]
+pub use cancel::CancelToken;
+#[cfg(feature = "std")]
+pub use clip::{clip_to_polygon, MaskMode, ViewportClip};
+#[cfg(feature = "std")]
+pub use device::{Device, Plan};
+#[cfg(feature = "std")]
+pub use document::{parse_document, Document, ViewBox};
+#[cfg(feature = "std")]
+pub use error::{Error, ErrorContext};
+#[cfg(feature = "std")]
+pub use export::Exporter;
+#[cfg(feature = "std")]
+pub use flat::FlatPolylines;
+pub use flatten::{
+    point_in_polylines, total_length, transform_all, CoordinatePair, FillRule, Polyline, Winding,
+};
+#[cfg(feature = "std")]
+pub use fonts::FontOptions;
+#[cfg(feature = "std")]
+pub use group::parse_group;
+#[cfg(feature = "std")]
+pub use html::{find_svg_fragments, parse_html};
+#[cfg(feature = "std")]
+pub use iter::{parse_iter, ParseIter};
+#[cfg(feature = "std")]
+pub use limits::Limits;
+pub use lyon_geom::euclid::Transform2D;
+#[cfg(feature = "std")]
+pub use metadata::{parse_with_metadata, PolylineWithMetadata};
+#[cfg(feature = "std")]
+pub use multi::{parse_many, PlacedPolyline, SvgInput};
+#[cfg(feature = "std")]
+pub use nesting::{nesting, NestingNode};
+#[cfg(feature = "std")]
+pub use options::{NonFinitePolicy, ParseOptions, Preprocess, UnsupportedPolicy};
+#[cfg(feature = "std")]
+pub use pack::pack;
+#[cfg(feature = "std")]
+pub use pages::{parse_pages, Page};
+#[cfg(feature = "std")]
+pub use parser::Parser;
+#[cfg(feature = "std")]
+pub use pipeline::{Pipeline, PipelineBuilder, Stage};
+#[cfg(feature = "std")]
+pub use reader::{parse_bytes, parse_file, parse_reader};
+#[cfg(feature = "std")]
+pub use sink::{parse_into, PathMeta, PolylineSink};
+#[cfg(feature = "std")]
+pub use strict::StrictViolation;
+#[cfg(feature = "std")]
+pub use svgtypes::{Align, AspectRatio, Color, Length, LengthUnit};
+#[cfg(feature = "std")]
+pub use unit::Unit;
+#[cfg(feature = "std")]
+pub use validate::{validate_envelope, EnvelopeViolation};
+#[cfg(feature = "std")]
+pub use warning::Warning;
+#[cfg(feature = "watch")]
+pub use watch::{watch, FileWatcher};
+
+#[cfg(feature = "std")]
+use crate::flatten::{subpath_estimates, CurrentLine};
+
+/// A path expression found by [`parse_xml`].
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq)]
+struct PathElement<'a> {
+    /// The path data, borrowed from the document if possible.
+    data: Cow<'a, str>,
 
-pub use error::Error;
-
-/// A pair of x and y coordinates.
-#[derive(Debug, PartialEq, Copy, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[repr(C)]
-pub struct CoordinatePair {
-    pub x: f64,
-    pub y: f64,
-}
-
-impl CoordinatePair {
-    pub fn new(x: f64, y: f64) -> Self {
-        Self { x, y }
-    }
+    /// The `id` attribute of the element.
+    id: Option<String>,
 
-    /// Apply a 2D transformation.
-    pub fn transform(&mut self, t: Transform2D<f64, f64, f64>) {
-        let Point2D { x, y, .. } = t.transform_point(Point2D::new(self.x, self.y));
-        self.x = x;
-        self.y = y;
-    }
-}
+    /// Index of the element in document order.
+    index: usize,
 
-impl From<(f64, f64)> for CoordinatePair {
-    fn from(val: (f64, f64)) -> Self {
-        Self { x: val.0, y: val.1 }
-    }
-}
+    /// Byte offset of the element in the parsed document.
+    offset: usize,
 
-/// A polyline is a vector of [`CoordinatePair`] instances.
-///
-/// Note: This is a newtype around a [`Vec`] that can be iterated and indexed.
-/// To get access to the underlying vector, use [`.as_ref()`](Polyline::as_ref)
-/// or [`.unwrap()`](Polyline::unwrap).
-#[repr(transparent)]
-#[derive(Debug, PartialEq)]
-pub struct Polyline(Vec<CoordinatePair>);
+    /// The transform of the element, composed with the transforms of all
+    /// ancestor elements.
+    transform: ComposedTransform,
 
-impl Polyline {
-    /// Create a new, empty polyline.
-    pub fn new() -> Self {
-        Polyline(vec![])
-    }
+    /// The dash pattern, only read if `options.dashes` is enabled.
+    dashes: Option<Dashes>,
 
-    /// Create a new polyline from a vector.
-    pub fn from_vec(vec: Vec<CoordinatePair>) -> Self {
-        Polyline(vec)
-    }
+    /// The stroke geometry, only read if `options.outline_strokes` is
+    /// enabled and the element is stroked.
+    stroke: Option<Stroke>,
 
-    /// Apply a transformation to all coordinate pairs
-    fn transform(mut self, t: Transform2D<f64, f64, f64>) -> Self {
-        for p in &mut self.0 {
-            p.transform(t);
-        }
-        self
-    }
+    /// The clip paths and masks of the element and its ancestors, only read
+    /// if `options.clip_paths` or `options.mask_threshold` is set.
+    clips: Vec<ClipReference>,
 
-    /// Unwrap and return the inner vector.
-    #[must_use]
-    pub fn unwrap(self) -> Vec<CoordinatePair> {
-        self.0
-    }
-}
+    /// The id of the pattern filling the element, only read if
+    /// `options.patterns` is enabled.
+    pattern: Option<String>,
 
-impl AsRef<Vec<CoordinatePair>> for Polyline {
-    fn as_ref(&self) -> &Vec<CoordinatePair> {
-        &self.0
-    }
-}
+    /// The markers of the element, only read if `options.markers` is
+    /// enabled.
+    markers: Option<Markers>,
 
-impl Default for Polyline {
-    fn default() -> Self {
-        Self::new()
-    }
+    /// The cutting depth of the element or its ancestors, only read if a
+    /// [`DepthSource`] is set.
+    depth: Option<f64>,
 }
 
-impl Index<usize> for Polyline {
-    type Output = CoordinatePair;
+/// Parse an SVG string, return the path expressions of all path and shape
+/// elements.
+///
+/// If `options.skip_invisible` is enabled, elements with neither a fill nor
+/// a stroke are skipped, and the content of clip paths, masks, patterns and
+/// markers is skipped if they are applied. Styles are only read if an option needs them.
+#[cfg(feature = "std")]
+fn parse_xml<'a>(svg: &'a str, options: &'a ParseOptions) -> Result<Vec<PathElement<'a>>, Error> {
+    trace!("parse_xml");
 
-    fn index(&self, id: usize) -> &Self::Output {
-        &self.0[id]
-    }
+    let sheet = if needs_style_sheet(options) {
+        StyleSheet::from_svg(svg)
+    } else {
+        StyleSheet::default()
+    };
+    let (paths, _) = parse_events(PathElements::borrowed(svg, sheet, Cow::Borrowed(options)))?;
+    Ok(paths)
 }
 
-impl IntoIterator for Polyline {
-    type Item = CoordinatePair;
-    type IntoIter = std::vec::IntoIter<Self::Item>;
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
-    }
+/// Whether the options need the style properties of elements, including the
+/// rules of `<style>` elements.
+#[cfg(feature = "std")]
+fn needs_style_sheet(options: &ParseOptions) -> bool {
+    options.skip_invisible
+        || options.outline_strokes
+        || options.dashes
+        || options.clip_paths
+        || options.mask_threshold.is_some()
+        || options.patterns
+        || options.markers
 }
 
-impl<'a> IntoIterator for &'a Polyline {
-    type Item = &'a CoordinatePair;
-    type IntoIter = std::slice::Iter<'a, CoordinatePair>;
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.iter()
+/// Read all path expressions, together with the attributes of the root
+/// element.
+#[cfg(feature = "std")]
+fn parse_events<R: BufRead>(
+    mut elements: PathElements<'_, R>,
+) -> Result<(Vec<PathElement<'_>>, Option<RootAttributes>), Error> {
+    let mut paths = Vec::new();
+    while let Some(path) = elements.next_element()? {
+        paths.push(path);
     }
+    trace!("parse_xml: Return {} paths", paths.len());
+    Ok((paths, elements.root))
 }
 
-impl std::ops::Deref for Polyline {
-    type Target = Vec<CoordinatePair>;
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+/// The XML reader of [`PathElements`].
+#[cfg(feature = "std")]
+enum XmlEvents<'a, R: BufRead> {
+    /// Reads the events into a buffer, so path data has to be copied.
+    Buffered(quick_xml::Reader<R>, Vec<u8>),
+    /// Reads the events from a string, so path data can be borrowed.
+    Borrowed(quick_xml::Reader<&'a [u8]>, &'a [u8]),
 }
 
-impl std::ops::DerefMut for Polyline {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
+/// Return the content of a tag that was read from `input` without a buffer
+/// as a slice of the input.
+#[cfg(feature = "std")]
+fn tag_content<'a>(input: &'a [u8], e: &BytesStart) -> Option<&'a [u8]> {
+    let start = (e.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+    input.get(start..start + e.len())
 }
 
-#[derive(Debug, PartialEq)]
-struct CurrentLine {
-    /// The polyline containing the coordinate pairs for the current line.
-    line: Polyline,
-
-    /// This is set to the start coordinates of the previous polyline if the
-    /// path expression contains multiple polylines.
-    prev_end: Option<CoordinatePair>,
+/// A start or empty tag, or another XML event.
+#[cfg(feature = "std")]
+enum Tag<'e> {
+    Open(BytesStart<'e>, bool),
+    Close,
+    Eof,
+    Other,
 }
 
-/// Simple data structure that acts as a [`Polyline`] buffer.
-impl CurrentLine {
-    fn new() -> Self {
-        Self {
-            line: Polyline::new(),
-            prev_end: None,
-        }
-    }
-
-    /// Add a [`CoordinatePair`] to the internal polyline.
-    fn add_absolute(&mut self, pair: CoordinatePair) {
-        self.line.push(pair);
-    }
-
-    /// Add a relative [`CoordinatePair`] to the internal polyline.
-    fn add_relative(&mut self, pair: CoordinatePair) {
-        if let Some(last) = self.line.last() {
-            let cp = CoordinatePair::new(last.x + pair.x, last.y + pair.y);
-            self.add_absolute(cp);
-        } else if let Some(last) = self.prev_end {
-            self.add_absolute(CoordinatePair::new(last.x + pair.x, last.y + pair.y));
-        } else {
-            self.add_absolute(pair);
-        }
-    }
-
-    /// Add a [`CoordinatePair`] to the internal polyline.
-    fn add(&mut self, abs: bool, pair: CoordinatePair) {
-        if abs {
-            self.add_absolute(pair);
-        } else {
-            self.add_relative(pair);
+#[cfg(feature = "std")]
+impl<'e> Tag<'e> {
+    fn from_event(event: quick_xml::Result<Event<'e>>) -> Result<Self, Error> {
+        match event {
+            Ok(Event::Start(e)) => Ok(Self::Open(e, true)),
+            Ok(Event::Empty(e)) => Ok(Self::Open(e, false)),
+            Ok(Event::End(_)) => Ok(Self::Close),
+            Ok(Event::Eof) => Ok(Self::Eof),
+            Ok(_) => Ok(Self::Other),
+            Err(e) => Err(Error::SvgParse(e.to_string())),
         }
     }
+}
 
-    /// A polyline is only valid if it has more than 1 [`CoordinatePair`].
-    fn is_valid(&self) -> bool {
-        self.line.len() > 1
-    }
-
-    /// Return the last [`CoordinatePair`] (if the line is not empty).
-    fn last_pair(&self) -> Option<CoordinatePair> {
-        self.line.last().copied()
-    }
+/// Reads the path expressions from the events of an XML reader one by one.
+#[cfg(feature = "std")]
+struct PathElements<'a, R: BufRead> {
+    events: XmlEvents<'a, R>,
+    sheet: StyleSheet,
+    options: Cow<'a, ParseOptions>,
+    transforms: TransformStack,
+    // Styles of the open elements, only tracked if needed
+    paints: Vec<Paint>,
+    dash_styles: Vec<DashStyle>,
+    stroke_styles: Vec<StrokeStyle>,
+    clip_states: Vec<ClipState>,
+    pattern_states: Vec<PatternState>,
+    marker_states: Vec<MarkerState>,
+    // Where cutting depths are read from, and the depths of the open elements
+    depth_source: Option<&'a DepthSource>,
+    depths: Vec<Option<f64>>,
+    // Identifies the elements referencing clip paths
+    element: usize,
+    /// The index of the next element in document order.
+    index: usize,
+    /// The number of path expressions returned so far.
+    paths: usize,
+    /// The attributes of the root element, once it has been read.
+    root: Option<RootAttributes>,
+    seen_root: bool,
+}
 
-    /// Return the last x coordinate (if the line is not empty).
-    fn last_x(&self) -> Option<f64> {
-        self.line.last().map(|pair| pair.x)
+#[cfg(feature = "std")]
+impl<'a> PathElements<'a, &'a [u8]> {
+    /// Read the path expressions of a string, borrowing the path data.
+    fn borrowed(svg: &'a str, sheet: StyleSheet, options: Cow<'a, ParseOptions>) -> Self {
+        let mut reader = quick_xml::Reader::from_str(svg);
+        reader.trim_text(true);
+        Self::with_events(XmlEvents::Borrowed(reader, svg.as_bytes()), sheet, options)
     }
+}
 
-    /// Return the last y coordinate (if the line is not empty).
-    fn last_y(&self) -> Option<f64> {
-        self.line.last().map(|pair| pair.y)
+#[cfg(feature = "std")]
+impl<'a, R: BufRead> PathElements<'a, R> {
+    fn new(
+        mut reader: quick_xml::Reader<R>,
+        sheet: StyleSheet,
+        options: Cow<'a, ParseOptions>,
+    ) -> Self {
+        reader.trim_text(true);
+        Self::with_events(XmlEvents::Buffered(reader, Vec::new()), sheet, options)
     }
 
-    /// Close the line by adding the first entry to the end.
-    fn close(&mut self) -> Result<(), Error> {
-        if self.line.len() < 2 {
-            Err(Error::Polyline(
-                "Lines with less than 2 coordinate pairs cannot be closed.".into(),
-            ))
-        } else {
-            let first = self.line[0];
-            self.line.push(first);
-            self.prev_end = Some(first);
-            Ok(())
+    fn with_events(
+        events: XmlEvents<'a, R>,
+        sheet: StyleSheet,
+        options: Cow<'a, ParseOptions>,
+    ) -> Self {
+        Self {
+            events,
+            sheet,
+            options,
+            transforms: TransformStack::default(),
+            paints: Vec::new(),
+            dash_styles: Vec::new(),
+            stroke_styles: Vec::new(),
+            clip_states: Vec::new(),
+            pattern_states: Vec::new(),
+            marker_states: Vec::new(),
+            depth_source: None,
+            depths: Vec::new(),
+            element: 0,
+            index: 0,
+            paths: 0,
+            root: None,
+            seen_root: false,
         }
     }
 
-    /// Replace the internal [`Polyline`] with a new instance and return the
-    /// previously stored [`Polyline`].
-    fn finish(&mut self) -> Polyline {
-        self.prev_end = self.line.last().copied();
-        let mut tmp = Polyline::new();
-        mem::swap(&mut self.line, &mut tmp);
-        tmp
+    /// Read the cutting depth of every element from `source`.
+    fn with_depths(mut self, source: &'a DepthSource) -> Self {
+        self.depth_source = Some(source);
+        self
     }
-}
-
-/// Parse an SVG string, return vector of `(path expression, transform
-/// expression)` tuples.
-fn parse_xml(svg: &str) -> Result<Vec<(String, Option<String>)>, Error> {
-    trace!("parse_xml");
-
-    let mut reader = quick_xml::Reader::from_str(svg);
-    reader.trim_text(true);
 
-    let mut paths = Vec::new();
-    let mut buf = Vec::new();
-    loop {
-        match reader.read_event(&mut buf) {
-            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
-                trace!("parse_xml: Matched start of {:?}", e.name());
-                match e.name() {
-                    b"path" => {
-                        trace!("parse_xml: Found path element");
-                        let mut path_expr: Option<String> = None;
-                        let mut transform_expr: Option<String> = None;
-                        for attr in e.attributes().filter_map(Result::ok) {
-                            let extract = || {
-                                attr.unescaped_value()
-                                    .ok()
-                                    .and_then(|v| str::from_utf8(&v).map(str::to_string).ok())
-                            };
-                            match attr.key {
-                                b"d" => path_expr = extract(),
-                                b"transform" => transform_expr = extract(),
-                                _ => {}
-                            }
-                        }
-                        if let Some(expr) = path_expr {
-                            paths.push((expr, transform_expr));
+    /// Return the next path expression, or `None` at the end of the
+    /// document.
+    fn next_element(&mut self) -> Result<Option<PathElement<'a>>, Error> {
+        let options = &*self.options;
+        let sheet = &self.sheet;
+        let track_paint = options.skip_invisible || options.outline_strokes;
+        let track_clips = options.clip_paths || options.mask_threshold.is_some();
+        let track_strokes = options.outline_strokes || options.markers;
+        loop {
+            let (tag, data, position) = match &mut self.events {
+                XmlEvents::Buffered(reader, buf) => {
+                    // If we don't keep a borrow elsewhere, we can clear the buffer to keep memory usage low
+                    buf.clear();
+                    let tag = Tag::from_event(reader.read_event(buf))?;
+                    let data = match &tag {
+                        Tag::Open(e, _) => {
+                            shape::path_data(e).map(|data| Cow::Owned(data.into_owned()))
                         }
-                    }
-                    _ => {}
+                        _ => None,
+                    };
+                    (tag, data, reader.buffer_position())
+                }
+                XmlEvents::Borrowed(reader, input) => {
+                    let tag = Tag::from_event(reader.read_event_unbuffered())?;
+                    let data = match &tag {
+                        Tag::Open(e, _) => match tag_content(input, e) {
+                            Some(content) => shape::borrowed_path_data(e, content),
+                            None => shape::path_data(e).map(|data| Cow::Owned(data.into_owned())),
+                        },
+                        _ => None,
+                    };
+                    (tag, data, reader.buffer_position())
+                }
+            };
+            let (e, is_start) = match tag {
+                Tag::Open(e, is_start) => (e, is_start),
+                Tag::Close => {
+                    self.transforms.pop();
+                    self.paints.pop();
+                    self.dash_styles.pop();
+                    self.stroke_styles.pop();
+                    self.clip_states.pop();
+                    self.pattern_states.pop();
+                    self.marker_states.pop();
+                    self.depths.pop();
+                    continue;
+                }
+                Tag::Eof => {
+                    trace!("parse_xml: EOF");
+                    return Ok(None);
+                }
+                Tag::Other => continue,
+            };
+            trace!("parse_xml: Matched start of {:?}", e.name());
+            if !self.seen_root {
+                self.root = RootAttributes::from_element(&e).ok();
+                self.seen_root = true;
+            }
+            let transform = self.transforms.compose(&e);
+            let paint = if track_paint {
+                self.paints
+                    .last()
+                    .copied()
+                    .unwrap_or_default()
+                    .inherit(&e, sheet)
+            } else {
+                Paint::default()
+            };
+            let dash_style = if options.dashes {
+                self.dash_styles
+                    .last()
+                    .cloned()
+                    .unwrap_or_default()
+                    .inherit(&e, sheet)
+            } else {
+                DashStyle::default()
+            };
+            let stroke_style = if track_strokes {
+                self.stroke_styles
+                    .last()
+                    .cloned()
+                    .unwrap_or_default()
+                    .inherit(&e, sheet)
+            } else {
+                StrokeStyle::default()
+            };
+            let clip_state = if track_clips {
+                self.element += 1;
+                self.clip_states
+                    .last()
+                    .cloned()
+                    .unwrap_or_default()
+                    .inherit(&e, sheet, &transform, self.element, options)
+            } else {
+                ClipState::default()
+            };
+            let pattern_state = if options.patterns {
+                self.pattern_states
+                    .last()
+                    .cloned()
+                    .unwrap_or_default()
+                    .inherit(&e, sheet)
+            } else {
+                PatternState::default()
+            };
+            let marker_state = if options.markers {
+                self.marker_states
+                    .last()
+                    .cloned()
+                    .unwrap_or_default()
+                    .inherit(&e, sheet)
+            } else {
+                MarkerState::default()
+            };
+            let inherited_depth = self.depths.last().copied().flatten();
+            let depth = self
+                .depth_source
+                .and_then(|source| depth::element_depth(&e, source, sheet).or(inherited_depth));
+            let mut path = None;
+            if let Some(data) = data {
+                if options.skip_invisible && !paint.is_visible(&e) {
+                    trace!("parse_xml: Skip invisible element");
+                } else if clip_state.hidden || pattern_state.hidden || marker_state.hidden {
+                    trace!("parse_xml: Skip clip path, mask, pattern or marker content");
+                } else {
+                    trace!("parse_xml: Found path data");
+                    path = Some(PathElement {
+                        data,
+                        id: style::attribute(&e, b"id"),
+                        index: self.index,
+                        offset: position.saturating_sub(e.len() + if is_start { 2 } else { 3 }),
+                        transform: transform.clone(),
+                        dashes: dash_style.dashes(),
+                        stroke: (options.outline_strokes && paint.stroke)
+                            .then(|| stroke_style.stroke()),
+                        clips: clip_state.references.clone(),
+                        pattern: pattern_state.fill.clone(),
+                        markers: marker_state.markers(&e, stroke_style.stroke().width()),
+                        depth,
+                    });
                 }
             }
-            Ok(Event::Eof) => {
-                trace!("parse_xml: EOF");
-                break;
+            self.index += 1;
+            if is_start {
+                self.transforms.push(transform);
+                self.paints.push(paint);
+                self.dash_styles.push(dash_style);
+                self.stroke_styles.push(stroke_style);
+                self.clip_states.push(clip_state);
+                self.pattern_states.push(pattern_state);
+                self.marker_states.push(marker_state);
+                self.depths.push(depth);
+            }
+            if path.is_some() {
+                cancel::check(options.cancel.as_ref())?;
+                self.paths += 1;
+                options.limits.check_paths(self.paths)?;
+                return Ok(path);
             }
-            Ok(_) => {}
-            Err(e) => return Err(Error::SvgParse(e.to_string())),
         }
-
-        // If we don't keep a borrow elsewhere, we can clear the buffer to keep memory usage low
-        buf.clear();
     }
-    trace!("parse_xml: Return {} paths", paths.len());
-    Ok(paths)
 }
 
-fn parse_path(expr: &str, tol: f64) -> Result<Vec<Polyline>, Error> {
+/// Parse a path expression, handling unsupported segments according to
+/// `policy`.
+#[cfg(feature = "std")]
+fn parse_path(
+    expr: &str,
+    tol: f64,
+    policy: UnsupportedPolicy,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<Polyline>, Error> {
+    parse_path_cancellable(expr, tol, policy, None, warnings)
+}
+
+/// Like [`parse_path`], but fail with [`Error::Cancelled`] between segments
+/// once `cancel` is cancelled.
+#[cfg(feature = "std")]
+fn parse_path_cancellable(
+    expr: &str,
+    tol: f64,
+    policy: UnsupportedPolicy,
+    cancel: Option<&CancelToken>,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<Polyline>, Error> {
     trace!("parse_path");
+    if let Some(lines) = line_path::parse(expr, cancel)? {
+        return Ok(lines);
+    }
+    parse_segments(expr, tol, policy, cancel, warnings)
+}
+
+/// Parse a path expression segment by segment, see [`parse_path_cancellable`].
+#[cfg(feature = "std")]
+fn parse_segments(
+    expr: &str,
+    tol: f64,
+    policy: UnsupportedPolicy,
+    cancel: Option<&CancelToken>,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<Polyline>, Error> {
     let mut lines = Vec::new();
     let mut line = CurrentLine::new();
+    let mut estimates = subpath_estimates(expr);
 
     // Process segments in path expression
     let mut prev_segment_store: Option<PathSegment> = None;
     for segment in PathParser::from(expr) {
+        cancel::check(cancel)?;
         let current_segment = segment.map_err(|e| Error::PathParse(e.to_string()))?;
         let prev_segment = prev_segment_store.replace(current_segment);
+        if let PathSegment::MoveTo { .. } = current_segment {
+            // Allocate the polyline of the new subpath at once
+            if line.is_valid() {
+                lines.push(line.finish());
+            }
+            line.reserve(estimates.next().unwrap_or(0));
+        }
+        if let PathSegment::SmoothQuadratic { abs, x, y } = current_segment {
+            let replacement = match policy {
+                UnsupportedPolicy::Error => None,
+                UnsupportedPolicy::Warn => {
+                    warnings.push(Warning::Unsupported {
+                        feature: "Smooth quadratic path segment".into(),
+                    });
+                    Some(PathSegment::MoveTo { abs, x, y })
+                }
+                UnsupportedPolicy::Skip => Some(PathSegment::MoveTo { abs, x, y }),
+                UnsupportedPolicy::Approximate => Some(PathSegment::LineTo { abs, x, y }),
+            };
+            if let Some(replacement) = replacement {
+                trace!("parse_path: Replace unsupported segment with {replacement:?}");
+                parse_path_segment(&replacement, prev_segment, &mut line, tol, &mut lines)?;
+                continue;
+            }
+        }
         parse_path_segment(&current_segment, prev_segment, &mut line, tol, &mut lines)?;
     }
 
@@ -323,44 +726,40 @@ fn parse_path(expr: &str, tol: f64) -> Result<Vec<Polyline>, Error> {
     Ok(lines)
 }
 
-/// Helper method for parsing both `CurveTo` and `SmoothCurveTo`.
-#[allow(clippy::too_many_arguments)]
-fn _handle_cubic_curve(
-    current_line: &mut CurrentLine,
-    tol: f64,
-    abs: bool,
-    x1: f64,
-    y1: f64,
-    x2: f64,
-    y2: f64,
-    x: f64,
-    y: f64,
-) -> Result<(), Error> {
-    let current = current_line.last_pair().ok_or_else(|| {
-        Error::PathParse("Invalid state: CurveTo or SmoothCurveTo on empty CurrentLine".to_string())
-    })?;
-    let curve = if abs {
-        CubicBezierSegment {
-            from: Point2D::new(current.x, current.y),
-            ctrl1: Point2D::new(x1, y1),
-            ctrl2: Point2D::new(x2, y2),
-            to: Point2D::new(x, y),
-        }
-    } else {
-        CubicBezierSegment {
-            from: Point2D::new(current.x, current.y),
-            ctrl1: Point2D::new(current.x + x1, current.y + y1),
-            ctrl2: Point2D::new(current.x + x2, current.y + y2),
-            to: Point2D::new(current.x + x, current.y + y),
+/// Parse the path expression of an element.
+///
+/// If `options.lenient` is set, invalid path data is recorded as a warning
+/// and `None` is returned instead of an error.
+#[cfg(feature = "std")]
+fn parse_element_path(
+    expr: &str,
+    id: Option<&str>,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Option<Vec<Polyline>>, Error> {
+    match parse_path_cancellable(
+        expr,
+        options.tolerance,
+        options.unsupported,
+        options.cancel.as_ref(),
+        warnings,
+    ) {
+        Ok(lines) => Ok(Some(lines)),
+        Err(Error::PathParse(message)) if options.lenient => {
+            trace!("parse_element_path: Skip invalid path: {message}");
+            warnings.push(Warning::InvalidPath {
+                id: id.map(str::to_string),
+                message,
+            });
+            Ok(None)
         }
-    };
-    for point in curve.flattened(tol) {
-        current_line.add_absolute(CoordinatePair::new(point.x, point.y));
+        Err(e) => Err(e),
     }
-    Ok(())
 }
 
-#[allow(clippy::similar_names)]
+/// Flatten a single segment parsed by svgtypes, see
+/// [`flatten_segment`](flatten::flatten_segment).
+#[cfg(feature = "std")]
 fn parse_path_segment(
     segment: &PathSegment,
     prev_segment: Option<PathSegment>,
@@ -368,448 +767,26 @@ fn parse_path_segment(
     tol: f64,
     lines: &mut Vec<Polyline>,
 ) -> Result<(), Error> {
-    trace!("parse_path_segment");
-    #[allow(clippy::match_wildcard_for_single_variants)]
-    match segment {
-        &PathSegment::MoveTo { abs, x, y } => {
-            trace!("parse_path_segment: MoveTo");
-            if current_line.is_valid() {
-                lines.push(current_line.finish());
-            }
-            current_line.add(abs, CoordinatePair::new(x, y));
-        }
-        &PathSegment::LineTo { abs, x, y } => {
-            trace!("parse_path_segment: LineTo");
-            current_line.add(abs, CoordinatePair::new(x, y));
-        }
-        &PathSegment::HorizontalLineTo { abs, x } => {
-            trace!("parse_path_segment: HorizontalLineTo");
-            match (current_line.last_y(), abs) {
-                (Some(y), true) => current_line.add_absolute(CoordinatePair::new(x, y)),
-                (Some(_), false) => current_line.add_relative(CoordinatePair::new(x, 0.0)),
-                (None, _) => {
-                    return Err(Error::PathParse(
-                        "Invalid state: HorizontalLineTo on emtpy CurrentLine".into(),
-                    ))
-                }
-            }
-        }
-        &PathSegment::VerticalLineTo { abs, y } => {
-            trace!("parse_path_segment: VerticalLineTo");
-            match (current_line.last_x(), abs) {
-                (Some(x), true) => current_line.add_absolute(CoordinatePair::new(x, y)),
-                (Some(_), false) => current_line.add_relative(CoordinatePair::new(0.0, y)),
-                (None, _) => {
-                    return Err(Error::PathParse(
-                        "Invalid state: VerticalLineTo on emtpy CurrentLine".into(),
-                    ))
-                }
-            }
-        }
-        &PathSegment::CurveTo {
-            abs,
-            x1,
-            y1,
-            x2,
-            y2,
-            x,
-            y,
-        } => {
-            trace!("parse_path_segment: CurveTo");
-            _handle_cubic_curve(current_line, tol, abs, x1, y1, x2, y2, x, y)?;
-        }
-        &PathSegment::SmoothCurveTo { abs, x2, y2, x, y } => {
-            trace!("parse_path_segment: SmoothCurveTo");
-
-            // Who on earth thought it would be a good idea to add a shortcut
-            // for curves with a mirrored control point? It generally makes
-            // implementations much more complex, while the data is perfectly
-            // equivalent to a fully written-out cubic curve m(
-            match prev_segment {
-                Some(PathSegment::CurveTo {
-                    x2: prev_x2,
-                    y2: prev_y2,
-                    x: prev_x,
-                    y: prev_y,
-                    ..
-                })
-                | Some(PathSegment::SmoothCurveTo {
-                    x2: prev_x2,
-                    y2: prev_y2,
-                    x: prev_x,
-                    y: prev_y,
-                    ..
-                }) => {
-                    // We have a previous curve. Mirror the previous control
-                    // point 2 along the previous end point.
-                    let dx = prev_x - prev_x2;
-                    let dy = prev_y - prev_y2;
-                    let (x1, y1) = if abs {
-                        let current = current_line.last_pair().ok_or_else(|| {
-                            Error::PathParse(
-                                "Invalid state: CurveTo or SmoothCurveTo on empty CurrentLine"
-                                    .into(),
-                            )
-                        })?;
-                        (current.x + dx, current.y + dy)
-                    } else {
-                        (dx, dy)
-                    };
-                    _handle_cubic_curve(current_line, tol, abs, x1, y1, x2, y2, x, y)?;
-                }
-                Some(_) | None => {
-                    // The previous segment was not a curve. Use the current
-                    // point as reference.
-                    match current_line.last_pair() {
-                        Some(pair) => {
-                            let x1 = pair.x;
-                            let y1 = pair.y;
-                            _handle_cubic_curve(current_line, tol, abs, x1, y1, x2, y2, x, y)?;
-                        }
-                        None => {
-                            return Err(Error::PathParse(
-                                "Invalid state: SmoothCurveTo without a reference point".into(),
-                            ))
-                        }
-                    }
-                }
-            }
-        }
-        &PathSegment::Quadratic { abs, x1, y1, x, y } => {
-            trace!("parse_path_segment: Quadratic");
-            let current = current_line.last_pair().ok_or_else(|| {
-                Error::PathParse("Invalid state: Quadratic on empty CurrentLine".into())
-            })?;
-            let curve = if abs {
-                QuadraticBezierSegment {
-                    from: Point2D::new(current.x, current.y),
-                    ctrl: Point2D::new(x1, y1),
-                    to: Point2D::new(x, y),
-                }
-            } else {
-                QuadraticBezierSegment {
-                    from: Point2D::new(current.x, current.y),
-                    ctrl: Point2D::new(current.x + x1, current.y + y1),
-                    to: Point2D::new(current.x + x, current.y + y),
-                }
-            };
-            for point in curve.flattened(tol) {
-                current_line.add_absolute(CoordinatePair::new(point.x, point.y));
-            }
-        }
-        &PathSegment::ClosePath { .. } => {
-            trace!("parse_path_segment: ClosePath");
-            current_line
-                .close()
-                .map_err(|e| Error::PathParse(format!("Invalid state: {}", e)))?;
-        }
-        &PathSegment::EllipticalArc {
-            abs,
-            rx,
-            ry,
-            x_axis_rotation,
-            large_arc,
-            sweep,
-            x,
-            y,
-        } => {
-            // The following code and comments are based on this project:
-            // https://github.com/BigBadaboom/androidsvg (Apache-2 license)
-            // And more specifically here:
-            // https://github.com/BigBadaboom/androidsvg/blob/1ad1c08c4f7ee09fcdd3dca31f8f31db7cacd3b0/androidsvg/src/main/java/com/caverock/androidsvg/utils/SVGAndroidRenderer.java#L2874-L3021
-            //
-            // This code in turn is partially based on the Batik library
-            // (Apache-2 license).
-
-            // SVG arc representation uses "endpoint parameterization" where we
-            // specify the start and endpoint of the arc. This is to be
-            // consistent with the other path commands. However we need to
-            // convert this to "centre point parameterization" in order to
-            // calculate the arc. Handily, the SVG spec provides all the
-            // required maths in section "F.6 Elliptical arc implementation
-            // notes".
-            trace!("parse_path_segment: EllipticalArc");
-            let current = current_line.last_pair().ok_or_else(|| {
-                Error::PathParse("Invalid state: EllipticalArc on empty CurrentLine".into())
-            })?;
-            let last_x = current.x;
-            let last_y = current.y;
-
-            // Calculating the end points of the curve based on the abs flag
-            let x_end = if abs { x } else { current.x + x };
-            let y_end = if abs { y } else { current.y + y };
-
-            // If the endpoints (x, y) and (x0, y0) are identical, then this is
-            // equivalent to omitting the elliptical arc segment entirely.
-            // (behavior specified by the spec)
-            let error_margin = f64::EPSILON;
-            if (last_x - x_end).abs() < error_margin && (last_y - y_end).abs() < error_margin {
-                return Ok(());
-            }
-
-            // Handle degenerate case (behavior specified by the spec)
-            if rx == 0.0 || ry == 0.0 {
-                current_line.add(abs, CoordinatePair::new(x_end, y_end));
-                return Ok(());
-            }
-
-            // Sign of the radii is ignored (behavior specified by the spec)
-            let mut rx = rx.abs();
-            let mut ry = ry.abs();
-
-            // Convert angle from degrees to radians
-            let angle_rad = (x_axis_rotation % 360.0) * (f64::consts::PI / 180.0);
-            let cos_angle = angle_rad.cos();
-            let sin_angle = angle_rad.sin();
-
-            // We simplify the calculations by transforming the arc so that the origin is at the
-            // midpoint calculated above followed by a rotation to line up the coordinate axes
-            // with the axes of the ellipse.
-
-            // Compute the midpoint of the line between the current and the end point
-            let dx2 = (last_x - x_end) / 2.0;
-            let dy2 = (last_y - y_end) / 2.0;
-
-            // Step 1: Compute (x1', y1')
-            // x1,y1 is the midpoint vector rotated to take the arc's angle out of consideration
-            let x1 = cos_angle * dx2 + sin_angle * dy2;
-            let y1 = -sin_angle * dx2 + cos_angle * dy2;
-
-            let mut rx_sq = rx * rx;
-            let mut ry_sq = ry * ry;
-            let x1_sq = x1 * x1;
-            let y1_sq = y1 * y1;
-
-            // Check that radii are large enough.
-            // If they are not, the spec says to scale them up so they are.
-            // This is to compensate for potential rounding errors/differences between SVG implementations.
-            let radii_check = x1_sq / rx_sq + y1_sq / ry_sq;
-            if radii_check > 0.99999 {
-                let radii_scale = radii_check.sqrt() * 1.00001;
-                rx *= radii_scale;
-                ry *= radii_scale;
-                rx_sq = rx * rx;
-                ry_sq = ry * ry;
-            }
-
-            // Step 2 : Compute (cx1, cy1) - the transformed centre point
-            let mut sign = if large_arc == sweep { -1.0 } else { 1.0 };
-            let sq = ((rx_sq * ry_sq) - (rx_sq * y1_sq) - (ry_sq * x1_sq))
-                / ((rx_sq * y1_sq) + (ry_sq * x1_sq));
-            let sq = if sq < 0.0 { 0.0 } else { sq };
-            let coef = sign * sq.sqrt();
-            let cx1 = coef * ((rx * y1) / ry);
-            let cy1 = coef * -((ry * x1) / rx);
-
-            // Step 3 : Compute (cx, cy) from (cx1, cy1)
-            let sx2 = (last_x + x_end) / 2.0;
-            let sy2 = (last_y + y_end) / 2.0;
-            let cx = sx2 + (cos_angle * cx1 - sin_angle * cy1);
-            let cy = sy2 + (sin_angle * cx1 + cos_angle * cy1);
-
-            // Step 4 : Compute the angleStart (angle1) and the angleExtent (dangle)
-            let ux = (x1 - cx1) / rx;
-            let uy = (y1 - cy1) / ry;
-            let vx = (-x1 - cx1) / rx;
-            let vy = (-y1 - cy1) / ry;
-
-            // Angle betwen two vectors is +/- acos( u.v / len(u) * len(v))
-            // Where '.' is the dot product. And +/- is calculated from the sign of the cross product (u x v)
-
-            // Compute the start angle
-            // The angle between (ux,uy) and the 0deg angle (1,0)
-            let mut n = ((ux * ux) + (uy * uy)).sqrt(); // len(u) * len(1,0) == len(u)
-            let mut p = ux; // u.v == (ux,uy).(1,0) == (1 * ux) + (0 * uy) == ux
-            sign = if uy < 0.0 { -1.0 } else { 1.0 }; // u x v == (1 * uy - ux * 0) == uy
-            let mut angle_start = sign * (p / n).acos(); // No need for checking the acos here. (p >= n) should always be true.
-
-            // Compute the angle extent
-            n = ((ux * ux + uy * uy) * (vx * vx + vy * vy)).sqrt();
-            p = ux * vx + uy * vy;
-            sign = if (ux * vy - uy * vx) < 0.0 { -1.0 } else { 1.0 };
-
-            let val = p / n;
-
-            let checked_arc_cos = if val < -1.0 {
-                f64::consts::PI
-            } else if val > 1.0 {
-                0.0
-            } else {
-                val.acos()
-            };
-            let mut angle_extent = sign * checked_arc_cos;
-
-            // Catch angleExtents of 0, which will cause problems later in arcToBeziers
-            if angle_extent == 0.0 {
-                current_line.add(abs, CoordinatePair::new(x_end, y_end));
-                return Ok(());
-            }
-
-            let two_pi = f64::consts::PI * 2.0;
-            if !sweep && angle_extent > 0.0 {
-                angle_extent -= two_pi;
-            } else if sweep && angle_extent < 0.0 {
-                angle_extent += two_pi;
-            }
-            angle_extent %= two_pi;
-            angle_start %= two_pi;
-
-            // Many elliptical arc implementations including the Java2D and Android ones, only
-            // support arcs that are axis aligned. Therefore we need to substitute the arc
-            // with bezier curves. The following function call will generate the beziers for
-            // a unit circle that covers the arc angles we want.
-
-            // The following code generates the control points and endpoints for a set of bezier
-            // curves that match a circular arc starting from angle 'angleStart' and sweep
-            // the angle 'angleExtent'.
-            // The circle the arc follows will be centred on (0,0) and have a radius of 1.0.
-            //
-            // Each bezier can cover no more than 90 degrees, so the arc will be divided evenly
-            // into a maximum of four curves.
-            //
-            // The resulting control points will later be scaled and rotated to match the final
-            // arc required.
-
-            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-            let num_segments = (angle_extent.abs() * 2.0 / f64::consts::PI).ceil() as u64;
-
-            #[allow(clippy::cast_precision_loss)] // Cannot happen
-            let angle_increment: f64 = angle_extent / num_segments as f64;
-
-            // The length of each control point vector is given by the following formula.
-            let control_length =
-                4.0 / 3.0 * (angle_increment / 2.0).sin() / (1.0 + (angle_increment / 2.0).cos());
-
-            let num_segments_usize: usize = num_segments.try_into().unwrap();
-            let mut bezier_points = Vec::with_capacity(num_segments_usize * 3);
-            for i in 0..num_segments {
-                #[allow(clippy::cast_precision_loss)] // Cannot happen
-                let mut angle = angle_start + i as f64 * angle_increment;
-                // Calculate the control vector at this angle
-                let mut dx = angle.cos();
-                let mut dy = angle.sin();
-
-                // First control point
-                bezier_points.push((dx - control_length * dy, dy + control_length * dx));
-
-                // Second control point
-                angle += angle_increment;
-                dx = angle.cos();
-                dy = angle.sin();
-                bezier_points.push((dx + control_length * dy, dy - control_length * dx));
-
-                // Endpoint of bezier
-                bezier_points.push((dx, dy));
-            }
-
-            // Check if no points were generated
-            let len = bezier_points.len();
-            if len == 0 {
-                return Ok(());
-            }
-
-            // Calculate a transformation matrix that will move and scale these bezier points to the correct location.
-            let mut bezier_points: Vec<(f64, f64)> = bezier_points
-                .into_iter()
-                // Scale
-                .map(|(a, b)| (a * rx, b * ry))
-                // Rotate around the calculated centre point
-                .map(|(a, b)| {
-                    let s = angle_rad.sin();
-                    let c = angle_rad.cos();
-
-                    let px = a - cx1;
-                    let py = b - cy1;
-
-                    let x_new = px * c - py * s;
-                    let y_new = px * s + py * c;
-
-                    (x_new + cx1, y_new + cy1)
-                })
-                // Translate
-                .map(|(a, b)| (a + cx, b + cy))
-                .collect();
-
-            // The last point in the bezier set should match exactly the last coord pair in the arc (ie: x,y). But
-            // considering all the mathematical manipulation we have been doing, it is bound to be off by a tiny
-            // fraction. Experiments show that it can be up to around 0.00002. So why don't we just set it to
-            // exactly what it ought to be.
-            bezier_points[len - 1] = (x_end, y_end);
-
-            // Final step is to add the bezier curves to the path
-            let mut last_x = last_x;
-            let mut last_y = last_y;
-            // Step trough points 3 at a time
-            for i in (0..bezier_points.len()).step_by(3) {
-                let curve = CubicBezierSegment {
-                    from: Point2D::new(last_x, last_y),
-                    ctrl1: Point2D::new(bezier_points[i].0, bezier_points[i].1),
-                    ctrl2: Point2D::new(bezier_points[i + 1].0, bezier_points[i + 1].1),
-                    to: Point2D::new(bezier_points[i + 2].0, bezier_points[i + 2].1),
-                };
-                // End of last curve is used as start point of next curve
-                last_x = bezier_points[i + 2].0;
-                last_y = bezier_points[i + 2].1;
-                for point in curve.flattened(tol) {
-                    current_line.add_absolute(CoordinatePair::new(point.x, point.y));
-                }
-            }
-        }
-        other => {
-            return Err(Error::PathParse(format!(
-                "Unsupported path segment: {:?}",
-                other
-            )));
-        }
-    }
+    flatten::flatten_segment(
+        &(*segment).into(),
+        prev_segment.map(Into::into),
+        current_line,
+        tol,
+        lines,
+    )?;
     Ok(())
 }
 
-/// Parse an SVG transformation into a ``Transform2D``.
+/// Parse an SVG transform list into a ``Transform2D``.
 ///
-/// Only matrix transformations are supported at the moment. (This shouldn't be
-/// an issue, because usvg converts all transformations into matrices.)
+/// All transform functions (`matrix`, `translate`, `scale`, `rotate`, `skewX`
+/// and `skewY`) are supported. The transforms of a list are composed, so that
+/// the last one is applied first.
+#[cfg(feature = "std")]
 #[allow(clippy::many_single_char_names)]
 fn parse_transform(transform: &str) -> Result<Transform2D<f64, f64, f64>, Error> {
-    // Extract matrix elements from SVG string
-    let transform = transform.trim();
-    if !transform.starts_with("matrix(") {
-        return Err(Error::Transform(format!(
-            "Only 'matrix' transform supported in transform '{}'",
-            transform
-        )));
-    }
-    if !transform.ends_with(')') {
-        return Err(Error::SvgParse(format!(
-            "Missing closing parenthesis in transform '{}'",
-            transform
-        )));
-    }
-    let matrix = transform
-        .strip_prefix("matrix(")
-        .expect("checked before")
-        .strip_suffix(')')
-        .expect("checked to be there");
-
-    // Convert elements to floats
-    let elements = matrix
-        .split_whitespace()
-        .map(str::parse)
-        .collect::<Result<Vec<f64>, _>>()
-        .map_err(|_| {
-            Error::SvgParse(format!(
-                "Invalid matrix elements in transform '{}'",
-                transform
-            ))
-        })?;
-
-    // Convert floats into Transform2D
-    let [a, b, c, d, e, f]: [f64; 6] = elements.as_slice().try_into().map_err(|_| {
-        Error::Transform(format!(
-            "Invalid number of matrix elements in transform '{}'",
-            transform
-        ))
-    })?;
+    let svgtypes::Transform { a, b, c, d, e, f } = svgtypes::Transform::from_str(transform)
+        .map_err(|e| Error::Transform(format!("Invalid transform '{}': {e}", transform.trim())))?;
     Ok(Transform2D::new(a, b, c, d, e, f))
 }
 
@@ -825,94 +802,556 @@ fn parse_transform(transform: &str) -> Result<Transform2D<f64, f64, f64>, Error>
 ///
 /// ## Preprocessing
 ///
-/// If `preprocess` is set to `true`,
+/// If `preprocess` is set to `true`, the SVG is first simplified with usvg,
+/// see [`Preprocess`]. This requires the `usvg` feature (enabled by default),
+/// otherwise the flag is ignored.
+///
+/// ## Data URIs
+///
+/// The input may also be a `data:image/svg+xml` URI, as copied from the image
+/// source in a browser. Both base64 and URL-encoded data are supported.
+#[cfg(feature = "std")]
 pub fn parse(svg: &str, tol: f64, preprocess: bool) -> Result<Vec<Polyline>, Error> {
-    trace!("parse");
+    parse_with_options(
+        svg,
+        &ParseOptions {
+            tolerance: tol,
+            preprocess: preprocess.into(),
+            ..Default::default()
+        },
+    )
+}
 
-    // Preprocess and simplify the SVG using the usvg library
-    let svg = if preprocess {
-        let usvg_input_options = usvg::Options::default();
-        let usvg_tree = usvg::Tree::from_str(svg, &usvg_input_options.to_ref())?;
-        let usvg_xml_options = usvg::XmlOptions::default();
-        usvg_tree.to_string(&usvg_xml_options)
+/// Preprocess and simplify the SVG using usvg, if enabled in the options.
+#[cfg(feature = "std")]
+fn preprocess(svg: &str, options: &ParseOptions) -> Result<String, Error> {
+    let filtered;
+    let svg = match &options.layers {
+        Some(layers) => {
+            filtered = layer::filter(svg, layers)?;
+            &filtered
+        }
+        None => svg,
+    };
+    let selected;
+    let svg = if options.include.is_some() || options.exclude.is_some() {
+        selected = select::filter(svg, options.include.as_deref(), options.exclude.as_deref())?;
+        &selected
+    } else {
+        svg
+    };
+    match &options.preprocess {
+        Preprocess::No => Ok(svg.to_string()),
+        #[cfg(feature = "usvg")]
+        Preprocess::Usvg(usvg_options) => {
+            let with_fonts;
+            let usvg_options = if options.fonts == FontOptions::default() {
+                usvg_options
+            } else {
+                let mut loaded = usvg_options.clone();
+                options.fonts.load(&mut loaded);
+                with_fonts = loaded;
+                &with_fonts
+            };
+            let usvg_tree = usvg::Tree::from_str(svg, &usvg_options.to_ref())?;
+            let usvg_xml_options = usvg::XmlOptions;
+            Ok(usvg_tree.to_string(&usvg_xml_options))
+        }
+    }
+}
+
+/// Parse an SVG string into a vector of [`Polyline`]s, using the specified
+/// [`ParseOptions`].
+#[cfg(feature = "std")]
+pub fn parse_with_options(svg: &str, options: &ParseOptions) -> Result<Vec<Polyline>, Error> {
+    parse_with_warnings(svg, options, &mut Vec::new())
+}
+
+/// Parse an SVG string into polylines of `f32` coordinates, using the
+/// specified [`ParseOptions`].
+///
+/// The conversion is done with `f64` precision, and only the output is
+/// rounded, see [`Polyline::to_f32`].
+///
+/// ```
+/// use svg2polylines::{parse_f32, ParseOptions, Preprocess};
+///
+/// let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><path d="M 0,0 10,0.1"/></svg>"#;
+/// let options = ParseOptions {
+///     preprocess: Preprocess::No,
+///     ..Default::default()
+/// };
+/// assert_eq!(parse_f32(svg, &options).unwrap(), vec![vec![[0.0, 0.0], [10.0, 0.1]]]);
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_f32(svg: &str, options: &ParseOptions) -> Result<Vec<Vec<[f32; 2]>>, Error> {
+    let polylines = parse_with_options(svg, options)?;
+    Ok(polylines.iter().map(Polyline::to_f32).collect())
+}
+
+/// Parse an SVG string into polylines of integer coordinates, using the
+/// specified [`ParseOptions`].
+///
+/// The coordinates are multiplied by `scale` and rounded, see
+/// [`Polyline::to_fixed`]. Together with [`ParseOptions::unit`], this results
+/// in coordinates in e.g. integer micrometers.
+///
+/// ```
+/// use svg2polylines::{parse_fixed, ParseOptions, Preprocess, Unit};
+///
+/// let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="20mm" height="10mm" viewBox="0 0 20 10">
+///     <path d="M 0,0 10,0.0012"/>
+/// </svg>"#;
+/// let options = ParseOptions {
+///     preprocess: Preprocess::No,
+///     unit: Unit::Millimeters,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     parse_fixed(svg, &options, 1000.0).unwrap(),
+///     vec![vec![[0, 0], [10_000, 1]]]
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_fixed(
+    svg: &str,
+    options: &ParseOptions,
+    scale: f64,
+) -> Result<Vec<Vec<[i64; 2]>>, Error> {
+    let polylines = parse_with_options(svg, options)?;
+    Ok(polylines
+        .iter()
+        .map(|polyline| polyline.to_fixed(scale))
+        .collect())
+}
+
+/// Index of an element in document order, see [`ErrorContext::element`].
+#[cfg(feature = "std")]
+pub type PathIndex = usize;
+
+/// Parse an SVG string into a vector of [`Polyline`]s, collecting the errors
+/// of individual paths instead of failing on the first one.
+///
+/// Paths that cannot be converted (e.g. due to a corrupted `d` attribute)
+/// are skipped, and their errors are returned together with their index.
+/// Errors that concern the whole document, like malformed XML, still fail
+/// the conversion.
+///
+/// ```
+/// use svg2polylines::{parse_all, ParseOptions, Preprocess};
+///
+/// let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+///     <path d="M 0,0 L 10,10"/>
+///     <path d="M 0,0 L 10"/>
+/// </svg>"#;
+/// let options = ParseOptions {
+///     preprocess: Preprocess::No,
+///     ..Default::default()
+/// };
+/// let (polylines, errors) = parse_all(svg, &options).unwrap();
+/// assert_eq!(polylines.len(), 1);
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(errors[0].0, 2);
+/// ```
+#[cfg(feature = "std")]
+#[allow(clippy::type_complexity)]
+pub fn parse_all(
+    svg: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<Polyline>, Vec<(PathIndex, Error)>), Error> {
+    let mut errors = Vec::new();
+    let polylines = parse_collecting(svg, options, &mut Vec::new(), Some(&mut errors))?;
+    Ok((polylines, errors))
+}
+
+/// Like [`parse_with_options`], but append problems that did not prevent
+/// the conversion to `warnings`.
+#[cfg(feature = "std")]
+pub(crate) fn parse_with_warnings(
+    svg: &str,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<Polyline>, Error> {
+    parse_collecting(svg, options, warnings, None)
+}
+
+/// Parse an SVG string. If `errors` is given, the errors of individual paths
+/// are collected there instead of failing the conversion.
+#[cfg(feature = "std")]
+fn parse_collecting(
+    svg: &str,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+    errors: Option<&mut Vec<(PathIndex, Error)>>,
+) -> Result<Vec<Polyline>, Error> {
+    let elements = parse_elements(svg, options, None, warnings, errors)?;
+    Ok(elements
+        .into_iter()
+        .flat_map(|(_, polylines)| polylines)
+        .collect())
+}
+
+/// The polylines of an element with its cutting depth.
+#[cfg(feature = "std")]
+type DepthElement = (Option<f64>, Vec<Polyline>);
+
+/// Parse an SVG string into the polylines of every element, together with
+/// the cutting depth of the element if `depth_source` is given.
+#[cfg(feature = "std")]
+pub(crate) fn parse_elements(
+    svg: &str,
+    options: &ParseOptions,
+    depth_source: Option<&DepthSource>,
+    warnings: &mut Vec<Warning>,
+    mut errors: Option<&mut Vec<(PathIndex, Error)>>,
+) -> Result<Vec<DepthElement>, Error> {
+    trace!("parse_with_options");
+    cancel::check(options.cancel.as_ref())?;
+    options.limits.check_input_size(svg.len())?;
+    let input = svg;
+
+    // Decode data URIs
+    let svg = data_uri::decode_input(svg)?;
+    let svg: &str = &svg;
+
+    // Repair malformed XML
+    let repaired;
+    let svg = if options.recover {
+        repaired = recover::repair(svg, warnings)?;
+        &repaired
     } else {
-        svg.to_string()
+        svg
     };
 
+    if options.strict {
+        strict::check(svg, options)?;
+    }
+    options.limits.check_use_depth(svg)?;
+
+    // The root attributes of the original document are needed to convert
+    // the output into physical units
+    let unit_transform = document::parse_root(svg)
+        .ok()
+        .and_then(|root| unit::transform(&root, options.unit, options.dpi));
+
+    // Preprocess and simplify the SVG using the usvg library
+    let svg = preprocess(svg, options)?;
+    cancel::check(options.cancel.as_ref())?;
+
     // Parse the XML string into a list of path expressions
-    let path_exprs = parse_xml(&svg)?;
-    trace!("parse: Found {} path expressions", path_exprs.len());
-
-    // Vector that will hold resulting polylines
-    let mut polylines: Vec<Polyline> = Vec::new();
-
-    // Process path expressions
-    for (path_expr, transform_expr) in path_exprs {
-        let path = parse_path(&path_expr, tol)?;
-        if let Some(e) = transform_expr {
-            let t = parse_transform(&e)?;
-            polylines.extend(path.into_iter().map(|polyline| polyline.transform(t)));
-        } else {
-            polylines.extend(path);
+    let path_elements = match depth_source {
+        Some(source) => {
+            let elements =
+                PathElements::borrowed(&svg, StyleSheet::from_svg(&svg), Cow::Borrowed(options));
+            parse_events(elements.with_depths(source))?.0
+        }
+        None => parse_xml(&svg, options)?,
+    };
+    trace!("parse: Found {} path expressions", path_elements.len());
+    let depths: HashMap<usize, f64> = path_elements
+        .iter()
+        .filter_map(|element| Some((element.index, element.depth?)))
+        .collect();
+
+    let definitions = Definitions::collect(&svg, options)?;
+    let viewport = document::parse_root(&svg)
+        .ok()
+        .and_then(|root| root.viewport());
+    let locate = |e: &mut Error| {
+        if let Error::Element { context, .. } = e {
+            // Offsets into a modified document would be misleading
+            if svg == input {
+                context.locate(input);
+            } else {
+                context.offset = None;
+            }
         }
+    };
+    let elements = convert_elements(
+        path_elements,
+        &definitions,
+        viewport,
+        unit_transform,
+        options,
+        warnings,
+        errors.as_deref_mut(),
+    )
+    .map_err(|mut e| {
+        locate(&mut e);
+        e
+    })?;
+    for (_, e) in errors.into_iter().flatten() {
+        locate(e);
     }
+    Ok(elements
+        .into_iter()
+        .map(|(index, polylines)| (depths.get(&index).copied(), polylines))
+        .collect())
+}
 
-    trace!("parse: This results in {} polylines", polylines.len());
-    Ok(polylines)
+/// The clip paths, masks, patterns and markers of a document by their id,
+/// if enabled in the options.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+struct Definitions {
+    clip_paths: HashMap<String, ClipPath>,
+    patterns: HashMap<String, Pattern>,
+    markers: HashMap<String, Marker>,
 }
 
-#[cfg(test)]
-#[allow(clippy::unreadable_literal)]
-mod tests {
-    use super::*;
+#[cfg(feature = "std")]
+impl Definitions {
+    fn collect(svg: &str, options: &ParseOptions) -> Result<Self, Error> {
+        let mut definitions = Self::default();
+        if options.clip_paths || options.mask_threshold.is_some() {
+            definitions.clip_paths = clip_path::collect(svg, options)?;
+        }
+        if options.patterns {
+            definitions.patterns = pattern::collect(svg, options)?;
+        }
+        if options.markers {
+            definitions.markers = marker::collect(svg, options)?;
+        }
+        Ok(definitions)
+    }
+}
 
-    const FLATTENING_TOLERANCE: f64 = 0.15;
+/// The polylines of an element with the clip paths to apply.
+#[cfg(feature = "std")]
+type ClippedElement = (Vec<Polyline>, Vec<ClipReference>);
 
-    #[test]
-    fn test_current_line() {
-        let mut line = CurrentLine::new();
-        assert!(!line.is_valid());
-        assert_eq!(line.last_x(), None);
-        assert_eq!(line.last_y(), None);
-        line.add_absolute((1.0, 2.0).into());
-        assert!(!line.is_valid());
-        assert_eq!(line.last_x(), Some(1.0));
-        assert_eq!(line.last_y(), Some(2.0));
-        line.add_absolute((2.0, 3.0).into());
-        assert!(line.is_valid());
-        assert_eq!(line.last_x(), Some(2.0));
-        assert_eq!(line.last_y(), Some(3.0));
-        let finished = line.finish();
-        assert_eq!(finished.len(), 2);
-        assert_eq!(finished[0], (1.0, 2.0).into());
-        assert_eq!(finished[1], (2.0, 3.0).into());
-        assert!(!line.is_valid());
-    }
+/// Convert a path expression into polylines, including its pattern fill and
+/// markers, and return them with the clip paths to apply. Return `None` if
+/// the element is skipped due to an unsupported transform.
+///
+/// Errors are wrapped into an [`Error::Element`] with the location of the
+/// element, except for [`Error::Cancelled`].
+#[cfg(feature = "std")]
+fn convert_element(
+    element: PathElement,
+    definitions: &Definitions,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Option<ClippedElement>, Error> {
+    let context = ErrorContext {
+        element: element.index,
+        id: element.id.clone(),
+        offset: Some(element.offset),
+        line: None,
+        column: None,
+    };
+    element_polylines(element, definitions, options, warnings).map_err(|source| match source {
+        Error::Cancelled => source,
+        _ => Error::Element {
+            context,
+            source: Box::new(source),
+        },
+    })
+}
 
-    #[test]
-    fn test_current_line_close() {
-        let mut line = CurrentLine::new();
-        assert_eq!(
-            line.close().unwrap_err().to_string(),
-            "Polyline error: Lines with less than 2 coordinate pairs cannot be closed.",
-        );
-        line.add_absolute((1.0, 2.0).into());
-        assert_eq!(
-            line.close().unwrap_err().to_string(),
-            "Polyline error: Lines with less than 2 coordinate pairs cannot be closed.",
-        );
-        line.add_absolute((2.0, 3.0).into());
-        assert!(line.close().is_ok());
-        let finished = line.finish();
-        assert_eq!(finished.len(), 3);
-        assert_eq!(finished[0], (1.0, 2.0).into());
-        assert_eq!(finished[2], (1.0, 2.0).into());
+#[cfg(feature = "std")]
+fn element_polylines(
+    element: PathElement,
+    definitions: &Definitions,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Option<ClippedElement>, Error> {
+    let Some(mut path) =
+        parse_element_path(&element.data, element.id.as_deref(), options, warnings)?
+    else {
+        return Ok(None);
+    };
+    let fill = element
+        .pattern
+        .and_then(|id| definitions.patterns.get(&id))
+        .map(|pattern| pattern.fill(&path));
+    let marker_content = match element.markers {
+        Some(element_markers) => {
+            element_markers.expand(&element.data, &definitions.markers, options.tolerance)?
+        }
+        None => Vec::new(),
+    };
+    if let Some(dashes) = element.dashes {
+        path = path
+            .iter()
+            .flat_map(|polyline| dashes.apply(polyline))
+            .collect();
+    }
+    if let Some(stroke) = element.stroke {
+        path = path
+            .iter()
+            .flat_map(|polyline| stroke.outline(polyline, options.tolerance))
+            .collect();
+    }
+    path.extend(fill.into_iter().flatten());
+    path.extend(marker_content);
+    if options.remove_duplicates {
+        for polyline in &mut path {
+            polyline.dedup();
+        }
     }
+    if let Some(tolerance) = options.merge_collinear {
+        path = path
+            .iter()
+            .map(|polyline| polyline.merge_collinear(tolerance))
+            .collect();
+    }
+    let Some(t) = element.transform.resolve(options.unsupported, warnings)? else {
+        return Ok(None);
+    };
+    if t != Transform2D::identity() {
+        path = path
+            .into_iter()
+            .map(|polyline| polyline.transform(t))
+            .collect();
+    }
+    sanitize_non_finite(&mut path, options.non_finite)?;
+    Ok(Some((path, element.clips)))
+}
 
-    #[test]
-    /// Parse segment data with a single `MoveTo` and three coordinates
-    fn test_parse_segment_data() {
-        let mut current_line = CurrentLine::new();
+/// Apply the [`NonFinitePolicy`] to the points of the polylines.
+#[cfg(feature = "std")]
+fn sanitize_non_finite(
+    polylines: &mut Vec<Polyline>,
+    policy: NonFinitePolicy,
+) -> Result<(), Error> {
+    let is_finite = |p: &CoordinatePair| p.x.is_finite() && p.y.is_finite();
+    match policy {
+        NonFinitePolicy::Keep => {}
+        NonFinitePolicy::Drop => {
+            for polyline in polylines.iter_mut() {
+                polyline.retain(is_finite);
+            }
+            polylines.retain(|polyline| !polyline.is_empty());
+        }
+        NonFinitePolicy::Error => {
+            if let Some(p) = polylines
+                .iter()
+                .flat_map(|p| p.iter())
+                .find(|p| !is_finite(p))
+            {
+                return Err(Error::Polyline(format!(
+                    "Coordinates are not finite: ({}, {})",
+                    p.x, p.y
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Convert path expressions into polylines, then clip them to the viewport
+/// and convert them into the output unit.
+///
+/// If `errors` is given, elements that cannot be converted are skipped and
+/// their errors are collected there.
+#[cfg(feature = "std")]
+fn convert(
+    path_elements: Vec<PathElement>,
+    definitions: &Definitions,
+    viewport: Option<BoundingBox>,
+    unit_transform: Option<Transform2D<f64, f64, f64>>,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+    errors: Option<&mut Vec<(PathIndex, Error)>>,
+) -> Result<Vec<Polyline>, Error> {
+    let elements = convert_elements(
+        path_elements,
+        definitions,
+        viewport,
+        unit_transform,
+        options,
+        warnings,
+        errors,
+    )?;
+    let polylines: Vec<Polyline> = elements
+        .into_iter()
+        .flat_map(|(_, polylines)| polylines)
+        .collect();
+    trace!("parse: This results in {} polylines", polylines.len());
+    Ok(polylines)
+}
+
+/// Like [`convert`], but return the polylines of every converted element
+/// separately, together with the index of the element.
+#[cfg(feature = "std")]
+fn convert_elements(
+    path_elements: Vec<PathElement>,
+    definitions: &Definitions,
+    viewport: Option<BoundingBox>,
+    unit_transform: Option<Transform2D<f64, f64, f64>>,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+    mut errors: Option<&mut Vec<(PathIndex, Error)>>,
+) -> Result<Vec<(usize, Vec<Polyline>)>, Error> {
+    // The polylines of every element with the clip paths to apply
+    let mut elements: Vec<ClippedElement> = Vec::new();
+    let mut indices = Vec::new();
+    let mut points = 0;
+    for element in path_elements {
+        cancel::check(options.cancel.as_ref())?;
+        let index = element.index;
+        match (
+            convert_element(element, definitions, options, warnings),
+            errors.as_deref_mut(),
+        ) {
+            (Ok(converted), _) => {
+                if let Some(converted) = converted {
+                    points += converted.0.iter().map(|p| p.len()).sum::<usize>();
+                    options.limits.check_points(points)?;
+                    elements.push(converted);
+                    indices.push(index);
+                }
+            }
+            (Err(e), Some(errors)) => {
+                trace!("convert: Skip element {index}: {e}");
+                errors.push((index, e));
+            }
+            (Err(e), None) => return Err(e),
+        }
+    }
+    let mut elements: Vec<(usize, Vec<Polyline>)> = indices
+        .into_iter()
+        .zip(clip_path::apply(elements, &definitions.clip_paths))
+        .collect();
+
+    // Remove or report geometry outside of the viewport
+    if let Some(viewport) = viewport {
+        if options.viewport_clip == ViewportClip::Off {
+            let polylines = elements.iter().flat_map(|(_, polylines)| polylines);
+            warnings.extend(clip::out_of_bounds(polylines, &viewport));
+        } else {
+            for (_, polylines) in &mut elements {
+                *polylines = clip::clip_to_viewport(
+                    std::mem::take(polylines),
+                    &viewport,
+                    options.viewport_clip,
+                );
+            }
+        }
+    }
+
+    if let Some(t) = unit_transform {
+        for (_, polylines) in &mut elements {
+            transform_all(polylines, t);
+        }
+    }
+    Ok(elements)
+}
+
+#[cfg(all(test, feature = "std"))]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use lyon_geom::euclid::Point2D;
+
+    use super::*;
+
+    const FLATTENING_TOLERANCE: f64 = 0.15;
+
+    #[test]
+    /// Parse segment data with a single `MoveTo` and three coordinates
+    fn test_parse_segment_data() {
+        let mut current_line = CurrentLine::new();
         let mut lines = Vec::new();
         parse_path_segment(
             &PathSegment::MoveTo {
@@ -1130,6 +1569,20 @@ mod tests {
         assert_eq!(finished.len(), 1);
     }
 
+    #[test]
+    fn test_parse_path_capacity() {
+        let lines = parse_path(
+            "M 0,0 L 10,0 10,10 0,10 Z m 5,5 1,1 M 2,2",
+            FLATTENING_TOLERANCE,
+            UnsupportedPolicy::Error,
+            &mut Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!((lines[0].len(), lines[0].capacity()), (5, 5));
+        assert_eq!((lines[1].len(), lines[1].capacity()), (2, 2));
+    }
+
     #[test]
     fn test_parse_simple_absolute_nonclosed() {
         let _ = env_logger::try_init();
@@ -1168,7 +1621,7 @@ mod tests {
         assert_eq!(result[0][3], (10., 10.).into());
     }
 
-    #[cfg(feature = "use_serde")]
+    #[cfg(feature = "serde")]
     #[test]
     fn test_serde() {
         let cp = CoordinatePair::new(10.0, 20.0);
@@ -1257,10 +1710,17 @@ mod tests {
             </svg>
         "#
         .trim();
-        let result = parse_xml(input).unwrap();
+        let result: Vec<_> = parse_xml(input, &ParseOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|element| (element.data.into_owned(), element.transform))
+            .collect();
         assert_eq!(
             result,
-            vec![("M 10,100 40,70 h 10 m -20,40 10,-20".to_string(), None)]
+            vec![(
+                "M 10,100 40,70 h 10 m -20,40 10,-20".to_string(),
+                ComposedTransform::default()
+            )]
         );
     }
 
@@ -1275,16 +1735,35 @@ mod tests {
             </svg>
         "#
         .trim();
-        let result = parse_xml(input).unwrap();
+        let result: Vec<_> = parse_xml(input, &ParseOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|element| (element.data.into_owned(), element.transform))
+            .collect();
         assert_eq!(
             result,
             vec![
-                ("M 10,100 40,70 h 10 m -20,40 10,-20".to_string(), None),
-                ("M 20,30".to_string(), None),
+                (
+                    "M 10,100 40,70 h 10 m -20,40 10,-20".to_string(),
+                    ComposedTransform::default()
+                ),
+                ("M 20,30".to_string(), ComposedTransform::default()),
             ]
         );
     }
 
+    /// Path data without escapes is borrowed from the document.
+    #[test]
+    fn test_parse_xml_borrowed() {
+        let input = r#"<svg><path d="M 0,0 L 1,1"/><path d="M 0,0&#32;L 2,2"/><rect width="1" height="1"/></svg>"#;
+        let options = ParseOptions::default();
+        let result = parse_xml(input, &options).unwrap();
+        assert!(matches!(result[0].data, Cow::Borrowed("M 0,0 L 1,1")));
+        assert_eq!(result[1].data, "M 0,0 L 2,2");
+        assert!(matches!(result[1].data, Cow::Owned(_)));
+        assert_eq!(result[2].data, "M 0,0 H 1 V 1 H 0 Z");
+    }
+
     /// If multiple "d" attributes are found, simply use the first one.
     #[test]
     fn test_parse_xml_duplicate_attr() {
@@ -1296,8 +1775,15 @@ mod tests {
             </svg>
         "#
         .trim();
-        let result = parse_xml(input).unwrap();
-        assert_eq!(result, vec![("M 20,30".to_string(), None)]);
+        let result: Vec<_> = parse_xml(input, &ParseOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|element| (element.data.into_owned(), element.transform))
+            .collect();
+        assert_eq!(
+            result,
+            vec![("M 20,30".to_string(), ComposedTransform::default())]
+        );
     }
 
     #[test]
@@ -1311,15 +1797,22 @@ mod tests {
             </svg>
         "#
         .trim();
-        let result = parse_xml(input).unwrap();
+        let result: Vec<_> = parse_xml(input, &ParseOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|element| (element.data.into_owned(), element.transform))
+            .collect();
         assert_eq!(
             result,
             vec![
                 (
                     "M 20,30".to_string(),
-                    Some("matrix(1 0 0 1 0 0)".to_string())
+                    ComposedTransform {
+                        transform: Transform2D::identity(),
+                        error: None,
+                    }
                 ),
-                ("M 30,40".to_string(), None)
+                ("M 30,40".to_string(), ComposedTransform::default())
             ],
         );
     }
@@ -1333,7 +1826,8 @@ mod tests {
             </baa>
         "#
         .trim();
-        let result = parse_xml(input);
+        let options = ParseOptions::default();
+        let result = parse_xml(input, &options);
         assert_eq!(
             result.unwrap_err().to_string(),
             "SVG parse error: Expecting </svg> found </baa>",
@@ -1357,7 +1851,7 @@ mod tests {
         assert_eq!(result[0].len(), 11);
         assert_eq!(
             result[0],
-            Polyline(vec![
+            Polyline::from_vec(vec![
                 CoordinatePair::new(0.10650371, 93.221877),
                 CoordinatePair::new(1.294403614814815, 91.96472118518521),
                 CoordinatePair::new(2.6361703106158494, 90.93256152046511),
@@ -1392,7 +1886,7 @@ mod tests {
         assert_eq!(result[0].len(), 39);
         assert_eq!(
             result[0],
-            Polyline(vec![
+            Polyline::from_vec(vec![
                 CoordinatePair::new(10.0, 80.0),
                 CoordinatePair::new(15.78100143969477, 67.25459368406422),
                 CoordinatePair::new(21.112891508939025, 56.89021833666841),
@@ -1466,6 +1960,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_transform_list() {
+        let t = parse_transform("translate(10, 20) scale(2)").unwrap();
+        assert_eq!(
+            t.transform_point(Point2D::new(1.0, 1.0)),
+            Point2D::new(12.0, 22.0)
+        );
+        let t = parse_transform("rotate(90 1 1),skewX(0)").unwrap();
+        let p = t.transform_point(Point2D::new(2.0, 1.0));
+        assert!((p.x - 1.0).abs() < 1e-9 && (p.y - 2.0).abs() < 1e-9);
+        assert!(matches!(
+            parse_transform("translateX(5)"),
+            Err(Error::Transform(_))
+        ));
+    }
+
     // Given the line `1,2 2,4`, apply the following transformation matrix:
     //
     // |1  0  2|
@@ -1514,7 +2024,7 @@ mod tests {
 
     #[test]
     fn test_polyline_iterate() {
-        let polyline = Polyline(vec![
+        let polyline = Polyline::from_vec(vec![
             CoordinatePair { x: 0.0, y: 1.0 },
             CoordinatePair { x: 1.0, y: 0.0 },
         ]);
@@ -1530,7 +2040,7 @@ mod tests {
     #[test]
     #[allow(clippy::needless_borrow)]
     fn test_polyline_deref() {
-        let polyline = Polyline(vec![
+        let polyline = Polyline::from_vec(vec![
             CoordinatePair { x: 0.0, y: 1.0 },
             CoordinatePair { x: 1.0, y: 0.0 },
         ]);
@@ -1538,4 +2048,879 @@ mod tests {
         let _empty = polyline.is_empty();
         let _empty = (&polyline).is_empty();
     }
+
+    #[test]
+    fn test_viewport_clip() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+                <path d="M 5,5 L 15,5"/>
+                <path d="M 20,20 L 30,20"/>
+            </svg>
+        "#
+        .trim();
+        let options = |viewport_clip| ParseOptions {
+            preprocess: Preprocess::No,
+            viewport_clip,
+            ..Default::default()
+        };
+        let result = parse_with_options(input, &options(ViewportClip::Off)).unwrap();
+        assert_eq!(result.len(), 2);
+        let result = parse_with_options(input, &options(ViewportClip::Drop)).unwrap();
+        assert_eq!(
+            result,
+            vec![Polyline::from_vec(vec![
+                (5.0, 5.0).into(),
+                (15.0, 5.0).into()
+            ])]
+        );
+        let result = parse_with_options(input, &options(ViewportClip::Clip)).unwrap();
+        assert_eq!(
+            result,
+            vec![Polyline::from_vec(vec![
+                (5.0, 5.0).into(),
+                (10.0, 5.0).into()
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_policy() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <path d="M 0,0 L 10,0 T 10,10 L 0,10"/>
+                <path d="M 0,0 L 1,1" transform="translateX(45)"/>
+            </svg>
+        "#
+        .trim();
+        let parse = |unsupported| {
+            let options = ParseOptions {
+                preprocess: Preprocess::No,
+                unsupported,
+                ..Default::default()
+            };
+            let mut warnings = Vec::new();
+            parse_with_warnings(input, &options, &mut warnings).map(|result| (result, warnings))
+        };
+        assert!(parse(UnsupportedPolicy::Error).is_err());
+
+        let (result, warnings) = parse(UnsupportedPolicy::Warn).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                Polyline::from_vec(vec![(0.0, 0.0).into(), (10.0, 0.0).into()]),
+                Polyline::from_vec(vec![(10.0, 10.0).into(), (0.0, 10.0).into()]),
+            ]
+        );
+        assert_eq!(warnings.len(), 2);
+
+        let (skipped, warnings) = parse(UnsupportedPolicy::Skip).unwrap();
+        assert_eq!(skipped, result);
+        assert!(warnings.is_empty());
+
+        let (result, warnings) = parse(UnsupportedPolicy::Approximate).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                Polyline::from_vec(vec![
+                    (0.0, 0.0).into(),
+                    (10.0, 0.0).into(),
+                    (10.0, 10.0).into(),
+                    (0.0, 10.0).into(),
+                ]),
+                Polyline::from_vec(vec![(0.0, 0.0).into(), (1.0, 1.0).into()]),
+            ]
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_rect() {
+        let input = r#"<svg><rect x="1" y="2" width="3" height="4"/></svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let result = parse_with_options(input, &options).unwrap();
+        let mut expected = Polyline::from_vec(vec![
+            (1.0, 2.0).into(),
+            (4.0, 2.0).into(),
+            (4.0, 6.0).into(),
+            (1.0, 6.0).into(),
+            (1.0, 2.0).into(),
+        ]);
+        expected.close();
+        assert_eq!(result, vec![expected]);
+    }
+
+    #[test]
+    fn test_parse_polygon_single_point() {
+        let input = r#"<svg><polygon points="5,5"/></svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        assert_eq!(parse_with_options(input, &options).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_parse_circle() {
+        let input = r#"<svg><circle cx="10" cy="10" r="5"/></svg>"#;
+        let parse = |tolerance| {
+            let options = ParseOptions {
+                tolerance,
+                preprocess: Preprocess::No,
+                ..Default::default()
+            };
+            parse_with_options(input, &options).unwrap()
+        };
+        let coarse = parse(1.0);
+        let fine = parse(0.01);
+        assert_eq!(coarse.len(), 1);
+        assert!(fine[0].len() > coarse[0].len());
+        for p in &*fine[0] {
+            let r = ((p.x - 10.0).powi(2) + (p.y - 10.0).powi(2)).sqrt();
+            assert!((r - 5.0).abs() < 0.01, "{:?} is not on the circle", p);
+        }
+    }
+
+    #[test]
+    fn test_group_transforms() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <g transform="matrix(2 0 0 2 0 0)">
+                    <g transform="matrix(1 0 0 1 10 0)">
+                        <path d="M 0,0 L 1,1" transform="matrix(1 0 0 1 0 5)"/>
+                    </g>
+                    <path d="M 0,0 L 1,1"/>
+                </g>
+                <path d="M 0,0 L 1,1"/>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let result = parse_with_options(input, &options).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                Polyline::from_vec(vec![(20.0, 10.0).into(), (22.0, 12.0).into()]),
+                Polyline::from_vec(vec![(0.0, 0.0).into(), (2.0, 2.0).into()]),
+                Polyline::from_vec(vec![(0.0, 0.0).into(), (1.0, 1.0).into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unit_conversion() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg" width="4in" height="2in" viewBox="0 0 400 200">
+                <path d="M 0,0 L 100,50"/>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            unit: Unit::Inches,
+            ..Default::default()
+        };
+        let result = parse_with_options(input, &options).unwrap();
+        assert_eq!(
+            result,
+            vec![Polyline::from_vec(vec![
+                (0.0, 0.0).into(),
+                (1.0, 0.5).into()
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_skip_invisible() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <style>.guide { stroke: none }</style>
+                <g fill="none">
+                    <path d="M 0,0 L 1,1" stroke="black"/>
+                    <path d="M 0,0 L 2,2" class="guide"/>
+                </g>
+                <path d="M 0,0 L 3,3" style="fill:none;stroke:none"/>
+                <path d="M 0,0 L 4,4"/>
+            </svg>
+        "#
+        .trim();
+        let parse = |skip_invisible| {
+            let options = ParseOptions {
+                preprocess: Preprocess::No,
+                skip_invisible,
+                ..Default::default()
+            };
+            parse_with_options(input, &options).unwrap()
+        };
+        assert_eq!(parse(false).len(), 4);
+        assert_eq!(
+            parse(true),
+            vec![
+                Polyline::from_vec(vec![(0.0, 0.0).into(), (1.0, 1.0).into()]),
+                Polyline::from_vec(vec![(0.0, 0.0).into(), (4.0, 4.0).into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layers() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg"
+                 xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape">
+                <g inkscape:groupmode="layer" inkscape:label="Pen 1">
+                    <path d="M 0,0 L 1,1"/>
+                </g>
+                <g inkscape:groupmode="layer" inkscape:label="Pen 2">
+                    <path d="M 0,0 L 2,2"/>
+                </g>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            layers: Some(vec!["Pen 2".into()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_with_options(input, &options).unwrap(),
+            vec![Polyline::from_vec(vec![
+                (0.0, 0.0).into(),
+                (2.0, 2.0).into()
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_include_exclude() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <g id="cut">
+                    <path d="M 0,0 L 1,1"/>
+                    <path d="M 0,0 L 2,2" class="guide"/>
+                </g>
+                <path d="M 0,0 L 3,3"/>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            include: Some("#cut".into()),
+            exclude: Some(".guide".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_with_options(input, &options).unwrap(),
+            vec![Polyline::from_vec(vec![
+                (0.0, 0.0).into(),
+                (1.0, 1.0).into()
+            ])]
+        );
+        let options = ParseOptions {
+            include: Some("g > path".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            parse_with_options(input, &options),
+            Err(Error::Selector(_))
+        ));
+    }
+
+    #[test]
+    fn test_dashes() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <g stroke-dasharray="4 2" transform="scale(2)">
+                    <path d="M 0,0 L 10,0" stroke-dashoffset="4"/>
+                </g>
+                <path d="M 0,0 L 10,0" style="stroke-dasharray:none"/>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            dashes: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_with_options(input, &options).unwrap(),
+            vec![
+                Polyline::from_vec(vec![(4.0, 0.0).into(), (12.0, 0.0).into()]),
+                Polyline::from_vec(vec![(16.0, 0.0).into(), (20.0, 0.0).into()]),
+                Polyline::from_vec(vec![(0.0, 0.0).into(), (10.0, 0.0).into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_non_finite() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <path d="M 0,0 L 1e300,0 L 1,1" transform="scale(1e10)"/>
+            <path d="M 1e300,1e300" transform="scale(1e10)"/>
+        </svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let polylines = parse_with_options(svg, &options).unwrap();
+        assert!(polylines[0][1].x.is_infinite());
+
+        let options = ParseOptions {
+            non_finite: NonFinitePolicy::Drop,
+            ..options
+        };
+        assert_eq!(
+            parse_with_options(svg, &options).unwrap(),
+            vec![Polyline::from_vec(vec![
+                (0.0, 0.0).into(),
+                (1e10, 1e10).into()
+            ])]
+        );
+
+        let options = ParseOptions {
+            non_finite: NonFinitePolicy::Error,
+            ..options
+        };
+        let error = parse_with_options(svg, &options).unwrap_err();
+        assert!(error.context().is_some());
+        assert!(matches!(
+            error,
+            Error::Element { source, .. } if matches!(*source, Error::Polyline(_))
+        ));
+    }
+
+    #[test]
+    fn test_remove_duplicates() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <path d="M 0,0 L 0,0 L 1,1 l 0,0 L 0,0 Z"/>
+            <path d="M 5,5 L 5,5"/>
+        </svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            remove_duplicates: true,
+            ..Default::default()
+        };
+        let polylines = parse_with_options(svg, &options).unwrap();
+        assert_eq!(polylines.len(), 2);
+        assert_eq!(
+            polylines[0].as_ref(),
+            &vec![(0.0, 0.0).into(), (1.0, 1.0).into(), (0.0, 0.0).into()]
+        );
+        assert!(polylines[0].is_closed());
+        assert_eq!(polylines[1].as_ref(), &vec![(5.0, 5.0).into()]);
+    }
+
+    #[test]
+    fn test_merge_collinear() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <path d="M 0,0 H 1 H 2 V 1 V 2 L 3,3"/>
+        </svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            merge_collinear: Some(0.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_with_options(svg, &options).unwrap(),
+            vec![Polyline::from_vec(vec![
+                (0.0, 0.0).into(),
+                (2.0, 0.0).into(),
+                (2.0, 2.0).into(),
+                (3.0, 3.0).into(),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_outline_strokes() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <path d="M 0,0 L 10,0" stroke="black" stroke-width="2"/>
+                <path d="M 0,0 L 10,0"/>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            outline_strokes: true,
+            ..Default::default()
+        };
+        let mut outline = Polyline::from_vec(vec![
+            (0.0, 1.0).into(),
+            (10.0, 1.0).into(),
+            (10.0, -1.0).into(),
+            (0.0, -1.0).into(),
+        ]);
+        outline.close();
+        assert_eq!(
+            parse_with_options(input, &options).unwrap(),
+            vec![
+                outline,
+                Polyline::from_vec(vec![(0.0, 0.0).into(), (10.0, 0.0).into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clip_paths() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <defs>
+                    <clipPath id="left"><rect width="5" height="10"/></clipPath>
+                    <clipPath id="half" clipPathUnits="objectBoundingBox">
+                        <rect width="0.5" height="1"/>
+                    </clipPath>
+                </defs>
+                <g transform="translate(10)" clip-path="url(#left)">
+                    <path d="M 0,5 L 10,5"/>
+                </g>
+                <path d="M 0,30 L 10,20" clip-path="url(#half)"/>
+                <path d="M 0,0 L 1,1" clip-path="url(#missing)"/>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            clip_paths: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_with_options(input, &options).unwrap(),
+            vec![
+                Polyline::from_vec(vec![(10.0, 5.0).into(), (15.0, 5.0).into()]),
+                Polyline::from_vec(vec![(0.0, 30.0).into(), (5.0, 25.0).into()]),
+                Polyline::from_vec(vec![(0.0, 0.0).into(), (1.0, 1.0).into()]),
+            ]
+        );
+
+        // By default, clip paths are converted like other shapes
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        assert_eq!(parse_with_options(input, &options).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_masks() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <mask id="mask">
+                    <rect width="10" height="10" fill="white"/>
+                    <rect x="4" width="2" height="10" fill="black"/>
+                </mask>
+                <path d="M 0,5 L 20,5" mask="url(#mask)"/>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            mask_threshold: Some(0.5),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_with_options(input, &options).unwrap(),
+            vec![
+                Polyline::from_vec(vec![(0.0, 5.0).into(), (4.0, 5.0).into()]),
+                Polyline::from_vec(vec![(6.0, 5.0).into(), (10.0, 5.0).into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_patterns() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <pattern id="stripes" width="2" height="4" patternUnits="userSpaceOnUse">
+                    <path d="M 1,0 L 1,4"/>
+                </pattern>
+                <rect x="0" y="0" width="4" height="2" fill="url(#stripes)"/>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            patterns: true,
+            ..Default::default()
+        };
+        let polylines = parse_with_options(input, &options).unwrap();
+        assert_eq!(polylines.len(), 3);
+        assert_eq!(
+            polylines[1..],
+            [
+                Polyline::from_vec(vec![(1.0, 0.0).into(), (1.0, 2.0).into()]),
+                Polyline::from_vec(vec![(3.0, 0.0).into(), (3.0, 2.0).into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markers() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <marker id="tick" orient="auto" markerUnits="userSpaceOnUse">
+                    <path d="M 0,-1 L 0,1"/>
+                </marker>
+                <g marker-end="url(#tick)">
+                    <path d="M 0,0 L 10,0" transform="translate(0,5)"/>
+                </g>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            markers: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_with_options(input, &options).unwrap(),
+            vec![
+                Polyline::from_vec(vec![(0.0, 5.0).into(), (10.0, 5.0).into()]),
+                Polyline::from_vec(vec![(10.0, 4.0).into(), (10.0, 6.0).into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lenient() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <path d="M 0,0 L 10,0"/>
+                <path id="broken" d="M 0,0 L 10"/>
+                <path d="M 0,5 L 10,5"/>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let error = parse_with_options(input, &options).unwrap_err();
+        assert!(
+            matches!(&error, Error::Element { source, .. } if matches!(**source, Error::PathParse(_)))
+        );
+        assert_eq!(
+            error.context(),
+            Some(&ErrorContext {
+                element: 2,
+                id: Some("broken".into()),
+                offset: Some(input.find("<path id").unwrap()),
+                line: Some(3),
+                column: Some(17),
+            })
+        );
+        assert!(error
+            .to_string()
+            .ends_with("in element 2 (#broken) at line 3, column 17"));
+        let options = ParseOptions {
+            lenient: true,
+            ..options
+        };
+        let document = parse_document(input, &options).unwrap();
+        assert_eq!(
+            document.polylines,
+            vec![
+                Polyline::from_vec(vec![(0.0, 0.0).into(), (10.0, 0.0).into()]),
+                Polyline::from_vec(vec![(0.0, 5.0).into(), (10.0, 5.0).into()]),
+            ]
+        );
+        assert!(matches!(
+            &document.warnings[..],
+            [Warning::InvalidPath { id: Some(id), .. }] if id == "broken"
+        ));
+    }
+
+    #[test]
+    fn test_limits() {
+        let input = r##"<svg xmlns="http://www.w3.org/2000/svg">
+            <path id="a" d="M 0,0 L 10,0 L 10,10"/>
+            <use href="#a"/>
+            <rect x="0" y="0" width="5" height="5"/>
+        </svg>"##;
+        let parse_limited = |limits| {
+            parse_with_options(
+                input,
+                &ParseOptions {
+                    preprocess: Preprocess::No,
+                    limits,
+                    ..Default::default()
+                },
+            )
+        };
+        let limits = Limits {
+            max_input_size: Some(input.len()),
+            max_paths: Some(2),
+            max_points: Some(8),
+            max_use_depth: Some(1),
+        };
+        assert_eq!(parse_limited(limits).unwrap().len(), 2);
+        for limits in [
+            Limits {
+                max_input_size: Some(input.len() - 1),
+                ..limits
+            },
+            Limits {
+                max_paths: Some(1),
+                ..limits
+            },
+            Limits {
+                max_points: Some(7),
+                ..limits
+            },
+            Limits {
+                max_use_depth: Some(0),
+                ..limits
+            },
+        ] {
+            assert!(matches!(parse_limited(limits), Err(Error::Limit(_))));
+        }
+    }
+
+    #[test]
+    fn test_parse_all() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <path id="a" d="M 0,0 L 10"/>
+                <path d="M 0,0 L 10,0"/>
+                <path id="b" d="M 0,0 L 1,1" transform="scale(a)"/>
+            </svg>
+        "#
+        .trim();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let (polylines, errors) = parse_all(input, &options).unwrap();
+        assert_eq!(
+            polylines,
+            vec![Polyline::from_vec(vec![
+                (0.0, 0.0).into(),
+                (10.0, 0.0).into()
+            ])]
+        );
+        let ids: Vec<_> = errors
+            .iter()
+            .map(|(index, e)| {
+                (
+                    *index,
+                    e.context().unwrap().id.as_deref(),
+                    e.context().unwrap().line,
+                )
+            })
+            .collect();
+        assert_eq!(ids, vec![(1, Some("a"), Some(2)), (3, Some("b"), Some(4))]);
+        assert!(parse_all("<svg><path d=\"M 0,0 L 1,1\"></svg>", &options).is_err());
+    }
+
+    #[test]
+    fn test_polyline_flat_views() {
+        let polyline = Polyline::from_vec(vec![
+            CoordinatePair { x: 0.0, y: 1.0 },
+            CoordinatePair { x: 2.0, y: 3.0 },
+        ]);
+        assert_eq!(polyline.as_flat_slice(), &[0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(polyline.as_point_array(), &[[0.0, 1.0], [2.0, 3.0]]);
+        assert_eq!(
+            Vec::<[f64; 2]>::from(polyline),
+            vec![[0.0, 1.0], [2.0, 3.0]]
+        );
+        assert!(Polyline::new().as_flat_slice().is_empty());
+    }
+
+    #[test]
+    fn test_polyline_closed() {
+        let lines = parse_path(
+            "M 0,0 L 1,0 1,1 Z M 5,5 L 6,5 5,5 M 0,0 L 1,0 1,1 Z L 2,2",
+            FLATTENING_TOLERANCE,
+            UnsupportedPolicy::Error,
+            &mut Vec::new(),
+        )
+        .unwrap();
+        let closed: Vec<bool> = lines.iter().map(Polyline::is_closed).collect();
+        assert_eq!(closed, vec![true, false, false]);
+        assert_eq!(lines[0].first(), lines[0].last());
+
+        let mut polyline = Polyline::from_vec(vec![(0.0, 0.0).into(), (1.0, 0.0).into()]);
+        polyline.close();
+        assert!(polyline.is_closed());
+        assert_eq!(polyline.len(), 3);
+        polyline.close();
+        assert_eq!(polyline.len(), 3);
+        let mut empty = Polyline::new();
+        empty.close();
+        assert!(!empty.is_closed());
+    }
+
+    #[test]
+    fn test_polyline_transform() {
+        let mut polyline = Polyline::from_vec(vec![(1.0, 0.0).into(), (0.0, 1.0).into()]);
+        polyline.close();
+        let scaled = polyline.transform(Transform2D::scale(2.0, 3.0));
+        assert!(scaled.is_closed());
+        assert_eq!(
+            scaled.to_vec(),
+            vec![(2.0, 0.0).into(), (0.0, 3.0).into(), (2.0, 0.0).into()]
+        );
+
+        let mut polylines = vec![scaled, Polyline::from_vec(vec![(1.0, 1.0).into()])];
+        transform_all(&mut polylines, Transform2D::translation(-1.0, 1.0));
+        assert_eq!(polylines[0][1], (-1.0, 4.0).into());
+        assert_eq!(polylines[1][0], (0.0, 2.0).into());
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_polyline_winding() {
+        // Clockwise as displayed, with the y axis pointing down
+        let mut square = Polyline::from_vec(vec![
+            (0.0, 0.0).into(),
+            (2.0, 0.0).into(),
+            (2.0, 2.0).into(),
+            (0.0, 2.0).into(),
+        ]);
+        assert_eq!(square.signed_area(), 4.0);
+        assert_eq!(square.winding(), Some(Winding::Clockwise));
+        square.close();
+        assert_eq!(square.signed_area(), 4.0);
+        square.reverse();
+        assert!(square.is_closed());
+        assert_eq!(square[0], square[4]);
+        assert_eq!(square[1], (0.0, 2.0).into());
+        assert_eq!(square.signed_area(), -4.0);
+        assert_eq!(square.winding(), Some(Winding::CounterClockwise));
+        assert_eq!(square.reversed().winding(), Some(Winding::Clockwise));
+
+        let line = Polyline::from_vec(vec![(0.0, 0.0).into(), (1.0, 1.0).into()]);
+        assert_eq!(line.winding(), None);
+        assert_eq!(Polyline::new().signed_area(), 0.0);
+    }
+
+    #[test]
+    fn test_polyline_contains_point() {
+        // A pentagram winds twice around its center
+        let star = Polyline::from_vec(vec![
+            (0.0, -10.0).into(),
+            (6.0, 8.0).into(),
+            (-9.5, -3.0).into(),
+            (9.5, -3.0).into(),
+            (-6.0, 8.0).into(),
+        ]);
+        let center = (0.0, 0.0).into();
+        assert!(star.contains_point(center, FillRule::NonZero));
+        assert!(!star.contains_point(center, FillRule::EvenOdd));
+        let tip = (0.0, -8.0).into();
+        assert!(star.contains_point(tip, FillRule::NonZero));
+        assert!(star.contains_point(tip, FillRule::EvenOdd));
+        assert!(!star.contains_point((20.0, 0.0).into(), FillRule::NonZero));
+        // A vertex at the height of the point is only counted once
+        assert!(!star.contains_point((-20.0, -3.0).into(), FillRule::EvenOdd));
+        assert!(!Polyline::new().contains_point(center, FillRule::NonZero));
+
+        // Overlapping squares with the same and with opposite windings
+        let square = |min: f64, max: f64| {
+            Polyline::from_vec(vec![
+                (min, min).into(),
+                (max, min).into(),
+                (max, max).into(),
+                (min, max).into(),
+            ])
+        };
+        let p = (3.0, 3.0).into();
+        let same = [square(0.0, 4.0), square(2.0, 6.0)];
+        assert!(point_in_polylines(&same, p, FillRule::NonZero));
+        assert!(!point_in_polylines(&same, p, FillRule::EvenOdd));
+        assert!(point_in_polylines(
+            &same,
+            (1.0, 1.0).into(),
+            FillRule::EvenOdd
+        ));
+        let opposite = [square(0.0, 4.0), square(2.0, 6.0).reversed()];
+        assert!(!point_in_polylines(&opposite, p, FillRule::NonZero));
+        assert!(!point_in_polylines(&opposite, p, FillRule::EvenOdd));
+        assert!(!point_in_polylines(&[], p, FillRule::NonZero));
+    }
+
+    #[test]
+    fn test_polyline_centroid_and_hull() {
+        let points = Polyline::from_vec(vec![
+            (0.0, 0.0).into(),
+            (4.0, 0.0).into(),
+            (1.0, 1.0).into(),
+            (4.0, 2.0).into(),
+            (2.0, 0.0).into(),
+            (0.0, 2.0).into(),
+        ]);
+        let mut expected = Polyline::from_vec(vec![
+            (0.0, 0.0).into(),
+            (4.0, 0.0).into(),
+            (4.0, 2.0).into(),
+            (0.0, 2.0).into(),
+        ]);
+        expected.close();
+        let hull = points.convex_hull();
+        assert!(hull.is_closed());
+        assert_eq!(hull, expected);
+        assert_eq!(hull.winding(), Some(Winding::Clockwise));
+        assert_eq!(hull.centroid(), Some((2.0, 1.0).into()));
+
+        // Without an area
+        let line = Polyline::from_vec(vec![
+            (0.0, 0.0).into(),
+            (1.0, 0.0).into(),
+            (4.0, 0.0).into(),
+        ]);
+        assert_eq!(line.centroid(), Some((2.0, 0.0).into()));
+        assert_eq!(
+            line.convex_hull(),
+            Polyline::from_vec(vec![(0.0, 0.0).into(), (4.0, 0.0).into()])
+        );
+        let point = Polyline::from_vec(vec![(1.0, 2.0).into(), (1.0, 2.0).into()]);
+        assert_eq!(point.centroid(), Some((1.0, 2.0).into()));
+        assert_eq!(point.convex_hull().len(), 1);
+        assert_eq!(Polyline::new().centroid(), None);
+        assert!(Polyline::new().convex_hull().is_empty());
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_polyline_length() {
+        let polylines = vec![
+            Polyline::from_vec(vec![
+                (0.0, 0.0).into(),
+                (3.0, 4.0).into(),
+                (3.0, 0.0).into(),
+            ]),
+            Polyline::from_vec(vec![(1.0, 1.0).into()]),
+            Polyline::new(),
+        ];
+        assert_eq!(polylines[0].length(), 9.0);
+        assert_eq!(polylines[1].length(), 0.0);
+        assert_eq!(total_length(&polylines), 9.0);
+        assert_eq!(total_length(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_polyline_to_f32() {
+        let polyline = Polyline::from_vec(vec![
+            CoordinatePair { x: 0.1, y: -2.5 },
+            CoordinatePair { x: 1e300, y: 3.0 },
+        ]);
+        assert_eq!(polyline.to_f32(), vec![[0.1, -2.5], [f32::INFINITY, 3.0]]);
+    }
+
+    #[test]
+    fn test_polyline_to_fixed() {
+        let polyline = Polyline::from_vec(vec![
+            CoordinatePair { x: 0.0125, y: -2.5 },
+            CoordinatePair {
+                x: 1e300,
+                y: f64::NAN,
+            },
+        ]);
+        assert_eq!(polyline.to_fixed(1000.0), vec![[13, -2500], [i64::MAX, 0]]);
+        assert_eq!(polyline.to_fixed(1.0)[0], [0, -3]);
+    }
 }
@@ -0,0 +1,202 @@
+//! Guard against documents that would take excessive time or memory to
+//! convert, see [`ParseOptions::limits`](crate::ParseOptions::limits).
+
+use std::collections::{HashMap, HashSet};
+
+use quick_xml::events::Event;
+
+use crate::{style, Error};
+
+/// Resource limits for untrusted input. Parsing aborts with an
+/// [`Error::Limit`] as soon as a limit is exceeded.
+///
+/// All limits are disabled by default.
+///
+/// ```
+/// use svg2polylines::{parse_with_options, Error, Limits, ParseOptions, Preprocess};
+///
+/// let options = ParseOptions {
+///     preprocess: Preprocess::No,
+///     limits: Limits {
+///         max_paths: Some(1),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+///     <path d="M 0,0 10,10"/>
+///     <path d="M 0,0 20,20"/>
+/// </svg>"#;
+/// assert!(matches!(parse_with_options(svg, &options), Err(Error::Limit(_))));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Limits {
+    /// The maximum size of the document in bytes. Compressed documents are
+    /// limited both before and after decompression.
+    pub max_input_size: Option<usize>,
+
+    /// The maximum number of path and shape elements.
+    pub max_paths: Option<usize>,
+
+    /// The maximum total number of points of the polylines. It is checked
+    /// after every element, so a single element can exceed it before parsing
+    /// is aborted.
+    pub max_points: Option<usize>,
+
+    /// The maximum nesting depth of `<use>` references, e.g. 1 if `<use>`
+    /// elements only reference elements that don't contain `<use>` elements
+    /// themselves. Circular references always exceed the limit.
+    pub max_use_depth: Option<usize>,
+}
+
+impl Limits {
+    pub(crate) fn check_input_size(&self, size: usize) -> Result<(), Error> {
+        exceeds(self.max_input_size, size, "bytes of input")
+    }
+
+    pub(crate) fn check_paths(&self, paths: usize) -> Result<(), Error> {
+        exceeds(self.max_paths, paths, "paths")
+    }
+
+    pub(crate) fn check_points(&self, points: usize) -> Result<(), Error> {
+        exceeds(self.max_points, points, "points")
+    }
+
+    /// Check the nesting depth of the `<use>` references in the document.
+    pub(crate) fn check_use_depth(&self, svg: &str) -> Result<(), Error> {
+        let Some(max) = self.max_use_depth else {
+            return Ok(());
+        };
+        let references = collect_references(svg)?;
+        let mut checked = HashSet::new();
+        for id in references.get("").into_iter().flatten() {
+            check_reference(id, 1, max, &references, &mut checked)?;
+        }
+        Ok(())
+    }
+}
+
+fn exceeds(limit: Option<usize>, value: usize, what: &str) -> Result<(), Error> {
+    match limit {
+        Some(max) if value > max => Err(Error::Limit(format!("More than {max} {what}"))),
+        _ => Ok(()),
+    }
+}
+
+/// The ids referenced by `<use>` elements, keyed by the id of every element
+/// containing them. The references of the whole document are stored with an
+/// empty id.
+type References = HashMap<String, Vec<String>>;
+
+fn collect_references(svg: &str) -> Result<References, Error> {
+    let mut reader = quick_xml::Reader::from_str(svg);
+    let mut references = References::new();
+    // The ids of the open elements, the document being the outermost one
+    let mut stack: Vec<Option<String>> = vec![Some(String::new())];
+    let mut buf = Vec::new();
+    loop {
+        let (e, is_start) = match reader.read_event(&mut buf) {
+            Ok(Event::Start(e)) => (e, true),
+            Ok(Event::Empty(e)) => (e, false),
+            Ok(Event::End(_)) => {
+                stack.pop();
+                buf.clear();
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {
+                buf.clear();
+                continue;
+            }
+            Err(e) => return Err(Error::SvgParse(e.to_string())),
+        };
+        let id = style::attribute(&e, b"id");
+        if e.name() == b"use" {
+            let href =
+                style::attribute(&e, b"href").or_else(|| style::attribute(&e, b"xlink:href"));
+            if let Some(target) = href.as_deref().and_then(|href| href.strip_prefix('#')) {
+                // A `<use>` element references its target from itself, too
+                for ancestor in stack.iter().chain(Some(&id)).flatten() {
+                    references
+                        .entry(ancestor.clone())
+                        .or_default()
+                        .push(target.to_string());
+                }
+            }
+        }
+        if is_start {
+            stack.push(id);
+        }
+        buf.clear();
+    }
+    Ok(references)
+}
+
+/// Check that the element `id`, referenced at the given depth, does not
+/// lead to references deeper than `max`.
+fn check_reference(
+    id: &str,
+    depth: usize,
+    max: usize,
+    references: &References,
+    checked: &mut HashSet<(String, usize)>,
+) -> Result<(), Error> {
+    if depth > max {
+        return Err(Error::Limit(format!(
+            "More than {max} nested <use> references"
+        )));
+    }
+    if !checked.insert((id.to_string(), depth)) {
+        return Ok(());
+    }
+    for target in references.get(id).into_iter().flatten() {
+        check_reference(target, depth + 1, max, references, checked)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn use_depth_ok(svg: &str, max: usize) -> bool {
+        let limits = Limits {
+            max_use_depth: Some(max),
+            ..Default::default()
+        };
+        limits.check_use_depth(svg).is_ok()
+    }
+
+    #[test]
+    fn test_check_use_depth() {
+        let svg = r##"<svg>
+            <defs>
+                <path id="leaf" d="M 0,0 1,1"/>
+                <g id="branch"><use href="#leaf"/><use href="#leaf"/></g>
+            </defs>
+            <use xlink:href="#branch"/>
+            <use href="#leaf"/>
+        </svg>"##;
+        assert!(use_depth_ok(svg, 2));
+        assert!(!use_depth_ok(svg, 1));
+        assert!(Limits::default().check_use_depth(svg).is_ok());
+
+        let circular =
+            r##"<svg><defs><g id="a"><use href="#b"/></g></defs><use id="b" href="#a"/></svg>"##;
+        assert!(!use_depth_ok(circular, 100));
+    }
+
+    #[test]
+    fn test_check_counts() {
+        let limits = Limits {
+            max_paths: Some(2),
+            ..Default::default()
+        };
+        assert!(limits.check_paths(2).is_ok());
+        assert_eq!(
+            limits.check_paths(3).unwrap_err().to_string(),
+            "Resource limit exceeded: More than 2 paths"
+        );
+        assert!(limits.check_points(usize::MAX).is_ok());
+    }
+}
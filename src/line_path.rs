@@ -0,0 +1,141 @@
+//! A fast path for path expressions made of straight lines only, which are
+//! common in CAD exports.
+
+use crate::{
+    cancel::{self, CancelToken},
+    flatten::{subpath_estimates, CurrentLine, Tokens},
+    CoordinatePair, Error, Polyline,
+};
+
+/// Parse a path expression that consists of `MoveTo`, `LineTo`,
+/// `HorizontalLineTo`, `VerticalLineTo` and `ClosePath` segments only,
+/// without the per-segment dispatch and curve handling of
+/// [`parse_path`](crate::parse_path).
+///
+/// Return `None` if the expression contains other segments or is invalid, so
+/// that it is parsed (and errors are reported) by the general parser.
+pub(crate) fn parse(
+    expr: &str,
+    cancel: Option<&CancelToken>,
+) -> Result<Option<Vec<Polyline>>, Error> {
+    let mut tokens = Tokens::new(expr);
+    let mut lines = Vec::new();
+    let mut line = CurrentLine::new();
+    let mut estimates = subpath_estimates(expr);
+    let mut prev_command = None;
+    loop {
+        tokens.skip_spaces();
+        let Some(&b) = tokens.bytes.get(tokens.pos) else {
+            break;
+        };
+        let command = match (b, prev_command) {
+            (b'M' | b'm' | b'L' | b'l' | b'H' | b'h' | b'V' | b'v' | b'Z' | b'z', _) => {
+                tokens.pos += 1;
+                b
+            }
+            // Numbers repeat the previous command, or are lines after a move
+            (b'0'..=b'9' | b'.' | b'+' | b'-', Some(b'M')) => b'L',
+            (b'0'..=b'9' | b'.' | b'+' | b'-', Some(b'm')) => b'l',
+            (b'0'..=b'9' | b'.' | b'+' | b'-', Some(command))
+                if command != b'Z' && command != b'z' =>
+            {
+                command
+            }
+            _ => return Ok(None),
+        };
+        if prev_command.is_none() && command != b'M' && command != b'm' {
+            return Ok(None);
+        }
+        cancel::check(cancel)?;
+        let abs = command.is_ascii_uppercase();
+        match command.to_ascii_lowercase() {
+            b'm' => {
+                let Some(pair) = tokens.pair() else {
+                    return Ok(None);
+                };
+                if line.is_valid() {
+                    lines.push(line.finish());
+                }
+                line.reserve(estimates.next().unwrap_or(0));
+                line.add(abs, pair);
+            }
+            b'l' => {
+                let Some(pair) = tokens.pair() else {
+                    return Ok(None);
+                };
+                line.add(abs, pair);
+            }
+            b'h' => {
+                let (Some(x), Some(y)) = (tokens.number(), line.last_y()) else {
+                    return Ok(None);
+                };
+                if abs {
+                    line.add_absolute(CoordinatePair::new(x, y));
+                } else {
+                    line.add_relative(CoordinatePair::new(x, 0.0));
+                }
+            }
+            b'v' => {
+                let (Some(y), Some(x)) = (tokens.number(), line.last_x()) else {
+                    return Ok(None);
+                };
+                if abs {
+                    line.add_absolute(CoordinatePair::new(x, y));
+                } else {
+                    line.add_relative(CoordinatePair::new(0.0, y));
+                }
+            }
+            _ => {
+                if line.close().is_err() {
+                    return Ok(None);
+                }
+            }
+        }
+        prev_command = Some(command);
+    }
+    if line.is_valid() {
+        lines.push(line.finish());
+    }
+    Ok(Some(lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_segments, UnsupportedPolicy};
+
+    fn general(expr: &str) -> Result<Vec<Polyline>, Error> {
+        parse_segments(expr, 0.15, UnsupportedPolicy::Error, None, &mut Vec::new())
+    }
+
+    #[test]
+    fn test_parse() {
+        for expr in [
+            "M 0,0 L 10,0 10,10 Z",
+            "m1 1 2 2 h 3 v-4.5e1 H 0 V .5 z m 1,1 l 2,2",
+            "M0,0L-1-1.5.5.5M 3 3",
+            "M 0 0 1e2 +2 Z L 5 5",
+            "m 10,10",
+            "",
+        ] {
+            let lines = parse(expr, None).unwrap();
+            assert_eq!(lines, Some(general(expr).unwrap()), "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_parse_fallback() {
+        for expr in [
+            "M 0,0 C 1,1 2,2 3,3",
+            "M 0,0 L 1,1 Z 2,2",
+            "L 1,1",
+            "M 0,0 L 1,,1",
+            "M 0,0 L 1e,1",
+            "M 0 0 Z",
+            "M 0 0 X",
+            "M0,0L-1-1.5.5M 3 3",
+        ] {
+            assert_eq!(parse(expr, None).unwrap(), None, "{expr}");
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Diagnostics for the path data of a document.
+//!
+//! The checks run on the original input (without preprocessing), so that the
+//! reported elements and segments can be found and fixed in the source file.
+
+use std::fmt;
+
+use quick_xml::events::Event;
+use svgtypes::{PathParser, PathSegment};
+
+use crate::{data_uri, style, Error};
+
+/// Thresholds for [`lint`].
+#[derive(Debug, Clone)]
+pub struct LintOptions {
+    /// Coordinates with a larger absolute value are reported as
+    /// [`Issue::HugeCoordinate`].
+    pub max_coordinate: f64,
+
+    /// Numbers with more decimal places are reported as
+    /// [`Issue::ExcessivePrecision`].
+    pub max_decimals: usize,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            max_coordinate: 1e6,
+            max_decimals: 6,
+        }
+    }
+}
+
+/// A problem in the path data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// The path data could not be parsed. Segments after the error are
+    /// ignored by the parser.
+    InvalidData { message: String },
+
+    /// A segment that does not move the pen.
+    ZeroLengthSegment,
+
+    /// An arc with a radius of 0, which is drawn as a straight line.
+    DegenerateArc,
+
+    /// A coordinate that is larger than
+    /// [`LintOptions::max_coordinate`].
+    HugeCoordinate { value: f64 },
+
+    /// Numbers with more decimal places than
+    /// [`LintOptions::max_decimals`]. Only the largest number of decimal
+    /// places in the element is reported.
+    ExcessivePrecision { decimals: usize },
+
+    /// A subpath that consists of a move command only.
+    EmptySubpath,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidData { message } => write!(f, "invalid path data: {message}"),
+            Self::ZeroLengthSegment => write!(f, "zero-length segment"),
+            Self::DegenerateArc => write!(f, "arc with a radius of 0"),
+            Self::HugeCoordinate { value } => write!(f, "huge coordinate {value}"),
+            Self::ExcessivePrecision { decimals } => {
+                write!(f, "number with {decimals} decimal places")
+            }
+            Self::EmptySubpath => write!(f, "empty subpath"),
+        }
+    }
+}
+
+/// A problem found by [`lint`], together with its location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Index of the `<path>` element in document order.
+    pub element: usize,
+
+    /// The `id` attribute of the element, if present.
+    pub id: Option<String>,
+
+    /// Index of the segment in the path data, if the issue concerns a single
+    /// segment.
+    pub segment: Option<usize>,
+
+    /// The problem.
+    pub issue: Issue,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "path {}", self.element)?;
+        if let Some(id) = &self.id {
+            write!(f, " (#{id})")?;
+        }
+        if let Some(segment) = self.segment {
+            write!(f, ", segment {segment}")?;
+        }
+        write!(f, ": {}", self.issue)
+    }
+}
+
+/// Return the absolute points defined by a segment (control points first, end
+/// point last).
+fn segment_points(
+    segment: &PathSegment,
+    current: (f64, f64),
+    start: (f64, f64),
+) -> Vec<(f64, f64)> {
+    let resolve = |abs: bool, x: f64, y: f64| {
+        if abs {
+            (x, y)
+        } else {
+            (current.0 + x, current.1 + y)
+        }
+    };
+    match *segment {
+        PathSegment::MoveTo { abs, x, y }
+        | PathSegment::LineTo { abs, x, y }
+        | PathSegment::SmoothQuadratic { abs, x, y }
+        | PathSegment::EllipticalArc { abs, x, y, .. } => vec![resolve(abs, x, y)],
+        PathSegment::HorizontalLineTo { abs, x } => {
+            vec![(if abs { x } else { current.0 + x }, current.1)]
+        }
+        PathSegment::VerticalLineTo { abs, y } => {
+            vec![(current.0, if abs { y } else { current.1 + y })]
+        }
+        PathSegment::CurveTo {
+            abs,
+            x1,
+            y1,
+            x2,
+            y2,
+            x,
+            y,
+        } => vec![
+            resolve(abs, x1, y1),
+            resolve(abs, x2, y2),
+            resolve(abs, x, y),
+        ],
+        PathSegment::SmoothCurveTo { abs, x2, y2, x, y }
+        | PathSegment::Quadratic {
+            abs,
+            x1: x2,
+            y1: y2,
+            x,
+            y,
+        } => vec![resolve(abs, x2, y2), resolve(abs, x, y)],
+        PathSegment::ClosePath { .. } => vec![start],
+    }
+}
+
+/// Return the largest number of decimal places of any number in the path
+/// data.
+fn max_decimals(data: &str) -> usize {
+    let mut max = 0;
+    let mut decimals: Option<usize> = None;
+    for c in data.chars() {
+        match (c, decimals) {
+            ('.', _) => decimals = Some(0),
+            ('0'..='9', Some(n)) => decimals = Some(n + 1),
+            _ => decimals = None,
+        }
+        max = max.max(decimals.unwrap_or(0));
+    }
+    max
+}
+
+/// Check the path data of a single element.
+fn lint_path(data: &str, options: &LintOptions, report: &mut dyn FnMut(Option<usize>, Issue)) {
+    let decimals = max_decimals(data);
+    if decimals > options.max_decimals {
+        report(None, Issue::ExcessivePrecision { decimals });
+    }
+
+    let mut current = (0.0, 0.0);
+    let mut start = (0.0, 0.0);
+    // Index of the move command that started the current subpath, as long as
+    // nothing was drawn
+    let mut empty_subpath: Option<usize> = None;
+    for (index, segment) in PathParser::from(data).enumerate() {
+        let segment = match segment {
+            Ok(segment) => segment,
+            Err(e) => {
+                report(
+                    None,
+                    Issue::InvalidData {
+                        message: e.to_string(),
+                    },
+                );
+                break;
+            }
+        };
+        let points = segment_points(&segment, current, start);
+        if let Some(&(x, y)) = points
+            .iter()
+            .find(|(x, y)| x.abs() > options.max_coordinate || y.abs() > options.max_coordinate)
+        {
+            let value = if x.abs() > options.max_coordinate {
+                x
+            } else {
+                y
+            };
+            report(Some(index), Issue::HugeCoordinate { value });
+        }
+        let end = points[points.len() - 1];
+
+        match segment {
+            PathSegment::MoveTo { .. } => {
+                if let Some(move_index) = empty_subpath.replace(index) {
+                    report(Some(move_index), Issue::EmptySubpath);
+                }
+                start = end;
+            }
+            PathSegment::ClosePath { .. } => {
+                if let Some(move_index) = empty_subpath.take() {
+                    report(Some(move_index), Issue::EmptySubpath);
+                }
+            }
+            _ => {
+                empty_subpath = None;
+                if let PathSegment::EllipticalArc { rx, ry, .. } = segment {
+                    if rx == 0.0 || ry == 0.0 {
+                        report(Some(index), Issue::DegenerateArc);
+                    }
+                }
+                if points.iter().all(|&p| p == current) {
+                    report(Some(index), Issue::ZeroLengthSegment);
+                }
+            }
+        }
+        current = end;
+    }
+    if let Some(move_index) = empty_subpath {
+        report(Some(move_index), Issue::EmptySubpath);
+    }
+}
+
+/// Report problems in the path data of all `<path>` elements of a document.
+///
+/// These problems don't prevent the conversion, but often result in poor
+/// plots, e.g. dots from zero-length segments or pen lifts from empty
+/// subpaths.
+pub fn lint(svg: &str, options: &LintOptions) -> Result<Vec<Diagnostic>, Error> {
+    let svg = data_uri::decode_input(svg)?;
+    let mut reader = quick_xml::Reader::from_str(&svg);
+    reader.trim_text(true);
+
+    let mut diagnostics = Vec::new();
+    let mut element = 0;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e) | Event::Empty(ref e)) if e.name() == b"path" => {
+                let id = style::attribute(e, b"id");
+                if let Some(data) = style::attribute(e, b"d") {
+                    lint_path(&data, options, &mut |segment, issue| {
+                        diagnostics.push(Diagnostic {
+                            element,
+                            id: id.clone(),
+                            segment,
+                            issue,
+                        });
+                    });
+                }
+                element += 1;
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => return Err(Error::SvgParse(e.to_string())),
+        }
+        buf.clear();
+    }
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(data: &str) -> Vec<(Option<usize>, Issue)> {
+        let mut result = Vec::new();
+        lint_path(data, &LintOptions::default(), &mut |segment, issue| {
+            result.push((segment, issue));
+        });
+        result
+    }
+
+    #[test]
+    fn test_lint_path() {
+        assert_eq!(issues("M 0,0 L 10,10 Z"), vec![]);
+        assert_eq!(
+            issues("M 0,0 L 10,10 l 0,0 H 10"),
+            vec![
+                (Some(2), Issue::ZeroLengthSegment),
+                (Some(3), Issue::ZeroLengthSegment),
+            ]
+        );
+        assert_eq!(
+            issues("M 0,0 A 0 5 0 0 1 10,10"),
+            vec![(Some(1), Issue::DegenerateArc)]
+        );
+        assert_eq!(
+            issues("M 0,0 M 5,5 L 10,10 M 20,20"),
+            vec![
+                (Some(0), Issue::EmptySubpath),
+                (Some(3), Issue::EmptySubpath)
+            ]
+        );
+        assert_eq!(
+            issues("M 0,0 L 2000000,0.1234567"),
+            vec![
+                (None, Issue::ExcessivePrecision { decimals: 7 }),
+                (Some(1), Issue::HugeCoordinate { value: 2_000_000.0 }),
+            ]
+        );
+        assert_eq!(
+            issues("M 0,0 L 10,10 X"),
+            vec![(
+                None,
+                Issue::InvalidData {
+                    message: "unexpected data at position 15".into()
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_lint() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg">
+                <path d="M 0,0 L 10,10"/>
+                <path id="dot" d="M 5,5 l 0,0"/>
+            </svg>
+        "#;
+        let diagnostics = lint(input, &LintOptions::default()).unwrap();
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                element: 1,
+                id: Some("dot".into()),
+                segment: Some(1),
+                issue: Issue::ZeroLengthSegment,
+            }]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "path 1 (#dot), segment 1: zero-length segment"
+        );
+    }
+}
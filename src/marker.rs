@@ -0,0 +1,495 @@
+//! Place the content of `<marker>` elements at the vertices of paths.
+
+use std::{collections::HashMap, f64::consts::PI, str::FromStr};
+
+use log::trace;
+use lyon_geom::euclid::{Angle, Point2D, Transform2D, Vector2D};
+use quick_xml::events::{BytesStart, Event};
+use svgtypes::{AspectRatio, Length, LengthUnit, PathParser, PathSegment};
+
+use crate::{
+    css::StyleSheet,
+    document::ViewBox,
+    parse_path, parse_path_segment, shape, style,
+    transform::{ComposedTransform, TransformStack},
+    CoordinatePair, CurrentLine, Error, ParseOptions, Polyline,
+};
+
+/// The orientation of a marker (`orient`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Orient {
+    /// Along the direction of the path.
+    Auto,
+    /// Like `Auto`, but the start marker points backwards.
+    AutoStartReverse,
+    /// A fixed angle in degrees.
+    Angle(f64),
+}
+
+/// A `<marker>` element.
+#[derive(Debug)]
+pub(crate) struct Marker {
+    /// The point of the content placed at the vertex (`refX`, `refY`).
+    reference: (f64, f64),
+
+    /// The size of the marker viewport (`markerWidth`, `markerHeight`).
+    size: (f64, f64),
+
+    /// Whether the marker is scaled by the stroke width (`markerUnits`, the
+    /// default).
+    stroke_width_units: bool,
+
+    orient: Orient,
+
+    view_box: Option<(ViewBox, AspectRatio)>,
+
+    /// The polylines of the content, in the marker coordinate system.
+    content: Vec<Polyline>,
+}
+
+impl Marker {
+    /// Return the transform of the content for a vertex at `at` with the
+    /// path direction `angle` in radians.
+    fn transform(
+        &self,
+        at: CoordinatePair,
+        angle: f64,
+        start: bool,
+        stroke_width: f64,
+    ) -> Transform2D<f64, f64, f64> {
+        let view_box = self
+            .view_box
+            .map_or_else(Transform2D::identity, |(vb, aspect)| {
+                vb.transform_to(self.size.0, self.size.1, aspect)
+            });
+        let reference = view_box.transform_point(Point2D::new(self.reference.0, self.reference.1));
+        let scale = if self.stroke_width_units {
+            stroke_width
+        } else {
+            1.0
+        };
+        let rotation = match self.orient {
+            Orient::AutoStartReverse if start => angle + PI,
+            Orient::Auto | Orient::AutoStartReverse => angle,
+            Orient::Angle(degrees) => degrees.to_radians(),
+        };
+        view_box
+            .then_translate(-reference.to_vector())
+            .then_scale(scale, scale)
+            .then_rotate(Angle::radians(rotation))
+            .then_translate(Vector2D::new(at.x, at.y))
+    }
+}
+
+/// The markers of an element, inherited from its ancestors, and whether it
+/// is rendered.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MarkerState {
+    start: Option<String>,
+    mid: Option<String>,
+    end: Option<String>,
+
+    /// Whether the element is part of a `<marker>` and thus not rendered
+    /// directly.
+    pub(crate) hidden: bool,
+}
+
+impl MarkerState {
+    /// Apply the `marker`, `marker-start`, `marker-mid` and `marker-end`
+    /// properties of an element to the state inherited from its parent.
+    pub(crate) fn inherit(&self, e: &BytesStart, sheet: &StyleSheet) -> Self {
+        let shorthand = style::property(e, "marker", sheet);
+        let value = |name, inherited: &Option<String>| match style::property(e, name, sheet)
+            .or_else(|| shorthand.clone())
+        {
+            Some(value) => style::reference(&value),
+            None => inherited.clone(),
+        };
+        Self {
+            start: value("marker-start", &self.start),
+            mid: value("marker-mid", &self.mid),
+            end: value("marker-end", &self.end),
+            hidden: self.hidden || e.name() == b"marker",
+        }
+    }
+
+    /// Return the markers of an element with the given stroke width, or
+    /// `None` if the element has no markers.
+    ///
+    /// Only paths, lines, polylines and polygons have markers.
+    pub(crate) fn markers(&self, e: &BytesStart, stroke_width: f64) -> Option<Markers> {
+        let markable = matches!(e.name(), b"path" | b"line" | b"polyline" | b"polygon");
+        let has_markers = self.start.is_some() || self.mid.is_some() || self.end.is_some();
+        (markable && has_markers).then(|| Markers {
+            start: self.start.clone(),
+            mid: self.mid.clone(),
+            end: self.end.clone(),
+            stroke_width,
+        })
+    }
+}
+
+/// The markers of a path element.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Markers {
+    start: Option<String>,
+    mid: Option<String>,
+    end: Option<String>,
+    stroke_width: f64,
+}
+
+/// A subpath with the indices of its vertices, i.e. the end points of the
+/// path segments as opposed to the points of flattened curves.
+struct Subpath {
+    points: Vec<CoordinatePair>,
+    vertices: Vec<usize>,
+    closed: bool,
+}
+
+impl Subpath {
+    /// Return the angle of the path direction at a vertex, bisecting the
+    /// incoming and outgoing directions.
+    fn angle(&self, vertex: usize) -> f64 {
+        let points = &self.points;
+        let p = points[vertex];
+        let angle = |a: CoordinatePair, b: CoordinatePair| (b.y - a.y).atan2(b.x - a.x);
+        let last = points.len() - 1;
+        let incoming = points[..vertex]
+            .iter()
+            .rev()
+            .find(|&&q| q != p)
+            .map(|&q| angle(q, p))
+            .or_else(|| {
+                // The closing segment leads into the start of closed subpaths
+                let end = points[last];
+                let q = points[..last].iter().rev().find(|&&q| q != end)?;
+                (self.closed && vertex == 0).then(|| angle(*q, end))
+            });
+        let outgoing = points[vertex + 1..]
+            .iter()
+            .find(|&&q| q != p)
+            .map(|&q| angle(p, q))
+            .or_else(|| {
+                let start = points[0];
+                let q = points[1..].iter().find(|&&q| q != start)?;
+                (self.closed && vertex == last).then(|| angle(start, *q))
+            });
+        match (incoming, outgoing) {
+            (Some(a), Some(b)) => {
+                // Half of the turn, wrapped into [-PI, PI]
+                let turn = (b - a + PI).rem_euclid(2.0 * PI) - PI;
+                a + turn / 2.0
+            }
+            (Some(a), None) | (None, Some(a)) => a,
+            (None, None) => 0.0,
+        }
+    }
+}
+
+/// Split path data into subpaths and find the vertices.
+fn subpaths(expr: &str, tolerance: f64) -> Result<Vec<Subpath>, Error> {
+    let mut subpaths = Vec::new();
+    let mut line = CurrentLine::new();
+    let mut vertices = Vec::new();
+    let mut closed = false;
+    let mut prev_segment_store: Option<PathSegment> = None;
+    for segment in PathParser::from(expr) {
+        let mut segment = segment.map_err(|e| Error::PathParse(e.to_string()))?;
+        // Only the end point matters for unsupported segments
+        if let PathSegment::SmoothQuadratic { abs, x, y } = segment {
+            segment = PathSegment::LineTo { abs, x, y };
+        }
+        let prev_segment = prev_segment_store.replace(segment);
+        if let PathSegment::MoveTo { .. } = segment {
+            if line.is_valid() {
+                subpaths.push(Subpath {
+                    points: line.line.to_vec(),
+                    vertices: std::mem::take(&mut vertices),
+                    closed,
+                });
+            }
+            vertices.clear();
+            closed = false;
+        }
+        parse_path_segment(
+            &segment,
+            prev_segment,
+            &mut line,
+            tolerance,
+            &mut Vec::new(),
+        )?;
+        closed |= matches!(segment, PathSegment::ClosePath { .. });
+        if let Some(vertex) = line.line.len().checked_sub(1) {
+            if vertices.last() != Some(&vertex) {
+                vertices.push(vertex);
+            }
+        }
+    }
+    if line.is_valid() {
+        subpaths.push(Subpath {
+            points: line.line.to_vec(),
+            vertices,
+            closed,
+        });
+    }
+    Ok(subpaths)
+}
+
+impl Markers {
+    /// Return the content of the markers placed at the vertices of the path
+    /// data, in the user space of the path.
+    ///
+    /// The start marker is placed at the first vertex, the end marker at the
+    /// last vertex and the mid marker at all other vertices. References to
+    /// missing markers are ignored.
+    pub(crate) fn expand(
+        &self,
+        data: &str,
+        markers: &HashMap<String, Marker>,
+        tolerance: f64,
+    ) -> Result<Vec<Polyline>, Error> {
+        let vertices: Vec<(CoordinatePair, f64)> = subpaths(data, tolerance)?
+            .iter()
+            .flat_map(|subpath| {
+                subpath
+                    .vertices
+                    .iter()
+                    .map(move |&vertex| (subpath.points[vertex], subpath.angle(vertex)))
+            })
+            .collect();
+        let last = vertices.len().saturating_sub(1);
+
+        let mut result = Vec::new();
+        for (i, &(at, angle)) in vertices.iter().enumerate() {
+            let mut place = |id: &Option<String>, start: bool| {
+                let Some(marker) = id.as_ref().and_then(|id| markers.get(id)) else {
+                    return;
+                };
+                let t = marker.transform(at, angle, start, self.stroke_width);
+                result.extend(
+                    marker
+                        .content
+                        .iter()
+                        .map(|polyline| polyline.clone().transform(t)),
+                );
+            };
+            if i == 0 {
+                place(&self.start, true);
+            }
+            if i > 0 && i < last {
+                place(&self.mid, false);
+            }
+            if i == last {
+                place(&self.end, false);
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Parse a length attribute in user units.
+fn length(e: &BytesStart, key: &[u8]) -> Option<f64> {
+    match Length::from_str(&style::attribute(e, key)?).ok()? {
+        Length {
+            number,
+            unit: LengthUnit::None | LengthUnit::Px,
+        } => Some(number),
+        _ => None,
+    }
+}
+
+/// Parse the `orient` attribute. Invalid values are replaced by the initial
+/// value, an angle of zero.
+fn orient(value: Option<&str>) -> Orient {
+    match value.map(str::trim) {
+        Some("auto") => Orient::Auto,
+        Some("auto-start-reverse") => Orient::AutoStartReverse,
+        Some(angle) => Orient::Angle(
+            angle
+                .strip_suffix("deg")
+                .unwrap_or(angle)
+                .parse()
+                .unwrap_or(0.0),
+        ),
+        None => Orient::Angle(0.0),
+    }
+}
+
+/// Collect the `<marker>` elements of a document by their id.
+///
+/// The content is not clipped to the marker viewport.
+pub(crate) fn collect(svg: &str, options: &ParseOptions) -> Result<HashMap<String, Marker>, Error> {
+    let mut reader = quick_xml::Reader::from_str(svg);
+    reader.trim_text(true);
+
+    let mut markers = HashMap::new();
+    // The id and contents of the current marker, and the transforms of the
+    // open elements inside of it
+    let mut current: Option<(String, Marker)> = None;
+    let mut transforms = TransformStack::default();
+    let mut depth = 0;
+    let mut buf = Vec::new();
+    loop {
+        let (e, is_start): (BytesStart, bool) = match reader.read_event(&mut buf) {
+            Ok(Event::Start(e)) => (e, true),
+            Ok(Event::Empty(e)) => (e, false),
+            Ok(Event::End(_)) if current.is_some() => {
+                transforms.pop();
+                depth -= 1;
+                if depth == 0 {
+                    if let Some((id, marker)) = current.take() {
+                        trace!("marker::collect: Found marker \"{id}\"");
+                        markers.insert(id, marker);
+                    }
+                }
+                buf.clear();
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {
+                buf.clear();
+                continue;
+            }
+            Err(e) => return Err(Error::SvgParse(e.to_string())),
+        };
+        if let Some((_, marker)) = &mut current {
+            let composed = transforms.compose(&e);
+            if let Some(data) = shape::path_data(&e) {
+                if let Some(t) = composed.resolve(options.unsupported, &mut Vec::new())? {
+                    let polylines = parse_path(
+                        &data,
+                        options.tolerance,
+                        options.unsupported,
+                        &mut Vec::new(),
+                    )?;
+                    marker
+                        .content
+                        .extend(polylines.into_iter().map(|polyline| polyline.transform(t)));
+                }
+            }
+            if is_start {
+                transforms.push(composed);
+                depth += 1;
+            }
+        } else if e.name() == b"marker" && is_start {
+            let Some(id) = style::attribute(&e, b"id") else {
+                buf.clear();
+                continue;
+            };
+            let view_box = style::attribute(&e, b"viewBox")
+                .and_then(|value| svgtypes::ViewBox::from_str(&value).ok())
+                .map(|vb| {
+                    let aspect = style::attribute(&e, b"preserveAspectRatio")
+                        .and_then(|value| AspectRatio::from_str(&value).ok())
+                        .unwrap_or_default();
+                    let view_box = ViewBox {
+                        x: vb.x,
+                        y: vb.y,
+                        width: vb.w,
+                        height: vb.h,
+                    };
+                    (view_box, aspect)
+                });
+            current = Some((
+                id,
+                Marker {
+                    reference: (
+                        length(&e, b"refX").unwrap_or(0.0),
+                        length(&e, b"refY").unwrap_or(0.0),
+                    ),
+                    size: (
+                        length(&e, b"markerWidth").unwrap_or(3.0),
+                        length(&e, b"markerHeight").unwrap_or(3.0),
+                    ),
+                    stroke_width_units: style::attribute(&e, b"markerUnits").as_deref()
+                        != Some("userSpaceOnUse"),
+                    orient: orient(style::attribute(&e, b"orient").as_deref()),
+                    view_box,
+                    content: Vec::new(),
+                },
+            ));
+            // The transforms of the ancestors don't apply to the content
+            transforms = TransformStack::default();
+            transforms.push(ComposedTransform::default());
+            depth = 1;
+        }
+        buf.clear();
+    }
+    Ok(markers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::line;
+
+    fn assert_close(actual: &[Polyline], expected: &[Polyline]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, b) in actual.iter().zip(expected) {
+            assert_eq!(a.len(), b.len());
+            for (p, q) in a.iter().zip(b.iter()) {
+                assert!(
+                    (p.x - q.x).abs() < 1e-9 && (p.y - q.y).abs() < 1e-9,
+                    "{:?} != {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_subpaths() {
+        let subpaths = subpaths("M 0,0 L 10,0 Q 20,0 20,10 M 5,5 L 6,6 Z", 0.15).unwrap();
+        assert_eq!(subpaths.len(), 2);
+        let first = &subpaths[0];
+        assert_eq!(first.vertices[..2], [0, 1]);
+        assert_eq!(first.vertices.len(), 3);
+        assert_eq!(first.points[first.vertices[2]], (20.0, 10.0).into());
+        assert!((first.angle(0)).abs() < 1e-9);
+        assert!((first.angle(first.vertices[2]) - PI / 2.0).abs() < 0.3);
+        assert!(subpaths[1].closed);
+        assert_eq!(subpaths[1].vertices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_expand() {
+        let svg = r#"<svg>
+            <marker id="dot" markerUnits="userSpaceOnUse" orient="auto" refX="1">
+                <path d="M 0,0 L 2,0"/>
+            </marker>
+            <marker id="arrow" orient="auto-start-reverse" viewBox="0 0 10 10"
+                    markerWidth="2" markerHeight="2">
+                <path d="M 0,0 L 10,5 L 0,10"/>
+            </marker>
+        </svg>"#;
+        let markers = collect(svg, &ParseOptions::default()).unwrap();
+        assert_eq!(markers.len(), 2);
+        let state = MarkerState {
+            start: Some("arrow".into()),
+            mid: Some("dot".into()),
+            end: Some("missing".into()),
+            hidden: false,
+        };
+        let e = BytesStart::borrowed_name(b"path");
+        let expanded = state
+            .markers(&e, 2.0)
+            .unwrap()
+            .expand("M 0,0 L 10,0 L 10,10", &markers, 0.15)
+            .unwrap();
+        assert_close(
+            &expanded,
+            &[
+                // Reversed, scaled by the stroke width to 4 units
+                line(&[(0.0, 0.0), (-4.0, -2.0), (0.0, -4.0)]),
+                // Rotated by 45 degrees around the reference point
+                line(&[
+                    (10.0 - 0.5_f64.sqrt(), -(0.5_f64.sqrt())),
+                    (10.0 + 0.5_f64.sqrt(), 0.5_f64.sqrt()),
+                ]),
+            ],
+        );
+        let rect = BytesStart::borrowed_name(b"rect");
+        assert_eq!(state.markers(&rect, 1.0), None);
+    }
+}
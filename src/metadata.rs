@@ -0,0 +1,299 @@
+//! Polylines together with information about their source element.
+
+use std::{collections::HashSet, str::FromStr};
+
+use log::trace;
+use quick_xml::events::Event;
+use svgtypes::Color;
+
+#[cfg(feature = "usvg")]
+use crate::Preprocess;
+use crate::{
+    css::StyleSheet,
+    layer, parse_element_path, preprocess, shape,
+    style::{attribute, property},
+    transform::TransformStack,
+    Error, ParseOptions, Polyline,
+};
+
+/// A polyline together with the style of the path it was created from.
+#[derive(Debug, PartialEq)]
+pub struct PolylineWithMetadata {
+    pub polyline: Polyline,
+
+    /// The `id` attribute of the source element.
+    pub id: Option<String>,
+
+    /// The `class` attribute of the source element.
+    pub class: Option<String>,
+
+    /// The name of the innermost Inkscape layer containing the source
+    /// element.
+    ///
+    /// Preprocessing removes the Inkscape attributes, so this is only
+    /// available without preprocessing (`Preprocess::No`).
+    pub layer: Option<String>,
+
+    /// The stroke color, or `None` if the path is not stroked.
+    pub stroke: Option<Color>,
+
+    /// The fill color, or `None` if the path is not filled.
+    pub fill: Option<Color>,
+
+    /// The stroke width in user units.
+    pub stroke_width: f64,
+
+    /// The `id` of the `<text>` element the polyline was converted from, e.g.
+    /// a glyph outline of a `<textPath>`.
+    ///
+    /// Text is only converted into outlines when preprocessing, see
+    /// [`FontOptions`](crate::FontOptions).
+    pub text: Option<String>,
+}
+
+/// Style properties inherited from ancestor elements.
+#[derive(Debug, Clone)]
+struct Inherited {
+    stroke: Option<Color>,
+    fill: Option<Color>,
+    stroke_width: f64,
+    layer: Option<String>,
+    text: Option<String>,
+}
+
+impl Default for Inherited {
+    fn default() -> Self {
+        Self {
+            stroke: None,
+            fill: Some(Color::black()),
+            stroke_width: 1.0,
+            layer: None,
+            text: None,
+        }
+    }
+}
+
+/// Return the ids of all `<text>` elements.
+///
+/// Preprocessing converts text into paths, but keeps the id of the text
+/// element on the group of its glyphs.
+fn text_ids(svg: &str) -> HashSet<String> {
+    let mut reader = quick_xml::Reader::from_str(svg);
+    let mut ids = HashSet::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e) | Event::Empty(ref e)) if e.name() == b"text" => {
+                ids.extend(attribute(e, b"id"));
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => {}
+        }
+        buf.clear();
+    }
+    ids
+}
+
+/// Parse an SVG string into polylines, together with the style of their
+/// source paths.
+///
+/// Paint servers (gradients, patterns) are not resolved and are reported as
+/// no stroke or fill.
+pub fn parse_with_metadata(
+    svg: &str,
+    options: &ParseOptions,
+) -> Result<Vec<PolylineWithMetadata>, Error> {
+    let text_ids = text_ids(svg);
+    // Keep the groups of glyph outlines, which carry the id of the text
+    #[cfg_attr(not(feature = "usvg"), allow(unused_mut))]
+    let mut options = options.clone();
+    #[cfg(feature = "usvg")]
+    if let Preprocess::Usvg(usvg_options) = &mut options.preprocess {
+        usvg_options.keep_named_groups = true;
+    }
+    let options = &options;
+    let svg = preprocess(svg, options)?;
+    let sheet = StyleSheet::from_svg(&svg);
+    let mut reader = quick_xml::Reader::from_str(&svg);
+    reader.trim_text(true);
+
+    let mut stack: Vec<Inherited> = Vec::new();
+    let mut transforms = TransformStack::default();
+    let mut result = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        let (e, is_start) = match reader.read_event(&mut buf) {
+            Ok(Event::Start(e)) => (e, true),
+            Ok(Event::Empty(e)) => (e, false),
+            Ok(Event::End(_)) => {
+                stack.pop();
+                transforms.pop();
+                buf.clear();
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {
+                buf.clear();
+                continue;
+            }
+            Err(e) => return Err(Error::SvgParse(e.to_string())),
+        };
+
+        let mut style = stack.last().cloned().unwrap_or_default();
+        if let Some(stroke) = property(&e, "stroke", &sheet) {
+            style.stroke = Color::from_str(&stroke).ok();
+        }
+        if let Some(fill) = property(&e, "fill", &sheet) {
+            style.fill = Color::from_str(&fill).ok();
+        }
+        if let Some(width) = property(&e, "stroke-width", &sheet).and_then(|w| w.parse().ok()) {
+            style.stroke_width = width;
+        }
+
+        if let Some(name) = layer::name(&e) {
+            style.layer = Some(name);
+        }
+        if let Some(id) = attribute(&e, b"id").filter(|id| text_ids.contains(id)) {
+            style.text = Some(id);
+        }
+
+        let composed = transforms.compose(&e);
+        if let Some(data) = shape::path_data(&e) {
+            let id = attribute(&e, b"id");
+            let class = attribute(&e, b"class");
+            let transform = composed.resolve(options.unsupported, &mut Vec::new())?;
+            let polylines = parse_element_path(&data, id.as_deref(), options, &mut Vec::new())?;
+            for polyline in polylines.into_iter().flatten() {
+                result.push(PolylineWithMetadata {
+                    polyline: match transform {
+                        Some(t) => polyline.transform(t),
+                        None => polyline,
+                    },
+                    id: id.clone(),
+                    class: class.clone(),
+                    layer: style.layer.clone(),
+                    stroke: style.stroke,
+                    fill: style.fill,
+                    stroke_width: style.stroke_width,
+                    text: style.text.clone(),
+                });
+            }
+        }
+        if is_start {
+            stack.push(style);
+            transforms.push(composed);
+        }
+        buf.clear();
+    }
+    trace!("parse_with_metadata: Found {} polylines", result.len());
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Preprocess;
+
+    #[test]
+    fn test_parse_with_metadata() {
+        let input = r#"<svg>
+            <g stroke="red" stroke-width="2">
+                <path d="M 0,0 1,1"/>
+                <path style="stroke:blue" d="M 0,0 1,1"/>
+                <path stroke="none" d="M 0,0 1,1"/>
+            </g>
+            <path d="M 0,0 1,1"/>
+        </svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let result = parse_with_metadata(input, &options).unwrap();
+        let styles: Vec<_> = result.iter().map(|p| (p.stroke, p.stroke_width)).collect();
+        assert_eq!(
+            styles,
+            vec![
+                (Some(Color::new_rgb(255, 0, 0)), 2.0),
+                (Some(Color::new_rgb(0, 0, 255)), 2.0),
+                (None, 2.0),
+                (None, 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_with_metadata_attributes() {
+        let input = r##"<svg>
+            <g fill="none">
+                <path id="outline" class="cut outer" d="M 0,0 1,1"/>
+                <path fill="#00ff00" d="M 0,0 1,1"/>
+            </g>
+            <rect width="1" height="1"/>
+        </svg>"##;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let result = parse_with_metadata(input, &options).unwrap();
+        assert_eq!(result[0].id.as_deref(), Some("outline"));
+        assert_eq!(result[0].class.as_deref(), Some("cut outer"));
+        assert_eq!(result[0].fill, None);
+        assert_eq!(result[1].id, None);
+        assert_eq!(result[1].fill, Some(Color::new_rgb(0, 255, 0)));
+        assert_eq!(result[2].fill, Some(Color::black()));
+    }
+
+    #[test]
+    fn test_parse_with_metadata_layers() {
+        let input = r#"<svg xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape">
+            <g inkscape:groupmode="layer" inkscape:label="Cut">
+                <g><path d="M 0,0 1,1"/></g>
+                <g inkscape:groupmode="layer" id="layer2"><path d="M 0,0 1,1"/></g>
+            </g>
+            <path d="M 0,0 1,1"/>
+        </svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let result = parse_with_metadata(input, &options).unwrap();
+        let layers: Vec<_> = result.iter().map(|p| p.layer.as_deref()).collect();
+        assert_eq!(layers, vec![Some("Cut"), Some("layer2"), None]);
+    }
+
+    #[test]
+    fn test_parse_with_metadata_text() {
+        // Like the output of preprocessing, which keeps the text id on the
+        // group of glyph outlines
+        let input = r##"<svg>
+            <text id="label"><textPath href="#curve">Text</textPath></text>
+            <g id="label"><path id="glyph" d="M 0,0 1,1"/></g>
+            <path id="curve" d="M 0,0 1,1"/>
+        </svg>"##;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let result = parse_with_metadata(input, &options).unwrap();
+        let texts: Vec<_> = result.iter().map(|p| p.text.as_deref()).collect();
+        assert_eq!(texts, vec![Some("label"), None]);
+        assert_eq!(result[0].id.as_deref(), Some("glyph"));
+    }
+
+    #[test]
+    fn test_parse_with_metadata_classes() {
+        let input = r#"<svg>
+            <style>.cut { stroke: #00ff00; stroke-width: 0.5 }</style>
+            <path class="cut" d="M 0,0 1,1"/>
+            <path class="cut" stroke-width="3" d="M 0,0 1,1"/>
+        </svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let result = parse_with_metadata(input, &options).unwrap();
+        let styles: Vec<_> = result.iter().map(|p| (p.stroke, p.stroke_width)).collect();
+        let green = Some(Color::new_rgb(0, 255, 0));
+        assert_eq!(styles, vec![(green, 0.5), (green, 0.5)]);
+    }
+}
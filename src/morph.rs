@@ -0,0 +1,112 @@
+//! Interpolate between polylines.
+
+use crate::{CoordinatePair, Polyline};
+
+/// Interpolate between two polylines.
+///
+/// Both polylines are resampled to the same number of points (the larger of
+/// the two point counts), then every point is linearly interpolated. A `t` of
+/// `0.0` returns the (resampled) `from` polyline, a `t` of `1.0` returns the
+/// (resampled) `to` polyline.
+pub fn interpolate(from: &Polyline, to: &Polyline, t: f64) -> Polyline {
+    let n = from.len().max(to.len());
+    let from = from.resample(n);
+    let to = to.resample(n);
+    if from.is_empty() || to.is_empty() {
+        return if t < 0.5 { from } else { to };
+    }
+    let mut polyline = Polyline::from_vec(
+        from.iter()
+            .zip(to.iter())
+            .map(|(a, b)| CoordinatePair::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t))
+            .collect(),
+    );
+    if from.is_closed() && to.is_closed() {
+        polyline.close();
+    }
+    polyline
+}
+
+/// Produce `steps` intermediate shapes between two polylines, including both
+/// end states.
+///
+/// This is useful to animate ("tween") between two drawings.
+pub fn tween(from: &Polyline, to: &Polyline, steps: usize) -> Vec<Polyline> {
+    match steps {
+        0 => vec![],
+        1 => vec![interpolate(from, to, 0.0)],
+        _ => (0..steps)
+            .map(|i| {
+                #[allow(clippy::cast_precision_loss)]
+                let t = i as f64 / (steps - 1) as f64;
+                interpolate(from, to, t)
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample() {
+        let line = Polyline::from_vec(vec![
+            CoordinatePair::new(0.0, 0.0),
+            CoordinatePair::new(2.0, 0.0),
+            CoordinatePair::new(2.0, 2.0),
+        ]);
+        let resampled = line.resample(5);
+        assert_eq!(
+            resampled,
+            Polyline::from_vec(vec![
+                CoordinatePair::new(0.0, 0.0),
+                CoordinatePair::new(1.0, 0.0),
+                CoordinatePair::new(2.0, 0.0),
+                CoordinatePair::new(2.0, 1.0),
+                CoordinatePair::new(2.0, 2.0),
+            ])
+        );
+        assert_eq!(line.resample(1).len(), 1);
+        assert!(Polyline::new().resample(3).is_empty());
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let from = Polyline::from_vec(vec![
+            CoordinatePair::new(0.0, 0.0),
+            CoordinatePair::new(4.0, 0.0),
+        ]);
+        let to = Polyline::from_vec(vec![
+            CoordinatePair::new(0.0, 2.0),
+            CoordinatePair::new(2.0, 2.0),
+            CoordinatePair::new(4.0, 2.0),
+        ]);
+        let half = interpolate(&from, &to, 0.5);
+        assert_eq!(
+            half,
+            Polyline::from_vec(vec![
+                CoordinatePair::new(0.0, 1.0),
+                CoordinatePair::new(2.0, 1.0),
+                CoordinatePair::new(4.0, 1.0),
+            ])
+        );
+        let frames = tween(&from, &to, 3);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1], half);
+        assert_eq!(frames[2], to);
+        assert!(!half.is_closed());
+
+        let square = |size: f64| {
+            let mut square = Polyline::from_vec(vec![
+                CoordinatePair::new(0.0, 0.0),
+                CoordinatePair::new(size, 0.0),
+                CoordinatePair::new(size, size),
+                CoordinatePair::new(0.0, size),
+            ]);
+            square.close();
+            square
+        };
+        assert_eq!(interpolate(&square(2.0), &square(4.0), 0.5), square(3.0));
+    }
+}
@@ -0,0 +1,93 @@
+//! Combine several SVG documents into one set of polylines.
+
+use log::trace;
+use lyon_geom::euclid::Transform2D;
+
+use crate::{parse_with_options, Error, ParseOptions, Polyline};
+
+/// An SVG document together with its placement in the combined output.
+#[derive(Debug, Clone, Copy)]
+pub struct SvgInput<'a> {
+    /// The SVG source.
+    pub svg: &'a str,
+
+    /// Transformation applied to all polylines of this document.
+    pub transform: Transform2D<f64, f64, f64>,
+}
+
+impl<'a> SvgInput<'a> {
+    /// Create a new input that is placed without any transformation.
+    pub fn new(svg: &'a str) -> Self {
+        Self {
+            svg,
+            transform: Transform2D::identity(),
+        }
+    }
+
+    /// Create a new input that is translated by `(dx, dy)`.
+    pub fn with_offset(svg: &'a str, dx: f64, dy: f64) -> Self {
+        Self {
+            svg,
+            transform: Transform2D::translation(dx, dy),
+        }
+    }
+}
+
+/// A polyline that remembers which input it originated from.
+#[derive(Debug, PartialEq)]
+pub struct PlacedPolyline {
+    /// Index of the source document in the slice passed to [`parse_many`].
+    pub source: usize,
+
+    /// The transformed polyline.
+    pub polyline: Polyline,
+}
+
+/// Parse multiple SVG documents and merge the resulting polylines.
+///
+/// Every document is parsed with the same `options`, then its polylines are
+/// transformed with the placement of the corresponding [`SvgInput`]. The
+/// result preserves the order of the inputs.
+pub fn parse_many(
+    inputs: &[SvgInput<'_>],
+    options: &ParseOptions,
+) -> Result<Vec<PlacedPolyline>, Error> {
+    trace!("parse_many: {} inputs", inputs.len());
+    let mut placed = Vec::new();
+    for (source, input) in inputs.iter().enumerate() {
+        let polylines = parse_with_options(input.svg, options)?;
+        placed.extend(polylines.into_iter().map(|polyline| PlacedPolyline {
+            source,
+            polyline: polyline.transform(input.transform),
+        }));
+    }
+    Ok(placed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Preprocess;
+
+    #[test]
+    fn test_parse_many() {
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let a = r#"<svg><path d="M 0,0 1,1"/></svg>"#;
+        let b = r#"<svg><path d="M 0,0 1,1"/><path d="M 2,2 3,3"/></svg>"#;
+        let result = parse_many(
+            &[SvgInput::new(a), SvgInput::with_offset(b, 10.0, 20.0)],
+            &options,
+        )
+        .unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].source, 0);
+        assert_eq!(result[0].polyline[1], (1.0, 1.0).into());
+        assert_eq!(result[1].source, 1);
+        assert_eq!(result[1].polyline[0], (10.0, 20.0).into());
+        assert_eq!(result[2].source, 1);
+        assert_eq!(result[2].polyline[1], (13.0, 23.0).into());
+    }
+}
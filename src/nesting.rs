@@ -0,0 +1,136 @@
+//! Find out which closed polylines lie inside of which others.
+
+use std::cmp::Ordering;
+
+use crate::{clip::point_in_rings, BoundingBox, Polyline};
+
+/// The position of a polyline in the containment hierarchy, see
+/// [`nesting`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NestingNode {
+    /// The index of the smallest closed polyline containing this one.
+    pub parent: Option<usize>,
+
+    /// The indices of the closed polylines directly inside of this one, in
+    /// ascending order.
+    pub children: Vec<usize>,
+
+    /// The number of closed polylines containing this one, e.g. 0 for an
+    /// outline and 1 for a hole in it.
+    pub depth: usize,
+}
+
+/// Return the containment hierarchy of the closed polylines, with one node
+/// per polyline in the order of `polylines`.
+///
+/// A closed polyline contains another one if it encloses its first point
+/// and is at least as large. Polylines that are not closed (see
+/// [`Polyline::is_closed`]) are neither contained nor contain others. The
+/// polylines are assumed not to cross each other.
+///
+/// This is useful e.g. to cut holes before the outlines around them, by
+/// sorting the polylines by decreasing depth.
+///
+/// ```
+/// use svg2polylines::{nesting, Polyline};
+///
+/// let square = |min: f64, max: f64| {
+///     let mut square = Polyline::from_vec(vec![
+///         (min, min).into(),
+///         (max, min).into(),
+///         (max, max).into(),
+///         (min, max).into(),
+///     ]);
+///     square.close();
+///     square
+/// };
+/// let nodes = nesting(&[square(0.0, 10.0), square(2.0, 4.0), square(20.0, 30.0)]);
+/// assert_eq!(nodes[0].children, vec![1]);
+/// assert_eq!(nodes[1].parent, Some(0));
+/// assert_eq!(nodes[1].depth, 1);
+/// assert_eq!(nodes[2].parent, None);
+/// ```
+pub fn nesting(polylines: &[Polyline]) -> Vec<NestingNode> {
+    let mut nodes = vec![NestingNode::default(); polylines.len()];
+
+    // Larger polylines first, so that every polyline can only be contained
+    // by polylines before it
+    let mut order: Vec<(usize, f64, BoundingBox)> = polylines
+        .iter()
+        .enumerate()
+        .filter(|(_, polyline)| polyline.is_closed())
+        .filter_map(|(i, polyline)| {
+            Some((i, polyline.signed_area().abs(), polyline.bounding_box()?))
+        })
+        .collect();
+    order.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
+
+    for (k, &(i, _, bbox)) in order.iter().enumerate() {
+        let point = polylines[i][0];
+        let parent = order[..k]
+            .iter()
+            .rev()
+            .find(|&&(j, _, outer)| {
+                outer.min_x <= bbox.min_x
+                    && outer.min_y <= bbox.min_y
+                    && outer.max_x >= bbox.max_x
+                    && outer.max_y >= bbox.max_y
+                    && point_in_rings(point, &[&polylines[j]])
+            })
+            .map(|&(j, _, _)| j);
+        if let Some(j) = parent {
+            nodes[i].parent = Some(j);
+            nodes[i].depth = nodes[j].depth + 1;
+            nodes[j].children.push(i);
+        }
+    }
+    for node in &mut nodes {
+        node.children.sort_unstable();
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: f64, max: f64) -> Polyline {
+        let mut square = Polyline::from_vec(vec![
+            (min, min).into(),
+            (max, min).into(),
+            (max, max).into(),
+            (min, max).into(),
+        ]);
+        square.close();
+        square
+    }
+
+    #[test]
+    fn test_nesting() {
+        let polylines = [
+            square(3.0, 4.0),
+            square(0.0, 10.0),
+            Polyline::from_vec(vec![(2.5, 2.5).into(), (4.5, 4.5).into()]),
+            square(2.0, 5.0),
+            square(6.0, 8.0),
+            square(20.0, 30.0),
+            // The same polyline twice
+            square(20.0, 30.0),
+        ];
+        let nodes = nesting(&polylines);
+        let parents: Vec<Option<usize>> = nodes.iter().map(|node| node.parent).collect();
+        assert_eq!(
+            parents,
+            vec![Some(3), None, None, Some(1), Some(1), None, Some(5)]
+        );
+        let depths: Vec<usize> = nodes.iter().map(|node| node.depth).collect();
+        assert_eq!(depths, vec![2, 0, 0, 1, 1, 0, 1]);
+        assert_eq!(nodes[1].children, vec![3, 4]);
+        assert!(nodes[2].children.is_empty());
+        assert!(nesting(&[]).is_empty());
+    }
+}
@@ -0,0 +1,837 @@
+//! Reduce the plotting time of a drawing by removing redundant geometry and
+//! pen lifts.
+
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    time::{Duration, Instant},
+};
+
+use crate::{bounding_box_of, simplify::segment_distance, CoordinatePair, Polyline};
+
+/// The maximum number of grid cells along the longer side of a drawing.
+const MAX_CELLS: f64 = 256.0;
+
+/// The largest absolute cell coordinate, so that the coordinates of
+/// neighbouring cells and rings cannot overflow.
+const MAX_CELL_INDEX: f64 = 1e15;
+
+/// A uniform grid of square cells to find items near a point.
+struct Grid<T> {
+    size: f64,
+    cells: HashMap<(i64, i64), Vec<T>>,
+    /// The lowest and highest cell coordinates with items.
+    bounds: Option<((i64, i64), (i64, i64))>,
+}
+
+impl<T: Copy> Grid<T> {
+    /// Create a grid with the given cell size, which must be positive.
+    fn new(size: f64) -> Self {
+        Self {
+            size,
+            cells: HashMap::new(),
+            bounds: None,
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn cell(&self, p: CoordinatePair) -> (i64, i64) {
+        let index = |v: f64| {
+            (v / self.size)
+                .floor()
+                .clamp(-MAX_CELL_INDEX, MAX_CELL_INDEX) as i64
+        };
+        (index(p.x), index(p.y))
+    }
+
+    /// Insert an item into all cells overlapping the rectangle between `min`
+    /// and `max`.
+    fn insert(&mut self, min: CoordinatePair, max: CoordinatePair, item: T) {
+        let (x0, y0) = self.cell(min);
+        let (x1, y1) = self.cell(max);
+        let ((bx0, by0), (bx1, by1)) = self.bounds.unwrap_or(((x0, y0), (x1, y1)));
+        self.bounds = Some(((bx0.min(x0), by0.min(y0)), (bx1.max(x1), by1.max(y1))));
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                self.cells.entry((x, y)).or_default().push(item);
+            }
+        }
+    }
+
+    /// Return the items of the cell containing `p`.
+    fn get(&self, p: CoordinatePair) -> &[T] {
+        self.cells.get(&self.cell(p)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Return the items of the cell containing `p` and its eight neighbours,
+    /// i.e. all items inserted at points closer to `p` than the cell size.
+    fn near(&self, p: CoordinatePair) -> impl Iterator<Item = T> + '_ {
+        let (x, y) = self.cell(p);
+        (x - 1..=x + 1)
+            .flat_map(move |x| (y - 1..=y + 1).map(move |y| (x, y)))
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+}
+
+impl<T: Copy + Ord> Grid<T> {
+    /// Remove an item inserted at the point `p`.
+    fn remove(&mut self, p: CoordinatePair, item: T) {
+        let cell = self.cell(p);
+        if let Some(items) = self.cells.get_mut(&cell) {
+            items.retain(|&other| other != item);
+        }
+    }
+
+    /// Return the item nearest to `p` according to `distance`, which must
+    /// not be less than the distance of the point at which the item was
+    /// inserted. Equally distant items are ordered by their value.
+    ///
+    /// The cells are searched in growing rings around `p`, until the rings
+    /// are farther away than the nearest item found.
+    fn nearest(&self, p: CoordinatePair, distance: impl Fn(T) -> f64) -> Option<T> {
+        let ((x0, y0), (x1, y1)) = self.bounds?;
+        let (x, y) = self.cell(p);
+        let max_ring = (x - x0)
+            .abs()
+            .max((x1 - x).abs())
+            .max((y - y0).abs())
+            .max((y1 - y).abs());
+        let mut best: Option<(f64, T)> = None;
+        for ring in 0..=max_ring {
+            // The rows above and below, then the columns left and right
+            let rows: &[i64] = if ring == 0 { &[0] } else { &[-ring, ring] };
+            let cells = (x - ring..=x + ring)
+                .flat_map(|cx| rows.iter().map(move |dy| (cx, y + dy)))
+                .chain((y - ring + 1..y + ring).flat_map(|cy| [(x - ring, cy), (x + ring, cy)]));
+            for cell in cells {
+                for &item in self.cells.get(&cell).into_iter().flatten() {
+                    let d = distance(item);
+                    if best.is_none_or(|(best_d, best_item)| {
+                        d.total_cmp(&best_d).then(item.cmp(&best_item)).is_lt()
+                    }) {
+                        best = Some((d, item));
+                    }
+                }
+            }
+            // Cells of the next ring are at least `ring` cells away from `p`
+            #[allow(clippy::cast_precision_loss)]
+            let reach = ring as f64 * self.size;
+            if best.is_some_and(|(d, _)| d < reach) {
+                break;
+            }
+        }
+        best.map(|(_, item)| item)
+    }
+}
+
+/// Options for [`join`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JoinOptions {
+    /// The maximum distance between the end points that are joined.
+    pub tolerance: f64,
+
+    /// Whether polylines may be reversed to join them, e.g. to join two
+    /// polylines that start at the same point.
+    pub reverse: bool,
+}
+
+impl Default for JoinOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.01,
+            reverse: true,
+        }
+    }
+}
+
+/// Remove segments that are drawn more than once, e.g. the shared edges of
+/// adjacent shapes in traced or CAD-exported drawings.
+///
+/// A segment is removed if both its end points are at most `tolerance` away
+/// from a single segment drawn before it, in either direction. Polylines are
+/// split where segments are removed. Segments covered only by several
+/// earlier segments together are kept.
+///
+/// ```
+/// use svg2polylines::{optimize::remove_overlaps, Polyline};
+///
+/// let square = Polyline::from_vec(vec![
+///     (0.0, 0.0).into(),
+///     (1.0, 0.0).into(),
+///     (1.0, 1.0).into(),
+///     (0.0, 1.0).into(),
+///     (0.0, 0.0).into(),
+/// ]);
+/// let edge = Polyline::from_vec(vec![(1.0, 1.0).into(), (1.0, 0.0).into()]);
+/// assert_eq!(remove_overlaps(&[square, edge], 0.01).len(), 1);
+/// ```
+pub fn remove_overlaps(polylines: &[Polyline], tolerance: f64) -> Vec<Polyline> {
+    let tolerance = tolerance.max(0.0);
+    let segments = polylines
+        .iter()
+        .map(|p| p.len().saturating_sub(1))
+        .sum::<usize>();
+    if segments == 0 {
+        return polylines
+            .iter()
+            .map(|p| Polyline {
+                points: p.points.clone(),
+                closed: p.closed,
+            })
+            .collect();
+    }
+
+    // Cells of about the average segment length keep both the number of
+    // cells per segment and the number of segments per cell small, a lower
+    // bound relative to the extent limits the cells of long outliers
+    #[allow(clippy::cast_precision_loss)]
+    let average = polylines.iter().map(Polyline::length).sum::<f64>() / segments as f64;
+    let mut grid = Grid::new(cell_size(polylines, average.max(tolerance)));
+    let mut drawn: Vec<(CoordinatePair, CoordinatePair)> = Vec::with_capacity(segments);
+
+    let mut result = Vec::with_capacity(polylines.len());
+    for polyline in polylines {
+        let mut pieces: Vec<Vec<CoordinatePair>> = Vec::new();
+        let mut current = polyline.points.first().map(|&p| vec![p]);
+        for w in polyline.windows(2) {
+            let (a, b) = (w[0], w[1]);
+            let covered = grid.get(a).iter().any(|&i| {
+                let (c, d) = drawn[i];
+                segment_distance(a, c, d) <= tolerance && segment_distance(b, c, d) <= tolerance
+            });
+            if covered {
+                pieces.extend(current.take().filter(|piece| piece.len() > 1));
+                continue;
+            }
+            current.get_or_insert_with(|| vec![a]).push(b);
+            let min = CoordinatePair::new(a.x.min(b.x) - tolerance, a.y.min(b.y) - tolerance);
+            let max = CoordinatePair::new(a.x.max(b.x) + tolerance, a.y.max(b.y) + tolerance);
+            grid.insert(min, max, drawn.len());
+            drawn.push((a, b));
+        }
+        match current {
+            // Nothing was removed
+            Some(points) if pieces.is_empty() && points.len() == polyline.len() => {
+                result.push(Polyline {
+                    points,
+                    closed: polyline.closed,
+                });
+                continue;
+            }
+            Some(points) if points.len() > 1 => pieces.push(points),
+            _ => {}
+        }
+        result.extend(pieces.into_iter().map(Polyline::from_vec));
+    }
+    result
+}
+
+/// The length of the longer side of the bounding box of a drawing.
+fn extent(polylines: &[Polyline]) -> f64 {
+    bounding_box_of(polylines).map_or(0.0, |bbox| bbox.width().max(bbox.height()))
+}
+
+/// The size of the grid cells for a drawing, at least `min`.
+fn cell_size(polylines: &[Polyline], min: f64) -> f64 {
+    let size = min.max(extent(polylines) / MAX_CELLS);
+    // Without tolerance all points are the same, so any size works
+    if size > 0.0 {
+        size
+    } else {
+        1.0
+    }
+}
+
+/// Join open polylines whose end points are at most
+/// [`tolerance`](JoinOptions::tolerance) apart into longer chains, so that
+/// they are drawn without lifting the pen, e.g. the segments of drawings
+/// made of many small path elements.
+///
+/// Every chain starts with the first polyline that is not yet part of a
+/// chain, and is extended at its end and then at its start by the nearest
+/// matching polylines. The chains keep the order of their first polylines.
+/// Closed polylines are never joined.
+///
+/// ```
+/// use svg2polylines::{
+///     optimize::{join, JoinOptions},
+///     Polyline,
+/// };
+///
+/// let polylines = [
+///     Polyline::from_vec(vec![(0.0, 0.0).into(), (1.0, 0.0).into()]),
+///     Polyline::from_vec(vec![(2.0, 0.0).into(), (1.0, 0.0).into()]),
+/// ];
+/// let joined = join(&polylines, &JoinOptions::default());
+/// assert_eq!(joined.len(), 1);
+/// assert_eq!(joined[0].len(), 3);
+/// ```
+pub fn join(polylines: &[Polyline], options: &JoinOptions) -> Vec<Polyline> {
+    let tolerance = options.tolerance.max(0.0);
+
+    // The start (`false`) and end (`true`) points of the open polylines
+    let mut grid = Grid::new(cell_size(polylines, tolerance));
+    for (i, polyline) in polylines.iter().enumerate() {
+        if let (Some(&first), Some(&last), false) =
+            (polyline.first(), polyline.last(), polyline.closed)
+        {
+            grid.insert(first, first, (i, false));
+            grid.insert(last, last, (i, true));
+        }
+    }
+    let mut used = vec![false; polylines.len()];
+
+    // Find the nearest unused polyline with an end point matching `p`,
+    // where `at_end` selects the end point to match unless polylines may be
+    // reversed
+    let find = |p: CoordinatePair, at_end: bool, used: &[bool]| {
+        grid.near(p)
+            .filter(|&(i, is_end)| !used[i] && (is_end == at_end || options.reverse))
+            .map(|(i, is_end)| {
+                let q = if is_end {
+                    polylines[i].last()
+                } else {
+                    polylines[i].first()
+                };
+                let q = q.copied().unwrap_or(p);
+                ((q.x - p.x).hypot(q.y - p.y), i, is_end)
+            })
+            .filter(|&(distance, _, _)| distance <= tolerance)
+            .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+            .map(|(_, i, is_end)| (i, is_end))
+    };
+
+    let mut result = Vec::new();
+    for (i, polyline) in polylines.iter().enumerate() {
+        if used[i] {
+            continue;
+        }
+        used[i] = true;
+        if polyline.closed || polyline.is_empty() {
+            result.push(Polyline {
+                points: polyline.points.clone(),
+                closed: polyline.closed,
+            });
+            continue;
+        }
+        let mut chain = polyline.points.clone();
+
+        // Append polylines starting at the end of the chain
+        while let Some((j, is_end)) = find(chain[chain.len() - 1], false, &used) {
+            used[j] = true;
+            let points = &polylines[j].points[..];
+            if is_end {
+                chain.extend(points.iter().rev().skip(1));
+            } else {
+                chain.extend(points.iter().skip(1));
+            }
+        }
+
+        // Prepend polylines ending at the start of the chain
+        let mut front: Vec<CoordinatePair> = Vec::new();
+        while let Some((j, is_end)) = find(front.last().copied().unwrap_or(chain[0]), true, &used) {
+            used[j] = true;
+            let points = &polylines[j].points[..];
+            if is_end {
+                front.extend(points.iter().rev().skip(1));
+            } else {
+                front.extend(points.iter().skip(1));
+            }
+        }
+        if !front.is_empty() {
+            front.reverse();
+            front.extend(chain);
+            chain = front;
+        }
+
+        result.push(Polyline::from_vec(chain));
+    }
+    result
+}
+
+/// Distance between two points.
+fn distance(a: CoordinatePair, b: CoordinatePair) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+/// Reorder the polylines to reduce the pen-up travel between them, starting
+/// at the origin.
+///
+/// The next polyline is always the one with the end point nearest to the
+/// current pen position. Open polylines are reversed if their last point is
+/// nearer than their first point; closed polylines are drawn as they are.
+///
+/// The end points are kept in a grid index, so that the nearest one is
+/// usually found without looking at most of the others. This scales to
+/// drawings with tens of thousands of polylines.
+///
+/// ```
+/// use svg2polylines::{optimize::sort_greedy, Polyline};
+///
+/// let mut polylines = vec![
+///     Polyline::from_vec(vec![(10.0, 0.0).into(), (20.0, 0.0).into()]),
+///     Polyline::from_vec(vec![(1.0, 0.0).into(), (0.0, 0.0).into()]),
+/// ];
+/// sort_greedy(&mut polylines);
+/// assert_eq!(polylines[0][0], (0.0, 0.0).into());
+/// assert_eq!(polylines[1][0], (10.0, 0.0).into());
+/// ```
+pub fn sort_greedy(polylines: &mut Vec<Polyline>) {
+    let (empty, remaining): (Vec<Polyline>, Vec<Polyline>) = polylines
+        .drain(..)
+        .partition(|polyline| polyline.is_empty());
+    polylines.extend(empty);
+
+    // The start (`false`) and end (`true`) points of the polylines, the end
+    // points of closed polylines are the same as their start points
+    #[allow(clippy::cast_precision_loss)]
+    let size = extent(&remaining) / (remaining.len() as f64).sqrt();
+    let mut grid = Grid::new(cell_size(&remaining, size));
+    let end_point = |i: usize, is_end: bool| {
+        let points = &remaining[i].points;
+        if is_end {
+            points[points.len() - 1]
+        } else {
+            points[0]
+        }
+    };
+    for (i, polyline) in remaining.iter().enumerate() {
+        grid.insert(end_point(i, false), end_point(i, false), (i, false));
+        if !polyline.closed {
+            grid.insert(end_point(i, true), end_point(i, true), (i, true));
+        }
+    }
+
+    // The first polyline is searched linearly, as the origin may be far
+    // outside of the grid
+    let origin = CoordinatePair::new(0.0, 0.0);
+    let mut next = grid.cells.values().flatten().copied().min_by(|&a, &b| {
+        distance(origin, end_point(a.0, a.1))
+            .total_cmp(&distance(origin, end_point(b.0, b.1)))
+            .then(a.cmp(&b))
+    });
+    let mut order = Vec::with_capacity(remaining.len());
+    while let Some((i, is_end)) = next {
+        grid.remove(end_point(i, false), (i, false));
+        grid.remove(end_point(i, true), (i, true));
+        order.push((i, is_end));
+        let position = end_point(i, !is_end);
+        next = grid.nearest(position, |(j, j_is_end)| {
+            distance(position, end_point(j, j_is_end))
+        });
+    }
+
+    let mut remaining: Vec<Option<Polyline>> = remaining.into_iter().map(Some).collect();
+    for (i, reverse) in order {
+        if let Some(polyline) = remaining[i].take() {
+            polylines.push(if reverse {
+                polyline.reversed()
+            } else {
+                polyline
+            });
+        }
+    }
+}
+
+/// Statistics of a drawing in its current order, see [`stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PlotStats {
+    /// The total length of all polylines, drawn with the pen down.
+    pub drawn_length: f64,
+
+    /// The total pen-up travel from the origin to the first polyline and
+    /// between the polylines.
+    pub travel_length: f64,
+
+    /// The number of times the pen is lifted, i.e. the number of polylines
+    /// that are not empty.
+    pub pen_lifts: usize,
+
+    /// The total number of points.
+    pub points: usize,
+}
+
+/// Return statistics about plotting the polylines in their current order,
+/// e.g. to compare the results of different optimizations.
+///
+/// ```
+/// use svg2polylines::{optimize::stats, Polyline};
+///
+/// let polylines = [
+///     Polyline::from_vec(vec![(0.0, 0.0).into(), (3.0, 4.0).into()]),
+///     Polyline::from_vec(vec![(3.0, 0.0).into(), (0.0, 0.0).into()]),
+/// ];
+/// let stats = stats(&polylines);
+/// assert_eq!(stats.drawn_length, 8.0);
+/// assert_eq!(stats.travel_length, 4.0);
+/// assert_eq!(stats.pen_lifts, 2);
+/// assert_eq!(stats.points, 4);
+/// ```
+pub fn stats(polylines: &[Polyline]) -> PlotStats {
+    let mut stats = PlotStats::default();
+    let mut position = CoordinatePair::new(0.0, 0.0);
+    for polyline in polylines {
+        let (Some(&first), Some(&last)) = (polyline.first(), polyline.last()) else {
+            continue;
+        };
+        stats.drawn_length += polyline.length();
+        stats.travel_length += distance(position, first);
+        stats.pen_lifts += 1;
+        stats.points += polyline.len();
+        position = last;
+    }
+    stats
+}
+
+/// The speeds and delays of a plotter, see [`estimate_time`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlotSpeeds {
+    /// The speed while drawing, in units of the coordinates per second.
+    pub draw_speed: f64,
+
+    /// The speed while travelling with the pen up, in units of the
+    /// coordinates per second.
+    pub travel_speed: f64,
+
+    /// The time to lift the pen.
+    pub pen_up_delay: Duration,
+
+    /// The time to lower the pen.
+    pub pen_down_delay: Duration,
+}
+
+/// Estimate the time to plot the polylines in their current order, e.g. to
+/// show it before sending a job to the machine.
+///
+/// The estimate assumes constant speeds, so acceleration is ignored. It is
+/// based on [`stats`], so it starts at the origin and ends at the last
+/// point.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use svg2polylines::{
+///     optimize::{estimate_time, PlotSpeeds},
+///     Polyline,
+/// };
+///
+/// let polylines = [Polyline::from_vec(vec![(30.0, 40.0).into(), (30.0, 0.0).into()])];
+/// let speeds = PlotSpeeds {
+///     draw_speed: 10.0,
+///     travel_speed: 50.0,
+///     pen_up_delay: Duration::from_millis(200),
+///     pen_down_delay: Duration::from_millis(300),
+/// };
+/// assert_eq!(estimate_time(&polylines, &speeds), Duration::from_millis(5500));
+/// ```
+pub fn estimate_time(polylines: &[Polyline], speeds: &PlotSpeeds) -> Duration {
+    let stats = stats(polylines);
+    let moving = stats.drawn_length / speeds.draw_speed + stats.travel_length / speeds.travel_speed;
+    let delays = (speeds.pen_up_delay + speeds.pen_down_delay)
+        .saturating_mul(u32::try_from(stats.pen_lifts).unwrap_or(u32::MAX));
+    Duration::try_from_secs_f64(moving)
+        .unwrap_or(Duration::MAX)
+        .saturating_add(delays)
+}
+
+/// Options for [`two_opt`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TwoOptOptions {
+    /// The maximum number of passes over all pairs of polylines. The
+    /// optimization stops earlier if a pass finds no improvement.
+    pub max_passes: usize,
+
+    /// The maximum time to spend, checked regularly during a pass. By
+    /// default, the time is not limited.
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for TwoOptOptions {
+    fn default() -> Self {
+        Self {
+            max_passes: 10,
+            time_limit: None,
+        }
+    }
+}
+
+/// Improve the order of the polylines, e.g. after [`sort_greedy`], by
+/// reversing runs of polylines (and the direction of every open polyline in
+/// the run) wherever this reduces the pen-up travel, starting at the origin.
+///
+/// This is the 2-opt heuristic. Every pass is quadratic in the number of
+/// polylines, so use [`TwoOptOptions`] to limit the effort for large
+/// drawings.
+///
+/// ```
+/// use svg2polylines::{
+///     optimize::{two_opt, TwoOptOptions},
+///     Polyline,
+/// };
+///
+/// let mut polylines = vec![
+///     Polyline::from_vec(vec![(0.0, 0.0).into(), (1.0, 0.0).into()]),
+///     Polyline::from_vec(vec![(3.0, 0.0).into(), (4.0, 0.0).into()]),
+///     Polyline::from_vec(vec![(2.0, 0.0).into(), (1.0, 1.0).into()]),
+/// ];
+/// two_opt(&mut polylines, &TwoOptOptions::default());
+/// assert_eq!(polylines[1][0], (1.0, 1.0).into());
+/// ```
+pub fn two_opt(polylines: &mut [Polyline], options: &TwoOptOptions) {
+    let start = Instant::now();
+    let out_of_time = || {
+        options
+            .time_limit
+            .is_some_and(|limit| start.elapsed() >= limit)
+    };
+    let origin = CoordinatePair::new(0.0, 0.0);
+    let first = |p: &Polyline| p.first().copied();
+    let last = |p: &Polyline| p.last().copied();
+    let n = polylines.len();
+
+    for _ in 0..options.max_passes {
+        let mut improved = false;
+        for i in 0..n {
+            if out_of_time() {
+                return;
+            }
+            let before = if i == 0 {
+                Some(origin)
+            } else {
+                last(&polylines[i - 1])
+            };
+            let (Some(before), Some(start_i)) = (before, first(&polylines[i])) else {
+                continue;
+            };
+            for j in i + 1..n {
+                let Some(end_j) = last(&polylines[j]) else {
+                    continue;
+                };
+                let after = polylines.get(j + 1).and_then(first);
+                let old = distance(before, start_i) + after.map_or(0.0, |p| distance(end_j, p));
+                let new = distance(before, end_j) + after.map_or(0.0, |p| distance(start_i, p));
+                // Ignore improvements that are only rounding noise
+                if new < old - 1e-9 {
+                    let run = &mut polylines[i..=j];
+                    run.reverse();
+                    for polyline in run.iter_mut().filter(|p| !p.is_closed()) {
+                        polyline.reverse();
+                    }
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::line;
+
+    #[test]
+    fn test_remove_overlaps() {
+        let mut square = line(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+        square.close();
+        let polylines = [
+            square,
+            // Shares the edge from (2, 0) to (2, 2) with the square
+            line(&[(4.0, 0.0), (2.0, 0.0), (2.0, 1.0), (2.001, 2.0), (4.0, 2.0)]),
+            line(&[(5.0, 5.0)]),
+        ];
+        let result = remove_overlaps(&polylines, 0.01);
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[0], polylines[0]);
+        assert!(result[0].is_closed());
+        assert_eq!(result[1], line(&[(4.0, 0.0), (2.0, 0.0)]));
+        assert_eq!(result[2], line(&[(2.001, 2.0), (4.0, 2.0)]));
+        assert_eq!(result[3], polylines[2]);
+
+        // Without tolerance, the slightly offset segment is kept
+        assert_eq!(remove_overlaps(&polylines, 0.0).len(), 4);
+        assert_eq!(remove_overlaps(&polylines, 0.0)[2].len(), 3);
+    }
+
+    #[test]
+    fn test_sort_greedy() {
+        let mut square = line(&[(5.0, 5.0), (6.0, 5.0), (6.0, 6.0)]);
+        square.close();
+        let mut polylines = vec![
+            line(&[(10.0, 10.0), (11.0, 11.0)]),
+            square,
+            line(&[(4.0, 4.0), (1.0, 1.0)]),
+            Polyline::new(),
+            line(&[(12.0, 12.0), (20.0, 20.0)]),
+        ];
+        sort_greedy(&mut polylines);
+        let starts: Vec<Option<CoordinatePair>> =
+            polylines.iter().map(|p| p.first().copied()).collect();
+        assert_eq!(
+            starts,
+            vec![
+                None,
+                Some((1.0, 1.0).into()),
+                Some((5.0, 5.0).into()),
+                Some((10.0, 10.0).into()),
+                Some((12.0, 12.0).into()),
+            ]
+        );
+        assert!(polylines[2].is_closed());
+    }
+
+    #[test]
+    fn test_sort_greedy_same_points() {
+        let mut polylines = vec![line(&[(5.0, 5.0), (5.0, 5.0)]), line(&[(5.0, 5.0)])];
+        sort_greedy(&mut polylines);
+        assert_eq!(polylines.len(), 2);
+    }
+
+    #[test]
+    fn test_sort_greedy_index() {
+        // Compare with a linear search on pseudo-random lines
+        let mut seed: u32 = 1;
+        let mut random = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            f64::from(seed >> 16) / 655.36
+        };
+        let mut polylines: Vec<Polyline> = (0..300)
+            .map(|_| line(&[(random(), random()), (random(), random())]))
+            .collect();
+        let mut expected = Vec::new();
+        let mut remaining: Vec<Polyline> = polylines
+            .iter()
+            .map(|p| Polyline::from_vec(p.points.clone()))
+            .collect();
+        let mut position = CoordinatePair::new(0.0, 0.0);
+        while !remaining.is_empty() {
+            let (i, reverse) = (0..remaining.len())
+                .flat_map(|i| [(i, false), (i, true)])
+                .min_by(|&(i, a), &(j, b)| {
+                    let end = |i: usize, reverse: bool| {
+                        let p = &remaining[i];
+                        distance(position, if reverse { p[1] } else { p[0] })
+                    };
+                    end(i, a).total_cmp(&end(j, b))
+                })
+                .unwrap();
+            let polyline = remaining.remove(i);
+            let polyline = if reverse {
+                polyline.reversed()
+            } else {
+                polyline
+            };
+            position = polyline[1];
+            expected.push(polyline);
+        }
+        sort_greedy(&mut polylines);
+        assert_eq!(polylines, expected);
+    }
+
+    #[test]
+    fn test_estimate_time() {
+        let speeds = PlotSpeeds {
+            draw_speed: 2.0,
+            travel_speed: 4.0,
+            pen_up_delay: Duration::from_millis(100),
+            pen_down_delay: Duration::from_millis(150),
+        };
+        let polylines = [
+            line(&[(0.0, 4.0), (0.0, 8.0)]),
+            Polyline::new(),
+            line(&[(0.0, 12.0), (0.0, 16.0)]),
+        ];
+        assert_eq!(
+            estimate_time(&polylines, &speeds),
+            Duration::from_millis(6500)
+        );
+        assert_eq!(estimate_time(&[], &speeds), Duration::ZERO);
+
+        let stopped = PlotSpeeds {
+            draw_speed: 0.0,
+            ..speeds
+        };
+        assert_eq!(estimate_time(&polylines, &stopped), Duration::MAX);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_two_opt() {
+        // Greedy ordering leaves a long jump back to the first column
+        let mut polylines = vec![
+            line(&[(0.0, 1.0), (0.0, 2.0)]),
+            line(&[(1.0, 2.0), (1.0, 1.0)]),
+            line(&[(10.0, 1.0), (10.0, 2.0)]),
+            line(&[(0.0, 3.0), (0.0, 4.0)]),
+        ];
+        let before = stats(&polylines);
+        two_opt(&mut polylines, &TwoOptOptions::default());
+        let after = stats(&polylines);
+        assert!(after.travel_length < before.travel_length);
+        assert_eq!(after.drawn_length, before.drawn_length);
+        assert_eq!(polylines[0], line(&[(0.0, 1.0), (0.0, 2.0)]));
+        assert_eq!(polylines[3], line(&[(10.0, 2.0), (10.0, 1.0)]));
+
+        // No time left
+        let mut unchanged = vec![line(&[(5.0, 5.0), (6.0, 6.0)]), line(&[(1.0, 1.0)])];
+        let options = TwoOptOptions {
+            time_limit: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        two_opt(&mut unchanged, &options);
+        assert_eq!(unchanged[1], line(&[(1.0, 1.0)]));
+    }
+
+    #[test]
+    fn test_join() {
+        let square = || {
+            let mut square = line(&[(5.0, 5.0), (6.0, 5.0), (6.0, 6.0)]);
+            square.close();
+            square
+        };
+        let polylines = [
+            line(&[(1.0, 0.0), (2.0, 0.0)]),
+            square(),
+            line(&[(2.0, 1.0), (2.001, 0.0)]),
+            line(&[(0.0, 0.0), (1.0, 0.0)]),
+            line(&[(2.0, 1.0), (3.0, 3.0)]),
+        ];
+        let options = JoinOptions::default();
+        assert_eq!(
+            join(&polylines, &options),
+            vec![
+                line(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 1.0), (3.0, 3.0)]),
+                square(),
+            ]
+        );
+
+        let options = JoinOptions {
+            reverse: false,
+            ..options
+        };
+        assert_eq!(
+            join(&polylines, &options),
+            vec![
+                line(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)]),
+                square(),
+                line(&[(2.0, 1.0), (2.001, 0.0)]),
+                line(&[(2.0, 1.0), (3.0, 3.0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_join_same_points() {
+        let polylines = [line(&[(5.0, 5.0), (5.0, 5.0)]), line(&[(5.0, 5.0)])];
+        let options = JoinOptions {
+            tolerance: 0.0,
+            ..JoinOptions::default()
+        };
+        assert_eq!(join(&polylines, &options), vec![polylines[0].clone()]);
+    }
+}
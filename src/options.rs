@@ -0,0 +1,296 @@
+use crate::{CancelToken, FontOptions, Limits, Unit, ViewportClip};
+
+/// Options controlling how an SVG document is converted to polylines.
+///
+/// Construct it with struct update syntax, so that new options can be added
+/// without breaking your code:
+///
+/// ```
+/// use svg2polylines::ParseOptions;
+///
+/// let options = ParseOptions {
+///     tolerance: 0.05,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ParseOptions {
+    /// The flattening tolerance. See [`parse`](crate::parse) for details.
+    pub tolerance: f64,
+
+    /// Whether and how to preprocess / simplify the SVG with usvg before
+    /// parsing.
+    pub preprocess: Preprocess,
+
+    /// The fonts used to convert text when preprocessing, in addition to the
+    /// font database of the usvg options.
+    pub fonts: FontOptions,
+
+    /// What to do with geometry outside of the document viewport.
+    ///
+    /// The viewport is given by the `viewBox` of the root element or, if
+    /// there is none, by its `width` and `height` (only if they are unitless
+    /// or in `px`). Documents without a viewport are never clipped.
+    ///
+    /// If this is [`ViewportClip::Off`], [`parse_document`](crate::parse_document)
+    /// reports polylines outside of the viewport as warnings instead.
+    pub viewport_clip: ViewportClip,
+
+    /// Whether to repair common well-formedness problems (unescaped
+    /// ampersands, missing namespace declarations, stray or missing closing
+    /// tags) instead of failing.
+    ///
+    /// Repairs are reported as warnings by
+    /// [`parse_document`](crate::parse_document).
+    pub recover: bool,
+
+    /// How to handle unsupported path segments and transforms. Unknown
+    /// elements are always ignored.
+    pub unsupported: UnsupportedPolicy,
+
+    /// Whether to skip paths with invalid path data instead of failing.
+    ///
+    /// Skipped paths are reported as
+    /// [`Warning::InvalidPath`](crate::Warning::InvalidPath) by
+    /// [`parse_document`](crate::parse_document). Together with `recover`
+    /// and [`UnsupportedPolicy::Warn`], this converts as much of a broken
+    /// document as possible.
+    pub lenient: bool,
+
+    /// Whether to fail with an [`Error::Strict`](crate::Error::Strict) if the
+    /// document contains anything that cannot be converted faithfully with
+    /// these options, e.g. unsupported elements, invalid transforms, ignored
+    /// clip paths or hidden paths that would be plotted.
+    ///
+    /// The check runs on the original document and overrides `lenient` and
+    /// [`UnsupportedPolicy`].
+    pub strict: bool,
+
+    /// Limits on the size and complexity of the document, e.g. for
+    /// documents uploaded by untrusted users. By default, there are no
+    /// limits.
+    pub limits: Limits,
+
+    /// A token to abort the conversion from another thread with
+    /// [`Error::Cancelled`](crate::Error::Cancelled), e.g. when the user of a
+    /// GUI closes the document.
+    ///
+    /// The token is checked between paths and between the segments of a
+    /// path, but not while preprocessing.
+    pub cancel: Option<CancelToken>,
+
+    /// Whether to drop paths that have neither a fill nor a stroke, like
+    /// construction lines (`fill:none;stroke:none`) left by vector editors.
+    pub skip_invisible: bool,
+
+    /// Only convert the Inkscape layers with these names (their
+    /// `inkscape:label`), e.g. to plot the layer of a single pen.
+    ///
+    /// Sublayers are kept or dropped together with their parent layer.
+    /// Elements outside of any layer are always kept. By default, all layers
+    /// are converted.
+    pub layers: Option<Vec<String>>,
+
+    /// Only convert the elements matching this selector and their content,
+    /// e.g. `#outline, .cut`.
+    ///
+    /// Selectors are comma separated lists of simple selectors: A type
+    /// selector (or `*`), an id and any number of classes.
+    pub include: Option<String>,
+
+    /// Don't convert the elements matching this selector and their content.
+    /// See `include` for the supported selectors.
+    pub exclude: Option<String>,
+
+    /// Whether to split paths with a `stroke-dasharray` into their dashes
+    /// (respecting `stroke-dashoffset`). By default, dash patterns are
+    /// ignored and paths are converted as solid lines.
+    pub dashes: bool,
+
+    /// Whether to replace stroked paths by the outlines of their strokes,
+    /// according to `stroke-width`, `stroke-linecap`, `stroke-linejoin` and
+    /// `stroke-miterlimit`, e.g. to fill or engrave thick lines. Paths
+    /// without a stroke are converted unchanged.
+    pub outline_strokes: bool,
+
+    /// Whether to clip paths by the `<clipPath>` elements referenced with
+    /// `clip-path`, and to skip the content of clip paths. By default, clip
+    /// paths are ignored and their content is converted like other shapes.
+    pub clip_paths: bool,
+
+    /// Whether and how to apply the `<mask>` elements referenced with `mask`,
+    /// and to skip the content of masks. By default, masks are ignored.
+    ///
+    /// Masks are approximated as clips: A shape inside of a mask is part of
+    /// the clip region if its luminance times its opacity is at least this
+    /// threshold, between 0 and 1 (e.g. 0.5). Darker shapes cut holes into
+    /// the shapes below them.
+    pub mask_threshold: Option<f64>,
+
+    /// Whether to fill shapes whose `fill` references a `<pattern>` with the
+    /// tiled pattern content, clipped to the shape, and to skip the content
+    /// of patterns. By default, pattern content is converted once like other
+    /// shapes.
+    pub patterns: bool,
+
+    /// Whether to place the content of the `<marker>` elements referenced
+    /// with `marker-start`, `marker-mid` and `marker-end` (e.g. arrowheads)
+    /// at the vertices of paths, and to skip the content of markers. By
+    /// default, marker content is converted once like other shapes.
+    pub markers: bool,
+
+    /// What to do with points whose coordinates are not finite (NaN or
+    /// infinite), e.g. after transforms with huge scale factors.
+    pub non_finite: NonFinitePolicy,
+
+    /// Whether to remove repeated points, i.e. zero-length segments, from
+    /// every path. Paths that consist of a single repeated point are kept as
+    /// single points.
+    pub remove_duplicates: bool,
+
+    /// Whether to merge consecutive collinear segments of every path, e.g.
+    /// runs of `H` and `V` segments or flattened curves that are almost
+    /// straight, see [`Polyline::merge_collinear`](crate::Polyline::merge_collinear).
+    ///
+    /// The value is the maximum distance of the removed points from the
+    /// merged segments, in the same units as `tolerance`.
+    pub merge_collinear: Option<f64>,
+
+    /// The unit of the output coordinates.
+    ///
+    /// The conversion uses the `width`, `height`, `viewBox` and
+    /// `preserveAspectRatio` attributes of the root element. The default
+    /// keeps the user units of the document.
+    pub unit: Unit,
+
+    /// The resolution used to convert pixels and unitless lengths into
+    /// physical units.
+    pub dpi: f64,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.15,
+            preprocess: Preprocess::default(),
+            fonts: FontOptions::default(),
+            viewport_clip: ViewportClip::Off,
+            recover: false,
+            unsupported: UnsupportedPolicy::Error,
+            lenient: false,
+            strict: false,
+            limits: Limits::default(),
+            cancel: None,
+            skip_invisible: false,
+            layers: None,
+            include: None,
+            exclude: None,
+            dashes: false,
+            outline_strokes: false,
+            clip_paths: false,
+            mask_threshold: None,
+            patterns: false,
+            markers: false,
+            non_finite: NonFinitePolicy::Keep,
+            remove_duplicates: false,
+            merge_collinear: None,
+            unit: Unit::User,
+            dpi: 96.0,
+        }
+    }
+}
+
+/// Whether to preprocess the SVG with usvg before parsing.
+///
+/// Preprocessing resolves styles, `<use>` references and text (see
+/// [`FontOptions`]), and converts all shapes into paths. The usvg options
+/// control e.g. the DPI, the default size and whether named groups are kept.
+///
+/// Preprocessing requires the `usvg` feature (enabled by default). Without
+/// it, the only variant is `No`.
+///
+/// ```
+/// use svg2polylines::{ParseOptions, Preprocess};
+///
+/// # #[cfg(feature = "usvg")] {
+/// let mut usvg_options = usvg::Options::default();
+/// usvg_options.dpi = 72.0;
+/// let options = ParseOptions {
+///     preprocess: Preprocess::Usvg(usvg_options),
+///     ..Default::default()
+/// };
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub enum Preprocess {
+    /// Parse the SVG as it is.
+    No,
+
+    /// Preprocess with the given usvg options.
+    #[cfg(feature = "usvg")]
+    Usvg(usvg::Options),
+}
+
+impl Default for Preprocess {
+    #[cfg(feature = "usvg")]
+    fn default() -> Self {
+        Self::Usvg(usvg::Options::default())
+    }
+
+    #[cfg(not(feature = "usvg"))]
+    fn default() -> Self {
+        Self::No
+    }
+}
+
+impl From<bool> for Preprocess {
+    /// Preprocess with the default usvg options if `true` (and the `usvg`
+    /// feature is enabled).
+    fn from(enabled: bool) -> Self {
+        if enabled {
+            Self::default()
+        } else {
+            Self::No
+        }
+    }
+}
+
+/// What to do with points whose coordinates are not finite, see
+/// [`ParseOptions::non_finite`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinitePolicy {
+    /// Keep the points.
+    #[default]
+    Keep,
+
+    /// Remove the points, and polylines without any finite points.
+    Drop,
+
+    /// Fail with an [`Error::Polyline`](crate::Error::Polyline).
+    Error,
+}
+
+/// What to do when the parser encounters an unsupported feature, e.g. a
+/// smooth quadratic path segment or an invalid transform (without
+/// preprocessing).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnsupportedPolicy {
+    /// Fail with an error.
+    #[default]
+    Error,
+
+    /// Skip the unsupported segment (or the path with an unsupported
+    /// transform) and record a
+    /// [`Warning::Unsupported`](crate::Warning::Unsupported).
+    Warn,
+
+    /// Skip the unsupported segment (or the path with an unsupported
+    /// transform) silently.
+    Skip,
+
+    /// Replace the unsupported feature by an approximation: Unsupported
+    /// segments are replaced by straight lines, unsupported transforms are
+    /// ignored.
+    Approximate,
+}
@@ -0,0 +1,397 @@
+//! Expand strokes into their outlines.
+
+use std::{f64::consts::PI, str::FromStr};
+
+use lyon_geom::euclid::{
+    default::{Point2D, Vector2D},
+    Angle,
+};
+use quick_xml::events::BytesStart;
+use svgtypes::{Length, LengthUnit};
+
+use crate::{css::StyleSheet, style, CoordinatePair, Polyline};
+
+type Point = Point2D<f64>;
+type Vector = Vector2D<f64>;
+
+/// The shape at the ends of open polylines (`stroke-linecap`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+/// The shape at the corners of polylines (`stroke-linejoin`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// The stroke properties of an element, inherited from its ancestors.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StrokeStyle {
+    width: Option<String>,
+    linecap: Option<String>,
+    linejoin: Option<String>,
+    miterlimit: Option<String>,
+}
+
+impl StrokeStyle {
+    /// Apply the stroke properties of an element to the style inherited from
+    /// its parent.
+    pub(crate) fn inherit(&self, e: &BytesStart, sheet: &StyleSheet) -> Self {
+        let value = |name, inherited: &Option<String>| {
+            style::property(e, name, sheet).or_else(|| inherited.clone())
+        };
+        Self {
+            width: value("stroke-width", &self.width),
+            linecap: value("stroke-linecap", &self.linecap),
+            linejoin: value("stroke-linejoin", &self.linejoin),
+            miterlimit: value("stroke-miterlimit", &self.miterlimit),
+        }
+    }
+
+    /// Return the stroke geometry. Invalid values are replaced by their
+    /// initial values.
+    pub(crate) fn stroke(&self) -> Stroke {
+        let width = match self.width.as_deref().map(Length::from_str) {
+            Some(Ok(Length {
+                number,
+                unit: LengthUnit::None | LengthUnit::Px,
+            })) if number >= 0.0 => number,
+            _ => 1.0,
+        };
+        Stroke {
+            width,
+            cap: match self.linecap.as_deref().map(str::trim) {
+                Some("round") => LineCap::Round,
+                Some("square") => LineCap::Square,
+                _ => LineCap::Butt,
+            },
+            join: match self.linejoin.as_deref().map(str::trim) {
+                Some("round") => LineJoin::Round,
+                Some("bevel") => LineJoin::Bevel,
+                _ => LineJoin::Miter,
+            },
+            miter_limit: self
+                .miterlimit
+                .as_deref()
+                .and_then(|limit| limit.trim().parse().ok())
+                .filter(|&limit| limit >= 1.0)
+                .unwrap_or(4.0),
+        }
+    }
+}
+
+/// The geometry of a stroke.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Stroke {
+    width: f64,
+    cap: LineCap,
+    join: LineJoin,
+    miter_limit: f64,
+}
+
+/// Append the points of a circular arc around `center`, from the direction
+/// `from` counterclockwise (in a y-up coordinate system) by `sweep` radians.
+/// The start point is not included.
+fn arc(points: &mut Vec<Point>, center: Point, from: Vector, sweep: f64, tolerance: f64) {
+    let radius = from.length();
+    let max_step = if tolerance < radius {
+        2.0 * (1.0 - tolerance / radius).acos()
+    } else {
+        PI / 2.0
+    };
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let steps = ((sweep.abs() / max_step).ceil() as usize).max(1);
+    let start = from.angle_from_x_axis().radians;
+    for i in 1..=steps {
+        #[allow(clippy::cast_precision_loss)]
+        let angle = start + sweep * i as f64 / steps as f64;
+        points.push(center + Vector::from_angle_and_length(Angle::radians(angle), radius));
+    }
+}
+
+impl Stroke {
+    pub(crate) fn width(&self) -> f64 {
+        self.width
+    }
+
+    /// Return the offset of the left side of a path, where left is the
+    /// direction of the normal `(-dy, dx)`.
+    ///
+    /// For closed paths, `points` must not repeat the first point, and the
+    /// result starts and ends at the join of the first point.
+    fn side(&self, points: &[Point], closed: bool, tolerance: f64) -> Vec<Point> {
+        let half = self.width / 2.0;
+        let n = points.len();
+        let segments = if closed { n } else { n - 1 };
+        let direction = |i: usize| (points[(i + 1) % n] - points[i]).normalize();
+        let normal = |i: usize| {
+            let d = direction(i);
+            Vector::new(-d.y, d.x) * half
+        };
+
+        let mut result = Vec::new();
+        if !closed {
+            result.push(points[0] + normal(0));
+        }
+        let vertices = if closed { 0..n } else { 1..n - 1 };
+        for i in vertices {
+            let (before, after) = ((i + segments - 1) % segments, i % segments);
+            let p = points[i];
+            let (n0, n1) = (normal(before), normal(after));
+            let turn = direction(before).cross(direction(after));
+            let cos = direction(before).dot(direction(after));
+            // sin(θ / 2), where θ is the angle between the segments
+            let sin = (0.5 + 0.5 * cos).sqrt();
+            if turn > 0.0 {
+                // Inner corner: Use the intersection of the offset lines, if
+                // it is within both segments
+                let miter = (n0 + n1).normalize() * (half / sin);
+                let reach = miter.dot(direction(before)).abs();
+                let length = |i: usize| (points[(i + 1) % n] - points[i]).length();
+                if reach <= length(before) && reach <= length(after) {
+                    result.push(p + miter);
+                } else {
+                    result.extend([p + n0, p, p + n1]);
+                }
+                continue;
+            }
+            // The miter length relative to the stroke width is 1 / sin(θ / 2)
+            match self.join {
+                LineJoin::Miter if sin > 0.0 && 1.0 / sin <= self.miter_limit => {
+                    result.push(p + (n0 + n1).normalize() * (half / sin));
+                }
+                LineJoin::Round => {
+                    result.push(p + n0);
+                    arc(&mut result, p, n0, n0.angle_to(n1).radians, tolerance);
+                }
+                LineJoin::Miter | LineJoin::Bevel => result.extend([p + n0, p + n1]),
+            }
+        }
+        if closed {
+            let first = result[0];
+            result.push(first);
+        } else {
+            result.push(points[n - 1] + normal(n - 2));
+        }
+        result
+    }
+
+    /// Append a cap at the end `p` of a path with the end direction `d`.
+    fn cap(&self, result: &mut Vec<Point>, p: Point, d: Vector, tolerance: f64) {
+        let half = self.width / 2.0;
+        let normal = Vector::new(-d.y, d.x) * half;
+        match self.cap {
+            LineCap::Butt => {}
+            LineCap::Round => {
+                arc(result, p, normal, -PI, tolerance);
+                result.pop();
+            }
+            LineCap::Square => {
+                result.push(p + normal + d * half);
+                result.push(p - normal + d * half);
+            }
+        }
+    }
+
+    /// Return the outline of the stroke of a polyline.
+    ///
+    /// Open polylines result in a single closed polyline, closed polylines in
+    /// two (one for each side). Overlaps at sharp inner corners are not
+    /// removed.
+    pub(crate) fn outline(&self, polyline: &Polyline, tolerance: f64) -> Vec<Polyline> {
+        if self.width <= 0.0 {
+            return Vec::new();
+        }
+        let mut points: Vec<Point> = polyline.iter().map(|p| Point::new(p.x, p.y)).collect();
+        points.dedup();
+        let closed = polyline.is_closed() && points.len() > 2;
+        if closed {
+            points.pop();
+        }
+        let to_polyline = |points: Vec<Point>| {
+            let mut outline = Polyline::from_vec(
+                points
+                    .into_iter()
+                    .map(|p| CoordinatePair::new(p.x, p.y))
+                    .collect(),
+            );
+            outline.close();
+            outline
+        };
+
+        match points.len() {
+            0 => Vec::new(),
+            // A single point only has caps
+            1 => {
+                let p = points[0];
+                let half = self.width / 2.0;
+                let mut result = match self.cap {
+                    LineCap::Butt => return Vec::new(),
+                    LineCap::Round => {
+                        let mut result = vec![p + Vector::new(half, 0.0)];
+                        arc(&mut result, p, Vector::new(half, 0.0), 2.0 * PI, tolerance);
+                        result.pop();
+                        result
+                    }
+                    LineCap::Square => vec![
+                        p + Vector::new(-half, -half),
+                        p + Vector::new(half, -half),
+                        p + Vector::new(half, half),
+                        p + Vector::new(-half, half),
+                    ],
+                };
+                result.push(result[0]);
+                vec![to_polyline(result)]
+            }
+            _ if closed => {
+                let outer = self.side(&points, true, tolerance);
+                points.reverse();
+                let inner = self.side(&points, true, tolerance);
+                vec![to_polyline(outer), to_polyline(inner)]
+            }
+            n => {
+                let mut result = self.side(&points, false, tolerance);
+                let end = (points[n - 1] - points[n - 2]).normalize();
+                self.cap(&mut result, points[n - 1], end, tolerance);
+                points.reverse();
+                result.extend(self.side(&points, false, tolerance));
+                let start = (points[n - 1] - points[n - 2]).normalize();
+                self.cap(&mut result, points[n - 1], start, tolerance);
+                result.push(result[0]);
+                vec![to_polyline(result)]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::line;
+
+    fn assert_close(actual: &[Polyline], expected: &[Polyline]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (a, b) in actual.iter().zip(expected) {
+            assert_eq!(a.len(), b.len(), "{a:?}");
+            for (p, q) in a.iter().zip(b.iter()) {
+                assert!(
+                    (p.x - q.x).abs() < 1e-9 && (p.y - q.y).abs() < 1e-9,
+                    "{:?}",
+                    a
+                );
+            }
+        }
+    }
+
+    fn stroke(width: f64, cap: LineCap, join: LineJoin) -> Stroke {
+        Stroke {
+            width,
+            cap,
+            join,
+            miter_limit: 4.0,
+        }
+    }
+
+    #[test]
+    fn test_stroke_style() {
+        let sheet = StyleSheet::default();
+        let e = BytesStart::borrowed(
+            br#"g stroke-width="2" style="stroke-linecap:round;stroke-linejoin:bevel""#,
+            1,
+        );
+        let parent = StrokeStyle::default().inherit(&e, &sheet);
+        let e = BytesStart::borrowed(br#"path stroke-miterlimit="0.5""#, 4);
+        assert_eq!(
+            parent.inherit(&e, &sheet).stroke(),
+            Stroke {
+                width: 2.0,
+                cap: LineCap::Round,
+                join: LineJoin::Bevel,
+                miter_limit: 4.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_outline_open() {
+        let polyline = line(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+        let outline = stroke(2.0, LineCap::Butt, LineJoin::Miter).outline(&polyline, 0.1);
+        assert!(outline[0].is_closed());
+        assert_close(
+            &stroke(2.0, LineCap::Butt, LineJoin::Miter).outline(&polyline, 0.1),
+            &[line(&[
+                (0.0, 1.0),
+                (9.0, 1.0),
+                (9.0, 10.0),
+                (11.0, 10.0),
+                (11.0, -1.0),
+                (0.0, -1.0),
+                (0.0, 1.0),
+            ])],
+        );
+        assert_close(
+            &stroke(2.0, LineCap::Square, LineJoin::Bevel).outline(&polyline, 0.1),
+            &[line(&[
+                (0.0, 1.0),
+                (9.0, 1.0),
+                (9.0, 10.0),
+                (9.0, 11.0),
+                (11.0, 11.0),
+                (11.0, 10.0),
+                (11.0, 0.0),
+                (10.0, -1.0),
+                (0.0, -1.0),
+                (-1.0, -1.0),
+                (-1.0, 1.0),
+                (0.0, 1.0),
+            ])],
+        );
+    }
+
+    #[test]
+    fn test_outline_closed() {
+        let mut square = line(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        square.close();
+        assert_close(
+            &stroke(2.0, LineCap::Butt, LineJoin::Miter).outline(&square, 0.1),
+            &[
+                line(&[(1.0, 1.0), (9.0, 1.0), (9.0, 9.0), (1.0, 9.0), (1.0, 1.0)]),
+                line(&[
+                    (-1.0, 11.0),
+                    (11.0, 11.0),
+                    (11.0, -1.0),
+                    (-1.0, -1.0),
+                    (-1.0, 11.0),
+                ]),
+            ],
+        );
+
+        // Returning to the start without closing the path results in caps
+        let triangle = line(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 0.0)]);
+        let outlines = stroke(2.0, LineCap::Butt, LineJoin::Miter).outline(&triangle, 0.1);
+        assert_eq!(outlines.len(), 1);
+        assert!(outlines[0].is_closed());
+    }
+
+    #[test]
+    fn test_outline_round() {
+        let outlines =
+            stroke(2.0, LineCap::Round, LineJoin::Round).outline(&line(&[(5.0, 5.0)]), 0.01);
+        assert_eq!(outlines.len(), 1);
+        assert!(outlines[0].len() > 8);
+        assert!(outlines[0].iter().all(|p| {
+            let r = (p.x - 5.0).hypot(p.y - 5.0);
+            (r - 1.0).abs() < 1e-9
+        }));
+        assert_eq!(
+            stroke(2.0, LineCap::Butt, LineJoin::Round).outline(&line(&[(5.0, 5.0)]), 0.01),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Pack several drawings onto a sheet.
+
+use std::cmp::Ordering;
+
+use lyon_geom::euclid::Transform2D;
+
+use crate::{bounding_box_of, Polyline};
+
+/// Pack the bounding boxes of several drawings onto a sheet of the given
+/// dimensions.
+///
+/// This uses simple shelf packing: drawings are sorted by height and placed
+/// in rows from left to right, starting at the origin. Neighbouring drawings
+/// are separated by `spacing`.
+///
+/// The result contains one entry per drawing, in the order of `drawings`.
+/// Each entry is the translation that moves the drawing to its place on the
+/// sheet, or `None` if it did not fit. Empty drawings are placed at the
+/// origin without taking up space.
+pub fn pack(
+    drawings: &[Vec<Polyline>],
+    sheet_width: f64,
+    sheet_height: f64,
+    spacing: f64,
+) -> Vec<Option<Transform2D<f64, f64, f64>>> {
+    let mut placements = vec![None; drawings.len()];
+
+    let mut items = Vec::with_capacity(drawings.len());
+    for (i, drawing) in drawings.iter().enumerate() {
+        match bounding_box_of(drawing) {
+            Some(bounds) => items.push((i, bounds)),
+            None => placements[i] = Some(Transform2D::identity()),
+        }
+    }
+    items.sort_by(|(_, a), (_, b)| {
+        b.height()
+            .partial_cmp(&a.height())
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let (mut x, mut y, mut shelf_height) = (0.0_f64, 0.0_f64, 0.0_f64);
+    for (i, bounds) in items {
+        let (width, height) = (bounds.width(), bounds.height());
+        if x > 0.0 && x + width > sheet_width {
+            // Start a new shelf
+            x = 0.0;
+            y += shelf_height + spacing;
+            shelf_height = 0.0;
+        }
+        if x + width > sheet_width || y + height > sheet_height {
+            continue;
+        }
+        placements[i] = Some(Transform2D::translation(x - bounds.min_x, y - bounds.min_y));
+        x += width + spacing;
+        shelf_height = shelf_height.max(height);
+    }
+
+    placements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoordinatePair;
+
+    fn rect(x: f64, y: f64, w: f64, h: f64) -> Vec<Polyline> {
+        vec![Polyline::from_vec(vec![
+            CoordinatePair::new(x, y),
+            CoordinatePair::new(x + w, y + h),
+        ])]
+    }
+
+    #[test]
+    fn test_pack_shelves() {
+        let drawings = vec![
+            rect(5.0, 5.0, 40.0, 10.0),
+            rect(0.0, 0.0, 40.0, 20.0),
+            rect(-3.0, 0.0, 40.0, 5.0),
+        ];
+        let placements = pack(&drawings, 100.0, 100.0, 2.0);
+        // The tallest drawing comes first, then the next one on the same shelf
+        assert_eq!(placements[1], Some(Transform2D::translation(0.0, 0.0)));
+        assert_eq!(placements[0], Some(Transform2D::translation(37.0, -5.0)));
+        // The third drawing does not fit on the first shelf anymore
+        assert_eq!(placements[2], Some(Transform2D::translation(3.0, 22.0)));
+    }
+
+    #[test]
+    fn test_pack_does_not_fit() {
+        let drawings = vec![rect(0.0, 0.0, 40.0, 40.0), rect(0.0, 0.0, 200.0, 1.0)];
+        let placements = pack(&drawings, 100.0, 50.0, 0.0);
+        assert!(placements[0].is_some());
+        assert!(placements[1].is_none());
+    }
+}
@@ -0,0 +1,212 @@
+//! Split Inkscape multipage documents into one drawing per page.
+
+use log::trace;
+use lyon_geom::euclid::Transform2D;
+use quick_xml::events::Event;
+
+use crate::{
+    bounding_box_of, data_uri, document::parse_root, parse_with_options, style, unit, BoundingBox,
+    Error, ParseOptions, Polyline,
+};
+
+/// A page of a document, together with the polylines drawn on it.
+#[derive(Debug, PartialEq)]
+pub struct Page {
+    /// The `id` of the `inkscape:page` element, if present.
+    pub id: Option<String>,
+
+    /// The `inkscape:label` of the page, if present.
+    pub label: Option<String>,
+
+    /// The area of the page in document coordinates, converted to
+    /// [`ParseOptions::unit`].
+    pub bounds: BoundingBox,
+
+    /// The polylines on this page, in page-local coordinates (relative to the
+    /// top left corner of the page).
+    pub polylines: Vec<Polyline>,
+}
+
+/// Read the `inkscape:page` elements (Inkscape 1.2+) of a document.
+fn read_pages(svg: &str) -> Result<Vec<Page>, Error> {
+    let mut reader = quick_xml::Reader::from_str(svg);
+    reader.trim_text(true);
+
+    let mut pages = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e) | Event::Empty(ref e)) if e.name() == b"inkscape:page" => {
+                let number = |key: &[u8]| {
+                    style::attribute(e, key).and_then(|v| v.trim().parse::<f64>().ok())
+                };
+                let (x, y) = (number(b"x").unwrap_or(0.0), number(b"y").unwrap_or(0.0));
+                let (Some(width), Some(height)) = (number(b"width"), number(b"height")) else {
+                    trace!("read_pages: Ignore page without dimensions");
+                    continue;
+                };
+                pages.push(Page {
+                    id: style::attribute(e, b"id"),
+                    label: style::attribute(e, b"inkscape:label"),
+                    bounds: BoundingBox::new(x, y, x + width, y + height),
+                    polylines: Vec::new(),
+                });
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => return Err(Error::SvgParse(e.to_string())),
+        }
+        buf.clear();
+    }
+    Ok(pages)
+}
+
+/// Apply a transform without rotation to a bounding box.
+fn transform_bounds(bounds: BoundingBox, t: &Transform2D<f64, f64, f64>) -> BoundingBox {
+    let a = t.transform_point((bounds.min_x, bounds.min_y).into());
+    let b = t.transform_point((bounds.max_x, bounds.max_y).into());
+    BoundingBox::new(a.x.min(b.x), a.y.min(b.y), a.x.max(b.x), a.y.max(b.y))
+}
+
+/// Parse an SVG string and split the polylines by page.
+///
+/// Inkscape 1.2 and later can store several pages in one document, as
+/// `inkscape:page` elements that define rectangles in a shared coordinate
+/// system. Every polyline is assigned to the first page that contains the
+/// center of its bounding box and translated into the coordinate system of
+/// that page. Polylines that are not on any page are dropped.
+///
+/// Pages may lie outside of the `viewBox`, so `options.viewport_clip` should
+/// be left at [`ViewportClip::Off`](crate::ViewportClip::Off).
+///
+/// Documents without pages result in a single page covering the viewport
+/// (or all polylines, if the document has no usable viewport).
+pub fn parse_pages(svg: &str, options: &ParseOptions) -> Result<Vec<Page>, Error> {
+    let svg = data_uri::decode_input(svg)?;
+    let mut pages = read_pages(&svg)?;
+    let polylines = parse_with_options(&svg, options)?;
+    trace!("parse_pages: {} pages", pages.len());
+
+    // The pages are given in user units, but the polylines in the output unit
+    let root = parse_root(&svg)?;
+    let unit_transform = unit::transform(&root, options.unit, options.dpi);
+    let to_unit = |bounds| {
+        unit_transform
+            .as_ref()
+            .map_or(bounds, |t| transform_bounds(bounds, t))
+    };
+    for page in &mut pages {
+        page.bounds = to_unit(page.bounds);
+    }
+
+    if pages.is_empty() {
+        let bounds = match root.viewport() {
+            Some(viewport) => to_unit(viewport),
+            None => {
+                bounding_box_of(&polylines).unwrap_or_else(|| BoundingBox::new(0.0, 0.0, 0.0, 0.0))
+            }
+        };
+        let t = Transform2D::translation(-bounds.min_x, -bounds.min_y);
+        return Ok(vec![Page {
+            id: None,
+            label: None,
+            bounds,
+            polylines: polylines.into_iter().map(|p| p.transform(t)).collect(),
+        }]);
+    }
+
+    for polyline in polylines {
+        let Some(b) = polyline.bounding_box() else {
+            continue;
+        };
+        let center = (b.min_x + b.width() * 0.5, b.min_y + b.height() * 0.5).into();
+        if let Some(page) = pages.iter_mut().find(|page| page.bounds.contains(center)) {
+            let t = Transform2D::translation(-page.bounds.min_x, -page.bounds.min_y);
+            page.polylines.push(polyline.transform(t));
+        }
+    }
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{flatten::line, Preprocess, Unit};
+
+    #[test]
+    fn test_parse_pages() {
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg"
+                 xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape"
+                 xmlns:sodipodi="http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd"
+                 viewBox="0 0 100 100">
+                <sodipodi:namedview id="namedview">
+                    <inkscape:page x="0" y="0" width="100" height="100" id="page1"/>
+                    <inkscape:page x="110" y="0" width="100" height="100" id="page2"
+                                   inkscape:label="Back"/>
+                </sodipodi:namedview>
+                <path d="M 10,10 L 20,20"/>
+                <path d="M 120,10 L 130,20"/>
+                <path d="M 105,10 L 105,20"/>
+            </svg>
+        "#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let pages = parse_pages(input, &options).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].id.as_deref(), Some("page1"));
+        assert_eq!(
+            pages[0].polylines,
+            vec![line(&[(10.0, 10.0), (20.0, 20.0)])]
+        );
+        assert_eq!(pages[1].label.as_deref(), Some("Back"));
+        assert_eq!(pages[1].bounds, BoundingBox::new(110.0, 0.0, 210.0, 100.0));
+        assert_eq!(
+            pages[1].polylines,
+            vec![line(&[(10.0, 10.0), (20.0, 20.0)])]
+        );
+    }
+
+    #[test]
+    fn test_parse_pages_unit() {
+        // One user unit is 0.1 mm
+        let input = r#"
+            <svg xmlns="http://www.w3.org/2000/svg"
+                 xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape"
+                 width="20mm" height="10mm" viewBox="0 0 200 100">
+                <inkscape:page x="0" y="0" width="100" height="100"/>
+                <inkscape:page x="100" y="0" width="100" height="100"/>
+                <path d="M 150,10 L 160,20"/>
+            </svg>
+        "#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            unit: Unit::Millimeters,
+            ..Default::default()
+        };
+        let pages = parse_pages(input, &options).unwrap();
+        assert_eq!(pages[1].bounds, BoundingBox::new(10.0, 0.0, 20.0, 10.0));
+        assert!(pages[0].polylines.is_empty());
+        assert_eq!(pages[1].polylines.len(), 1);
+        let p = pages[1].polylines[0][1];
+        assert!((p.x - 6.0).abs() < 1e-9 && (p.y - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_pages_single() {
+        let input = r#"<svg viewBox="10 10 50 50"><path d="M 20,20 L 30,30"/></svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let pages = parse_pages(input, &options).unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].bounds, BoundingBox::new(10.0, 10.0, 60.0, 60.0));
+        assert_eq!(
+            pages[0].polylines,
+            vec![line(&[(10.0, 10.0), (20.0, 20.0)])]
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Fit drawings onto standard paper sizes.
+
+use lyon_geom::euclid::Transform2D;
+
+use crate::{bounding_box_of, transform_all, Polyline};
+
+/// A paper size. All dimensions are in millimeters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaperSize {
+    A5,
+    A4,
+    A3,
+    A2,
+    Letter,
+    /// A custom size, given as width and height in portrait orientation.
+    Custom {
+        width: f64,
+        height: f64,
+    },
+}
+
+impl PaperSize {
+    /// Return `(width, height)` in portrait orientation.
+    pub fn dimensions(self) -> (f64, f64) {
+        match self {
+            Self::A5 => (148.0, 210.0),
+            Self::A4 => (210.0, 297.0),
+            Self::A3 => (297.0, 420.0),
+            Self::A2 => (420.0, 594.0),
+            Self::Letter => (215.9, 279.4),
+            Self::Custom { width, height } => (width, height),
+        }
+    }
+}
+
+/// The orientation of the paper.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+/// Options for [`fit_to_paper`].
+#[derive(Debug, Clone)]
+pub struct FitOptions {
+    /// The paper size.
+    pub paper: PaperSize,
+
+    /// The paper orientation.
+    pub orientation: Orientation,
+
+    /// Distance between the drawing and every edge of the paper (in mm).
+    pub margin: f64,
+
+    /// Whether drawings smaller than the paper are scaled up to fill it. If
+    /// this is `false`, small drawings are only centered.
+    pub upscale: bool,
+
+    /// Whether to rotate the drawing by 90° if it can be scaled larger that
+    /// way, e.g. to fit a landscape drawing onto portrait paper. The
+    /// rotation is included in the transform returned by [`fit_to_paper`].
+    pub auto_rotate: bool,
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        Self {
+            paper: PaperSize::A4,
+            orientation: Orientation::Portrait,
+            margin: 10.0,
+            upscale: true,
+            auto_rotate: false,
+        }
+    }
+}
+
+/// Scale the polylines uniformly to fit onto the paper (inside the margins)
+/// and center them.
+///
+/// The polylines are modified in place. The return value is the transform
+/// that was applied, so that it can be reused for related geometry. Empty
+/// drawings are left unchanged.
+pub fn fit_to_paper(
+    polylines: &mut [Polyline],
+    options: &FitOptions,
+) -> Transform2D<f64, f64, f64> {
+    let (paper_width, paper_height) = match (options.paper.dimensions(), options.orientation) {
+        ((w, h), Orientation::Portrait) => (w.min(h), w.max(h)),
+        ((w, h), Orientation::Landscape) => (w.max(h), w.min(h)),
+    };
+    let rotate = options.auto_rotate
+        && bounding_box_of(polylines).is_some_and(|bounds| {
+            let available = (
+                paper_width - 2.0 * options.margin,
+                paper_height - 2.0 * options.margin,
+            );
+            let (width, height) = (bounds.width(), bounds.height());
+            uniform_scale((height, width), available) > uniform_scale((width, height), available)
+        });
+
+    // Rotate clockwise as displayed, with the y axis pointing down
+    let rotation = if rotate {
+        Transform2D::new(0.0, 1.0, -1.0, 0.0, 0.0, 0.0)
+    } else {
+        Transform2D::identity()
+    };
+    if rotate {
+        transform_all(polylines, rotation);
+    }
+    let transform = fit(
+        polylines,
+        (paper_width, paper_height),
+        options.margin,
+        true,
+        options.upscale,
+    );
+    rotation.then(&transform)
+}
+
+/// The largest uniform scale of a drawing of the given size that fits into
+/// the available size, infinite if the drawing has no size.
+fn uniform_scale(
+    (width, height): (f64, f64),
+    (available_width, available_height): (f64, f64),
+) -> f64 {
+    let scale = |size: f64, available: f64| {
+        if size > 0.0 {
+            available.max(0.0) / size
+        } else {
+            f64::INFINITY
+        }
+    };
+    scale(width, available_width).min(scale(height, available_height))
+}
+
+/// Scale the polylines to fit into a `width` by `height` rectangle at the
+/// origin, e.g. the bed of a plotter, keeping a distance of `margin` to every
+/// edge, and center them.
+///
+/// If `keep_aspect` is `true`, the polylines are scaled uniformly. Otherwise
+/// they are stretched to fill the available width and height.
+///
+/// The polylines are modified in place. The return value is the transform
+/// that was applied. Empty drawings are left unchanged.
+///
+/// ```
+/// use svg2polylines::{paper::fit_to, Polyline};
+///
+/// let mut polylines = vec![Polyline::from_vec(vec![(0.0, 0.0).into(), (10.0, 5.0).into()])];
+/// fit_to(&mut polylines, 100.0, 100.0, 10.0, true);
+/// assert_eq!(polylines[0].to_vec(), vec![(10.0, 30.0).into(), (90.0, 70.0).into()]);
+/// ```
+pub fn fit_to(
+    polylines: &mut [Polyline],
+    width: f64,
+    height: f64,
+    margin: f64,
+    keep_aspect: bool,
+) -> Transform2D<f64, f64, f64> {
+    fit(polylines, (width, height), margin, keep_aspect, true)
+}
+
+fn fit(
+    polylines: &mut [Polyline],
+    (target_width, target_height): (f64, f64),
+    margin: f64,
+    keep_aspect: bool,
+    upscale: bool,
+) -> Transform2D<f64, f64, f64> {
+    let Some(bounds) = bounding_box_of(polylines) else {
+        return Transform2D::identity();
+    };
+    let available_width = (target_width - 2.0 * margin).max(0.0);
+    let available_height = (target_height - 2.0 * margin).max(0.0);
+    let (width, height) = (bounds.width(), bounds.height());
+
+    // Degenerate dimensions (e.g. a horizontal line) don't constrain the scale
+    let scale_x = if width > 0.0 {
+        available_width / width
+    } else {
+        f64::INFINITY
+    };
+    let scale_y = if height > 0.0 {
+        available_height / height
+    } else {
+        f64::INFINITY
+    };
+    // An unconstrained scale falls back to the other scale, or to 1
+    let finite = |scale: f64, other: f64| match (scale.is_finite(), other.is_finite()) {
+        (true, _) => scale,
+        (false, true) => other,
+        (false, false) => 1.0,
+    };
+    let (mut scale_x, mut scale_y) = if keep_aspect {
+        let scale = finite(scale_x.min(scale_y), 1.0);
+        (scale, scale)
+    } else {
+        (finite(scale_x, scale_y), finite(scale_y, scale_x))
+    };
+    if !upscale {
+        scale_x = scale_x.min(1.0);
+        scale_y = scale_y.min(1.0);
+    }
+
+    let offset_x = margin + (available_width - width * scale_x) / 2.0;
+    let offset_y = margin + (available_height - height * scale_y) / 2.0;
+    let transform = Transform2D::new(
+        scale_x,
+        0.0,
+        0.0,
+        scale_y,
+        offset_x - bounds.min_x * scale_x,
+        offset_y - bounds.min_y * scale_y,
+    );
+    transform_all(polylines, transform);
+    transform
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoordinatePair;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_fit_to_paper() {
+        let mut polylines = vec![Polyline::from_vec(vec![
+            (100.0, 100.0).into(),
+            (150.0, 120.0).into(),
+        ])];
+        let options = FitOptions {
+            paper: PaperSize::Custom {
+                width: 70.0,
+                height: 120.0,
+            },
+            orientation: Orientation::Landscape,
+            ..Default::default()
+        };
+        let transform = fit_to_paper(&mut polylines, &options);
+        // 100 x 50 mm are available, the drawing is limited by its width
+        assert_eq!(transform.m11, 2.0);
+        assert_eq!(
+            polylines[0].to_vec(),
+            vec![(10.0, 15.0).into(), (110.0, 55.0).into()]
+        );
+        assert_eq!(PaperSize::A4.dimensions(), (210.0, 297.0));
+    }
+
+    #[test]
+    fn test_fit_to_paper_without_upscale() {
+        let mut polylines = vec![Polyline::from_vec(vec![
+            (0.0, 0.0).into(),
+            (10.0, 0.0).into(),
+        ])];
+        let options = FitOptions {
+            paper: PaperSize::Custom {
+                width: 100.0,
+                height: 50.0,
+            },
+            margin: 0.0,
+            upscale: false,
+            ..Default::default()
+        };
+        fit_to_paper(&mut polylines, &options);
+        // Portrait orientation swaps the custom dimensions
+        assert_eq!(
+            polylines[0].to_vec(),
+            vec![(20.0, 50.0).into(), (30.0, 50.0).into()]
+        );
+        assert_eq!(fit_to_paper(&mut [], &options), Transform2D::identity());
+    }
+
+    #[test]
+    fn test_fit_to_paper_auto_rotate() {
+        let drawing = || {
+            vec![Polyline::from_vec(vec![
+                (0.0, 0.0).into(),
+                (40.0, 20.0).into(),
+            ])]
+        };
+        let options = FitOptions {
+            paper: PaperSize::Custom {
+                width: 20.0,
+                height: 40.0,
+            },
+            margin: 0.0,
+            auto_rotate: true,
+            ..Default::default()
+        };
+        let mut polylines = drawing();
+        let transform = fit_to_paper(&mut polylines, &options);
+        assert_eq!(
+            polylines[0].to_vec(),
+            vec![(20.0, 0.0).into(), (0.0, 40.0).into()]
+        );
+        let mut p = CoordinatePair::new(40.0, 20.0);
+        p.transform(transform);
+        assert_eq!(p, polylines[0][1]);
+
+        // Landscape paper fits without rotation
+        let options = FitOptions {
+            orientation: Orientation::Landscape,
+            ..options
+        };
+        let mut polylines = drawing();
+        fit_to_paper(&mut polylines, &options);
+        assert_eq!(
+            polylines[0].to_vec(),
+            vec![(0.0, 0.0).into(), (40.0, 20.0).into()]
+        );
+    }
+
+    #[test]
+    fn test_fit_to() {
+        let polylines = || {
+            vec![Polyline::from_vec(vec![
+                (-10.0, 0.0).into(),
+                (10.0, 10.0).into(),
+            ])]
+        };
+        let mut stretched = polylines();
+        let transform = fit_to(&mut stretched, 50.0, 30.0, 5.0, false);
+        assert_eq!((transform.m11, transform.m22), (2.0, 2.0));
+        assert_eq!(
+            stretched[0].to_vec(),
+            vec![(5.0, 5.0).into(), (45.0, 25.0).into()]
+        );
+
+        let mut stretched = polylines();
+        fit_to(&mut stretched, 50.0, 50.0, 5.0, false);
+        assert_eq!(
+            stretched[0].to_vec(),
+            vec![(5.0, 5.0).into(), (45.0, 45.0).into()]
+        );
+
+        let mut line = vec![Polyline::from_vec(vec![
+            (0.0, 3.0).into(),
+            (4.0, 3.0).into(),
+        ])];
+        fit_to(&mut line, 10.0, 10.0, 1.0, false);
+        assert_eq!(line[0].to_vec(), vec![(1.0, 5.0).into(), (9.0, 5.0).into()]);
+    }
+}
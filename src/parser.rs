@@ -0,0 +1,112 @@
+//! A reusable parser that keeps its configuration between calls.
+
+#[cfg(feature = "usvg")]
+use crate::Preprocess;
+use crate::{
+    parse_document, parse_with_warnings, Document, Error, FontOptions, ParseOptions, Polyline,
+};
+
+/// A configured parser for converting many documents.
+///
+/// The free functions like [`parse_with_options`](crate::parse_with_options)
+/// load the fonts of [`ParseOptions::fonts`] on every call. A `Parser` does
+/// this once, so that e.g. a conversion service can load its
+/// fonts at startup and share a single parser between all requests and
+/// threads.
+///
+/// ```
+/// use svg2polylines::{ParseOptions, Parser, Preprocess};
+///
+/// let parser = Parser::new(ParseOptions {
+///     preprocess: Preprocess::No,
+///     ..Default::default()
+/// });
+/// let polylines = parser
+///     .parse(r#"<svg xmlns="http://www.w3.org/2000/svg"><path d="M 0,0 10,10"/></svg>"#)
+///     .unwrap();
+/// assert_eq!(polylines.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Parser {
+    options: ParseOptions,
+}
+
+impl Parser {
+    /// Create a parser with the given options, loading the fonts of
+    /// `options.fonts` into the usvg options.
+    pub fn new(mut options: ParseOptions) -> Self {
+        #[cfg(feature = "usvg")]
+        if let Preprocess::Usvg(usvg_options) = &mut options.preprocess {
+            options.fonts.load(usvg_options);
+        }
+        options.fonts = FontOptions::default();
+        Self { options }
+    }
+
+    /// Create a parser with the given options, preprocessing with the given
+    /// usvg options.
+    ///
+    /// This is a shortcut for setting `options.preprocess` to
+    /// [`Preprocess::Usvg`].
+    #[cfg(feature = "usvg")]
+    pub fn with_usvg_options(mut options: ParseOptions, usvg_options: usvg::Options) -> Self {
+        options.preprocess = Preprocess::Usvg(usvg_options);
+        Self::new(options)
+    }
+
+    /// Return the parse options. The fonts are already loaded into the usvg
+    /// options.
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Return the usvg options, or `None` if preprocessing is disabled.
+    #[cfg(feature = "usvg")]
+    pub fn usvg_options(&self) -> Option<&usvg::Options> {
+        match &self.options.preprocess {
+            Preprocess::No => None,
+            Preprocess::Usvg(usvg_options) => Some(usvg_options),
+        }
+    }
+
+    /// Parse an SVG string into a vector of [`Polyline`]s.
+    pub fn parse(&self, svg: &str) -> Result<Vec<Polyline>, Error> {
+        parse_with_warnings(svg, &self.options, &mut Vec::new())
+    }
+
+    /// Parse an SVG string into a [`Document`].
+    pub fn parse_document(&self, svg: &str) -> Result<Document, Error> {
+        parse_document(svg, &self.options)
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new(ParseOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Preprocess;
+
+    #[test]
+    fn test_parser_reuse() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Parser>();
+
+        let parser = Parser::new(ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        });
+        let first = parser
+            .parse(r#"<svg><path d="M 0,0 L 10,10"/></svg>"#)
+            .unwrap();
+        let second = parser
+            .parse_document(r#"<svg width="20" height="20"><path d="M 0,0 L 10,10"/></svg>"#)
+            .unwrap();
+        assert_eq!(first, second.polylines);
+        assert!(parser.parse("<svg><path d=\"M 0,0 X\"/></svg>").is_err());
+    }
+}
@@ -0,0 +1,326 @@
+//! Fill shapes with the tiles of a `<pattern>`.
+
+use std::{collections::HashMap, str::FromStr};
+
+use log::trace;
+use lyon_geom::euclid::{Transform2D, Vector2D};
+use quick_xml::events::{BytesStart, Event};
+use svgtypes::{Length, LengthUnit};
+
+use crate::{
+    bounding_box_of,
+    clip::{clip_to_rings, MaskMode},
+    css::StyleSheet,
+    parse_path, parse_transform, shape, style,
+    transform::{ComposedTransform, TransformStack},
+    CoordinatePair, Error, ParseOptions, Polyline,
+};
+
+/// Maximum number of tiles per shape, to protect against tiny tiles in huge
+/// shapes.
+const MAX_TILES: f64 = 100_000.0;
+
+/// A `<pattern>` element.
+#[derive(Debug)]
+pub(crate) struct Pattern {
+    /// The tile rectangle `(x, y, width, height)`.
+    tile: (f64, f64, f64, f64),
+
+    /// Whether the tile is relative to the bounding box of the filled shape
+    /// (`patternUnits`, the default).
+    bounding_box_units: bool,
+
+    /// Whether the content is relative to the bounding box of the filled
+    /// shape (`patternContentUnits`).
+    content_bounding_box_units: bool,
+
+    /// The `patternTransform`.
+    transform: Transform2D<f64, f64, f64>,
+
+    /// The polylines of the content, relative to the tile origin.
+    content: Vec<Polyline>,
+}
+
+/// The pattern fill of an element and whether it is rendered.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PatternState {
+    /// The id of the pattern referenced by the `fill` property.
+    pub(crate) fill: Option<String>,
+
+    /// Whether the element is part of a `<pattern>` and thus not rendered
+    /// directly.
+    pub(crate) hidden: bool,
+}
+
+impl PatternState {
+    /// Apply the `fill` property of an element to the state inherited from
+    /// its parent.
+    pub(crate) fn inherit(&self, e: &BytesStart, sheet: &StyleSheet) -> Self {
+        Self {
+            fill: match style::property(e, "fill", sheet) {
+                Some(value) => style::reference(&value),
+                None => self.fill.clone(),
+            },
+            hidden: self.hidden || e.name() == b"pattern",
+        }
+    }
+}
+
+/// Parse a length attribute in user units, or as a fraction for bounding box
+/// units.
+fn length(e: &BytesStart, key: &[u8], bounding_box_units: bool) -> Option<f64> {
+    match Length::from_str(&style::attribute(e, key)?).ok()? {
+        Length {
+            number,
+            unit: LengthUnit::None | LengthUnit::Px,
+        } => Some(number),
+        Length {
+            number,
+            unit: LengthUnit::Percent,
+        } if bounding_box_units => Some(number / 100.0),
+        _ => None,
+    }
+}
+
+fn transformed(polyline: &Polyline, t: Transform2D<f64, f64, f64>) -> Polyline {
+    polyline.clone().transform(t)
+}
+
+fn rectangle(x: f64, y: f64, width: f64, height: f64) -> Vec<CoordinatePair> {
+    vec![
+        CoordinatePair::new(x, y),
+        CoordinatePair::new(x + width, y),
+        CoordinatePair::new(x + width, y + height),
+        CoordinatePair::new(x, y + height),
+    ]
+}
+
+impl Pattern {
+    /// Tile the pattern across the region bounded by the rings (even-odd
+    /// rule) and return the content clipped to the region.
+    ///
+    /// The rings and the result are in the user space of the filled shape.
+    pub(crate) fn fill(&self, rings: &[Polyline]) -> Vec<Polyline> {
+        let Some(bbox) = bounding_box_of(rings) else {
+            return Vec::new();
+        };
+        let (bbox_width, bbox_height) = (bbox.width(), bbox.height());
+        let (x, y, width, height) = if self.bounding_box_units {
+            let (x, y, width, height) = self.tile;
+            (
+                bbox.min_x + x * bbox_width,
+                bbox.min_y + y * bbox_height,
+                width * bbox_width,
+                height * bbox_height,
+            )
+        } else {
+            self.tile
+        };
+        if width <= 0.0 || height <= 0.0 {
+            return Vec::new();
+        }
+
+        // The tiles covering the region in the pattern coordinate system
+        let Some(inverse) = self.transform.inverse() else {
+            return Vec::new();
+        };
+        let local: Vec<Polyline> = rings
+            .iter()
+            .map(|ring| transformed(ring, inverse))
+            .collect();
+        let Some(local_bbox) = bounding_box_of(&local) else {
+            return Vec::new();
+        };
+        let (first_column, last_column) = (
+            ((local_bbox.min_x - x) / width).floor(),
+            ((local_bbox.max_x - x) / width).ceil(),
+        );
+        let (first_row, last_row) = (
+            ((local_bbox.min_y - y) / height).floor(),
+            ((local_bbox.max_y - y) / height).ceil(),
+        );
+        if (last_column - first_column) * (last_row - first_row) > MAX_TILES {
+            trace!("pattern::fill: Skip pattern with too many tiles");
+            return Vec::new();
+        }
+
+        let content_transform = if self.content_bounding_box_units {
+            Transform2D::scale(bbox_width, bbox_height)
+        } else {
+            Transform2D::identity()
+        };
+        let mut tiles = Vec::new();
+        let mut row = first_row;
+        while row < last_row {
+            let mut column = first_column;
+            while column < last_column {
+                let (tile_x, tile_y) = (x + column * width, y + row * height);
+                let t = content_transform.then_translate(Vector2D::new(tile_x, tile_y));
+                let content: Vec<Polyline> = self
+                    .content
+                    .iter()
+                    .map(|polyline| transformed(polyline, t))
+                    .collect();
+                // The content overflowing the tile is hidden
+                let tile = rectangle(tile_x, tile_y, width, height);
+                tiles.extend(clip_to_rings(&content, &[&tile], MaskMode::KeepInside));
+                column += 1.0;
+            }
+            row += 1.0;
+        }
+
+        let tiles: Vec<Polyline> = tiles
+            .into_iter()
+            .map(|polyline| polyline.transform(self.transform))
+            .collect();
+        let rings: Vec<&[CoordinatePair]> = rings.iter().map(|ring| ring.as_slice()).collect();
+        clip_to_rings(&tiles, &rings, MaskMode::KeepInside)
+    }
+}
+
+/// Collect the `<pattern>` elements of a document by their id.
+///
+/// The `viewBox` of patterns and references to other patterns with `href`
+/// are not supported.
+pub(crate) fn collect(
+    svg: &str,
+    options: &ParseOptions,
+) -> Result<HashMap<String, Pattern>, Error> {
+    let mut reader = quick_xml::Reader::from_str(svg);
+    reader.trim_text(true);
+
+    let mut patterns = HashMap::new();
+    // The id and contents of the current pattern, and the transforms of the
+    // open elements inside of it
+    let mut current: Option<(String, Pattern)> = None;
+    let mut transforms = TransformStack::default();
+    let mut depth = 0;
+    let mut buf = Vec::new();
+    loop {
+        let (e, is_start): (BytesStart, bool) = match reader.read_event(&mut buf) {
+            Ok(Event::Start(e)) => (e, true),
+            Ok(Event::Empty(e)) => (e, false),
+            Ok(Event::End(_)) if current.is_some() => {
+                transforms.pop();
+                depth -= 1;
+                if depth == 0 {
+                    if let Some((id, pattern)) = current.take() {
+                        trace!("pattern::collect: Found pattern \"{id}\"");
+                        patterns.insert(id, pattern);
+                    }
+                }
+                buf.clear();
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {
+                buf.clear();
+                continue;
+            }
+            Err(e) => return Err(Error::SvgParse(e.to_string())),
+        };
+        if let Some((_, pattern)) = &mut current {
+            let composed = transforms.compose(&e);
+            if let Some(data) = shape::path_data(&e) {
+                if let Some(t) = composed.resolve(options.unsupported, &mut Vec::new())? {
+                    let polylines = parse_path(
+                        &data,
+                        options.tolerance,
+                        options.unsupported,
+                        &mut Vec::new(),
+                    )?;
+                    pattern
+                        .content
+                        .extend(polylines.into_iter().map(|polyline| polyline.transform(t)));
+                }
+            }
+            if is_start {
+                transforms.push(composed);
+                depth += 1;
+            }
+        } else if e.name() == b"pattern" && is_start {
+            let Some(id) = style::attribute(&e, b"id") else {
+                buf.clear();
+                continue;
+            };
+            let bounding_box_units =
+                style::attribute(&e, b"patternUnits").as_deref() != Some("userSpaceOnUse");
+            let transform = style::attribute(&e, b"patternTransform")
+                .and_then(|expr| {
+                    let t = parse_transform(&expr).ok();
+                    if t.is_none() {
+                        trace!("pattern::collect: Ignore invalid pattern transform");
+                    }
+                    t
+                })
+                .unwrap_or_else(Transform2D::identity);
+            let attribute = |key| length(&e, key, bounding_box_units).unwrap_or(0.0);
+            current = Some((
+                id,
+                Pattern {
+                    tile: (
+                        attribute(b"x"),
+                        attribute(b"y"),
+                        attribute(b"width"),
+                        attribute(b"height"),
+                    ),
+                    bounding_box_units,
+                    content_bounding_box_units: style::attribute(&e, b"patternContentUnits")
+                        .as_deref()
+                        == Some("objectBoundingBox"),
+                    transform,
+                    content: Vec::new(),
+                },
+            ));
+            // The pattern transform applies to the tiles, not to the content
+            transforms = TransformStack::default();
+            transforms.push(ComposedTransform::default());
+            depth = 1;
+        }
+        buf.clear();
+    }
+    Ok(patterns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::line;
+
+    #[test]
+    fn test_collect() {
+        let svg = r#"<svg>
+            <pattern id="a" width="0.5" height="25%" patternTransform="rotate(90)">
+                <path d="M 0,0 L 1,1" transform="scale(2)"/>
+            </pattern>
+            <pattern id="b" patternUnits="userSpaceOnUse" width="10%" height="5"/>
+        </svg>"#;
+        let patterns = collect(svg, &ParseOptions::default()).unwrap();
+        assert_eq!(patterns.len(), 1);
+        let a = &patterns["a"];
+        assert_eq!(a.tile, (0.0, 0.0, 0.5, 0.25));
+        assert!(a.bounding_box_units);
+        assert!(!a.content_bounding_box_units);
+        assert_eq!(a.content, vec![line(&[(0.0, 0.0), (2.0, 2.0)])]);
+    }
+
+    #[test]
+    fn test_fill() {
+        // Vertical lines every 2 units, clipped to a triangle
+        let pattern = Pattern {
+            tile: (0.0, 0.0, 2.0, 10.0),
+            bounding_box_units: false,
+            content_bounding_box_units: false,
+            transform: Transform2D::identity(),
+            content: vec![line(&[(1.0, 0.0), (1.0, 10.0)])],
+        };
+        let triangle = line(&[(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)]);
+        assert_eq!(
+            pattern.fill(&[triangle]),
+            vec![
+                line(&[(1.0, 0.0), (1.0, 3.0)]),
+                line(&[(3.0, 0.0), (3.0, 1.0)]),
+            ]
+        );
+    }
+}
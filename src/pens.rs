@@ -0,0 +1,239 @@
+//! Reduce the stroke colors of a drawing to the pens of a plotter.
+
+use std::cmp::Reverse;
+
+use svgtypes::Color;
+
+use crate::PolylineWithMetadata;
+
+/// Maximum number of k-means iterations.
+const MAX_ITERATIONS: usize = 32;
+
+/// The color space in which colors are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// Euclidean distance of the sRGB components.
+    Rgb,
+    /// Euclidean distance in CIELAB, which matches the perceived difference
+    /// much better.
+    #[default]
+    Lab,
+}
+
+/// The result of [`quantize`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pens {
+    /// The color of every pen. Every pen color is one of the input colors.
+    pub colors: Vec<Color>,
+
+    /// The pen index of every polyline, in input order. Polylines without a
+    /// stroke color are not assigned to a pen.
+    pub assignments: Vec<Option<usize>>,
+}
+
+/// Convert a color to coordinates in the given color space.
+#[allow(clippy::many_single_char_names)]
+fn coordinates(color: Color, space: ColorSpace) -> [f64; 3] {
+    let rgb = [color.red, color.green, color.blue].map(f64::from);
+    if space == ColorSpace::Rgb {
+        return rgb;
+    }
+    let [r, g, b] = rgb.map(|c| {
+        let c = c / 255.0;
+        if c <= 0.040_45 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    // Linear sRGB to XYZ, relative to the D65 white point
+    let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / 0.950_47;
+    let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175_0 * b;
+    let z = (0.019_333_9 * r + 0.119_192_0 * g + 0.950_304_1 * b) / 1.088_83;
+    let f = |t: f64| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum()
+}
+
+/// Return the index of the point closest to `p`.
+fn nearest(p: [f64; 3], points: &[[f64; 3]]) -> usize {
+    (0..points.len())
+        .min_by(|&a, &b| distance(p, points[a]).total_cmp(&distance(p, points[b])))
+        .unwrap_or(0)
+}
+
+/// Cluster the weighted points into `k` clusters and return the centers.
+///
+/// The centers are initialized deterministically with the first point and
+/// then repeatedly with the point farthest away from all centers.
+fn kmeans(points: &[[f64; 3]], weights: &[f64], k: usize) -> Vec<[f64; 3]> {
+    let mut centers = vec![points[0]];
+    while centers.len() < k {
+        let farthest = |p: &&[f64; 3]| distance(**p, centers[nearest(**p, &centers)]);
+        let Some(&next) = points
+            .iter()
+            .max_by(|a, b| farthest(a).total_cmp(&farthest(b)))
+        else {
+            break;
+        };
+        centers.push(next);
+    }
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut sums = vec![([0.0; 3], 0.0); centers.len()];
+        for (&p, &weight) in points.iter().zip(weights) {
+            let (sum, total) = &mut sums[nearest(p, &centers)];
+            for i in 0..3 {
+                sum[i] += p[i] * weight;
+            }
+            *total += weight;
+        }
+        let updated: Vec<[f64; 3]> = sums
+            .iter()
+            .zip(&centers)
+            .map(|(&(sum, total), &center)| {
+                if total > 0.0 {
+                    sum.map(|s| s / total)
+                } else {
+                    center
+                }
+            })
+            .collect();
+        if updated == centers {
+            break;
+        }
+        centers = updated;
+    }
+    centers
+}
+
+/// Return the index of the pen whose color is closest to the stroke color of
+/// every polyline.
+///
+/// Use this to assign polylines to the pens of a given carousel. Polylines
+/// without a stroke color are not assigned to a pen.
+pub fn assign(
+    polylines: &[PolylineWithMetadata],
+    colors: &[Color],
+    space: ColorSpace,
+) -> Vec<Option<usize>> {
+    let pens: Vec<[f64; 3]> = colors.iter().map(|&c| coordinates(c, space)).collect();
+    polylines
+        .iter()
+        .map(|p| {
+            let stroke = p.stroke.filter(|_| !pens.is_empty())?;
+            Some(nearest(coordinates(stroke, space), &pens))
+        })
+        .collect()
+}
+
+/// Cluster the stroke colors of the polylines into at most `pens` colors and
+/// assign every polyline to the pen with the closest color.
+///
+/// Colors are weighted by the number of polylines using them, and the first
+/// pen gets the most frequent color. The result is deterministic.
+pub fn quantize(polylines: &[PolylineWithMetadata], pens: usize, space: ColorSpace) -> Pens {
+    // Distinct stroke colors with the number of polylines, most frequent first
+    let mut colors: Vec<(Color, usize)> = Vec::new();
+    for stroke in polylines.iter().filter_map(|p| p.stroke) {
+        match colors.iter_mut().find(|(color, _)| *color == stroke) {
+            Some((_, count)) => *count += 1,
+            None => colors.push((stroke, 1)),
+        }
+    }
+    colors.sort_by_key(|&(_, count)| Reverse(count));
+
+    let palette: Vec<Color> = if colors.len() <= pens {
+        colors.iter().map(|&(color, _)| color).collect()
+    } else if pens == 0 {
+        Vec::new()
+    } else {
+        let points: Vec<[f64; 3]> = colors
+            .iter()
+            .map(|&(color, _)| coordinates(color, space))
+            .collect();
+        #[allow(clippy::cast_precision_loss)]
+        let weights: Vec<f64> = colors.iter().map(|&(_, count)| count as f64).collect();
+        // Use the input color closest to every center, so that the pens
+        // have colors of the drawing
+        let mut palette = Vec::new();
+        for center in kmeans(&points, &weights, pens) {
+            let color = colors[nearest(center, &points)].0;
+            if !palette.contains(&color) {
+                palette.push(color);
+            }
+        }
+        palette
+    };
+
+    Pens {
+        assignments: assign(polylines, &palette, space),
+        colors: palette,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Polyline;
+
+    fn stroked(stroke: Option<Color>) -> PolylineWithMetadata {
+        PolylineWithMetadata {
+            polyline: Polyline::new(),
+            id: None,
+            class: None,
+            layer: None,
+            stroke,
+            fill: None,
+            stroke_width: 1.0,
+            text: None,
+        }
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_coordinates() {
+        let lab = |r, g, b| coordinates(Color::new_rgb(r, g, b), ColorSpace::Lab);
+        let [l, a, b] = lab(255, 255, 255);
+        assert!((l - 100.0).abs() < 1e-3 && a.abs() < 1e-3 && b.abs() < 1e-3);
+        assert_eq!(lab(0, 0, 0), [0.0, 0.0, 0.0]);
+        let [l, a, b] = lab(255, 0, 0);
+        assert!((l - 53.24).abs() < 0.01 && (a - 80.09).abs() < 0.01 && (b - 67.20).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_quantize() {
+        let red = Color::new_rgb(255, 0, 0);
+        let dark_red = Color::new_rgb(200, 0, 0);
+        let blue = Color::new_rgb(0, 0, 255);
+        let polylines: Vec<_> = [Some(dark_red), Some(red), None, Some(blue), Some(red)]
+            .iter()
+            .copied()
+            .map(stroked)
+            .collect();
+        for space in [ColorSpace::Rgb, ColorSpace::Lab] {
+            let pens = quantize(&polylines, 2, space);
+            assert_eq!(pens.colors, vec![red, blue]);
+            assert_eq!(
+                pens.assignments,
+                vec![Some(0), Some(0), None, Some(1), Some(0)]
+            );
+        }
+        assert_eq!(quantize(&polylines, 5, ColorSpace::Lab).colors.len(), 3);
+        assert_eq!(
+            quantize(&polylines, 0, ColorSpace::Lab).assignments,
+            vec![None; 5]
+        );
+    }
+}
@@ -0,0 +1,415 @@
+//! Chain post-processing stages.
+
+use crate::{
+    centerline::{centerlines, CenterlineOptions},
+    clip::{clip_to_polygon, clip_to_viewport, MaskMode, ViewportClip},
+    jitter::{jitter, JitterOptions},
+    optimize::{join, remove_overlaps, sort_greedy, two_opt, JoinOptions, TwoOptOptions},
+    weave::{weave, WeaveOptions},
+    BoundingBox, CoordinatePair, Error, Polyline,
+};
+
+/// A single post-processing stage of a [`Pipeline`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stage {
+    /// Reduce every polyline to at most `max_points` points, see
+    /// [`Polyline::decimate`].
+    Decimate { max_points: usize },
+
+    /// Remove points that deviate less than `epsilon` from every polyline,
+    /// see [`Polyline::simplify`].
+    Simplify { epsilon: f64 },
+
+    /// Remove repeated points, i.e. zero-length segments, from every
+    /// polyline.
+    Dedupe,
+
+    /// Merge consecutive collinear segments of every polyline, see
+    /// [`Polyline::merge_collinear`].
+    MergeCollinear { tolerance: f64 },
+
+    /// Snap every polyline to a grid with the spacing `step`, see
+    /// [`Polyline::quantize`].
+    Quantize { step: f64 },
+
+    /// Remove segments that are drawn more than once, see
+    /// [`remove_overlaps`](crate::optimize::remove_overlaps).
+    RemoveOverlaps { tolerance: f64 },
+
+    /// Join polylines with coincident end points, see
+    /// [`join`](crate::optimize::join).
+    Join(JoinOptions),
+
+    /// Reorder the polylines to reduce the pen-up travel, see
+    /// [`sort_greedy`](crate::optimize::sort_greedy).
+    SortGreedy,
+
+    /// Improve the order of the polylines, see
+    /// [`two_opt`](crate::optimize::two_opt).
+    TwoOpt(TwoOptOptions),
+
+    /// Replace thin outlines by center lines, see
+    /// [`centerlines`](crate::centerline::centerlines).
+    Centerline(CenterlineOptions),
+
+    /// Clip all polylines to a rectangle.
+    Crop(BoundingBox),
+
+    /// Clip all polylines against a polygon, see
+    /// [`clip_to_polygon`](crate::clip_to_polygon).
+    Mask {
+        polygon: Vec<CoordinatePair>,
+        mode: MaskMode,
+    },
+
+    /// Cut gaps at crossings, see [`weave`](crate::weave::weave).
+    Weave(WeaveOptions),
+
+    /// Give the polylines a hand-drawn look, see
+    /// [`jitter`](crate::jitter::jitter).
+    Jitter(JitterOptions),
+}
+
+impl Stage {
+    /// Check the parameters of the stage.
+    fn validate(&self) -> Result<(), String> {
+        let check = |valid: bool, message: &str| {
+            if valid {
+                Ok(())
+            } else {
+                Err(message.to_string())
+            }
+        };
+        let non_negative = |value: f64| value >= 0.0 && value.is_finite();
+        let positive = |value: f64| value > 0.0 && value.is_finite();
+        match self {
+            Self::Decimate { max_points } => {
+                check(*max_points >= 2, "decimate: max_points must be at least 2")
+            }
+            Self::Simplify { epsilon } => check(
+                non_negative(*epsilon),
+                "simplify: epsilon must be finite and not negative",
+            ),
+            Self::MergeCollinear { tolerance } => check(
+                non_negative(*tolerance),
+                "merge_collinear: tolerance must be finite and not negative",
+            ),
+            Self::Quantize { step } => check(
+                positive(*step),
+                "quantize: step must be finite and positive",
+            ),
+            Self::RemoveOverlaps { tolerance } => check(
+                non_negative(*tolerance),
+                "remove_overlaps: tolerance must be finite and not negative",
+            ),
+            Self::Join(options) => check(
+                non_negative(options.tolerance),
+                "join: tolerance must be finite and not negative",
+            ),
+            Self::Dedupe | Self::SortGreedy | Self::TwoOpt(_) => Ok(()),
+            Self::Centerline(options) => {
+                check(
+                    non_negative(options.max_width),
+                    "centerline: max_width must be finite and not negative",
+                )?;
+                check(
+                    positive(options.spacing),
+                    "centerline: spacing must be finite and positive",
+                )
+            }
+            Self::Crop(rect) => check(
+                non_negative(rect.width()) && non_negative(rect.height()),
+                "crop: the rectangle must be finite and not have a negative size",
+            ),
+            Self::Mask { polygon, .. } => {
+                check(
+                    polygon.len() >= 3,
+                    "mask: the polygon needs at least 3 points",
+                )?;
+                check(
+                    polygon.iter().all(|p| p.x.is_finite() && p.y.is_finite()),
+                    "mask: the polygon must be finite",
+                )
+            }
+            Self::Weave(options) => check(
+                non_negative(options.gap),
+                "weave: gap must be finite and not negative",
+            ),
+            Self::Jitter(options) => {
+                check(
+                    options.amplitude.is_finite(),
+                    "jitter: amplitude must be finite",
+                )?;
+                check(
+                    positive(options.frequency),
+                    "jitter: frequency must be finite and positive",
+                )?;
+                check(options.passes >= 1, "jitter: passes must be at least 1")
+            }
+        }
+    }
+
+    fn apply(&self, polylines: Vec<Polyline>) -> Vec<Polyline> {
+        match self {
+            Self::Decimate { max_points } => {
+                polylines.iter().map(|p| p.decimate(*max_points)).collect()
+            }
+            Self::Simplify { epsilon } => polylines.iter().map(|p| p.simplify(*epsilon)).collect(),
+            Self::Dedupe => {
+                let mut polylines = polylines;
+                for polyline in &mut polylines {
+                    polyline.dedup();
+                }
+                polylines
+            }
+            Self::MergeCollinear { tolerance } => polylines
+                .iter()
+                .map(|p| p.merge_collinear(*tolerance))
+                .collect(),
+            Self::Quantize { step } => polylines.iter().map(|p| p.quantize(*step)).collect(),
+            Self::RemoveOverlaps { tolerance } => remove_overlaps(&polylines, *tolerance),
+            Self::Join(options) => join(&polylines, options),
+            Self::SortGreedy => {
+                let mut polylines = polylines;
+                sort_greedy(&mut polylines);
+                polylines
+            }
+            Self::TwoOpt(options) => {
+                let mut polylines = polylines;
+                two_opt(&mut polylines, options);
+                polylines
+            }
+            Self::Centerline(options) => centerlines(&polylines, options),
+            Self::Crop(rect) => clip_to_viewport(polylines, rect, ViewportClip::Clip),
+            Self::Mask { polygon, mode } => clip_to_polygon(&polylines, polygon, *mode),
+            Self::Weave(options) => weave(&polylines, options),
+            Self::Jitter(options) => jitter(&polylines, options),
+        }
+    }
+}
+
+/// Collects the stages of a [`Pipeline`].
+///
+/// ```
+/// use svg2polylines::{BoundingBox, PipelineBuilder};
+///
+/// let pipeline = PipelineBuilder::new()
+///     .decimate(100)
+///     .crop(BoundingBox::new(0.0, 0.0, 210.0, 297.0))
+///     .build()
+///     .unwrap();
+/// let result = pipeline.apply(Vec::new());
+/// assert!(result.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PipelineBuilder {
+    stages: Vec<Stage>,
+}
+
+impl PipelineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an arbitrary stage.
+    #[must_use]
+    pub fn stage(mut self, stage: Stage) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Append a [`Stage::Decimate`] stage.
+    #[must_use]
+    pub fn decimate(self, max_points: usize) -> Self {
+        self.stage(Stage::Decimate { max_points })
+    }
+
+    /// Append a [`Stage::Simplify`] stage.
+    #[must_use]
+    pub fn simplify(self, epsilon: f64) -> Self {
+        self.stage(Stage::Simplify { epsilon })
+    }
+
+    /// Append a [`Stage::Dedupe`] stage.
+    #[must_use]
+    pub fn dedupe(self) -> Self {
+        self.stage(Stage::Dedupe)
+    }
+
+    /// Append a [`Stage::MergeCollinear`] stage.
+    #[must_use]
+    pub fn merge_collinear(self, tolerance: f64) -> Self {
+        self.stage(Stage::MergeCollinear { tolerance })
+    }
+
+    /// Append a [`Stage::Quantize`] stage.
+    #[must_use]
+    pub fn quantize(self, step: f64) -> Self {
+        self.stage(Stage::Quantize { step })
+    }
+
+    /// Append a [`Stage::RemoveOverlaps`] stage.
+    #[must_use]
+    pub fn remove_overlaps(self, tolerance: f64) -> Self {
+        self.stage(Stage::RemoveOverlaps { tolerance })
+    }
+
+    /// Append a [`Stage::Join`] stage.
+    #[must_use]
+    pub fn join(self, options: JoinOptions) -> Self {
+        self.stage(Stage::Join(options))
+    }
+
+    /// Append a [`Stage::SortGreedy`] stage.
+    #[must_use]
+    pub fn sort_greedy(self) -> Self {
+        self.stage(Stage::SortGreedy)
+    }
+
+    /// Append a [`Stage::TwoOpt`] stage.
+    #[must_use]
+    pub fn two_opt(self, options: TwoOptOptions) -> Self {
+        self.stage(Stage::TwoOpt(options))
+    }
+
+    /// Append a [`Stage::Centerline`] stage.
+    #[must_use]
+    pub fn centerline(self, options: CenterlineOptions) -> Self {
+        self.stage(Stage::Centerline(options))
+    }
+
+    /// Append a [`Stage::Crop`] stage.
+    #[must_use]
+    pub fn crop(self, rect: BoundingBox) -> Self {
+        self.stage(Stage::Crop(rect))
+    }
+
+    /// Append a [`Stage::Mask`] stage.
+    #[must_use]
+    pub fn mask(self, polygon: Vec<CoordinatePair>, mode: MaskMode) -> Self {
+        self.stage(Stage::Mask { polygon, mode })
+    }
+
+    /// Append a [`Stage::Weave`] stage.
+    #[must_use]
+    pub fn weave(self, options: WeaveOptions) -> Self {
+        self.stage(Stage::Weave(options))
+    }
+
+    /// Append a [`Stage::Jitter`] stage.
+    #[must_use]
+    pub fn jitter(self, options: JitterOptions) -> Self {
+        self.stage(Stage::Jitter(options))
+    }
+
+    /// Validate the configuration of all stages and return the pipeline.
+    pub fn build(self) -> Result<Pipeline, Error> {
+        for stage in &self.stages {
+            stage.validate().map_err(Error::Pipeline)?;
+        }
+        Ok(Pipeline {
+            stages: self.stages,
+        })
+    }
+}
+
+/// A validated sequence of post-processing stages, created with a
+/// [`PipelineBuilder`].
+///
+/// The stages are applied in the order in which they were added. A pipeline
+/// can be applied to any number of results.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    stages: Vec<Stage>,
+}
+
+impl Pipeline {
+    /// Return the stages of the pipeline.
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// Run all stages on `polylines`.
+    pub fn apply(&self, polylines: Vec<Polyline>) -> Vec<Polyline> {
+        self.stages
+            .iter()
+            .fold(polylines, |polylines, stage| stage.apply(polylines))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline() {
+        let pipeline = PipelineBuilder::new()
+            .crop(BoundingBox::new(0.0, 0.0, 10.0, 10.0))
+            .decimate(2)
+            .build()
+            .unwrap();
+        assert_eq!(pipeline.stages().len(), 2);
+        let polylines = vec![Polyline::from_vec(vec![
+            (-5.0, 5.0).into(),
+            (5.0, 5.0).into(),
+            (15.0, 5.0).into(),
+        ])];
+        assert_eq!(
+            pipeline.apply(polylines),
+            vec![Polyline::from_vec(vec![
+                (0.0, 5.0).into(),
+                (10.0, 5.0).into()
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_pipeline_validation() {
+        let result = PipelineBuilder::new()
+            .mask(vec![(0.0, 0.0).into()], MaskMode::KeepInside)
+            .build();
+        assert!(matches!(result, Err(Error::Pipeline(_))));
+        let result = PipelineBuilder::new().simplify(-1.0).build();
+        assert!(matches!(result, Err(Error::Pipeline(_))));
+        let result = PipelineBuilder::new()
+            .jitter(JitterOptions {
+                frequency: f64::INFINITY,
+                ..Default::default()
+            })
+            .build();
+        assert!(matches!(result, Err(Error::Pipeline(_))));
+        let result = PipelineBuilder::new()
+            .weave(WeaveOptions {
+                gap: f64::NAN,
+                ..Default::default()
+            })
+            .build();
+        assert!(matches!(result, Err(Error::Pipeline(_))));
+        let result = PipelineBuilder::new().quantize(0.0).build();
+        assert!(matches!(result, Err(Error::Pipeline(_))));
+    }
+
+    #[test]
+    fn test_pipeline_cleanup() {
+        let pipeline = PipelineBuilder::new()
+            .quantize(1.0)
+            .dedupe()
+            .merge_collinear(0.0)
+            .build()
+            .unwrap();
+        let polylines = vec![Polyline::from_vec(vec![
+            (0.0, 0.0).into(),
+            (0.9, 0.1).into(),
+            (1.0, 0.0).into(),
+            (2.0, 0.0).into(),
+            (2.0, 0.0).into(),
+        ])];
+        assert_eq!(
+            pipeline.apply(polylines),
+            vec![Polyline::from_vec(vec![
+                (0.0, 0.0).into(),
+                (2.0, 0.0).into()
+            ])]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Parse SVG documents from bytes, readers and files.
+
+use std::{
+    borrow::Cow,
+    convert::TryFrom,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+#[cfg(feature = "svgz")]
+use flate2::read::GzDecoder;
+use log::trace;
+
+use crate::{
+    convert,
+    css::StyleSheet,
+    document::RootAttributes,
+    encoding::{self, Encoding},
+    needs_style_sheet, parse_events, parse_with_options, unit, Definitions, Error, ParseOptions,
+    PathElements, Polyline, Preprocess,
+};
+
+/// Parse an SVG document given as bytes into a vector of [`Polyline`]s,
+/// using the specified [`ParseOptions`].
+///
+/// The document is decoded according to its byte order mark or the
+/// `encoding` of its XML declaration, and UTF-8 is assumed without either.
+/// UTF-8, UTF-16, Latin-1 (ISO-8859-1) and Windows-1252 are supported.
+///
+/// Gzip-compressed documents (`.svgz`) are decompressed if the `svgz`
+/// feature is enabled.
+///
+/// ```
+/// use svg2polylines::{parse_bytes, ParseOptions, Preprocess};
+///
+/// let svg = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>
+/// <svg xmlns=\"http://www.w3.org/2000/svg\"><title>Gr\xfcn</title><path d=\"M 0,0 10,10\"/></svg>";
+/// let options = ParseOptions {
+///     preprocess: Preprocess::No,
+///     ..Default::default()
+/// };
+/// let polylines = parse_bytes(svg, &options).unwrap();
+/// assert_eq!(polylines.len(), 1);
+/// ```
+pub fn parse_bytes(svg: &[u8], options: &ParseOptions) -> Result<Vec<Polyline>, Error> {
+    options.limits.check_input_size(svg.len())?;
+    if is_gzip(svg) {
+        return parse_reader(svg, options);
+    }
+    parse_with_options(&encoding::decode(svg)?, options)
+}
+
+/// Parse an SVG document from a reader into a vector of [`Polyline`]s, using
+/// the specified [`ParseOptions`].
+///
+/// If the options allow it, the XML is streamed through the parser, so that
+/// very large documents are never held in memory as a whole. This requires
+/// that preprocessing, XML recovery, layer and element selection and all
+/// options reading styles (e.g. `skip_invisible`, `dashes` or `clip_paths`)
+/// are disabled. Otherwise, or if the input is a data URI or not encoded in
+/// UTF-8, the document is read into memory and parsed with [`parse_bytes`].
+/// If [`Limits::max_input_size`](crate::Limits::max_input_size) is set, at
+/// most that many bytes are read before parsing is aborted.
+///
+/// Gzip-compressed documents (`.svgz`) are decompressed on the fly if the
+/// `svgz` feature is enabled.
+///
+/// ```
+/// use svg2polylines::{parse_reader, ParseOptions, Preprocess};
+///
+/// let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><path d="M 0,0 10,10"/></svg>"#;
+/// let options = ParseOptions {
+///     preprocess: Preprocess::No,
+///     ..Default::default()
+/// };
+/// let polylines = parse_reader(svg.as_bytes(), &options).unwrap();
+/// assert_eq!(polylines.len(), 1);
+/// ```
+pub fn parse_reader<R: Read>(reader: R, options: &ParseOptions) -> Result<Vec<Polyline>, Error> {
+    let mut reader = BufReader::new(reader);
+    if is_gzip(reader.fill_buf()?) {
+        trace!("parse_reader: Decompress the document");
+        #[cfg(feature = "svgz")]
+        return parse_buffered(BufReader::new(GzDecoder::new(reader)), options);
+        #[cfg(not(feature = "svgz"))]
+        return Err(Error::Encoding(
+            "Compressed SVG input requires the `svgz` feature".into(),
+        ));
+    }
+    parse_buffered(reader, options)
+}
+
+/// Parse an uncompressed SVG document from a buffered reader.
+fn parse_buffered<R: BufRead>(
+    mut reader: R,
+    options: &ParseOptions,
+) -> Result<Vec<Polyline>, Error> {
+    if let Some(max) = options.limits.max_input_size {
+        trace!("parse_reader: Read at most {max} bytes into memory");
+        let mut svg = Vec::new();
+        reader
+            .take(u64::try_from(max).unwrap_or(u64::MAX).saturating_add(1))
+            .read_to_end(&mut svg)?;
+        options.limits.check_input_size(svg.len())?;
+        return parse_bytes(&svg, options);
+    }
+    let start = reader.fill_buf()?;
+    if !is_streamable(options) || is_data_uri(start) || encoding::detect(start)? != Encoding::Utf8 {
+        trace!("parse_reader: Read the document into memory");
+        let mut svg = Vec::new();
+        reader.read_to_end(&mut svg)?;
+        return parse_bytes(&svg, options);
+    }
+
+    trace!("parse_reader: Stream the document");
+    let (path_elements, root) = parse_events(PathElements::new(
+        quick_xml::Reader::from_reader(reader),
+        StyleSheet::default(),
+        Cow::Borrowed(options),
+    ))?;
+    let viewport = root.as_ref().and_then(RootAttributes::viewport);
+    let unit_transform = root
+        .as_ref()
+        .and_then(|root| unit::transform(root, options.unit, options.dpi));
+    convert(
+        path_elements,
+        &Definitions::default(),
+        viewport,
+        unit_transform,
+        options,
+        &mut Vec::new(),
+        None,
+    )
+}
+
+/// Parse the SVG file at `path` into a vector of [`Polyline`]s, using the
+/// specified [`ParseOptions`].
+///
+/// See [`parse_reader`] for when the file is streamed.
+pub fn parse_file<P: AsRef<Path>>(path: P, options: &ParseOptions) -> Result<Vec<Polyline>, Error> {
+    parse_reader(File::open(path)?, options)
+}
+
+/// Whether the document can be parsed in a single pass, without knowing the
+/// whole document in advance.
+pub(crate) fn is_streamable(options: &ParseOptions) -> bool {
+    matches!(options.preprocess, Preprocess::No)
+        && !options.recover
+        && options.layers.is_none()
+        && options.include.is_none()
+        && options.exclude.is_none()
+        && !needs_style_sheet(options)
+}
+
+/// Whether the input starts with the magic number of gzip.
+fn is_gzip(start: &[u8]) -> bool {
+    start.starts_with(&[0x1F, 0x8B])
+}
+
+/// Whether the start of the input is a data URI.
+pub(crate) fn is_data_uri(start: &[u8]) -> bool {
+    start
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .and_then(|i| start[i..].get(..5))
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case(b"data:"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Limits, Unit, ViewportClip};
+
+    const INPUT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="20mm" height="10mm" viewBox="0 0 20 10">
+    <g transform="translate(5 0)">
+        <path d="M 0,0 L 10,10"/>
+    </g>
+    <rect x="10" y="0" width="20" height="5"/>
+</svg>"#;
+
+    fn options() -> ParseOptions {
+        ParseOptions {
+            preprocess: Preprocess::No,
+            viewport_clip: ViewportClip::Clip,
+            unit: Unit::Centimeters,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let options = options();
+        assert!(is_streamable(&options));
+        assert_eq!(
+            parse_reader(INPUT.as_bytes(), &options).unwrap(),
+            parse_with_options(INPUT, &options).unwrap()
+        );
+
+        // Not streamed
+        let options = ParseOptions {
+            dashes: true,
+            ..options
+        };
+        assert!(!is_streamable(&options));
+        assert_eq!(
+            parse_reader(INPUT.as_bytes(), &options).unwrap(),
+            parse_with_options(INPUT, &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_reader_data_uri() {
+        let input = "  data:image/svg+xml,%3Csvg%3E%3Cpath d='M 0,0 1,1'/%3E%3C/svg%3E";
+        assert!(is_data_uri(input.as_bytes()));
+        assert_eq!(
+            parse_reader(input.as_bytes(), &options()).unwrap(),
+            parse_with_options(input, &options()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_reader_utf16() {
+        let input: Vec<u8> = INPUT.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(
+            parse_reader(&input[..], &options()).unwrap(),
+            parse_with_options(INPUT, &options()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let input = b"<?xml version=\"1.0\" encoding=\"windows-1252\"?>
+            <svg><text>\x80</text><path d=\"M 0,0 L 1,1\"/></svg>";
+        assert_eq!(
+            parse_bytes(input, &options()).unwrap(),
+            parse_with_options("<svg><path d=\"M 0,0 L 1,1\"/></svg>", &options()).unwrap()
+        );
+        assert!(matches!(
+            parse_bytes(b"<svg id=\"\xFF\"/>", &options()),
+            Err(Error::Encoding(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "svgz")]
+    fn test_parse_svgz() {
+        use std::io::Write;
+
+        use flate2::{write::GzEncoder, Compression};
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(INPUT.as_bytes()).unwrap();
+        let input = encoder.finish().unwrap();
+        assert!(is_gzip(&input));
+        let expected = parse_with_options(INPUT, &options()).unwrap();
+        assert_eq!(parse_bytes(&input, &options()).unwrap(), expected);
+        assert_eq!(parse_reader(&input[..], &options()).unwrap(), expected);
+        assert!(parse_bytes(&input[..input.len() / 2], &options()).is_err());
+    }
+
+    #[test]
+    fn test_parse_reader_malformed() {
+        let input = r#"<svg><path d="M 0,0 L 1,1"></svg>"#;
+        assert!(matches!(
+            parse_reader(input.as_bytes(), &options()),
+            Err(Error::SvgParse(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_reader_max_input_size() {
+        let options = ParseOptions {
+            limits: Limits {
+                max_input_size: Some(INPUT.len()),
+                ..Default::default()
+            },
+            ..options()
+        };
+        assert_eq!(
+            parse_reader(INPUT.as_bytes(), &options).unwrap(),
+            parse_with_options(INPUT, &options).unwrap()
+        );
+        let input = format!("{INPUT}\n");
+        assert!(matches!(
+            parse_reader(input.as_bytes(), &options),
+            Err(Error::Limit(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_file() {
+        assert!(matches!(
+            parse_file("does-not-exist.svg", &options()),
+            Err(Error::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,228 @@
+//! Repair common well-formedness problems of real-world SVG files.
+
+use std::collections::BTreeSet;
+
+use quick_xml::events::{BytesEnd, Event};
+
+use crate::{Error, Warning};
+
+/// Namespace URIs of prefixes that are commonly used without a declaration.
+const KNOWN_NAMESPACES: &[(&str, &str)] = &[
+    ("xlink", "http://www.w3.org/1999/xlink"),
+    ("inkscape", "http://www.inkscape.org/namespaces/inkscape"),
+    (
+        "sodipodi",
+        "http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd",
+    ),
+];
+
+/// Return whether `s` starts with a character or entity reference, without
+/// the leading `&`.
+fn is_reference(s: &str) -> bool {
+    let Some(end) = s.find(';') else {
+        return false;
+    };
+    let name = &s[..end];
+    if let Some(number) = name.strip_prefix('#') {
+        match number.strip_prefix('x') {
+            Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+            None => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+        }
+    } else {
+        name.chars().next().is_some_and(char::is_alphabetic)
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+    }
+}
+
+/// Escape ampersands that don't start a reference. CDATA sections are left
+/// untouched.
+fn escape_ampersands(svg: &str) -> (String, usize) {
+    let mut out = String::with_capacity(svg.len());
+    let mut count = 0;
+    let mut rest = svg;
+    while let Some(pos) = rest.find(['&', '<']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with("<![CDATA[") {
+            let end = rest.find("]]>").map_or(rest.len(), |end| end + 3);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else if rest.starts_with('<') {
+            out.push('<');
+            rest = &rest[1..];
+        } else {
+            if is_reference(&rest[1..]) {
+                out.push('&');
+            } else {
+                out.push_str("&amp;");
+                count += 1;
+            }
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    (out, count)
+}
+
+/// Return the namespace prefix of a qualified name.
+fn prefix(name: &[u8]) -> Option<&[u8]> {
+    let pos = name.iter().position(|&b| b == b':')?;
+    Some(&name[..pos])
+}
+
+/// Try to repair a malformed SVG document.
+///
+/// This escapes stray ampersands, drops closing tags without a matching
+/// start tag, closes unclosed elements and declares missing namespaces.
+/// Every repair is recorded as a [`Warning::MalformedXml`].
+pub(crate) fn repair(svg: &str, warnings: &mut Vec<Warning>) -> Result<String, Error> {
+    let (svg, escaped) = escape_ampersands(svg);
+    if escaped > 0 {
+        warnings.push(Warning::MalformedXml {
+            message: format!("Escaped {escaped} unescaped ampersand(s)"),
+        });
+    }
+
+    let mut reader = quick_xml::Reader::from_str(&svg);
+    reader.check_end_names(false);
+    let mut events: Vec<Event<'static>> = Vec::new();
+    let mut open: Vec<Vec<u8>> = Vec::new();
+    let mut declared: BTreeSet<Vec<u8>> = BTreeSet::new();
+    let mut used: BTreeSet<Vec<u8>> = BTreeSet::new();
+    let mut root: Option<usize> = None;
+    let mut buf = Vec::new();
+    loop {
+        let event = reader
+            .read_event(&mut buf)
+            .map_err(|e| Error::SvgParse(e.to_string()))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                if root.is_none() {
+                    root = Some(events.len());
+                }
+                used.extend(prefix(e.name()).map(<[u8]>::to_vec));
+                for attr in e.attributes().filter_map(Result::ok) {
+                    if let Some(ns) = attr.key.strip_prefix(b"xmlns:") {
+                        declared.insert(ns.to_vec());
+                    } else {
+                        used.extend(prefix(attr.key).map(<[u8]>::to_vec));
+                    }
+                }
+                if let Event::Start(ref e) = event {
+                    open.push(e.name().to_vec());
+                }
+                events.push(event.into_owned());
+            }
+            Event::End(ref e) => {
+                if let Some(pos) = open.iter().rposition(|name| name == e.name()) {
+                    for name in open.drain(pos + 1..).rev() {
+                        warnings.push(Warning::MalformedXml {
+                            message: format!(
+                                "Closed unclosed <{}>",
+                                String::from_utf8_lossy(&name)
+                            ),
+                        });
+                        events.push(Event::End(BytesEnd::owned(name)));
+                    }
+                    open.pop();
+                    events.push(event.into_owned());
+                } else {
+                    warnings.push(Warning::MalformedXml {
+                        message: format!("Dropped stray </{}>", String::from_utf8_lossy(e.name())),
+                    });
+                }
+            }
+            Event::Eof => break,
+            event => events.push(event.into_owned()),
+        }
+        buf.clear();
+    }
+    for name in open.into_iter().rev() {
+        warnings.push(Warning::MalformedXml {
+            message: format!("Closed unclosed <{}>", String::from_utf8_lossy(&name)),
+        });
+        events.push(Event::End(BytesEnd::owned(name)));
+    }
+
+    // Declare missing namespaces on the root element
+    if let Some(root) = root {
+        if let Event::Start(e) | Event::Empty(e) = &mut events[root] {
+            if !e
+                .attributes()
+                .filter_map(Result::ok)
+                .any(|a| a.key == b"xmlns")
+            {
+                e.push_attribute(("xmlns", "http://www.w3.org/2000/svg"));
+                warnings.push(Warning::MalformedXml {
+                    message: "Added missing SVG namespace declaration".into(),
+                });
+            }
+            for ns in used.difference(&declared) {
+                let ns = String::from_utf8_lossy(ns);
+                if ns == "xml" || ns == "xmlns" {
+                    continue;
+                }
+                let uri = KNOWN_NAMESPACES
+                    .iter()
+                    .find(|(prefix, _)| *prefix == ns)
+                    .map_or_else(
+                        || format!("urn:x-undeclared:{ns}"),
+                        |(_, uri)| (*uri).to_string(),
+                    );
+                e.push_attribute((format!("xmlns:{ns}").as_str(), uri.as_str()));
+                warnings.push(Warning::MalformedXml {
+                    message: format!("Added missing declaration of namespace prefix \"{ns}\""),
+                });
+            }
+        }
+    }
+
+    let mut writer = quick_xml::Writer::new(Vec::new());
+    for event in &events {
+        writer
+            .write_event(event)
+            .map_err(|e| Error::SvgParse(e.to_string()))?;
+    }
+    String::from_utf8(writer.into_inner()).map_err(|e| Error::SvgParse(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_ampersands() {
+        let (out, count) =
+            escape_ampersands("<a t=\"A & B &amp; &#38; &#x26;\">&nbsp; & <![CDATA[&]]></a>");
+        assert_eq!(
+            out,
+            "<a t=\"A &amp; B &amp; &#38; &#x26;\">&nbsp; &amp; <![CDATA[&]]></a>"
+        );
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_repair() {
+        let mut warnings = Vec::new();
+        let repaired = repair(
+            r#"<svg><g inkscape:label="A & B"><path d="M 0,0 1,1"/></p></g>"#,
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(
+            repaired,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape"><g inkscape:label="A &amp; B"><path d="M 0,0 1,1"/></g></svg>"#
+        );
+        assert_eq!(warnings.len(), 5);
+    }
+
+    #[test]
+    fn test_repair_well_formed() {
+        let input = r#"<svg xmlns="http://www.w3.org/2000/svg"><path d="M 0,0 1,1"/></svg>"#;
+        let mut warnings = Vec::new();
+        assert_eq!(repair(input, &mut warnings).unwrap(), input);
+        assert!(warnings.is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+//! Select the elements to convert by CSS selectors.
+
+use log::trace;
+use quick_xml::events::{BytesStart, Event};
+
+use crate::{css::SelectorList, Error};
+
+/// Elements that are rendered directly.
+const GRAPHICS: &[&[u8]] = &[
+    b"path",
+    b"rect",
+    b"circle",
+    b"ellipse",
+    b"line",
+    b"polyline",
+    b"polygon",
+    b"use",
+    b"text",
+    b"image",
+];
+
+/// Elements whose content is only rendered when referenced from elsewhere.
+const REFERENCED: &[&[u8]] = &[
+    b"defs",
+    b"symbol",
+    b"clipPath",
+    b"mask",
+    b"marker",
+    b"pattern",
+];
+
+/// How to treat an open element and its content.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Inside of an element that is only rendered when referenced. Its
+    /// content is kept unchanged.
+    Referenced,
+    /// Inside of an element that matches `include` (or everything, if there
+    /// is no `include` selector).
+    Included,
+    /// Not (yet) inside of an included element.
+    Outside,
+}
+
+/// Return a copy of the document without the graphics elements that are not
+/// matched by `include`, or that are matched by `exclude`.
+///
+/// An element is matched if the selector matches the element itself or any
+/// of its ancestors, so selecting a group selects all of its content.
+/// Definitions are kept, so that included `<use>` elements still resolve.
+pub(crate) fn filter(
+    svg: &str,
+    include: Option<&str>,
+    exclude: Option<&str>,
+) -> Result<String, Error> {
+    let include = include.map(SelectorList::parse).transpose()?;
+    let exclude = exclude.map(SelectorList::parse).transpose()?;
+
+    let mut reader = quick_xml::Reader::from_str(svg);
+    let mut writer = quick_xml::Writer::new(Vec::new());
+    let mut write = |event: &Event| {
+        writer
+            .write_event(event)
+            .map_err(|e| Error::SvgParse(e.to_string()))
+    };
+    let mut buf = Vec::new();
+
+    let initial = if include.is_some() {
+        State::Outside
+    } else {
+        State::Included
+    };
+    let mut stack: Vec<State> = Vec::new();
+    // Nesting depth inside of a dropped element
+    let mut skip = 0;
+    loop {
+        let event = reader
+            .read_event(&mut buf)
+            .map_err(|e| Error::SvgParse(e.to_string()))?;
+        match event {
+            Event::Eof => break,
+            Event::Start(_) if skip > 0 => skip += 1,
+            Event::End(_) if skip > 0 => skip -= 1,
+            _ if skip > 0 => {}
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let parent = stack.last().copied().unwrap_or(initial);
+                if let Some(state) = state(e, parent, include.as_ref(), exclude.as_ref()) {
+                    if matches!(event, Event::Start(_)) {
+                        stack.push(state);
+                    }
+                    write(&event)?;
+                } else {
+                    trace!(
+                        "select::filter: Drop <{}> element",
+                        String::from_utf8_lossy(e.name())
+                    );
+                    if matches!(event, Event::Start(_)) {
+                        skip = 1;
+                    }
+                }
+            }
+            event => {
+                if matches!(event, Event::End(_)) {
+                    stack.pop();
+                }
+                write(&event)?;
+            }
+        }
+        buf.clear();
+    }
+    String::from_utf8(writer.into_inner()).map_err(|e| Error::SvgParse(e.to_string()))
+}
+
+/// Return the state of an element, or `None` if it should be dropped.
+fn state(
+    e: &BytesStart,
+    parent: State,
+    include: Option<&SelectorList>,
+    exclude: Option<&SelectorList>,
+) -> Option<State> {
+    if let State::Referenced = parent {
+        return Some(parent);
+    }
+    if REFERENCED.contains(&e.name()) {
+        return Some(State::Referenced);
+    }
+    if exclude.is_some_and(|exclude| exclude.matches(e)) {
+        return None;
+    }
+    let state = match parent {
+        State::Outside if include.is_some_and(|include| include.matches(e)) => State::Included,
+        parent => parent,
+    };
+    if let State::Outside = state {
+        if GRAPHICS.contains(&e.name()) {
+            return None;
+        }
+    }
+    Some(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = r##"<svg><defs><path id="shape" class="guide" d="M 0,0 1,1"/></defs><g id="cut"><path d="M 0,0 2,2"/><path class="guide" d="M 0,0 3,3"/></g><use href="#shape"/><path id="engrave" d="M 0,0 4,4"/></svg>"##;
+
+    #[test]
+    fn test_filter() {
+        assert_eq!(
+            filter(INPUT, Some("#cut, #engrave"), Some(".guide")).unwrap(),
+            r#"<svg><defs><path id="shape" class="guide" d="M 0,0 1,1"/></defs><g id="cut"><path d="M 0,0 2,2"/></g><path id="engrave" d="M 0,0 4,4"/></svg>"#
+        );
+        assert_eq!(
+            filter(INPUT, None, Some("g")).unwrap(),
+            r##"<svg><defs><path id="shape" class="guide" d="M 0,0 1,1"/></defs><use href="#shape"/><path id="engrave" d="M 0,0 4,4"/></svg>"##
+        );
+        assert!(filter(INPUT, Some("g path"), None).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Convert basic shapes to path data, so that they can be parsed without
+//! preprocessing.
+
+use std::{borrow::Cow, str::FromStr};
+
+use quick_xml::events::BytesStart;
+use svgtypes::{Length, LengthUnit, PointsParser};
+
+use crate::style;
+
+/// Return a length attribute in user units.
+///
+/// Only unitless and `px` lengths are supported, because other units depend
+/// on the viewport or the font size.
+fn length(e: &BytesStart, key: &[u8]) -> Option<f64> {
+    match Length::from_str(&style::attribute(e, key)?).ok()? {
+        Length {
+            number,
+            unit: LengthUnit::None | LengthUnit::Px,
+        } => Some(number),
+        _ => None,
+    }
+}
+
+/// Convert a `<rect>` element to path data.
+///
+/// Rectangles without a positive width and height are not rendered. The
+/// corner radii are clamped to half of the width and height, and a missing
+/// `rx` or `ry` defaults to the other one.
+fn rect(e: &BytesStart) -> Option<String> {
+    let x = length(e, b"x").unwrap_or(0.0);
+    let y = length(e, b"y").unwrap_or(0.0);
+    let width = length(e, b"width").filter(|&w| w > 0.0)?;
+    let height = length(e, b"height").filter(|&h| h > 0.0)?;
+    let (rx, ry) = match (
+        length(e, b"rx").filter(|&r| r >= 0.0),
+        length(e, b"ry").filter(|&r| r >= 0.0),
+    ) {
+        (Some(rx), Some(ry)) => (rx, ry),
+        (Some(r), None) | (None, Some(r)) => (r, r),
+        (None, None) => (0.0, 0.0),
+    };
+    let rx = rx.min(width / 2.0);
+    let ry = ry.min(height / 2.0);
+
+    let (right, bottom) = (x + width, y + height);
+    if rx == 0.0 || ry == 0.0 {
+        return Some(format!("M {x},{y} H {right} V {bottom} H {x} Z"));
+    }
+    let arc = format!("A {rx} {ry} 0 0 1");
+    Some(format!(
+        "M {},{y} H {} {arc} {right},{} V {} {arc} {},{bottom} H {} {arc} {x},{} V {} {arc} {},{y} Z",
+        x + rx,
+        right - rx,
+        y + ry,
+        bottom - ry,
+        right - rx,
+        x + rx,
+        bottom - ry,
+        y + ry,
+        x + rx,
+    ))
+}
+
+/// Convert an ellipse with the given center and radii to path data made of
+/// four arcs, which are flattened with the same tolerance as curves.
+fn ellipse_path(cx: f64, cy: f64, rx: f64, ry: f64) -> String {
+    let arc = format!("A {rx} {ry} 0 0 1");
+    format!(
+        "M {},{cy} {arc} {cx},{} {arc} {},{cy} {arc} {cx},{} {arc} {},{cy} Z",
+        cx + rx,
+        cy + ry,
+        cx - rx,
+        cy - ry,
+        cx + rx,
+    )
+}
+
+/// Convert a `<circle>` element to path data.
+fn circle(e: &BytesStart) -> Option<String> {
+    let r = length(e, b"r").filter(|&r| r > 0.0)?;
+    Some(ellipse_path(
+        length(e, b"cx").unwrap_or(0.0),
+        length(e, b"cy").unwrap_or(0.0),
+        r,
+        r,
+    ))
+}
+
+/// Convert an `<ellipse>` element to path data.
+///
+/// A missing `rx` or `ry` defaults to the other one.
+fn ellipse(e: &BytesStart) -> Option<String> {
+    let (rx, ry) = match (length(e, b"rx"), length(e, b"ry")) {
+        (Some(rx), Some(ry)) => (rx, ry),
+        (Some(r), None) | (None, Some(r)) => (r, r),
+        (None, None) => return None,
+    };
+    if rx <= 0.0 || ry <= 0.0 {
+        return None;
+    }
+    Some(ellipse_path(
+        length(e, b"cx").unwrap_or(0.0),
+        length(e, b"cy").unwrap_or(0.0),
+        rx,
+        ry,
+    ))
+}
+
+/// Convert a `<line>` element to path data.
+fn line(e: &BytesStart) -> String {
+    let coordinate = |key: &[u8]| length(e, key).unwrap_or(0.0);
+    format!(
+        "M {},{} L {},{}",
+        coordinate(b"x1"),
+        coordinate(b"y1"),
+        coordinate(b"x2"),
+        coordinate(b"y2"),
+    )
+}
+
+/// Convert the `points` of a `<polyline>` or `<polygon>` element to path
+/// data, closing the path for polygons with at least two points.
+///
+/// Like in browsers, the points are used up to the first parse error.
+fn points(e: &BytesStart, close: bool) -> Option<String> {
+    let points = style::attribute(e, b"points")?;
+    let points: Vec<String> = PointsParser::from(points.as_str())
+        .map(|(x, y)| format!("{x},{y}"))
+        .collect();
+    if points.is_empty() {
+        return None;
+    }
+    let mut data = format!("M {}", points.join(" L "));
+    if close && points.len() > 1 {
+        data.push_str(" Z");
+    }
+    Some(data)
+}
+
+/// Return the path data of a `<path>` element, or convert a basic shape
+/// element to path data.
+///
+/// Return `None` for other elements and for shapes that are not rendered.
+pub(crate) fn path_data<'e>(e: &'e BytesStart) -> Option<Cow<'e, str>> {
+    borrowed_path_data(e, e)
+}
+
+/// Like [`path_data`], but borrow the `d` attribute of paths from `content`,
+/// the content of the element, which can outlive the element.
+pub(crate) fn borrowed_path_data<'a>(e: &BytesStart, content: &'a [u8]) -> Option<Cow<'a, str>> {
+    let data = match e.name() {
+        b"path" => return style::borrowed_attribute(content, e.name().len(), b"d"),
+        b"rect" => rect(e),
+        b"circle" => circle(e),
+        b"ellipse" => ellipse(e),
+        b"line" => Some(line(e)),
+        b"polyline" => points(e, false),
+        b"polygon" => points(e, true),
+        _ => None,
+    };
+    data.map(Cow::Owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(content: &str) -> BytesStart<'_> {
+        let name_len = content.find(' ').unwrap_or(content.len());
+        BytesStart::borrowed(content.as_bytes(), name_len)
+    }
+
+    #[test]
+    fn test_rect() {
+        assert_eq!(
+            path_data(&element(r#"rect x="1" y="2" width="10" height="5px""#)),
+            Some("M 1,2 H 11 V 7 H 1 Z".into())
+        );
+        assert_eq!(
+            path_data(&element(r#"rect width="10" height="4" rx="1" ry="3""#)),
+            Some("M 1,0 H 9 A 1 2 0 0 1 10,2 V 2 A 1 2 0 0 1 9,4 H 1 A 1 2 0 0 1 0,2 V 2 A 1 2 0 0 1 1,0 Z".into())
+        );
+        assert_eq!(path_data(&element(r#"rect width="10""#)), None);
+        assert_eq!(path_data(&element(r#"rect width="10%" height="5""#)), None);
+        assert_eq!(path_data(&element(r#"g width="10" height="5""#)), None);
+    }
+
+    #[test]
+    fn test_circle_ellipse() {
+        assert_eq!(
+            path_data(&element(r#"circle cx="5" cy="5" r="2""#)),
+            Some("M 7,5 A 2 2 0 0 1 5,7 A 2 2 0 0 1 3,5 A 2 2 0 0 1 5,3 A 2 2 0 0 1 7,5 Z".into())
+        );
+        assert_eq!(
+            path_data(&element(r#"ellipse rx="2" ry="1""#)),
+            Some(
+                "M 2,0 A 2 1 0 0 1 0,1 A 2 1 0 0 1 -2,0 A 2 1 0 0 1 0,-1 A 2 1 0 0 1 2,0 Z".into()
+            )
+        );
+        assert_eq!(
+            path_data(&element(r#"ellipse rx="2""#)),
+            path_data(&element(r#"circle r="2""#))
+        );
+        assert_eq!(path_data(&element(r#"circle r="0""#)), None);
+    }
+
+    #[test]
+    fn test_line_polyline_polygon() {
+        assert_eq!(
+            path_data(&element(r#"line x1="1" y1="2" x2="3""#)),
+            Some("M 1,2 L 3,0".into())
+        );
+        assert_eq!(
+            path_data(&element(r#"polyline points="0,0 10,0 10 10""#)),
+            Some("M 0,0 L 10,0 L 10,10".into())
+        );
+        assert_eq!(
+            path_data(&element(r#"polygon points="0,0 10,0 10,10 5""#)),
+            Some("M 0,0 L 10,0 L 10,10 Z".into())
+        );
+        assert_eq!(
+            path_data(&element(r#"polygon points="5,5""#)),
+            Some("M 5,5".into())
+        );
+        assert_eq!(path_data(&element(r#"polygon points="x""#)), None);
+    }
+
+    #[test]
+    fn test_path() {
+        let path = element(r#"path id="a" d="M 0,0 L 1,1""#);
+        assert!(matches!(
+            path_data(&path),
+            Some(Cow::Borrowed("M 0,0 L 1,1"))
+        ));
+        let path = element(r#"path d="M 0,0&#x20;L 1,1""#);
+        assert!(matches!(path_data(&path), Some(Cow::Owned(d)) if d == "M 0,0 L 1,1"));
+        assert_eq!(path_data(&element(r#"path id="a""#)), None);
+    }
+}
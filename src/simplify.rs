@@ -0,0 +1,359 @@
+//! Reduce the number of points in a polyline.
+
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::{CoordinatePair, Polyline};
+
+/// Area of the triangle spanned by three points.
+fn triangle_area(a: CoordinatePair, b: CoordinatePair, c: CoordinatePair) -> f64 {
+    ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)).abs() / 2.0
+}
+
+/// Distance of `p` to the line segment from `a` to `b`.
+pub(crate) fn segment_distance(p: CoordinatePair, a: CoordinatePair, b: CoordinatePair) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0.0 {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (p.x - a.x - t * dx).hypot(p.y - a.y - t * dy)
+}
+
+/// A point that may be removed, ordered so that the smallest area is popped
+/// first from a [`BinaryHeap`].
+#[derive(Debug, PartialEq)]
+struct Candidate {
+    area: f64,
+    index: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .area
+            .partial_cmp(&self.area)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl Polyline {
+    /// Reduce the polyline to at most `max_points` points.
+    ///
+    /// Points are removed one by one using the Visvalingam–Whyatt algorithm:
+    /// the point that contributes the least area (together with its two
+    /// neighbours) is removed first. The first and last point are always
+    /// kept, unless `max_points` is smaller than 2.
+    #[must_use]
+    pub fn decimate(&self, max_points: usize) -> Polyline {
+        let points = &self.points;
+        let n = points.len();
+        if n <= max_points {
+            return Polyline {
+                points: points.clone(),
+                closed: self.closed,
+            };
+        }
+        if max_points < 2 {
+            return Polyline::from_vec(points.iter().take(max_points).copied().collect());
+        }
+
+        let mut prev: Vec<usize> = (0..n).map(|i| i.saturating_sub(1)).collect();
+        let mut next: Vec<usize> = (0..n).map(|i| (i + 1).min(n - 1)).collect();
+        let mut areas = vec![f64::INFINITY; n];
+        let mut removed = vec![false; n];
+        let mut heap = BinaryHeap::with_capacity(n);
+        for i in 1..n - 1 {
+            areas[i] = triangle_area(points[i - 1], points[i], points[i + 1]);
+            heap.push(Candidate {
+                area: areas[i],
+                index: i,
+            });
+        }
+
+        let mut remaining = n;
+        while remaining > max_points {
+            let Some(Candidate { area, index }) = heap.pop() else {
+                break;
+            };
+            #[allow(clippy::float_cmp)]
+            let stale = removed[index] || area != areas[index];
+            if stale {
+                continue;
+            }
+            removed[index] = true;
+            remaining -= 1;
+
+            let (p, q) = (prev[index], next[index]);
+            next[p] = q;
+            prev[q] = p;
+            for neighbour in [p, q] {
+                if neighbour != 0 && neighbour != n - 1 {
+                    areas[neighbour] = triangle_area(
+                        points[prev[neighbour]],
+                        points[neighbour],
+                        points[next[neighbour]],
+                    );
+                    heap.push(Candidate {
+                        area: areas[neighbour],
+                        index: neighbour,
+                    });
+                }
+            }
+        }
+
+        Polyline {
+            points: points
+                .iter()
+                .zip(removed)
+                .filter(|(_, removed)| !removed)
+                .map(|(p, _)| *p)
+                .collect(),
+            closed: self.closed,
+        }
+    }
+
+    /// Snap all coordinates to a grid with the spacing `step`, and remove
+    /// repeated points created by the snapping.
+    ///
+    /// This makes nearly identical polylines, e.g. from traced artwork,
+    /// identical. If `step` is not positive, only repeated points are
+    /// removed.
+    #[must_use]
+    pub fn quantize(&self, step: f64) -> Polyline {
+        let snap = |v: f64| {
+            if step > 0.0 {
+                (v / step).round() * step
+            } else {
+                v
+            }
+        };
+        let mut points: Vec<CoordinatePair> = self
+            .points
+            .iter()
+            .map(|p| CoordinatePair::new(snap(p.x), snap(p.y)))
+            .collect();
+        points.dedup();
+        Polyline {
+            points,
+            closed: self.closed,
+        }
+    }
+
+    /// Merge runs of consecutive collinear segments into single segments.
+    ///
+    /// A point is removed if it and all points removed before it since the
+    /// last kept point are at most `tolerance` away from the merged segment,
+    /// so a tolerance of 0 only merges exactly collinear points. Points where
+    /// the polyline turns back are kept. The first and last point are always
+    /// kept.
+    #[must_use]
+    pub fn merge_collinear(&self, tolerance: f64) -> Polyline {
+        let points = &self.points;
+        let Some((&last, inner)) = points.split_last() else {
+            return Polyline::new();
+        };
+        let mut merged = Vec::with_capacity(points.len());
+        merged.push(points[0]);
+        let mut start = 0;
+        for (i, &p) in inner.iter().enumerate().skip(1) {
+            let (a, b) = (points[start], points[i + 1]);
+            if points[start + 1..=i]
+                .iter()
+                .any(|&q| segment_distance(q, a, b) > tolerance)
+            {
+                merged.push(p);
+                start = i;
+            }
+        }
+        if points.len() > 1 {
+            merged.push(last);
+        }
+        Polyline {
+            points: merged,
+            closed: self.closed,
+        }
+    }
+
+    /// Remove points that deviate less than `epsilon` from the simplified
+    /// polyline.
+    ///
+    /// This uses the Ramer–Douglas–Peucker algorithm: the point farthest
+    /// from the line between the first and last point is kept if its
+    /// distance exceeds `epsilon`, and both halves are simplified in turn.
+    /// The first and last point are always kept, so closed polylines stay
+    /// closed.
+    #[must_use]
+    pub fn simplify(&self, epsilon: f64) -> Polyline {
+        let points = &self.points;
+        let n = points.len();
+        if n <= 2 || epsilon.is_nan() || epsilon <= 0.0 {
+            return Polyline {
+                points: points.clone(),
+                closed: self.closed,
+            };
+        }
+        let mut keep = vec![false; n];
+        keep[0] = true;
+        keep[n - 1] = true;
+
+        // Ranges to simplify, an explicit stack avoids deep recursion
+        let mut ranges = vec![(0, n - 1)];
+        while let Some((start, end)) = ranges.pop() {
+            if end <= start + 1 {
+                continue;
+            }
+            let (a, b) = (points[start], points[end]);
+            let (index, distance) = (start + 1..end)
+                .map(|i| (i, segment_distance(points[i], a, b)))
+                .fold((start, 0.0), |max, candidate| {
+                    if candidate.1 > max.1 {
+                        candidate
+                    } else {
+                        max
+                    }
+                });
+            if distance > epsilon {
+                keep[index] = true;
+                ranges.push((start, index));
+                ranges.push((index, end));
+            }
+        }
+
+        Polyline {
+            points: points
+                .iter()
+                .zip(keep)
+                .filter(|(_, keep)| *keep)
+                .map(|(p, _)| *p)
+                .collect(),
+            closed: self.closed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimate_keeps_corners() {
+        let line = Polyline::from_vec(vec![
+            CoordinatePair::new(0.0, 0.0),
+            CoordinatePair::new(1.0, 0.01),
+            CoordinatePair::new(2.0, 0.0),
+            CoordinatePair::new(2.0, 2.0),
+            CoordinatePair::new(2.01, 3.0),
+            CoordinatePair::new(2.0, 4.0),
+        ]);
+        assert_eq!(
+            line.decimate(3),
+            Polyline::from_vec(vec![
+                CoordinatePair::new(0.0, 0.0),
+                CoordinatePair::new(2.0, 0.0),
+                CoordinatePair::new(2.0, 4.0),
+            ])
+        );
+        assert_eq!(line.decimate(10), line);
+        assert_eq!(line.decimate(1).len(), 1);
+    }
+
+    #[test]
+    fn test_quantize() {
+        let line = Polyline::from_vec(vec![
+            CoordinatePair::new(0.04, -0.02),
+            CoordinatePair::new(0.1, 0.0),
+            CoordinatePair::new(0.26, 0.74),
+            CoordinatePair::new(0.24, 0.76),
+        ]);
+        assert_eq!(
+            line.quantize(0.5),
+            Polyline::from_vec(vec![
+                CoordinatePair::new(0.0, 0.0),
+                CoordinatePair::new(0.5, 0.5),
+                CoordinatePair::new(0.0, 1.0),
+            ])
+        );
+        assert_eq!(line.quantize(0.0), line);
+    }
+
+    #[test]
+    fn test_merge_collinear() {
+        let line = Polyline::from_vec(vec![
+            CoordinatePair::new(0.0, 0.0),
+            CoordinatePair::new(1.0, 0.0),
+            CoordinatePair::new(2.0, 0.0),
+            CoordinatePair::new(3.0, 0.05),
+            CoordinatePair::new(3.0, 1.0),
+            CoordinatePair::new(3.0, 0.5),
+        ]);
+        assert_eq!(
+            line.merge_collinear(0.0),
+            Polyline::from_vec(vec![
+                CoordinatePair::new(0.0, 0.0),
+                CoordinatePair::new(2.0, 0.0),
+                CoordinatePair::new(3.0, 0.05),
+                CoordinatePair::new(3.0, 1.0),
+                CoordinatePair::new(3.0, 0.5),
+            ])
+        );
+        assert_eq!(
+            line.merge_collinear(0.1),
+            Polyline::from_vec(vec![
+                CoordinatePair::new(0.0, 0.0),
+                CoordinatePair::new(3.0, 0.05),
+                CoordinatePair::new(3.0, 1.0),
+                CoordinatePair::new(3.0, 0.5),
+            ])
+        );
+        let point = Polyline::from_vec(vec![CoordinatePair::new(1.0, 1.0)]);
+        assert_eq!(point.merge_collinear(0.1), point);
+        assert!(Polyline::new().merge_collinear(0.1).is_empty());
+    }
+
+    #[test]
+    fn test_simplify() {
+        let line = Polyline::from_vec(vec![
+            CoordinatePair::new(0.0, 0.0),
+            CoordinatePair::new(1.0, 0.1),
+            CoordinatePair::new(2.0, -0.1),
+            CoordinatePair::new(3.0, 5.0),
+            CoordinatePair::new(4.0, 6.0),
+            CoordinatePair::new(5.0, 7.0),
+        ]);
+        assert_eq!(
+            line.simplify(0.5),
+            Polyline::from_vec(vec![
+                CoordinatePair::new(0.0, 0.0),
+                CoordinatePair::new(2.0, -0.1),
+                CoordinatePair::new(3.0, 5.0),
+                CoordinatePair::new(5.0, 7.0),
+            ])
+        );
+        assert_eq!(line.simplify(0.0), line);
+        assert_eq!(line.simplify(100.0).len(), 2);
+
+        let mut square = Polyline::from_vec(vec![
+            CoordinatePair::new(0.0, 0.0),
+            CoordinatePair::new(1.0, 0.0),
+            CoordinatePair::new(2.0, 0.0),
+            CoordinatePair::new(2.0, 2.0),
+            CoordinatePair::new(0.0, 2.0),
+        ]);
+        square.close();
+        let simplified = square.simplify(0.1);
+        assert!(simplified.is_closed());
+        assert_eq!(simplified.len(), 5);
+        assert!(Polyline::new().simplify(1.0).is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+//! Stream polylines point by point into a consumer, e.g. a G-code writer.
+
+use crate::{parse_iter, CoordinatePair, Error, Polyline};
+
+/// Information about the path a polyline was created from, see
+/// [`PolylineSink::begin_path`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathMeta<'a> {
+    /// The index of the polyline in the document.
+    pub index: usize,
+
+    /// The `id` attribute of the source element.
+    pub id: Option<&'a str>,
+}
+
+/// A consumer of polylines, which receives them point by point.
+///
+/// Every polyline starts with a call to `begin_path`, followed by its points
+/// and a call to `end_path`.
+pub trait PolylineSink {
+    /// Start a new polyline.
+    fn begin_path(&mut self, meta: &PathMeta);
+
+    /// Add a point to the current polyline.
+    fn point(&mut self, x: f64, y: f64);
+
+    /// End the current polyline. `closed` is set if the polyline is closed,
+    /// see [`Polyline::is_closed`].
+    fn end_path(&mut self, closed: bool);
+}
+
+/// Collect the polylines into a vector.
+impl PolylineSink for Vec<Polyline> {
+    fn begin_path(&mut self, _meta: &PathMeta) {
+        self.push(Polyline::new());
+    }
+
+    fn point(&mut self, x: f64, y: f64) {
+        if let Some(polyline) = self.last_mut() {
+            polyline.push(CoordinatePair::new(x, y));
+        }
+    }
+
+    fn end_path(&mut self, closed: bool) {
+        if let Some(polyline) = self.last_mut().filter(|_| closed) {
+            polyline.close();
+        }
+    }
+}
+
+/// Parse an SVG string and pass the polylines to `sink`.
+///
+/// Like [`parse_iter`], paths are parsed and flattened one by one without
+/// preprocessing, and the points are passed to the sink without collecting
+/// the polylines of the document. If an error occurs, the polylines before
+/// it have already been passed to the sink.
+///
+/// ```
+/// use svg2polylines::{parse_into, PathMeta, PolylineSink};
+///
+/// struct Gcode(String);
+///
+/// impl PolylineSink for Gcode {
+///     fn begin_path(&mut self, meta: &PathMeta) {
+///         self.0 += &format!("; {}\n", meta.id.unwrap_or("path"));
+///     }
+///     fn point(&mut self, x: f64, y: f64) {
+///         self.0 += &format!("G1 X{x} Y{y}\n");
+///     }
+///     fn end_path(&mut self, _closed: bool) {}
+/// }
+///
+/// let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><path id="a" d="M 0,0 10,10"/></svg>"#;
+/// let mut gcode = Gcode(String::new());
+/// parse_into(svg, 0.15, &mut gcode).unwrap();
+/// assert_eq!(gcode.0, "; a\nG1 X0 Y0\nG1 X10 Y10\n");
+/// ```
+pub fn parse_into<S: PolylineSink + ?Sized>(
+    svg: &str,
+    tol: f64,
+    sink: &mut S,
+) -> Result<(), Error> {
+    let mut paths = parse_iter(svg, tol);
+    let mut index = 0;
+    while let Some((id, polylines)) = paths.next_path()? {
+        for polyline in polylines {
+            sink.begin_path(&PathMeta {
+                index,
+                id: id.as_deref(),
+            });
+            for point in &polyline {
+                sink.point(point.x, point.y);
+            }
+            sink.end_path(polyline.is_closed());
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    /// Records the calls as strings.
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl PolylineSink for Recorder {
+        fn begin_path(&mut self, meta: &PathMeta) {
+            self.0.push(format!("begin {} {:?}", meta.index, meta.id));
+        }
+
+        fn point(&mut self, x: f64, y: f64) {
+            self.0.push(format!("{x},{y}"));
+        }
+
+        fn end_path(&mut self, closed: bool) {
+            self.0.push(format!("end {closed}"));
+        }
+    }
+
+    #[test]
+    fn test_parse_into() {
+        let svg = r#"<svg>
+            <path id="a" d="M 0,0 L 1,0 L 1,1 Z M 5,5 L 6,6"/>
+            <path d="M 2,2 L 3,3"/>
+        </svg>"#;
+        let mut recorder = Recorder::default();
+        parse_into(svg, 0.15, &mut recorder).unwrap();
+        assert_eq!(
+            recorder.0,
+            vec![
+                "begin 0 Some(\"a\")",
+                "0,0",
+                "1,0",
+                "1,1",
+                "0,0",
+                "end true",
+                "begin 1 Some(\"a\")",
+                "5,5",
+                "6,6",
+                "end false",
+                "begin 2 None",
+                "2,2",
+                "3,3",
+                "end false",
+            ]
+        );
+
+        let mut polylines = Vec::new();
+        parse_into(svg, 0.15, &mut polylines).unwrap();
+        assert_eq!(polylines, parse(svg, 0.15, false).unwrap());
+    }
+
+    #[test]
+    fn test_parse_into_error() {
+        let mut polylines = Vec::new();
+        let svg = r#"<svg><path d="M 0,0 L 1,1"/><path d="M 0,0 X"/></svg>"#;
+        assert!(parse_into(svg, 0.15, &mut polylines).is_err());
+        assert_eq!(polylines.len(), 1);
+    }
+}
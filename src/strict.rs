@@ -0,0 +1,254 @@
+//! Check that a document can be converted faithfully, see
+//! [`ParseOptions::strict`](crate::ParseOptions::strict).
+
+use std::fmt;
+
+use quick_xml::events::{BytesStart, Event};
+use svgtypes::{PathParser, PathSegment};
+
+use crate::{
+    css::StyleSheet,
+    parse_transform, shape,
+    style::{self, Paint},
+    Error, ParseOptions, Preprocess,
+};
+
+/// Something in the document that the parser cannot convert faithfully.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrictViolation {
+    /// Index of the element in document order.
+    pub element: usize,
+
+    /// The name of the element, e.g. `text`.
+    pub name: String,
+
+    /// The `id` attribute of the element, if present.
+    pub id: Option<String>,
+
+    /// What cannot be converted.
+    pub reason: String,
+}
+
+impl fmt::Display for StrictViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{}> {}", self.name, self.element)?;
+        if let Some(id) = &self.id {
+            write!(f, " (#{id})")?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// The inherited state of an element that decides whether it is rendered.
+#[derive(Debug, Clone, Copy)]
+struct Visibility {
+    paint: Paint,
+    /// Whether the element or one of its ancestors has `display: none`.
+    display_none: bool,
+    visible: bool,
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Self {
+            paint: Paint::default(),
+            display_none: false,
+            visible: true,
+        }
+    }
+}
+
+impl Visibility {
+    fn inherit(self, e: &BytesStart, sheet: &StyleSheet) -> Self {
+        Self {
+            paint: self.paint.inherit(e, sheet),
+            display_none: self.display_none
+                || style::property(e, "display", sheet).as_deref() == Some("none"),
+            visible: match style::property(e, "visibility", sheet).as_deref() {
+                Some("inherit") | None => self.visible,
+                Some(value) => value == "visible",
+            },
+        }
+    }
+}
+
+/// Return why an element cannot be converted faithfully.
+fn problems(
+    e: &BytesStart,
+    visibility: Visibility,
+    sheet: &StyleSheet,
+    options: &ParseOptions,
+) -> Vec<String> {
+    let preprocessed = !matches!(options.preprocess, Preprocess::No);
+    let mut problems = Vec::new();
+    match e.name() {
+        b"image" | b"foreignObject" => problems.push("Unsupported element".into()),
+        b"text" | b"use" if !preprocessed => {
+            problems.push("Unsupported element without preprocessing".into());
+        }
+        _ => {}
+    }
+    if let Some(Err(Error::Transform(message))) =
+        style::attribute(e, b"transform").map(|expr| parse_transform(&expr))
+    {
+        problems.push(message);
+    }
+    let has_reference = |name| {
+        style::property(e, name, sheet)
+            .and_then(|value| style::reference(&value))
+            .is_some()
+    };
+    if !options.clip_paths && has_reference("clip-path") {
+        problems.push("Clip path is ignored".into());
+    }
+    if options.mask_threshold.is_none() && has_reference("mask") {
+        problems.push("Mask is ignored".into());
+    }
+    if !options.markers
+        && !preprocessed
+        && ["marker", "marker-start", "marker-mid", "marker-end"]
+            .iter()
+            .any(|&name| has_reference(name))
+    {
+        problems.push("Markers are ignored".into());
+    }
+
+    let Some(data) = shape::path_data(e) else {
+        return problems;
+    };
+    for segment in PathParser::from(&*data) {
+        match segment {
+            Ok(PathSegment::SmoothQuadratic { .. }) if !preprocessed => {
+                problems.push("Unsupported smooth quadratic path segment".into());
+                break;
+            }
+            Ok(_) => {}
+            Err(err) => {
+                problems.push(format!("Invalid path data: {err}"));
+                break;
+            }
+        }
+    }
+    if !preprocessed && (visibility.display_none || !visibility.visible) {
+        problems.push("Hidden element would be plotted".into());
+    }
+    if !options.skip_invisible && !visibility.paint.is_visible(e) {
+        problems.push("Element without fill and stroke would be plotted".into());
+    }
+    problems
+}
+
+/// Check the original document for elements, attributes and path data that
+/// cannot be converted faithfully with the given options.
+///
+/// All problems are collected into an [`Error::Strict`].
+pub(crate) fn check(svg: &str, options: &ParseOptions) -> Result<(), Error> {
+    let sheet = StyleSheet::from_svg(svg);
+    let mut reader = quick_xml::Reader::from_str(svg);
+    reader.trim_text(true);
+
+    let mut violations = Vec::new();
+    let mut stack: Vec<Visibility> = Vec::new();
+    let mut element = 0;
+    let mut buf = Vec::new();
+    loop {
+        let (e, is_start) = match reader.read_event(&mut buf) {
+            Ok(Event::Start(e)) => (e, true),
+            Ok(Event::Empty(e)) => (e, false),
+            Ok(Event::End(_)) => {
+                stack.pop();
+                buf.clear();
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {
+                buf.clear();
+                continue;
+            }
+            Err(e) => return Err(Error::SvgParse(e.to_string())),
+        };
+        let visibility = stack
+            .last()
+            .copied()
+            .unwrap_or_default()
+            .inherit(&e, &sheet);
+        for reason in problems(&e, visibility, &sheet, options) {
+            violations.push(StrictViolation {
+                element,
+                name: String::from_utf8_lossy(e.name()).into_owned(),
+                id: style::attribute(&e, b"id"),
+                reason,
+            });
+        }
+        element += 1;
+        if is_start {
+            stack.push(visibility);
+        }
+        buf.clear();
+    }
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Strict(violations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reasons(svg: &str, options: &ParseOptions) -> Vec<String> {
+        match check(svg, options) {
+            Ok(()) => Vec::new(),
+            Err(Error::Strict(violations)) => violations.iter().map(ToString::to_string).collect(),
+            Err(e) => panic!("Unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let svg = r#"<svg>
+            <path d="M 0,0 L 1,1"/>
+            <g style="display:none">
+                <path id="hidden" d="M 0,0 L 1,1"/>
+            </g>
+            <path d="M 0,0 T 1,1" transform="rotate(a)"/>
+            <text>Hello</text>
+            <path d="M 0,0 L 1,1" fill="none" clip-path="url(#clip)"/>
+            <image href="photo.png"/>
+        </svg>"#;
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let found = reasons(svg, &options);
+        assert_eq!(found.len(), 7, "{found:?}");
+        assert_eq!(
+            found[0],
+            "<path> 3 (#hidden): Hidden element would be plotted"
+        );
+        assert!(found[1].starts_with("<path> 4: Invalid transform"));
+        assert_eq!(
+            found[2],
+            "<path> 4: Unsupported smooth quadratic path segment"
+        );
+        assert_eq!(
+            found[3],
+            "<text> 5: Unsupported element without preprocessing"
+        );
+        assert_eq!(found[4], "<path> 6: Clip path is ignored");
+        assert_eq!(
+            found[5],
+            "<path> 6: Element without fill and stroke would be plotted"
+        );
+        assert_eq!(found[6], "<image> 7: Unsupported element");
+
+        let options = ParseOptions {
+            skip_invisible: true,
+            clip_paths: true,
+            ..options
+        };
+        assert_eq!(reasons(svg, &options).len(), 5);
+        assert!(check(r#"<svg><path d="M 0,0 L 1,1"/></svg>"#, &options).is_ok());
+    }
+}
@@ -0,0 +1,157 @@
+//! Helpers to read attributes and presentation properties of elements.
+
+use std::{borrow::Cow, str};
+
+use quick_xml::events::{attributes::Attributes, BytesStart};
+
+use crate::css::{parse_declarations, StyleSheet};
+
+/// Return the unescaped value of an attribute.
+pub(crate) fn attribute(e: &BytesStart, key: &[u8]) -> Option<String> {
+    let attr = e
+        .attributes()
+        .filter_map(Result::ok)
+        .find(|attr| attr.key == key)?;
+    let value = attr.unescaped_value().ok()?;
+    str::from_utf8(&value).map(str::to_string).ok()
+}
+
+/// Like [`attribute`], but borrow the value from `content`, the content of
+/// an element with a name of `name_len` bytes, if it contains no escapes.
+/// This avoids copying long values like path data.
+pub(crate) fn borrowed_attribute<'a>(
+    content: &'a [u8],
+    name_len: usize,
+    key: &[u8],
+) -> Option<Cow<'a, str>> {
+    let attr = Attributes::new(content, name_len)
+        .filter_map(Result::ok)
+        .find(|attr| attr.key == key)?;
+    match attr.value {
+        Cow::Borrowed(value) if !value.contains(&b'&') => {
+            str::from_utf8(value).ok().map(Cow::Borrowed)
+        }
+        _ => {
+            let value = attr.unescaped_value().ok()?;
+            str::from_utf8(&value)
+                .map(|value| Cow::Owned(value.to_string()))
+                .ok()
+        }
+    }
+}
+
+/// Return a presentation property of an element, e.g. `stroke`.
+///
+/// Declarations in the `style` attribute take precedence over the rules of
+/// the style sheet, which take precedence over presentation attributes.
+pub(crate) fn property(e: &BytesStart, name: &str, sheet: &StyleSheet) -> Option<String> {
+    let from_style = attribute(e, b"style").and_then(|style| {
+        parse_declarations(&style)
+            .into_iter()
+            .rev()
+            .find_map(|(key, value)| (key == name).then_some(value))
+    });
+    from_style
+        .or_else(|| sheet.property(e, name))
+        .or_else(|| attribute(e, name.as_bytes()))
+}
+
+/// Return the id referenced by a property value like `url(#id)`, ignoring a
+/// fallback color after the reference.
+pub(crate) fn reference(value: &str) -> Option<String> {
+    let (url, _fallback) = value.trim().strip_prefix("url(")?.split_once(')')?;
+    let id = url
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .strip_prefix('#')?;
+    Some(id.to_string())
+}
+
+/// Whether the fill and the stroke of an element are painted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Paint {
+    pub(crate) fill: bool,
+    pub(crate) stroke: bool,
+}
+
+impl Default for Paint {
+    /// The initial values: Black fill, no stroke.
+    fn default() -> Self {
+        Self {
+            fill: true,
+            stroke: false,
+        }
+    }
+}
+
+impl Paint {
+    /// Apply the `fill` and `stroke` properties of an element to the paint
+    /// inherited from its parent.
+    pub(crate) fn inherit(self, e: &BytesStart, sheet: &StyleSheet) -> Self {
+        let painted = |name, inherited| match property(e, name, sheet) {
+            Some(value) => value != "none",
+            None => inherited,
+        };
+        Self {
+            fill: painted("fill", self.fill),
+            stroke: painted("stroke", self.stroke),
+        }
+    }
+
+    /// Return whether an element with this paint is visible. Lines can't be
+    /// filled, so they need a stroke.
+    pub(crate) fn is_visible(self, e: &BytesStart) -> bool {
+        self.stroke || (self.fill && e.name() != b"line")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_property() {
+        let e = BytesStart::borrowed(
+            br#"path stroke="red" fill="blue" style="fill: none; stroke-width:2""#,
+            4,
+        );
+        let sheet = StyleSheet::default();
+        assert_eq!(property(&e, "stroke", &sheet), Some("red".into()));
+        assert_eq!(property(&e, "fill", &sheet), Some("none".into()));
+        assert_eq!(property(&e, "stroke-width", &sheet), Some("2".into()));
+        assert_eq!(property(&e, "opacity", &sheet), None);
+
+        let sheet = StyleSheet::from_svg("<style>path { stroke: blue; opacity: 0.5 }</style>");
+        assert_eq!(property(&e, "stroke", &sheet), Some("blue".into()));
+        assert_eq!(property(&e, "fill", &sheet), Some("none".into()));
+        assert_eq!(property(&e, "opacity", &sheet), Some("0.5".into()));
+    }
+
+    #[test]
+    fn test_paint() {
+        let sheet = StyleSheet::default();
+        let e = BytesStart::borrowed(br#"g fill="none""#, 1);
+        let group = Paint::default().inherit(&e, &sheet);
+        assert_eq!(
+            group,
+            Paint {
+                fill: false,
+                stroke: false
+            }
+        );
+        let e = BytesStart::borrowed(b"path", 4);
+        assert!(!group.inherit(&e, &sheet).is_visible(&e));
+        let e = BytesStart::borrowed(br#"path style="stroke:red""#, 4);
+        assert!(group.inherit(&e, &sheet).is_visible(&e));
+        let e = BytesStart::borrowed(b"line", 4);
+        assert!(!Paint::default().inherit(&e, &sheet).is_visible(&e));
+    }
+
+    #[test]
+    fn test_reference() {
+        assert_eq!(reference("url(#a)"), Some("a".into()));
+        assert_eq!(reference(" url(\"#a\") red"), Some("a".into()));
+        assert_eq!(reference("url(a.svg)"), None);
+        assert_eq!(reference("red"), None);
+    }
+}
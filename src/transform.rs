@@ -0,0 +1,192 @@
+//! Compose the transforms of nested elements while walking the XML tree.
+
+use std::str::FromStr;
+
+use lyon_geom::euclid::Transform2D;
+use quick_xml::events::BytesStart;
+use svgtypes::{AspectRatio, Length, LengthUnit};
+
+use crate::{document::ViewBox, parse_transform, style, Error, UnsupportedPolicy, Warning};
+
+/// The transform of an element, composed with the transforms of all of its
+/// ancestors.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ComposedTransform {
+    /// The composed transform. Invalid transforms are treated as identity.
+    pub(crate) transform: Transform2D<f64, f64, f64>,
+
+    /// The error message of the first invalid transform, if any.
+    pub(crate) error: Option<String>,
+}
+
+impl Default for ComposedTransform {
+    fn default() -> Self {
+        Self {
+            transform: Transform2D::identity(),
+            error: None,
+        }
+    }
+}
+
+impl ComposedTransform {
+    /// Return the transform to apply to the polylines of an element, or
+    /// `None` if the element should be skipped according to `policy`.
+    pub(crate) fn resolve(
+        &self,
+        policy: UnsupportedPolicy,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Option<Transform2D<f64, f64, f64>>, Error> {
+        match (&self.error, policy) {
+            (None, _) | (Some(_), UnsupportedPolicy::Approximate) => Ok(Some(self.transform)),
+            (Some(message), UnsupportedPolicy::Error) => Err(Error::Transform(message.clone())),
+            (Some(message), UnsupportedPolicy::Warn) => {
+                warnings.push(Warning::Unsupported {
+                    feature: message.clone(),
+                });
+                Ok(None)
+            }
+            (Some(_), UnsupportedPolicy::Skip) => Ok(None),
+        }
+    }
+}
+
+/// Return the transform of the viewport established by a nested `<svg>`
+/// element: The `viewBox` is mapped onto the viewport, which is offset by
+/// `x` and `y`.
+///
+/// Only unitless and `px` lengths are supported. Without a `viewBox`, or if
+/// `width` or `height` is missing or a percentage, only the offset applies.
+fn nested_viewport(e: &BytesStart) -> Transform2D<f64, f64, f64> {
+    let length = |key| match Length::from_str(&style::attribute(e, key)?).ok()? {
+        Length {
+            number,
+            unit: LengthUnit::None | LengthUnit::Px,
+        } => Some(number),
+        _ => None,
+    };
+    let view_box = style::attribute(e, b"viewBox")
+        .and_then(|value| svgtypes::ViewBox::from_str(&value).ok())
+        .map(|vb| ViewBox {
+            x: vb.x,
+            y: vb.y,
+            width: vb.w,
+            height: vb.h,
+        });
+    let aspect = style::attribute(e, b"preserveAspectRatio")
+        .and_then(|value| AspectRatio::from_str(&value).ok())
+        .unwrap_or_default();
+    let view_box_transform = match (view_box, length(b"width"), length(b"height")) {
+        (Some(view_box), Some(width), Some(height)) => view_box.transform_to(width, height, aspect),
+        _ => Transform2D::identity(),
+    };
+    view_box_transform
+        .then_translate((length(b"x").unwrap_or(0.0), length(b"y").unwrap_or(0.0)).into())
+}
+
+/// The composed transforms of the open elements.
+///
+/// Push the transform of every start element and pop it at the
+/// corresponding end element, like the renderer's matrix stack.
+#[derive(Debug, Default)]
+pub(crate) struct TransformStack {
+    stack: Vec<ComposedTransform>,
+}
+
+impl TransformStack {
+    /// Compose the `transform` attribute of an element with the transforms of
+    /// the open ancestor elements, including the viewports of nested `<svg>`
+    /// elements.
+    pub(crate) fn compose(&self, e: &BytesStart) -> ComposedTransform {
+        let mut composed = self.stack.last().cloned().unwrap_or_default();
+        if let Some(expr) = style::borrowed_attribute(e, e.name().len(), b"transform") {
+            match parse_transform(&expr) {
+                // The element's own transform is applied first
+                Ok(t) => composed.transform = t.then(&composed.transform),
+                Err(Error::Transform(message)) if composed.error.is_none() => {
+                    composed.error = Some(message);
+                }
+                Err(_) => {}
+            }
+        }
+        // Nested `<svg>` elements establish a new viewport
+        if e.name() == b"svg" && !self.stack.is_empty() {
+            composed.transform = nested_viewport(e).then(&composed.transform);
+        }
+        composed
+    }
+
+    pub(crate) fn push(&mut self, composed: ComposedTransform) {
+        self.stack.push(composed);
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.stack.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use lyon_geom::euclid::Point2D;
+
+    #[test]
+    fn test_transform_stack() {
+        let mut stack = TransformStack::default();
+        let outer = stack.compose(&BytesStart::borrowed(br#"g transform="scale(2)""#, 1));
+        stack.push(outer);
+        let inner = stack.compose(&BytesStart::borrowed(br#"g transform="translate(1)""#, 1));
+        stack.push(inner);
+        let path = stack.compose(&BytesStart::borrowed(b"path", 4));
+        assert_eq!(
+            path.transform.transform_point(Point2D::new(1.0, 1.0)),
+            Point2D::new(4.0, 2.0)
+        );
+        stack.pop();
+        let invalid = stack.compose(&BytesStart::borrowed(br#"path transform="foo""#, 4));
+        assert_eq!(invalid.transform, Transform2D::scale(2.0, 2.0));
+        assert!(invalid.error.is_some());
+        stack.pop();
+        assert_eq!(
+            stack.compose(&BytesStart::borrowed(b"path", 4)),
+            ComposedTransform::default()
+        );
+
+        let mut warnings = Vec::new();
+        assert!(invalid
+            .resolve(UnsupportedPolicy::Error, &mut warnings)
+            .is_err());
+        assert_eq!(
+            invalid
+                .resolve(UnsupportedPolicy::Warn, &mut warnings)
+                .unwrap(),
+            None
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            invalid
+                .resolve(UnsupportedPolicy::Approximate, &mut warnings)
+                .unwrap(),
+            Some(Transform2D::scale(2.0, 2.0))
+        );
+    }
+
+    #[test]
+    fn test_nested_viewport() {
+        let mut stack = TransformStack::default();
+        let root = stack.compose(&BytesStart::borrowed(br#"svg x="5" viewBox="0 0 1 1""#, 3));
+        assert_eq!(root, ComposedTransform::default());
+        stack.push(root);
+        let nested = stack.compose(&BytesStart::borrowed(
+            br#"svg x="10" y="20" width="100" height="50" viewBox="0 0 10 10""#,
+            3,
+        ));
+        // Scaled uniformly by 5 and centered horizontally
+        assert_eq!(
+            nested.transform.transform_point(Point2D::new(1.0, 1.0)),
+            Point2D::new(10.0 + 25.0 + 5.0, 20.0 + 5.0)
+        );
+        let offset = stack.compose(&BytesStart::borrowed(br#"svg x="10" width="50%""#, 3));
+        assert_eq!(offset.transform, Transform2D::translation(10.0, 0.0));
+    }
+}
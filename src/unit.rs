@@ -0,0 +1,108 @@
+//! Convert user units into physical units.
+
+use lyon_geom::euclid::Transform2D;
+use svgtypes::{Length, LengthUnit};
+
+use crate::document::RootAttributes;
+
+/// The unit of the output coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Unit {
+    /// Keep the user units of the document, without any conversion.
+    #[default]
+    User,
+    Millimeters,
+    Centimeters,
+    Inches,
+    /// Typographic points (1/72 inch).
+    Points,
+    /// Pixels at the configured DPI.
+    Pixels,
+}
+
+impl Unit {
+    /// Return the size of the unit in millimeters, or `None` for user units.
+    fn millimeters(self, dpi: f64) -> Option<f64> {
+        match self {
+            Self::User => None,
+            Self::Millimeters => Some(1.0),
+            Self::Centimeters => Some(10.0),
+            Self::Inches => Some(25.4),
+            Self::Points => Some(25.4 / 72.0),
+            Self::Pixels => Some(25.4 / dpi),
+        }
+    }
+}
+
+/// Convert a length into millimeters.
+///
+/// Unitless lengths are pixels. Relative units (`em`, `ex`, `%`) can't be
+/// converted.
+fn length_millimeters(length: Length, dpi: f64) -> Option<f64> {
+    let factor = match length.unit {
+        LengthUnit::None | LengthUnit::Px => 25.4 / dpi,
+        LengthUnit::In => 25.4,
+        LengthUnit::Cm => 10.0,
+        LengthUnit::Mm => 1.0,
+        LengthUnit::Pt => 25.4 / 72.0,
+        LengthUnit::Pc => 25.4 / 6.0,
+        LengthUnit::Em | LengthUnit::Ex | LengthUnit::Percent => return None,
+    };
+    Some(length.number * factor)
+}
+
+/// Return the transform from user units into `unit`, or `None` if no
+/// conversion is needed.
+///
+/// If the root element has a `viewBox` as well as an absolute `width` and
+/// `height`, the view box is mapped onto this area (honoring
+/// `preserveAspectRatio`). Otherwise, a user unit is one pixel.
+pub(crate) fn transform(
+    root: &RootAttributes,
+    unit: Unit,
+    dpi: f64,
+) -> Option<Transform2D<f64, f64, f64>> {
+    let unit_size = unit.millimeters(dpi)?;
+    let to_millimeters = match (
+        root.view_box,
+        root.width.and_then(|w| length_millimeters(w, dpi)),
+        root.height.and_then(|h| length_millimeters(h, dpi)),
+    ) {
+        (Some(view_box), Some(width), Some(height)) => {
+            view_box.transform_to(width, height, root.preserve_aspect_ratio)
+        }
+        _ => Transform2D::scale(25.4 / dpi, 25.4 / dpi),
+    };
+    Some(to_millimeters.then_scale(1.0 / unit_size, 1.0 / unit_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::document::parse_root;
+
+    fn convert(svg: &str, unit: Unit, dpi: f64) -> Option<(f64, f64)> {
+        let root = parse_root(svg).unwrap();
+        let p = transform(&root, unit, dpi)?.transform_point((10.0, 20.0).into());
+        Some((p.x, p.y))
+    }
+
+    #[test]
+    fn test_transform() {
+        let a4 = r#"<svg width="210mm" height="297mm" viewBox="0 0 105 148.5"/>"#;
+        assert_eq!(convert(a4, Unit::User, 96.0), None);
+        assert_eq!(convert(a4, Unit::Millimeters, 96.0), Some((20.0, 40.0)));
+        assert_eq!(convert(a4, Unit::Centimeters, 96.0), Some((2.0, 4.0)));
+
+        // Without a view box, user units are pixels
+        let pixels = r#"<svg width="100" height="100"/>"#;
+        assert_eq!(convert(pixels, Unit::Inches, 10.0), Some((1.0, 2.0)));
+        assert_eq!(convert(pixels, Unit::Pixels, 72.0), Some((10.0, 20.0)));
+        assert_eq!(convert(pixels, Unit::Points, 72.0), Some((10.0, 20.0)));
+
+        // Relative sizes fall back to pixels
+        let relative = r#"<svg width="100%" height="100%" viewBox="0 0 1 1"/>"#;
+        assert_eq!(convert(relative, Unit::Inches, 10.0), Some((1.0, 2.0)));
+    }
+}
@@ -0,0 +1,72 @@
+//! Check polylines against the working area of a machine.
+
+use crate::{BoundingBox, Error, Polyline};
+
+/// A polyline that does not fit into the machine envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvelopeViolation {
+    /// Index of the polyline.
+    pub index: usize,
+
+    /// The largest distance of a point outside of the envelope.
+    pub overshoot: f64,
+
+    /// The bounding box of the polyline.
+    pub extent: BoundingBox,
+}
+
+/// Check that all polylines lie within `envelope`.
+///
+/// Run this on the final polylines (after all transformations) before
+/// sending them to an exporter or device. If any polyline exceeds the
+/// envelope, an [`Error::Envelope`] listing all offending polylines is
+/// returned.
+pub fn validate_envelope(polylines: &[Polyline], envelope: &BoundingBox) -> Result<(), Error> {
+    let violations: Vec<_> = polylines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, polyline)| {
+            let overshoot = envelope.polyline_overshoot(polyline);
+            if overshoot <= 0.0 {
+                return None;
+            }
+            let extent = polyline.bounding_box()?;
+            Some(EnvelopeViolation {
+                index,
+                overshoot,
+                extent,
+            })
+        })
+        .collect();
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Envelope(violations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_envelope() {
+        let envelope = BoundingBox::new(0.0, 0.0, 200.0, 100.0);
+        let polylines = vec![
+            Polyline::from_vec(vec![(0.0, 0.0).into(), (200.0, 100.0).into()]),
+            Polyline::from_vec(vec![(10.0, 10.0).into(), (210.0, 20.0).into()]),
+        ];
+        assert!(validate_envelope(&polylines[..1], &envelope).is_ok());
+        match validate_envelope(&polylines, &envelope) {
+            Err(Error::Envelope(violations)) => assert_eq!(
+                violations,
+                vec![EnvelopeViolation {
+                    index: 1,
+                    overshoot: 10.0,
+                    extent: BoundingBox::new(10.0, 10.0, 210.0, 20.0),
+                }]
+            ),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+
+/// A problem that was found while parsing, but did not prevent the
+/// conversion.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// A polyline extends beyond the document viewport.
+    OutOfBounds {
+        /// Index of the polyline in the result.
+        index: usize,
+        /// The largest distance of a point outside of the viewport, in user
+        /// units.
+        overshoot: f64,
+    },
+
+    /// The document was not well-formed and has been repaired, see
+    /// [`ParseOptions::recover`](crate::ParseOptions::recover).
+    MalformedXml {
+        /// What was repaired.
+        message: String,
+    },
+
+    /// A path with invalid path data was skipped, see
+    /// [`ParseOptions::lenient`](crate::ParseOptions::lenient).
+    InvalidPath {
+        /// The `id` attribute of the path element.
+        id: Option<String>,
+        /// Why the path data is invalid.
+        message: String,
+    },
+
+    /// An unsupported feature was skipped, see
+    /// [`UnsupportedPolicy::Warn`](crate::UnsupportedPolicy::Warn).
+    Unsupported {
+        /// Description of the feature.
+        feature: String,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { index, overshoot } => write!(
+                f,
+                "Polyline {index} extends {overshoot} units beyond the viewport"
+            ),
+            Self::MalformedXml { message } => write!(f, "Malformed XML: {message}"),
+            Self::InvalidPath {
+                id: Some(id),
+                message,
+            } => {
+                write!(f, "Invalid path \"{id}\" skipped: {message}")
+            }
+            Self::InvalidPath { id: None, message } => write!(f, "Invalid path skipped: {message}"),
+            Self::Unsupported { feature } => write!(f, "Unsupported feature skipped: {feature}"),
+        }
+    }
+}
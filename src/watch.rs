@@ -0,0 +1,141 @@
+//! Watch an SVG file and re-parse it whenever it changes.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use log::trace;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{parse_with_options, Error, ParseOptions, Polyline};
+
+/// How long the file must stay unchanged before it is parsed again.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Handle returned by [`watch`].
+///
+/// The file is being watched for as long as this handle is alive. Drop it to
+/// stop watching.
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+fn load(path: &Path, options: &ParseOptions) -> Result<Vec<Polyline>, Error> {
+    let svg = fs::read_to_string(path)?;
+    parse_with_options(&svg, options)
+}
+
+/// Watch the SVG file at `path` and invoke `callback` with freshly parsed
+/// polylines whenever it changes.
+///
+/// The callback is invoked once right away on the calling thread with the
+/// current file contents, and then on a background thread after every
+/// modification. Changes are collected until the file has been unchanged for
+/// 200 ms, so that a file written in several steps is only parsed once it is
+/// complete.
+///
+/// Note that the parent directory is watched instead of the file itself,
+/// because many editors save files by replacing them, not by writing to them.
+pub fn watch<P, F>(path: P, options: ParseOptions, mut callback: F) -> Result<FileWatcher, Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(Result<Vec<Polyline>, Error>) + Send + 'static,
+{
+    let path = path.as_ref().to_path_buf();
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::Watch(format!("Not a file: {}", path.display())))?
+        .to_owned();
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    callback(load(&path, &options));
+
+    // The watcher reports changes (`Ok`) and its errors to the thread that
+    // parses the file, which ends when the watcher is dropped
+    let (tx, rx) = mpsc::channel::<Result<(), Error>>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) => {
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                return;
+            }
+            if event
+                .paths
+                .iter()
+                .any(|p| p.file_name() == Some(file_name.as_os_str()))
+            {
+                let _ = tx.send(Ok(()));
+            }
+        }
+        Err(e) => {
+            let _ = tx.send(Err(Error::Watch(e.to_string())));
+        }
+    })
+    .map_err(|e| Error::Watch(e.to_string()))?;
+    thread::spawn(move || {
+        while let Ok(message) = rx.recv() {
+            if let Err(e) = message {
+                callback(Err(e));
+                continue;
+            }
+            loop {
+                match rx.recv_timeout(DEBOUNCE) {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => callback(Err(e)),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            trace!("watch: {} changed", path.display());
+            callback(load(&path, &options));
+        }
+    });
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| Error::Watch(e.to_string()))?;
+
+    Ok(FileWatcher { _watcher: watcher })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Preprocess;
+
+    #[test]
+    fn test_watch_initial_and_change() {
+        let dir = std::env::temp_dir().join(format!("svg2polylines-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("drawing.svg");
+        fs::write(&path, r#"<svg><path d="M 0,0 1,1"/></svg>"#).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let options = ParseOptions {
+            preprocess: Preprocess::No,
+            ..Default::default()
+        };
+        let _watcher = watch(&path, options, move |result| {
+            let _ = tx.send(result.map(|polylines| polylines.len()));
+        })
+        .unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap(), 1);
+
+        // Writing in several steps only parses the complete file
+        fs::write(&path, r#"<svg><path d="M 0,0 1,1"/><path d="M 2,2"#).unwrap();
+        fs::write(
+            &path,
+            r#"<svg><path d="M 0,0 1,1"/><path d="M 2,2 3,3"/></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap(), 2);
+        assert!(rx.recv_timeout(DEBOUNCE * 2).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+//! Insert gaps at crossings for an over/under weave effect.
+
+use std::cmp::Ordering;
+
+use crate::{CoordinatePair, Polyline};
+
+/// Options for [`weave`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeaveOptions {
+    /// Length of the gap that is cut into the lower polyline at every
+    /// crossing.
+    pub gap: f64,
+
+    /// If `false`, polylines that come later are drawn over earlier ones, as
+    /// in the SVG painting order. If `true`, every polyline alternates
+    /// between going over and under the polylines it crosses.
+    pub alternate: bool,
+}
+
+impl Default for WeaveOptions {
+    fn default() -> Self {
+        Self {
+            gap: 1.0,
+            alternate: false,
+        }
+    }
+}
+
+/// Return the cumulative arc length at every point of the polyline.
+fn arc_lengths(points: &[CoordinatePair]) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut lengths = Vec::with_capacity(points.len());
+    lengths.push(0.0);
+    for w in points.windows(2) {
+        total += (w[1].x - w[0].x).hypot(w[1].y - w[0].y);
+        lengths.push(total);
+    }
+    lengths
+}
+
+/// Return the point at arc length `at`.
+fn point_at(points: &[CoordinatePair], lengths: &[f64], at: f64) -> CoordinatePair {
+    let index = lengths
+        .partition_point(|&length| length <= at)
+        .clamp(1, points.len() - 1);
+    let (start, end) = (points[index - 1], points[index]);
+    let segment_length = lengths[index] - lengths[index - 1];
+    let t = if segment_length > 0.0 {
+        (at - lengths[index - 1]) / segment_length
+    } else {
+        0.0
+    };
+    CoordinatePair::new(
+        start.x + (end.x - start.x) * t,
+        start.y + (end.y - start.y) * t,
+    )
+}
+
+/// Return the part of the polyline between the arc lengths `from` and `to`.
+fn extract(points: &[CoordinatePair], lengths: &[f64], from: f64, to: f64) -> Polyline {
+    let mut part = vec![point_at(points, lengths, from)];
+    part.extend(
+        points
+            .iter()
+            .zip(lengths)
+            .filter(|(_, &l)| l > from && l < to)
+            .map(|(p, _)| *p),
+    );
+    part.push(point_at(points, lengths, to));
+    Polyline::from_vec(part)
+}
+
+/// Return the parameters at which two segments cross.
+///
+/// The parameter ranges are half-open, so that a crossing at a shared vertex
+/// of two consecutive segments is only found once.
+fn crossing(
+    first: (CoordinatePair, CoordinatePair),
+    second: (CoordinatePair, CoordinatePair),
+) -> Option<(f64, f64)> {
+    let (rx, ry) = (first.1.x - first.0.x, first.1.y - first.0.y);
+    let (sx, sy) = (second.1.x - second.0.x, second.1.y - second.0.y);
+    let denominator = rx * sy - ry * sx;
+    if denominator == 0.0 {
+        return None;
+    }
+    let (ox, oy) = (second.0.x - first.0.x, second.0.y - first.0.y);
+    let t = (ox * sy - oy * sx) / denominator;
+    let u = (ox * ry - oy * rx) / denominator;
+    ((0.0..1.0).contains(&t) && (0.0..1.0).contains(&u)).then_some((t, u))
+}
+
+/// A crossing between an earlier polyline and a later one, given by the arc
+/// length along both polylines.
+struct Crossing {
+    first: usize,
+    first_at: f64,
+    second: usize,
+    second_at: f64,
+}
+
+/// Cut a short gap into one of the two polylines at every crossing, so that
+/// the other one appears to pass over it.
+///
+/// Crossings of a polyline with itself are ignored. Polylines that are split
+/// by gaps are returned as multiple polylines, in the original order.
+pub fn weave(polylines: &[Polyline], options: &WeaveOptions) -> Vec<Polyline> {
+    let lengths: Vec<Vec<f64>> = polylines.iter().map(|p| arc_lengths(p)).collect();
+
+    // Find all crossings
+    let mut crossings = Vec::new();
+    for (i, first) in polylines.iter().enumerate() {
+        for (j, second) in polylines.iter().enumerate().skip(i + 1) {
+            for (k, a) in first.windows(2).enumerate() {
+                for (l, b) in second.windows(2).enumerate() {
+                    let Some((t, u)) = crossing((a[0], a[1]), (b[0], b[1])) else {
+                        continue;
+                    };
+                    let first_at = lengths[i][k] + t * (lengths[i][k + 1] - lengths[i][k]);
+                    let second_at = lengths[j][l] + u * (lengths[j][l + 1] - lengths[j][l]);
+                    // Touching start points are no crossing
+                    if first_at > 0.0 && second_at > 0.0 {
+                        crossings.push(Crossing {
+                            first: i,
+                            first_at,
+                            second: j,
+                            second_at,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // Decide which polyline goes under at every crossing
+    crossings.sort_by(|a, b| {
+        (a.first, a.first_at)
+            .partial_cmp(&(b.first, b.first_at))
+            .unwrap_or(Ordering::Equal)
+    });
+    let mut gaps: Vec<Vec<f64>> = vec![Vec::new(); polylines.len()];
+    let mut count = vec![0_usize; polylines.len()];
+    for c in &crossings {
+        let first_under = if options.alternate {
+            count[c.first] += 1;
+            count[c.first] % 2 == 1
+        } else {
+            true
+        };
+        if first_under {
+            gaps[c.first].push(c.first_at);
+        } else {
+            gaps[c.second].push(c.second_at);
+        }
+    }
+
+    // Cut the gaps
+    let half_gap = options.gap.max(0.0) / 2.0;
+    let mut result = Vec::with_capacity(polylines.len());
+    for ((polyline, lengths), mut gaps) in polylines.iter().zip(&lengths).zip(gaps) {
+        if gaps.is_empty() || half_gap == 0.0 {
+            result.push(polyline.clone());
+            continue;
+        }
+        gaps.sort_by(f64::total_cmp);
+        let total = lengths[lengths.len() - 1];
+        let mut start = 0.0;
+        for at in gaps {
+            let end = at - half_gap;
+            if end > start {
+                result.push(extract(polyline, lengths, start, end));
+            }
+            start = start.max(at + half_gap);
+        }
+        if total > start {
+            result.push(extract(polyline, lengths, start, total));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::line;
+
+    #[test]
+    fn test_weave() {
+        let polylines = vec![
+            line(&[(0.0, 5.0), (10.0, 5.0)]),
+            line(&[(5.0, 0.0), (5.0, 10.0)]),
+        ];
+        let options = WeaveOptions {
+            gap: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            weave(&polylines, &options),
+            vec![
+                line(&[(0.0, 5.0), (4.0, 5.0)]),
+                line(&[(6.0, 5.0), (10.0, 5.0)]),
+                line(&[(5.0, 0.0), (5.0, 10.0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_weave_alternate() {
+        // One horizontal line crossing two vertical ones
+        let polylines = vec![
+            line(&[(0.0, 5.0), (5.0, 5.0), (20.0, 5.0)]),
+            line(&[(5.0, 0.0), (5.0, 10.0)]),
+            line(&[(15.0, 0.0), (15.0, 10.0)]),
+        ];
+        let options = WeaveOptions {
+            gap: 2.0,
+            alternate: true,
+        };
+        assert_eq!(
+            weave(&polylines, &options),
+            vec![
+                line(&[(0.0, 5.0), (4.0, 5.0)]),
+                line(&[(6.0, 5.0), (20.0, 5.0)]),
+                line(&[(5.0, 0.0), (5.0, 10.0)]),
+                line(&[(15.0, 0.0), (15.0, 4.0)]),
+                line(&[(15.0, 6.0), (15.0, 10.0)]),
+            ]
+        );
+    }
+}